@@ -103,7 +103,7 @@ actor BackendService {
     
     func resetDatabase() async throws {
         let s = try getSession()
-        try await YokuUniffi.resetDatabase(session: s)
+        try await YokuUniffi.resetDatabaseConfirmed(session: s, confirmation: "RESET")
     }
 }
 
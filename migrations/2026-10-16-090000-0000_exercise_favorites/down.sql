@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_exercises_is_favorite;
+
+ALTER TABLE exercises DROP COLUMN is_favorite;
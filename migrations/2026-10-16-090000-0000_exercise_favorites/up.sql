@@ -0,0 +1,3 @@
+ALTER TABLE exercises ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_exercises_is_favorite ON exercises(is_favorite);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_exercises_experience_level;
+
+ALTER TABLE exercises DROP COLUMN experience_level;
@@ -0,0 +1,3 @@
+ALTER TABLE exercises ADD COLUMN experience_level TEXT NOT NULL DEFAULT 'intermediate' CHECK(experience_level IN ('beginner', 'intermediate', 'advanced'));
+
+CREATE INDEX IF NOT EXISTS idx_exercises_experience_level ON exercises(experience_level);
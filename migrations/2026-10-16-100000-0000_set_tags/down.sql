@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_set_tags_tag;
+
+DROP TABLE IF EXISTS set_tags;
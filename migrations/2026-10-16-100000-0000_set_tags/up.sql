@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS set_tags (
+    set_id INTEGER NOT NULL REFERENCES workout_sets(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (set_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_set_tags_tag ON set_tags(tag);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_user_equipment_equipment_id;
+
+DROP TABLE IF EXISTS user_equipment;
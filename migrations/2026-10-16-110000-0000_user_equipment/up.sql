@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS user_equipment (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    equipment_id INTEGER NOT NULL REFERENCES equipment(id) ON DELETE CASCADE,
+    created_at INTEGER NOT NULL DEFAULT (CAST(strftime('%s','now') AS INTEGER)),
+    PRIMARY KEY (user_id, equipment_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_equipment_equipment_id ON user_equipment(equipment_id);
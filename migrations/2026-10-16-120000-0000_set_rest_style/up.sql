@@ -0,0 +1 @@
+ALTER TABLE workout_sets ADD COLUMN rest_style TEXT CHECK(rest_style IN ('hypertrophy', 'strength', 'conditioning', 'giant'));
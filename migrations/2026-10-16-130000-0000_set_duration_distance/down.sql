@@ -0,0 +1,3 @@
+ALTER TABLE workout_sets DROP COLUMN distance_meters;
+
+ALTER TABLE workout_sets DROP COLUMN duration_seconds;
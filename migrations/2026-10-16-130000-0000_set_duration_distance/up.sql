@@ -0,0 +1,3 @@
+ALTER TABLE workout_sets ADD COLUMN duration_seconds INTEGER;
+
+ALTER TABLE workout_sets ADD COLUMN distance_meters REAL;
@@ -0,0 +1 @@
+ALTER TABLE workout_sets ADD COLUMN superset_group INTEGER;
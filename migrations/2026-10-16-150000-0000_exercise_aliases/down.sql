@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS exercises_seed_aliases;
+
+DROP TABLE IF EXISTS exercise_alias_seeds;
+
+DROP INDEX IF EXISTS idx_exercise_aliases_exercise_id;
+
+DROP TABLE IF EXISTS exercise_aliases;
@@ -0,0 +1,40 @@
+CREATE TABLE IF NOT EXISTS exercise_aliases (
+    alias TEXT NOT NULL UNIQUE,
+    exercise_id INTEGER NOT NULL REFERENCES exercises(id) ON DELETE CASCADE,
+    created_at INTEGER NOT NULL DEFAULT (CAST(strftime('%s','now') AS INTEGER))
+);
+
+CREATE INDEX IF NOT EXISTS idx_exercise_aliases_exercise_id ON exercise_aliases(exercise_id);
+
+-- Common shorthands, attached to their canonical exercise (by slug) once it exists.
+CREATE TABLE IF NOT EXISTS exercise_alias_seeds (
+    alias TEXT PRIMARY KEY,
+    slug TEXT NOT NULL
+);
+
+INSERT OR IGNORE INTO exercise_alias_seeds (alias, slug) VALUES
+    ('bench', 'bench-press'),
+    ('flat bench', 'bench-press'),
+    ('barbell bench', 'bench-press'),
+    ('barbell bench press', 'bench-press'),
+    ('squat', 'barbell-back-squat'),
+    ('back squat', 'barbell-back-squat'),
+    ('barbell squat', 'barbell-back-squat'),
+    ('dl', 'deadlift'),
+    ('conventional deadlift', 'deadlift'),
+    ('rdl', 'romanian-deadlift'),
+    ('ohp', 'overhead-press'),
+    ('military press', 'overhead-press'),
+    ('pullup', 'pull-up'),
+    ('pull up', 'pull-up'),
+    ('chinup', 'chin-up'),
+    ('chin up', 'chin-up');
+
+INSERT OR IGNORE INTO exercise_aliases (alias, exercise_id)
+SELECT s.alias, e.id FROM exercise_alias_seeds s JOIN exercises e ON e.slug = s.slug;
+
+CREATE TRIGGER IF NOT EXISTS exercises_seed_aliases AFTER INSERT ON exercises
+BEGIN
+    INSERT OR IGNORE INTO exercise_aliases (alias, exercise_id)
+    SELECT alias, NEW.id FROM exercise_alias_seeds WHERE slug = NEW.slug;
+END;
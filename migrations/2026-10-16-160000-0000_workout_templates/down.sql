@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_template_exercises_exercise_id;
+
+DROP TABLE IF EXISTS template_exercises;
+
+DROP TABLE IF EXISTS workout_templates;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_bodyweight_entries_user_recorded;
+
+DROP TABLE IF EXISTS bodyweight_entries;
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS bodyweight_entries (
+    id INTEGER NOT NULL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    weight REAL NOT NULL CHECK (weight > 0),
+    recorded_at INTEGER NOT NULL,
+    created_at INTEGER NOT NULL DEFAULT (CAST(strftime('%s','now') AS INTEGER))
+);
+
+CREATE INDEX IF NOT EXISTS idx_bodyweight_entries_user_recorded ON bodyweight_entries(user_id, recorded_at);
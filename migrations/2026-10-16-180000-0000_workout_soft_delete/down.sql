@@ -0,0 +1 @@
+ALTER TABLE workout_sessions DROP COLUMN deleted_at;
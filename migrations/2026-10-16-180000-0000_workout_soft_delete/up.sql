@@ -0,0 +1 @@
+ALTER TABLE workout_sessions ADD COLUMN deleted_at INTEGER;
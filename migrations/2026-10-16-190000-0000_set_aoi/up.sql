@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS set_aoi (
+    set_id INTEGER NOT NULL PRIMARY KEY REFERENCES workout_sets(id) ON DELETE CASCADE,
+    aoi TEXT NOT NULL
+);
@@ -1,19 +1,30 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand, ValueEnum};
 use dotenvy::dotenv;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use sqlx::sqlite::SqliteConnectOptions;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::io::{BufRead, Write};
 
-use yoku_core::db::models::DisplayableSet;
-use yoku_core::db::operations::{
-    create_workout_session, delete_workout_session, delete_workout_set, get_all_exercises,
-    get_all_workout_sessions, get_exercise, get_or_create_exercise, get_sets_for_session,
+use yoku::db::models::{DisplayableSet, NewExerciseMuscle, WorkoutSession, WorkoutSet};
+use yoku::db::operations::{
+    add_workout_set, create_request_string_for_username, create_workout_session,
+    delete_workout_session, delete_workout_set, estimate_one_rep_max, get_all_exercises,
+    get_all_workout_sessions, get_exercise, get_or_create_exercise, get_or_create_muscle,
+    get_session_volume, get_sets_for_session, get_workout_session, link_exercise_to_muscle,
+    set_workout_set_created_at,
 };
-use yoku_core::graph::GraphManager;
-use yoku_core::llm::{
-    generate_exercise_to_equipment_and_muscles, LlmInterface, ParsedSet, PromptBuilder,
-    PromptContext,
+use yoku::db::{get_db_path, init_database};
+use yoku::graph::GraphManager;
+use yoku::llm::{
+    LlmInterface, ParsedSet, PromptBuilder, PromptContext,
+    generate_exercise_to_equipment_and_muscles,
 };
-use yoku_core::session::Session;
+use yoku::recommendation::GraphManager as RecGraphManager;
+use yoku::session::{GraphBackend, Session, SessionConfig};
+use yoku::uniffi_interface::modifications::{Modification, ModificationType};
 
 #[derive(Parser, Debug)]
 #[command(version, about = "Yoku - Workout Tracker CLI", long_about = None)]
@@ -26,6 +37,10 @@ struct Cli {
 
     #[arg(short, long)]
     model: Option<String>,
+
+    /// Output format for list commands.
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
 }
 
 #[derive(Subcommand, Debug)]
@@ -63,6 +78,33 @@ enum Commands {
         #[arg(short, long, default_value_t = 50)]
         limit: i64,
     },
+
+    /// Print the recommendation graph as GraphViz DOT.
+    DumpRecGraph {
+        graph_path: String,
+    },
+
+    Export {
+        session_id: String,
+        #[arg(long)]
+        pretty: bool,
+    },
+
+    Import {
+        path: String,
+        #[arg(long)]
+        session_name: Option<String>,
+    },
+
+    /// Print set, tonnage and estimated 1RM totals for one session, or for all sessions.
+    Stats {
+        session_id: Option<String>,
+    },
+
+    /// Log sets interactively in natural language; `:sets`, `:undo` and `:quit` are built in.
+    Repl {
+        session_id: String,
+    },
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -81,16 +123,31 @@ impl fmt::Display for ParserType {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Text => write!(f, "text"),
+            OutputFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
     let cli = Cli::parse();
 
     let parser: Option<LlmInterface> = match cli.command {
-        Commands::AddSet { .. } | Commands::SuggestExerciseLinks { .. } => {
+        Commands::AddSet { .. } | Commands::SuggestExerciseLinks { .. } | Commands::Repl { .. } => {
             let llm = match cli.parser {
                 ParserType::Ollama => LlmInterface::new_ollama(cli.model.clone()).await?,
-                ParserType::OpenAI => LlmInterface::new_openai(cli.model.clone()).await?,
+                ParserType::OpenAI => LlmInterface::new_openai(None, cli.model.clone()).await?,
             };
             Some(llm)
         }
@@ -100,22 +157,25 @@ async fn main() -> Result<()> {
     let prompt_context = PromptContext::default();
     let prompt_builder = PromptBuilder::new(prompt_context);
 
+    let pool = open_pool().await?;
+
     match cli.command {
-        Commands::List {} => cmd_list().await?,
-        Commands::Create { name } => cmd_create(name).await?,
-        Commands::Delete { id } => cmd_delete(&id).await?,
-        Commands::ListSets { session_id } => cmd_list_sets(&session_id).await?,
+        Commands::List {} => cmd_list(&pool, cli.format).await?,
+        Commands::Create { name } => cmd_create(&pool, name).await?,
+        Commands::Delete { id } => cmd_delete(&pool, &id).await?,
+        Commands::ListSets { session_id } => cmd_list_sets(&pool, &session_id, cli.format).await?,
         Commands::AddSet { session_id, input } => {
             if let Some(p) = parser {
-                cmd_add_set(&session_id, &input, p).await?
+                let sess = open_session().await?;
+                cmd_add_set(&sess, &session_id, &input, p).await?
             } else {
                 eprintln!("Parser not initialized");
             }
         }
-        Commands::DeleteSet { set_id } => cmd_delete_set(&set_id).await?,
+        Commands::DeleteSet { set_id } => cmd_delete_set(&pool, &set_id).await?,
         Commands::SuggestExerciseLinks { name } => {
             if let Some(p) = parser {
-                cmd_suggest_exercise_links(&name, &p, &prompt_builder).await?
+                cmd_suggest_exercise_links(&pool, &name, &p, &prompt_builder, cli.format).await?
             } else {
                 eprintln!("Parser not initialized");
             }
@@ -125,27 +185,89 @@ async fn main() -> Result<()> {
             println!("Dumping graph with limit {}", limit);
             gm.dump_graph(limit).await?;
         }
+        Commands::DumpRecGraph { graph_path } => {
+            let gm = RecGraphManager::new(&graph_path)?;
+            print!("{}", gm.export_dot()?);
+        }
+        Commands::Export { session_id, pretty } => cmd_export(&pool, &session_id, pretty).await?,
+        Commands::Import { path, session_name } => cmd_import(&pool, &path, session_name).await?,
+        Commands::Stats { session_id } => cmd_stats(&pool, session_id.as_deref()).await?,
+        Commands::Repl { session_id } => {
+            if let Some(p) = parser {
+                let sess = open_session().await?;
+                cmd_repl(&sess, &session_id, p).await?
+            } else {
+                eprintln!("Parser not initialized");
+            }
+        }
     }
 
     Ok(())
 }
 
-async fn cmd_list() -> Result<()> {
-    let sessions = get_all_workout_sessions().await?;
-    if sessions.is_empty() {
-        println!("No workout sessions found.");
-        return Ok(());
+/// Opens (creating if needed) and migrates the database named by `DATABASE_URL`.
+async fn open_pool() -> Result<SqlitePool> {
+    let options = SqliteConnectOptions::new()
+        .filename(get_db_path().await)
+        .create_if_missing(true);
+    let pool = SqlitePool::connect_with(options).await?;
+    init_database(&pool).await?;
+    Ok(pool)
+}
+
+/// Opens a session on the database named by `DATABASE_URL`, for the commands that log sets
+/// through it. The CLI keeps no recommendation graph of its own, so the session's graph is
+/// held in memory. Sets are parsed with the CLI's own parser, so the session keeps the default
+/// model.
+async fn open_session() -> Result<Session> {
+    Session::new_with_config(
+        get_db_path().await,
+        None,
+        SessionConfig {
+            graph_backend: GraphBackend::Memory,
+            ..SessionConfig::default()
+        },
+    )
+    .await
+}
+
+#[derive(Debug, Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize, PartialEq))]
+struct ListedSession {
+    id: i64,
+    name: Option<String>,
+}
+
+fn render_sessions(sessions: &[ListedSession], format: OutputFormat) -> Result<String> {
+    if format == OutputFormat::Json {
+        return Ok(serde_json::to_string(sessions)?);
     }
-    for s in sessions {
-        let name = s.name.unwrap_or_else(|| "(unnamed)".to_string());
-        println!("{}  —  {}", s.id, name);
+    if sessions.is_empty() {
+        return Ok("No workout sessions found.".to_string());
     }
+    let lines: Vec<String> = sessions
+        .iter()
+        .map(|s| format!("{}  —  {}", s.id, s.name.as_deref().unwrap_or("(unnamed)")))
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+async fn cmd_list(pool: &SqlitePool, format: OutputFormat) -> Result<()> {
+    let sessions: Vec<ListedSession> = get_all_workout_sessions(pool, None, None, None, None, None)
+        .await?
+        .into_iter()
+        .map(|s| ListedSession {
+            id: s.id,
+            name: s.name,
+        })
+        .collect();
+    println!("{}", render_sessions(&sessions, format)?);
     Ok(())
 }
 
-async fn cmd_create(name: Option<String>) -> Result<()> {
-    // create_workout_session(user_id, name, notes, duration_seconds, status)
-    let ws = create_workout_session(None, name, None, None, None).await?;
+async fn cmd_create(pool: &SqlitePool, name: Option<String>) -> Result<()> {
+    // create_workout_session(pool, user_id, name, notes, duration_seconds, status)
+    let ws = create_workout_session(pool, None, name, None, None, None).await?;
     println!(
         "Created workout session: {} (id {})",
         ws.name.unwrap_or_default(),
@@ -154,45 +276,209 @@ async fn cmd_create(name: Option<String>) -> Result<()> {
     Ok(())
 }
 
-async fn cmd_delete(id: &i64) -> Result<()> {
-    // Parse the provided UUID string and pass its 16-byte representation to the DB layer.
-    let deleted = delete_workout_session(*id).await?;
+async fn cmd_delete(pool: &SqlitePool, id: &i64) -> Result<()> {
+    let deleted = delete_workout_session(pool, *id).await?;
     println!("Deleted {} rows for session {}", deleted, id);
     Ok(())
 }
 
-async fn cmd_list_sets(session_id: &i64) -> Result<()> {
-    // Parse into a Uuid then convert to 16-byte Vec<u8> for DB calls
-    let sets = get_sets_for_session(*session_id).await?;
+async fn cmd_list_sets(pool: &SqlitePool, session_id: &i64, format: OutputFormat) -> Result<()> {
+    let sets = get_sets_for_session(pool, *session_id, None, None).await?;
+    if format == OutputFormat::Json {
+        let mut listed = Vec::with_capacity(sets.len());
+        for set in sets {
+            let exercise = get_exercise(pool, set.exercise_id).await?;
+            listed.push(ExportedSet {
+                set,
+                exercise_name: exercise.name,
+            });
+        }
+        println!("{}", serde_json::to_string(&listed)?);
+        return Ok(());
+    }
     if sets.is_empty() {
         println!("No sets for session {}", session_id);
         return Ok(());
     }
     for s in sets {
-        // s.exercise_id is Vec<u8>, clone and pass to get_exercise
-        let exercise = get_exercise(s.exercise_id.clone()).await?;
+        let exercise = get_exercise(pool, s.exercise_id).await?;
         let display = DisplayableSet::new(s, exercise.name);
         println!("{}", display);
     }
     Ok(())
 }
 
-async fn cmd_add_set(session_id: &i64, input: &str, parser: LlmInterface) -> Result<()> {
-    let sess = Session::new_blank().await;
+#[derive(Serialize)]
+struct ExportedSet {
+    #[serde(flatten)]
+    set: WorkoutSet,
+    exercise_name: String,
+}
+
+#[derive(Serialize)]
+struct SessionExport {
+    session: WorkoutSession,
+    sets: Vec<ExportedSet>,
+}
+
+async fn cmd_export(pool: &SqlitePool, session_id: &str, pretty: bool) -> Result<()> {
+    let Ok(id) = session_id.trim().parse::<i64>() else {
+        eprintln!("Invalid session id: {}", session_id);
+        std::process::exit(1);
+    };
+    let session = match get_workout_session(pool, id).await {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Could not load session {}: {}", id, e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut sets = Vec::new();
+    for set in get_sets_for_session(pool, id, None, None).await? {
+        let exercise = get_exercise(pool, set.exercise_id).await?;
+        sets.push(ExportedSet {
+            set,
+            exercise_name: exercise.name,
+        });
+    }
+
+    let export = SessionExport { session, sets };
+    let json = if pretty {
+        serde_json::to_string_pretty(&export)?
+    } else {
+        serde_json::to_string(&export)?
+    };
+    println!("{}", json);
+    Ok(())
+}
+
+struct ImportRow {
+    exercise: String,
+    weight: f64,
+    reps: i64,
+    rpe: Option<f64>,
+    timestamp: i64,
+}
+
+/// Parses one `exercise,weight,reps,rpe,date` row; `rpe` may be empty and `date` is
+/// `YYYY-MM-DD`.
+fn parse_import_row(line: &str) -> std::result::Result<ImportRow, String> {
+    let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+    let [exercise, weight, reps, rpe, date] = fields.as_slice() else {
+        return Err(format!("expected 5 columns, found {}", fields.len()));
+    };
+    if exercise.is_empty() {
+        return Err("missing exercise".to_string());
+    }
+    let weight = weight
+        .parse::<f64>()
+        .map_err(|_| format!("invalid weight '{}'", weight))?;
+    let reps = reps
+        .parse::<i64>()
+        .map_err(|_| format!("invalid reps '{}'", reps))?;
+    let rpe = if rpe.is_empty() {
+        None
+    } else {
+        Some(
+            rpe.parse::<f64>()
+                .map_err(|_| format!("invalid rpe '{}'", rpe))?,
+        )
+    };
+    let timestamp = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{}'", date))?
+        .and_hms_opt(0, 0, 0)
+        .map(|dt| dt.and_utc().timestamp())
+        .ok_or_else(|| format!("invalid date '{}'", date))?;
+
+    Ok(ImportRow {
+        exercise: exercise.to_string(),
+        weight,
+        reps,
+        rpe,
+        timestamp,
+    })
+}
+
+async fn import_row(pool: &SqlitePool, session_id: i64, line: &str, row: &ImportRow) -> Result<()> {
+    let exercise = get_or_create_exercise(pool, &row.exercise).await?;
+    let request = create_request_string_for_username(pool, "cli", line.to_string()).await?;
+    let set = add_workout_set(
+        pool,
+        &session_id,
+        &exercise.id,
+        &request.id,
+        &row.weight,
+        &row.reps,
+        row.rpe,
+    )
+    .await?;
+    set_workout_set_created_at(pool, set.id, row.timestamp).await?;
+    Ok(())
+}
+
+async fn cmd_import(pool: &SqlitePool, path: &str, session_name: Option<String>) -> Result<()> {
+    let contents = std::fs::read_to_string(path)?;
+    let name = session_name.unwrap_or_else(|| format!("Import from {}", path));
+
+    let existing = get_all_workout_sessions(pool, None, None, None, None, None)
+        .await?
+        .into_iter()
+        .find(|s| s.name.as_deref() == Some(name.as_str()));
+    let session = match existing {
+        Some(session) => session,
+        None => create_workout_session(pool, None, Some(name), None, None, None).await?,
+    };
+
+    let mut succeeded = 0;
+    let mut failed = 0;
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        if line.trim().is_empty() || (index == 0 && line.trim().starts_with("exercise")) {
+            continue;
+        }
+        let result = match parse_import_row(line) {
+            Ok(row) => import_row(pool, session.id, line, &row)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => succeeded += 1,
+            Err(e) => {
+                eprintln!("line {}: {}", line_number, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!(
+        "Imported {} rows into session {} ({} failed)",
+        succeeded, session.id, failed
+    );
+    Ok(())
+}
+
+async fn cmd_add_set(
+    sess: &Session,
+    session_id: &i64,
+    input: &str,
+    parser: LlmInterface,
+) -> Result<()> {
     sess.set_workout_id(*session_id).await?;
 
     // Fetch known exercises to help the parser be consistent
-    let exercises = get_all_exercises().await?;
+    let exercises = get_all_exercises(&sess.db_pool).await?;
     let known_exs: Vec<String> = exercises.into_iter().map(|e| e.name).collect();
 
     // Build prompt context and builder (inject known exercises; examples may be provided from seed later)
-    let ctx = yoku_core::llm::PromptContext {
+    let ctx = yoku::llm::PromptContext {
         known_exercises: known_exs.clone(),
         ..Default::default()
     };
-    let builder = yoku_core::llm::PromptBuilder::new(ctx);
+    let builder = yoku::llm::PromptBuilder::new(ctx);
 
-    let parsed: ParsedSet = yoku_core::llm::parse_set_string(&parser, &builder, input).await?;
+    let parsed: ParsedSet = yoku::llm::parse_set_string(&parser, &builder, input).await?;
 
     // Let the session handle adding the set (it will create/get exercises as needed)
     sess.add_set_from_parsed(&parsed).await?;
@@ -201,20 +487,74 @@ async fn cmd_add_set(session_id: &i64, input: &str, parser: LlmInterface) -> Res
     Ok(())
 }
 
-async fn cmd_delete_set(set_id: &i64) -> Result<()> {
-    let deleted = delete_workout_set(*set_id).await?;
+async fn cmd_delete_set(pool: &SqlitePool, set_id: &i64) -> Result<()> {
+    let deleted = delete_workout_set(pool, *set_id).await?;
     println!("Deleted {} rows for set {}", deleted, set_id);
     Ok(())
 }
 
+/// One suggested link, as printed by `suggest-exercise-links --format json`.
+#[derive(Serialize)]
+struct SuggestedLink {
+    kind: &'static str,
+    target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    strength: Option<f32>,
+}
+
 async fn cmd_suggest_exercise_links(
+    pool: &SqlitePool,
     name: &str,
     llm: &LlmInterface,
     builder: &PromptBuilder,
+    format: OutputFormat,
 ) -> Result<()> {
-    let exercise = get_or_create_exercise(name).await?;
+    let exercise = get_or_create_exercise(pool, name).await?;
     let (equip_links, muscle_links, variant_links) =
         generate_exercise_to_equipment_and_muscles(llm, builder, &exercise.name).await?;
+    for (muscle, link_type, _) in &muscle_links {
+        let muscle = get_or_create_muscle(pool, muscle).await?;
+        link_exercise_to_muscle(
+            pool,
+            &NewExerciseMuscle {
+                exercise_id: exercise.id,
+                muscle_id: muscle.id,
+                relation_type: link_type.trim().to_lowercase(),
+            },
+        )
+        .await?;
+    }
+    if format == OutputFormat::Json {
+        let plain = |kind, target| SuggestedLink {
+            kind,
+            target,
+            link_type: None,
+            strength: None,
+        };
+        let links: Vec<SuggestedLink> = equip_links
+            .into_iter()
+            .map(|target| plain("equipment", target))
+            .chain(
+                muscle_links
+                    .into_iter()
+                    .map(|(target, link_type, strength)| SuggestedLink {
+                        kind: "muscle",
+                        target,
+                        link_type: Some(link_type),
+                        strength: Some(strength),
+                    }),
+            )
+            .chain(
+                variant_links
+                    .into_iter()
+                    .map(|target| plain("variant", target)),
+            )
+            .collect();
+        println!("{}", serde_json::to_string(&links)?);
+        return Ok(());
+    }
     for suggestion in equip_links {
         println!("Suggested equipment link: {}", suggestion);
     }
@@ -229,3 +569,256 @@ async fn cmd_suggest_exercise_links(
     }
     Ok(())
 }
+
+async fn cmd_stats(pool: &SqlitePool, session_id: Option<&str>) -> Result<()> {
+    let session_ids: Vec<i64> = match session_id {
+        Some(raw) => {
+            let Ok(id) = raw.trim().parse::<i64>() else {
+                eprintln!("Invalid session id: {}", raw);
+                std::process::exit(1);
+            };
+            if let Err(e) = get_workout_session(pool, id).await {
+                eprintln!("Could not load session {}: {}", id, e);
+                std::process::exit(1);
+            }
+            vec![id]
+        }
+        None => get_all_workout_sessions(pool, None, None, None, None, None)
+            .await?
+            .into_iter()
+            .map(|s| s.id)
+            .collect(),
+    };
+
+    let mut sets = Vec::new();
+    let mut tonnage = 0.0;
+    for id in &session_ids {
+        sets.extend(get_sets_for_session(pool, *id, None, None).await?);
+        tonnage += get_session_volume(pool, *id).await?;
+    }
+    if sets.is_empty() {
+        match session_id {
+            Some(raw) => println!("No sets for session {}", raw.trim()),
+            None => println!("No sets logged yet."),
+        }
+        return Ok(());
+    }
+
+    // Best Epley estimate per exercise, keyed by name so the table is alphabetical.
+    let mut best_1rm: BTreeMap<String, f64> = BTreeMap::new();
+    let mut names: HashMap<i64, String> = HashMap::new();
+    for set in &sets {
+        if !names.contains_key(&set.exercise_id) {
+            let exercise = get_exercise(pool, set.exercise_id).await?;
+            names.insert(set.exercise_id, exercise.name);
+        }
+        let estimate = estimate_one_rep_max(set.weight, set.reps);
+        let best = best_1rm
+            .entry(names[&set.exercise_id].clone())
+            .or_insert(estimate);
+        *best = best.max(estimate);
+    }
+
+    println!("{:<20} {:>10}", "Sets", sets.len());
+    println!("{:<20} {:>10.1}", "Tonnage", tonnage);
+    println!("{:<20} {:>10}", "Exercises", best_1rm.len());
+    println!();
+
+    let width = best_1rm
+        .keys()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Exercise".len());
+    println!("{:<width$}  {:>10}", "Exercise", "Est. 1RM", width = width);
+    println!("{}", "-".repeat(width + 12));
+    for (name, estimate) in &best_1rm {
+        println!("{:<width$}  {:>10.1}", name, estimate, width = width);
+    }
+    Ok(())
+}
+
+/// One REPL input's changes plus the sets as they were beforehand, so `:undo` can revert it.
+struct ReplUndo {
+    modifications: Vec<Modification>,
+    previous_sets: Vec<WorkoutSet>,
+}
+
+async fn cmd_repl(sess: &Session, session_id: &str, parser: LlmInterface) -> Result<()> {
+    let Ok(id) = session_id.trim().parse::<i64>() else {
+        eprintln!("Invalid session id: {}", session_id);
+        std::process::exit(1);
+    };
+    if let Err(e) = sess.set_workout_id(id).await {
+        eprintln!("Could not load session {}: {}", id, e);
+        std::process::exit(1);
+    }
+
+    println!("Logging to session {}. Commands: :sets, :undo, :quit", id);
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut last: Option<ReplUndo> = None;
+    loop {
+        print!("yoku> ");
+        std::io::stdout().flush()?;
+        // Ctrl-D closes stdin and ends the loop like `:quit`.
+        let Some(line) = lines.next() else {
+            println!();
+            break;
+        };
+        let line = line?;
+        let input = line.trim();
+        match input {
+            "" => continue,
+            ":quit" => break,
+            ":sets" => print_repl_sets(sess).await?,
+            ":undo" => match last.take() {
+                Some(undo) => undo_repl_input(sess, undo).await?,
+                None => println!("Nothing to undo"),
+            },
+            _ => {
+                let previous_sets = sess.get_all_sets().await?;
+                match sess
+                    .process_user_input_with(&parser, input, None, vec![])
+                    .await
+                {
+                    Ok(modifications) => {
+                        if modifications.is_empty() {
+                            println!("No changes");
+                        }
+                        for m in &modifications {
+                            println!("{:?}: sets {:?}", m.modification_type, m.set_ids);
+                        }
+                        last = Some(ReplUndo {
+                            modifications,
+                            previous_sets,
+                        });
+                    }
+                    Err(e) => eprintln!("Error: {}", e),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn print_repl_sets(sess: &Session) -> Result<()> {
+    let sets = sess.get_all_sets().await?;
+    if sets.is_empty() {
+        println!("No sets yet");
+        return Ok(());
+    }
+    let names: HashMap<i64, String> = sess
+        .get_all_exercises()
+        .await?
+        .into_iter()
+        .map(|e| (e.id, e.name))
+        .collect();
+    for s in sets {
+        let name = names.get(&s.exercise_id).cloned().unwrap_or_default();
+        println!("{}", DisplayableSet::new(s, name));
+    }
+    Ok(())
+}
+
+/// Deletes sets the input added and puts back, as they were before the input, sets it edited
+/// or removed.
+async fn undo_repl_input(sess: &Session, undo: ReplUndo) -> Result<()> {
+    for m in undo.modifications.iter().rev() {
+        match m.modification_type {
+            ModificationType::SetAdded | ModificationType::ExerciseAdded => {
+                for set_id in &m.set_ids {
+                    sess.delete_set(*set_id).await?;
+                    println!("Removed set {}", set_id);
+                }
+            }
+            ModificationType::SetModified | ModificationType::SetRemoved => {
+                for set_id in &m.set_ids {
+                    let Some(old) = undo.previous_sets.iter().find(|s| s.id == *set_id) else {
+                        continue;
+                    };
+                    sess.restore_set(old).await?;
+                    println!("Restored set {}", set_id);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_session_list_round_trips() {
+        let sessions = vec![
+            ListedSession {
+                id: 1,
+                name: Some("Push day".to_string()),
+            },
+            ListedSession { id: 2, name: None },
+        ];
+
+        let json = render_sessions(&sessions, OutputFormat::Json).unwrap();
+        let parsed: Vec<ListedSession> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, sessions);
+        assert_eq!(render_sessions(&[], OutputFormat::Json).unwrap(), "[]");
+
+        let text = render_sessions(&sessions, OutputFormat::Text).unwrap();
+        assert_eq!(text, "1  —  Push day\n2  —  (unnamed)");
+    }
+
+    #[test]
+    fn import_row_parses_columns() {
+        let row = parse_import_row("Squat, 100, 5, , 2024-03-01").unwrap();
+        assert_eq!(row.exercise, "Squat");
+        assert_eq!(row.weight, 100.0);
+        assert_eq!(row.reps, 5);
+        assert_eq!(row.rpe, None);
+        assert_eq!(row.timestamp, 1709251200);
+
+        assert_eq!(
+            parse_import_row("Bench,80,5,8.5,2024-03-01").unwrap().rpe,
+            Some(8.5)
+        );
+        assert!(parse_import_row("Squat,100,5,2024-03-01").is_err());
+        assert!(parse_import_row(",100,5,,2024-03-01").is_err());
+        assert!(parse_import_row("Squat,heavy,5,,2024-03-01").is_err());
+        assert!(parse_import_row("Squat,100,5,,03/01/2024").is_err());
+    }
+
+    #[tokio::test]
+    async fn import_backdates_sets_and_skips_bad_rows() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        init_database(&pool).await.unwrap();
+        let path = std::env::temp_dir().join(format!("yoku-import-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "exercise,weight,reps,rpe,date\nSquat,100,5,8,2024-03-01\nSquat,oops,5,,2024-03-01\n",
+        )
+        .unwrap();
+
+        cmd_import(&pool, path.to_str().unwrap(), Some("History".to_string()))
+            .await
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let sessions = get_all_workout_sessions(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let session = sessions
+            .iter()
+            .find(|s| s.name.as_deref() == Some("History"))
+            .unwrap();
+        let sets = get_sets_for_session(&pool, session.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].weight, 100.0);
+        assert_eq!(sets[0].rpe, Some(8.0));
+        assert_eq!(sets[0].created_at, 1709251200);
+        let exercise = get_exercise(&pool, sets[0].exercise_id).await.unwrap();
+        assert_eq!(exercise.name, "Squat");
+    }
+}
@@ -2,11 +2,14 @@ pub mod models;
 pub mod operations;
 
 use anyhow::Result;
+use futures::future::BoxFuture;
 use log::{debug, info};
 use sqlx::SqlitePool;
 use std::env;
 use tokio::sync::OnceCell;
 
+use self::models::ExperienceLevel;
+
 static DB_PATH: OnceCell<String> = OnceCell::const_new();
 
 #[inline(always)]
@@ -29,12 +32,25 @@ pub async fn drop_all_tables(pool: &SqlitePool) -> Result<()> {
     sqlx::query("DELETE FROM request_strings")
         .execute(pool)
         .await?;
+    sqlx::query("DELETE FROM workout_templates")
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM bodyweight_entries")
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM user_equipment")
+        .execute(pool)
+        .await?;
     sqlx::query("DELETE FROM users").execute(pool).await?;
     sqlx::query("DELETE FROM exercise_muscles")
         .execute(pool)
         .await?;
+    sqlx::query("DELETE FROM exercise_equipment")
+        .execute(pool)
+        .await?;
     sqlx::query("DELETE FROM exercises").execute(pool).await?;
     sqlx::query("DELETE FROM muscles").execute(pool).await?;
+    sqlx::query("DELETE FROM equipment").execute(pool).await?;
     Ok(())
 }
 
@@ -47,15 +63,169 @@ pub async fn set_db_path(path: &str) -> Result<()> {
 struct Migration {
     name: &'static str,
     up_sql: &'static str,
+    down_sql: &'static str,
+    /// Runs after `up_sql`, for data changes that follow rules kept in Rust.
+    backfill: Option<fn(&SqlitePool) -> BoxFuture<'_, Result<()>>>,
 }
 
-const MIGRATION_2025_11_11_220309_0000_SETUP_TABLES: &str =
+const MIGRATION_2025_11_11_220309_0000_SETUP_TABLES_UP: &str =
     include_str!("../../../migrations/2025-11-11-220309-0000_setup_tables/up.sql");
+const MIGRATION_2025_11_11_220309_0000_SETUP_TABLES_DOWN: &str =
+    include_str!("../../../migrations/2025-11-11-220309-0000_setup_tables/down.sql");
+
+const MIGRATION_2026_10_16_090000_0000_EXERCISE_FAVORITES_UP: &str =
+    include_str!("../../../migrations/2026-10-16-090000-0000_exercise_favorites/up.sql");
+const MIGRATION_2026_10_16_090000_0000_EXERCISE_FAVORITES_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-090000-0000_exercise_favorites/down.sql");
 
-const MIGRATIONS: &[Migration] = &[Migration {
-    name: "2025-11-11-220309-0000_setup_tables",
-    up_sql: MIGRATION_2025_11_11_220309_0000_SETUP_TABLES,
-}];
+const MIGRATION_2026_10_16_093000_0000_EXERCISE_EXPERIENCE_LEVEL_UP: &str =
+    include_str!("../../../migrations/2026-10-16-093000-0000_exercise_experience_level/up.sql");
+const MIGRATION_2026_10_16_093000_0000_EXERCISE_EXPERIENCE_LEVEL_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-093000-0000_exercise_experience_level/down.sql");
+
+const MIGRATION_2026_10_16_100000_0000_SET_TAGS_UP: &str =
+    include_str!("../../../migrations/2026-10-16-100000-0000_set_tags/up.sql");
+const MIGRATION_2026_10_16_100000_0000_SET_TAGS_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-100000-0000_set_tags/down.sql");
+
+const MIGRATION_2026_10_16_110000_0000_USER_EQUIPMENT_UP: &str =
+    include_str!("../../../migrations/2026-10-16-110000-0000_user_equipment/up.sql");
+const MIGRATION_2026_10_16_110000_0000_USER_EQUIPMENT_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-110000-0000_user_equipment/down.sql");
+
+const MIGRATION_2026_10_16_120000_0000_SET_REST_STYLE_UP: &str =
+    include_str!("../../../migrations/2026-10-16-120000-0000_set_rest_style/up.sql");
+const MIGRATION_2026_10_16_120000_0000_SET_REST_STYLE_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-120000-0000_set_rest_style/down.sql");
+
+const MIGRATION_2026_10_16_130000_0000_SET_DURATION_DISTANCE_UP: &str =
+    include_str!("../../../migrations/2026-10-16-130000-0000_set_duration_distance/up.sql");
+const MIGRATION_2026_10_16_130000_0000_SET_DURATION_DISTANCE_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-130000-0000_set_duration_distance/down.sql");
+const MIGRATION_2026_10_16_140000_0000_SET_SUPERSET_GROUP_UP: &str =
+    include_str!("../../../migrations/2026-10-16-140000-0000_set_superset_group/up.sql");
+const MIGRATION_2026_10_16_140000_0000_SET_SUPERSET_GROUP_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-140000-0000_set_superset_group/down.sql");
+const MIGRATION_2026_10_16_150000_0000_EXERCISE_ALIASES_UP: &str =
+    include_str!("../../../migrations/2026-10-16-150000-0000_exercise_aliases/up.sql");
+const MIGRATION_2026_10_16_150000_0000_EXERCISE_ALIASES_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-150000-0000_exercise_aliases/down.sql");
+const MIGRATION_2026_10_16_160000_0000_WORKOUT_TEMPLATES_UP: &str =
+    include_str!("../../../migrations/2026-10-16-160000-0000_workout_templates/up.sql");
+const MIGRATION_2026_10_16_160000_0000_WORKOUT_TEMPLATES_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-160000-0000_workout_templates/down.sql");
+const MIGRATION_2026_10_16_170000_0000_BODYWEIGHT_ENTRIES_UP: &str =
+    include_str!("../../../migrations/2026-10-16-170000-0000_bodyweight_entries/up.sql");
+const MIGRATION_2026_10_16_170000_0000_BODYWEIGHT_ENTRIES_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-170000-0000_bodyweight_entries/down.sql");
+const MIGRATION_2026_10_16_180000_0000_WORKOUT_SOFT_DELETE_UP: &str =
+    include_str!("../../../migrations/2026-10-16-180000-0000_workout_soft_delete/up.sql");
+const MIGRATION_2026_10_16_180000_0000_WORKOUT_SOFT_DELETE_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-180000-0000_workout_soft_delete/down.sql");
+const MIGRATION_2026_10_16_190000_0000_SET_AOI_UP: &str =
+    include_str!("../../../migrations/2026-10-16-190000-0000_set_aoi/up.sql");
+const MIGRATION_2026_10_16_190000_0000_SET_AOI_DOWN: &str =
+    include_str!("../../../migrations/2026-10-16-190000-0000_set_aoi/down.sql");
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        name: "2025-11-11-220309-0000_setup_tables",
+        up_sql: MIGRATION_2025_11_11_220309_0000_SETUP_TABLES_UP,
+        down_sql: MIGRATION_2025_11_11_220309_0000_SETUP_TABLES_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-090000-0000_exercise_favorites",
+        up_sql: MIGRATION_2026_10_16_090000_0000_EXERCISE_FAVORITES_UP,
+        down_sql: MIGRATION_2026_10_16_090000_0000_EXERCISE_FAVORITES_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-093000-0000_exercise_experience_level",
+        up_sql: MIGRATION_2026_10_16_093000_0000_EXERCISE_EXPERIENCE_LEVEL_UP,
+        down_sql: MIGRATION_2026_10_16_093000_0000_EXERCISE_EXPERIENCE_LEVEL_DOWN,
+        backfill: Some(backfill_experience_levels),
+    },
+    Migration {
+        name: "2026-10-16-100000-0000_set_tags",
+        up_sql: MIGRATION_2026_10_16_100000_0000_SET_TAGS_UP,
+        down_sql: MIGRATION_2026_10_16_100000_0000_SET_TAGS_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-110000-0000_user_equipment",
+        up_sql: MIGRATION_2026_10_16_110000_0000_USER_EQUIPMENT_UP,
+        down_sql: MIGRATION_2026_10_16_110000_0000_USER_EQUIPMENT_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-120000-0000_set_rest_style",
+        up_sql: MIGRATION_2026_10_16_120000_0000_SET_REST_STYLE_UP,
+        down_sql: MIGRATION_2026_10_16_120000_0000_SET_REST_STYLE_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-130000-0000_set_duration_distance",
+        up_sql: MIGRATION_2026_10_16_130000_0000_SET_DURATION_DISTANCE_UP,
+        down_sql: MIGRATION_2026_10_16_130000_0000_SET_DURATION_DISTANCE_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-140000-0000_set_superset_group",
+        up_sql: MIGRATION_2026_10_16_140000_0000_SET_SUPERSET_GROUP_UP,
+        down_sql: MIGRATION_2026_10_16_140000_0000_SET_SUPERSET_GROUP_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-150000-0000_exercise_aliases",
+        up_sql: MIGRATION_2026_10_16_150000_0000_EXERCISE_ALIASES_UP,
+        down_sql: MIGRATION_2026_10_16_150000_0000_EXERCISE_ALIASES_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-160000-0000_workout_templates",
+        up_sql: MIGRATION_2026_10_16_160000_0000_WORKOUT_TEMPLATES_UP,
+        down_sql: MIGRATION_2026_10_16_160000_0000_WORKOUT_TEMPLATES_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-170000-0000_bodyweight_entries",
+        up_sql: MIGRATION_2026_10_16_170000_0000_BODYWEIGHT_ENTRIES_UP,
+        down_sql: MIGRATION_2026_10_16_170000_0000_BODYWEIGHT_ENTRIES_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-180000-0000_workout_soft_delete",
+        up_sql: MIGRATION_2026_10_16_180000_0000_WORKOUT_SOFT_DELETE_UP,
+        down_sql: MIGRATION_2026_10_16_180000_0000_WORKOUT_SOFT_DELETE_DOWN,
+        backfill: None,
+    },
+    Migration {
+        name: "2026-10-16-190000-0000_set_aoi",
+        up_sql: MIGRATION_2026_10_16_190000_0000_SET_AOI_UP,
+        down_sql: MIGRATION_2026_10_16_190000_0000_SET_AOI_DOWN,
+        backfill: None,
+    },
+];
+
+/// Gives existing exercises the level a new exercise of the same name would get from
+/// [`ExperienceLevel::default_for_exercise`].
+fn backfill_experience_levels(pool: &SqlitePool) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        let exercises = sqlx::query_as::<_, (i64, String)>("SELECT id, name FROM exercises")
+            .fetch_all(pool)
+            .await?;
+        for (id, name) in exercises {
+            let level = ExperienceLevel::default_for_exercise(&name);
+            sqlx::query("UPDATE exercises SET experience_level = ?1 WHERE id = ?2")
+                .bind(level.as_str())
+                .bind(id)
+                .execute(pool)
+                .await?;
+        }
+        Ok(())
+    })
+}
 
 async fn init_migrations_table(pool: &SqlitePool) -> Result<()> {
     sqlx::query(
@@ -86,21 +256,116 @@ async fn mark_migration_applied(pool: &SqlitePool, migration_name: &str) -> Resu
     Ok(())
 }
 
+/// Splits a migration into statements on `;`, except inside quoted strings or identifiers,
+/// comments, `CASE ... END` expressions and the `BEGIN ... END` body of a `CREATE TRIGGER`.
+/// Comments are dropped.
 fn parse_sql_statements(sql: &str) -> Vec<String> {
-    sql.lines()
-        .filter(|line| {
-            let trimmed = line.trim();
-            !trimmed.is_empty() && !trimmed.starts_with("--")
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
-        .split(';')
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect()
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut first_word: Option<String> = None;
+    let mut in_trigger = false;
+    let mut depth = 0usize;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                current.push(c);
+                while let Some(q) = chars.next() {
+                    current.push(q);
+                    if q == c {
+                        if chars.peek() == Some(&c) {
+                            current.push(chars.next().unwrap());
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.by_ref().take_while(|&n| n != '\n').for_each(drop);
+                current.push('\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for n in chars.by_ref() {
+                    if previous == '*' && n == '/' {
+                        break;
+                    }
+                    previous = n;
+                }
+                current.push(' ');
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut word = String::from(c);
+                while let Some(n) = chars.next_if(|n| n.is_ascii_alphanumeric() || *n == '_') {
+                    word.push(n);
+                }
+                current.push_str(&word);
+
+                let word = word.to_ascii_uppercase();
+                match word.as_str() {
+                    "TRIGGER" if first_word.as_deref() == Some("CREATE") => in_trigger = true,
+                    "BEGIN" if in_trigger => depth += 1,
+                    "CASE" => depth += 1,
+                    "END" => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                first_word.get_or_insert(word);
+            }
+            ';' if depth == 0 => {
+                let statement = current.trim();
+                if !statement.is_empty() {
+                    statements.push(statement.to_string());
+                }
+                current.clear();
+                first_word = None;
+                in_trigger = false;
+            }
+            c => current.push(c),
+        }
+    }
+
+    let statement = current.trim();
+    if !statement.is_empty() {
+        statements.push(statement.to_string());
+    }
+    statements
+}
+
+async fn execute_migration_sql(pool: &SqlitePool, migration_name: &str, sql: &str) -> Result<()> {
+    for statement in parse_sql_statements(sql) {
+        sqlx::query(&statement).execute(pool).await.map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to execute migration statement in {}: {} - Error: {}",
+                migration_name,
+                statement,
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+async fn apply_migration(pool: &SqlitePool, migration: &Migration) -> Result<()> {
+    info!("Applying migration: {}", migration.name);
+    execute_migration_sql(pool, migration.name, migration.up_sql).await?;
+    if let Some(backfill) = migration.backfill {
+        backfill(pool).await?;
+    }
+    mark_migration_applied(pool, migration.name).await?;
+    info!("Migration {} applied successfully", migration.name);
+    Ok(())
 }
 
 pub async fn init_database(pool: &SqlitePool) -> Result<()> {
+    init_database_with(pool, false).await
+}
+
+/// Like [`init_database`], additionally inserting the starter exercises, muscles and
+/// equipment from [`operations::seed_defaults`] when `seed_defaults` is set.
+pub async fn init_database_with(pool: &SqlitePool, seed_defaults: bool) -> Result<()> {
     init_migrations_table(pool).await?;
 
     for migration in MIGRATIONS {
@@ -108,26 +373,188 @@ pub async fn init_database(pool: &SqlitePool) -> Result<()> {
             debug!("Migration {} already applied, skipping", migration.name);
             continue;
         }
+        apply_migration(pool, migration).await?;
+    }
 
-        info!("Applying migration: {}", migration.name);
-        let statements = parse_sql_statements(migration.up_sql);
-
-        for statement in statements {
-            if !statement.trim().is_empty() {
-                sqlx::query(&statement).execute(pool).await.map_err(|e| {
-                    anyhow::anyhow!(
-                        "Failed to execute migration statement in {}: {} - Error: {}",
-                        migration.name,
-                        statement,
-                        e
-                    )
-                })?;
-            }
+    if seed_defaults {
+        operations::seed_defaults(pool).await?;
+    }
+    Ok(())
+}
+
+/// Reverts the most recently applied migration by running its `down.sql` and forgetting that
+/// it was applied, so the next `init_database` re-applies it.
+pub async fn rollback_last_migration(pool: &SqlitePool) -> Result<()> {
+    init_migrations_table(pool).await?;
+
+    let last_applied =
+        sqlx::query_scalar::<_, String>("SELECT name FROM _migrations ORDER BY id DESC LIMIT 1")
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No applied migrations to roll back"))?;
+    let migration = MIGRATIONS
+        .iter()
+        .find(|m| m.name == last_applied)
+        .ok_or_else(|| {
+            anyhow::anyhow!("Unknown migration {} cannot be rolled back", last_applied)
+        })?;
+
+    info!("Rolling back migration: {}", migration.name);
+    execute_migration_sql(pool, migration.name, migration.down_sql).await?;
+    // Rolling back the setup migration drops the tracking table along with everything else.
+    let tracked = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = '_migrations'",
+    )
+    .fetch_one(pool)
+    .await?
+        > 0;
+    if tracked {
+        sqlx::query("DELETE FROM _migrations WHERE name = ?1")
+            .bind(migration.name)
+            .execute(pool)
+            .await?;
+    }
+    info!("Migration {} rolled back successfully", migration.name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn table_exists(pool: &SqlitePool, table: &str) -> bool {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        )
+        .bind(table)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+            > 0
+    }
+
+    #[test]
+    fn test_parse_keeps_trigger_body_together() {
+        let sql = "CREATE TABLE a (id INTEGER);
+            -- keep updated_at fresh; see below
+            CREATE TRIGGER a_touch AFTER UPDATE ON a
+            BEGIN
+                UPDATE a SET id = CASE WHEN NEW.id < 0 THEN 0 ELSE NEW.id END WHERE id = NEW.id;
+                INSERT INTO log (msg) VALUES ('a; updated');
+            END;
+            CREATE INDEX idx_a ON a (id);";
+
+        let statements = parse_sql_statements(sql);
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[0], "CREATE TABLE a (id INTEGER)");
+        assert!(statements[1].starts_with("CREATE TRIGGER a_touch"));
+        assert!(statements[1].contains("'a; updated'"));
+        assert!(statements[1].ends_with("END"));
+        assert_eq!(statements[2], "CREATE INDEX idx_a ON a (id)");
+    }
+
+    #[test]
+    fn test_parse_ignores_semicolons_in_strings_and_comments() {
+        let sql = "INSERT INTO t (s) VALUES ('it''s; fine'); /* a; b */ SELECT 1;\n-- done;";
+        assert_eq!(
+            parse_sql_statements(sql),
+            vec!["INSERT INTO t (s) VALUES ('it''s; fine')", "SELECT 1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rollback_setup_migration_drops_tables() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        init_migrations_table(&pool).await.unwrap();
+        apply_migration(&pool, &MIGRATIONS[0]).await.unwrap();
+        assert!(table_exists(&pool, "workout_sets").await);
+        assert!(table_exists(&pool, "exercises").await);
+
+        rollback_last_migration(&pool).await.unwrap();
+        for table in [
+            "workout_sets",
+            "workout_sessions",
+            "exercises",
+            "muscles",
+            "users",
+            "_migrations",
+        ] {
+            assert!(!table_exists(&pool, table).await, "{} still exists", table);
         }
+        assert!(rollback_last_migration(&pool).await.is_err());
+        assert!(
+            !is_migration_applied(&pool, MIGRATIONS[0].name)
+                .await
+                .unwrap()
+        );
 
-        mark_migration_applied(pool, migration.name).await?;
-        info!("Migration {} applied successfully", migration.name);
+        init_database(&pool).await.unwrap();
+        assert!(table_exists(&pool, "workout_sets").await);
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn test_experience_level_backfill_matches_new_exercises() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        init_migrations_table(&pool).await.unwrap();
+        let experience_level = MIGRATIONS
+            .iter()
+            .position(|m| m.name.ends_with("_exercise_experience_level"))
+            .unwrap();
+        for migration in &MIGRATIONS[..experience_level] {
+            apply_migration(&pool, migration).await.unwrap();
+        }
+        let names = ["Power Clean", "Muscle Up", "Pistol Squat", "Back Squat"];
+        for (i, name) in names.iter().enumerate() {
+            sqlx::query("INSERT INTO exercises (slug, name) VALUES (?1, ?2)")
+                .bind(format!("exercise-{}", i))
+                .bind(name)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        apply_migration(&pool, &MIGRATIONS[experience_level])
+            .await
+            .unwrap();
+        for name in names {
+            let level = sqlx::query_scalar::<_, String>(
+                "SELECT experience_level FROM exercises WHERE name = ?1",
+            )
+            .bind(name)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(
+                level,
+                ExperienceLevel::default_for_exercise(name).as_str(),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(
+            ExperienceLevel::default_for_exercise("Pistol Squat"),
+            ExperienceLevel::Advanced
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rollback_reverts_newest_migration_first() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        init_database(&pool).await.unwrap();
+        assert!(table_exists(&pool, "user_equipment").await);
+
+        // Roll back everything applied after user_equipment, then user_equipment itself.
+        let user_equipment = MIGRATIONS
+            .iter()
+            .position(|m| m.name.ends_with("_user_equipment"))
+            .unwrap();
+        for _ in user_equipment..MIGRATIONS.len() {
+            rollback_last_migration(&pool).await.unwrap();
+        }
+        assert!(!table_exists(&pool, "user_equipment").await);
+        assert!(table_exists(&pool, "set_tags").await);
+
+        init_database(&pool).await.unwrap();
+        assert!(table_exists(&pool, "user_equipment").await);
+    }
 }
@@ -50,6 +50,81 @@ impl ExercisePatternType {
             _ => ExercisePatternType::Unknown,
         }
     }
+
+    /// Best guess at the pattern from keywords in an exercise name, for when no model is
+    /// available to classify it. Unknown when no keyword matches.
+    pub fn from_exercise_name(name: &str) -> Self {
+        const RULES: &[(&[&str], ExercisePatternType)] = &[
+            (
+                &["lunge", "split squat", "step up", "step-up"],
+                ExercisePatternType::Lunge,
+            ),
+            (&["squat", "leg press"], ExercisePatternType::Squat),
+            (
+                &["deadlift", "rdl", "hip thrust", "good morning", "swing"],
+                ExercisePatternType::HipHinge,
+            ),
+            (&["carry", "farmer"], ExercisePatternType::Carry),
+            (
+                &["twist", "woodchop", "wood chop", "rotation"],
+                ExercisePatternType::Rotation,
+            ),
+            (
+                &[
+                    "pull-up",
+                    "pull up",
+                    "pullup",
+                    "chin-up",
+                    "chin up",
+                    "chinup",
+                    "pulldown",
+                    "pull down",
+                ],
+                ExercisePatternType::VerticalPull,
+            ),
+            (&["row"], ExercisePatternType::HorizontalPull),
+            (
+                &[
+                    "overhead press",
+                    "shoulder press",
+                    "military press",
+                    "push press",
+                    "ohp",
+                ],
+                ExercisePatternType::VerticalPush,
+            ),
+            (
+                &[
+                    "bench",
+                    "push-up",
+                    "push up",
+                    "pushup",
+                    "chest press",
+                    "dip",
+                ],
+                ExercisePatternType::HorizontalPush,
+            ),
+            (
+                &[
+                    "curl",
+                    "extension",
+                    "raise",
+                    "fly",
+                    "flye",
+                    "kickback",
+                    "shrug",
+                ],
+                ExercisePatternType::Isolation,
+            ),
+        ];
+
+        let name = name.to_lowercase();
+        RULES
+            .iter()
+            .find(|(keywords, _)| keywords.iter().any(|k| name.contains(k)))
+            .map(|(_, pattern)| *pattern)
+            .unwrap_or(ExercisePatternType::Unknown)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -61,7 +136,8 @@ pub enum InjuryConstraint {
     LimitLoad,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RestStyle {
     #[default]
     Hypertrophy, // moderate rest: 60-90 sec
@@ -70,6 +146,59 @@ pub enum RestStyle {
     Giant,        // minimal rest, you absolute monster
 }
 
+impl RestStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestStyle::Hypertrophy => "hypertrophy",
+            RestStyle::Strength => "strength",
+            RestStyle::Conditioning => "conditioning",
+            RestStyle::Giant => "giant",
+        }
+    }
+}
+
+impl FromStr for RestStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hypertrophy" => Ok(RestStyle::Hypertrophy),
+            "strength" => Ok(RestStyle::Strength),
+            "conditioning" => Ok(RestStyle::Conditioning),
+            "giant" => Ok(RestStyle::Giant),
+            _ => Err(format!("Invalid rest style: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for RestStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Type<Sqlite> for RestStyle {
+    fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
+        <&str as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for RestStyle {
+    fn encode_by_ref(
+        &self,
+        args: &mut Vec<sqlx::sqlite::SqliteArgumentValue<'q>>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        <&str as Encode<'q, Sqlite>>::encode_by_ref(&self.as_str(), args)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for RestStyle {
+    fn decode(value: sqlx::sqlite::SqliteValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let s = <&str as Decode<Sqlite>>::decode(value)?;
+        RestStyle::from_str(s).map_err(|e| e.into())
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum SessionStyle {
     #[default]
@@ -81,7 +210,7 @@ pub enum SessionStyle {
     SkillFocused,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
 pub enum ExperienceLevel {
     Beginner,
     #[default]
@@ -89,6 +218,95 @@ pub enum ExperienceLevel {
     Advanced,
 }
 
+const ADVANCED_EXERCISE_KEYWORDS: &[&str] = &[
+    "snatch",
+    "clean",
+    "jerk",
+    "muscle up",
+    "muscle-up",
+    "pistol",
+];
+
+impl ExperienceLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExperienceLevel::Beginner => "beginner",
+            ExperienceLevel::Intermediate => "intermediate",
+            ExperienceLevel::Advanced => "advanced",
+        }
+    }
+
+    /// Levels whose exercises are appropriate for someone at this level.
+    pub fn accessible_levels(&self) -> &'static [ExperienceLevel] {
+        match self {
+            ExperienceLevel::Beginner => &[ExperienceLevel::Beginner],
+            ExperienceLevel::Intermediate => {
+                &[ExperienceLevel::Beginner, ExperienceLevel::Intermediate]
+            }
+            ExperienceLevel::Advanced => &[
+                ExperienceLevel::Beginner,
+                ExperienceLevel::Intermediate,
+                ExperienceLevel::Advanced,
+            ],
+        }
+    }
+
+    /// Default level for a newly created exercise. Olympic lifts and other technical
+    /// movements start out as advanced, everything else as intermediate.
+    pub fn default_for_exercise(name: &str) -> Self {
+        let name = name.to_lowercase();
+        if ADVANCED_EXERCISE_KEYWORDS
+            .iter()
+            .any(|keyword| name.contains(keyword))
+        {
+            ExperienceLevel::Advanced
+        } else {
+            ExperienceLevel::Intermediate
+        }
+    }
+}
+
+impl FromStr for ExperienceLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beginner" => Ok(ExperienceLevel::Beginner),
+            "intermediate" => Ok(ExperienceLevel::Intermediate),
+            "advanced" => Ok(ExperienceLevel::Advanced),
+            _ => Err(format!("Invalid experience level: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for ExperienceLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Type<Sqlite> for ExperienceLevel {
+    fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
+        <&str as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for ExperienceLevel {
+    fn encode_by_ref(
+        &self,
+        args: &mut Vec<sqlx::sqlite::SqliteArgumentValue<'q>>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        <&str as Encode<'q, Sqlite>>::encode_by_ref(&self.as_str(), args)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for ExperienceLevel {
+    fn decode(value: sqlx::sqlite::SqliteValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let s = <&str as Decode<Sqlite>>::decode(value)?;
+        ExperienceLevel::from_str(s).map_err(|e| e.into())
+    }
+}
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct Muscle {
     pub id: i64,
@@ -109,6 +327,12 @@ pub struct NewEquipment {
     pub name: String,
 }
 
+pub struct NewExerciseEquipment {
+    pub exercise_id: i64,
+    pub equipment_id: i64,
+    pub relation_type: String,
+}
+
 pub struct NewMuscle {
     pub name: String,
 }
@@ -119,6 +343,8 @@ pub struct Exercise {
     pub slug: String,
     pub name: String,
     pub description: Option<String>,
+    pub is_favorite: bool,
+    pub experience_level: ExperienceLevel,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -170,7 +396,8 @@ pub struct NewRequestString {
     pub string: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum WorkoutStatus {
     InProgress,
     Completed,
@@ -225,7 +452,7 @@ impl<'r> Decode<'r, Sqlite> for WorkoutStatus {
     }
 }
 
-#[derive(Debug, Clone, FromRow)]
+#[derive(Debug, Clone, FromRow, Serialize)]
 pub struct WorkoutSession {
     pub id: i64,
     pub user_id: Option<i64>,
@@ -246,7 +473,48 @@ pub struct NewWorkoutSession {
     pub status: Option<WorkoutStatus>,
 }
 
-#[derive(Debug, Clone, FromRow)]
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct BodyweightEntry {
+    pub id: i64,
+    pub user_id: i64,
+    pub weight: f64,
+    pub recorded_at: i64,
+    pub created_at: i64,
+}
+
+/// A reusable plan of exercises that can be started as a new workout.
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct WorkoutTemplate {
+    pub id: i64,
+    pub name: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct TemplateExercise {
+    pub template_id: i64,
+    pub exercise_id: i64,
+    pub position: i64,
+    pub target_sets: i64,
+    pub target_reps: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewTemplateExercise {
+    pub exercise_id: i64,
+    pub target_sets: i64,
+    pub target_reps: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct WorkoutStats {
+    pub completed_workouts: i64,
+    pub total_sets: i64,
+    pub total_volume: f64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
 pub struct WorkoutSet {
     pub id: i64,
     pub session_id: i64,
@@ -257,12 +525,52 @@ pub struct WorkoutSet {
     pub set_index: i64,
     pub rpe: Option<f64>,
     pub notes: Option<String>,
+    /// Rest planned after this set; `None` for sets logged without one, which clients treat
+    /// as the default hypertrophy rest.
+    pub rest_style: Option<RestStyle>,
+    /// How long a timed set (plank, run) lasted.
+    pub duration_seconds: Option<i64>,
+    /// Distance covered in a cardio set.
+    pub distance_meters: Option<f64>,
+    /// Sets sharing a group id were performed back to back as a superset.
+    pub superset_group: Option<i64>,
     pub created_at: i64,
     pub updated_at: i64,
 }
 
+impl WorkoutSet {
+    /// Describes a set without weight or reps by its distance and time, e.g. "5.00 km in
+    /// 25:00" or "45s". Returns `None` for regular weight x reps sets.
+    pub fn cardio_description(&self) -> Option<String> {
+        if self.weight != 0.0 || self.reps != 0 {
+            return None;
+        }
+        let distance = self.distance_meters.map(|m| {
+            if m >= 1000.0 {
+                format!("{:.2} km", m / 1000.0)
+            } else {
+                format!("{:.0} m", m)
+            }
+        });
+        let duration = self.duration_seconds.map(|s| match s {
+            s if s < 60 => format!("{}s", s),
+            s if s < 3600 => format!("{}:{:02}", s / 60, s % 60),
+            s => format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60),
+        });
+        match (distance, duration) {
+            (Some(distance), Some(duration)) => Some(format!("{} in {}", distance, duration)),
+            (Some(distance), None) => Some(distance),
+            (None, Some(duration)) => Some(duration),
+            (None, None) => None,
+        }
+    }
+}
+
 impl fmt::Display for WorkoutSet {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(cardio) = self.cardio_description() {
+            return write!(f, "Exercise {}: {}", self.id, cardio);
+        }
         let rpe_str = self.rpe.map(|r| format!(" @{:.1}", r)).unwrap_or_default();
         write!(
             f,
@@ -284,6 +592,13 @@ impl DisplayableSet {
 
 impl fmt::Display for DisplayableSet {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(cardio) = self.set.cardio_description() {
+            return write!(
+                f,
+                "{} (set #{}): {}",
+                self.exercise_name, self.set.set_index, cardio
+            );
+        }
         let rpe_str = self
             .set
             .rpe
@@ -308,6 +623,9 @@ pub struct NewWorkoutSet {
     pub set_index: i64,
     pub rpe: Option<f64>,
     pub notes: Option<String>,
+    pub rest_style: Option<RestStyle>,
+    pub duration_seconds: Option<i64>,
+    pub distance_meters: Option<f64>,
 }
 
 #[derive(Debug)]
@@ -319,5 +637,7 @@ pub struct UpdateWorkoutSet {
     pub reps: Option<i64>,
     pub rpe: Option<f64>,
     pub set_index: Option<i64>,
+    /// `None` keeps the set's note; an empty string clears it.
     pub notes: Option<String>,
+    pub rest_style: Option<RestStyle>,
 }
@@ -1,11 +1,13 @@
 use anyhow::Result;
 use log::{debug, error, info, warn};
-use sqlx::SqlitePool;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool, Transaction};
 
 use crate::{
     db::models::{
-        Equipment, Exercise, Muscle, RequestString, UpdateWorkoutSet, User, WorkoutSession,
-        WorkoutSet, WorkoutStatus,
+        BodyweightEntry, Equipment, Exercise, ExerciseMuscle, ExperienceLevel, Muscle,
+        NewExerciseEquipment, NewExerciseMuscle, NewTemplateExercise, NewWorkoutSet, RequestString,
+        TemplateExercise, UpdateWorkoutSet, User, WorkoutSession, WorkoutSet, WorkoutStats,
+        WorkoutStatus, WorkoutTemplate,
     },
     llm::ParsedSet,
 };
@@ -82,12 +84,54 @@ pub async fn create_workout_session(
     Ok(res)
 }
 
+pub async fn create_workout_session_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    user_id: Option<i64>,
+    name: Option<String>,
+    notes: Option<String>,
+    duration_seconds: Option<i64>,
+    status: Option<WorkoutStatus>,
+) -> Result<WorkoutSession> {
+    debug!(
+        "create_workout_session_tx called user_id={:?} name={:?} duration_seconds={:?} status={:?}",
+        user_id, name, duration_seconds, status
+    );
+
+    let dur_secs = duration_seconds.unwrap_or(0);
+    let status_enum = status.unwrap_or(WorkoutStatus::InProgress);
+    let now = chrono::Utc::now().timestamp();
+
+    let res = sqlx::query_as::<_, WorkoutSession>(
+        "INSERT INTO workout_sessions (user_id, name, duration_seconds, notes, status, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+         RETURNING id, user_id, name, duration_seconds, notes, status, summary, created_at, updated_at"
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(dur_secs)
+    .bind(notes)
+    .bind(&status_enum)
+    .bind(now)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        error!("create_workout_session_tx failed inserting to DB: {}", e);
+        anyhow::Error::from(e)
+    })?;
+
+    info!(
+        "created workout session id={} status={} (in transaction)",
+        res.id, res.status
+    );
+    Ok(res)
+}
+
 pub async fn get_workout_session(pool: &SqlitePool, session_id: i64) -> Result<WorkoutSession> {
     debug!("get_workout_session called session_id={}", session_id);
 
     sqlx::query_as::<_, WorkoutSession>(
         "SELECT id, user_id, name, datetime, duration_seconds, notes, status, summary, created_at, updated_at
-         FROM workout_sessions WHERE id = ?1",
+         FROM workout_sessions WHERE id = ?1 AND deleted_at IS NULL",
     )
     .bind(session_id)
     .fetch_one(pool)
@@ -98,50 +142,123 @@ pub async fn get_workout_session(pool: &SqlitePool, session_id: i64) -> Result<W
     })
 }
 
+/// Lists workout sessions, optionally only those with `status_filter` and created in
+/// `[after, before)`: `after` is inclusive and `before` exclusive, both unix seconds.
+/// Passing `limit` or `offset` pages through the results newest first.
 pub async fn get_all_workout_sessions(
     pool: &SqlitePool,
     status_filter: Option<WorkoutStatus>,
+    after: Option<i64>,
+    before: Option<i64>,
+    limit: Option<i64>,
+    offset: Option<i64>,
 ) -> Result<Vec<WorkoutSession>> {
     debug!(
-        "get_all_workout_sessions called status_filter={:?}",
-        status_filter
+        "get_all_workout_sessions called status_filter={:?} after={:?} before={:?} limit={:?} offset={:?}",
+        status_filter, after, before, limit, offset
     );
 
-    let result = if let Some(status) = status_filter {
-        sqlx::query_as::<_, WorkoutSession>(
-            "SELECT id, user_id, name, datetime, duration_seconds, notes, status, summary, created_at, updated_at
-             FROM workout_sessions WHERE status = ?1",
-        )
-        .bind(&status)
-        .fetch_all(pool)
-        .await
-    } else {
-        sqlx::query_as::<_, WorkoutSession>(
-            "SELECT id, user_id, name, datetime, duration_seconds, notes, status, summary, created_at, updated_at
-             FROM workout_sessions",
-        )
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, user_id, name, datetime, duration_seconds, notes, status, summary, created_at, updated_at
+         FROM workout_sessions WHERE deleted_at IS NULL",
+    );
+    if let Some(status) = status_filter {
+        query.push(" AND status = ").push_bind(status);
+    }
+    if let Some(after) = after {
+        query.push(" AND created_at >= ").push_bind(after);
+    }
+    if let Some(before) = before {
+        query.push(" AND created_at < ").push_bind(before);
+    }
+    if limit.is_some() || offset.is_some() {
+        query
+            .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+            .push_bind(limit.unwrap_or(-1))
+            .push(" OFFSET ")
+            .push_bind(offset.unwrap_or(0));
+    }
+
+    query
+        .build_query_as::<WorkoutSession>()
         .fetch_all(pool)
         .await
-    };
-
-    result.map_err(|e| {
-        warn!("get_all_workout_sessions failed: {}", e);
-        anyhow::Error::from(e)
-    })
+        .map_err(|e| {
+            warn!("get_all_workout_sessions failed: {}", e);
+            anyhow::Error::from(e)
+        })
 }
 
+/// Soft-deletes a workout: it is hidden from listings until restored with
+/// [`restore_workout_session`] or removed for good by [`purge_deleted`].
 pub async fn delete_workout_session(pool: &SqlitePool, session_id: i64) -> Result<u64> {
     debug!("delete_workout_session called session_id={}", session_id);
 
-    let result = sqlx::query("DELETE FROM workout_sessions WHERE id = ?1")
-        .bind(session_id)
-        .execute(pool)
-        .await
-        .map_err(|e| {
-            warn!("delete_workout_session failed for id {}: {}", session_id, e);
-            anyhow::Error::from(e)
-        })?;
+    let now = chrono::Utc::now().timestamp();
+    let result = sqlx::query(
+        "UPDATE workout_sessions SET deleted_at = ?1, updated_at = ?1
+         WHERE id = ?2 AND deleted_at IS NULL",
+    )
+    .bind(now)
+    .bind(session_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        warn!("delete_workout_session failed for id {}: {}", session_id, e);
+        anyhow::Error::from(e)
+    })?;
+
+    Ok(result.rows_affected())
+}
+
+/// Brings back a soft-deleted workout. Errors if the workout doesn't exist or isn't deleted.
+pub async fn restore_workout_session(pool: &SqlitePool, session_id: i64) -> Result<()> {
+    debug!("restore_workout_session called session_id={}", session_id);
+
+    let now = chrono::Utc::now().timestamp();
+    let result = sqlx::query(
+        "UPDATE workout_sessions SET deleted_at = NULL, updated_at = ?1
+         WHERE id = ?2 AND deleted_at IS NOT NULL",
+    )
+    .bind(now)
+    .bind(session_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "restore_workout_session failed for id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(anyhow::anyhow!(
+            "No deleted workout session with id {}",
+            session_id
+        ));
+    }
+    info!("restored workout session id={}", session_id);
+    Ok(())
+}
+
+/// Permanently deletes workouts soft-deleted before the `older_than` unix timestamp, along
+/// with their sets. Returns the number of workouts removed.
+pub async fn purge_deleted(pool: &SqlitePool, older_than: i64) -> Result<u64> {
+    debug!("purge_deleted called older_than={}", older_than);
+
+    let result = sqlx::query(
+        "DELETE FROM workout_sessions WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+    )
+    .bind(older_than)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("purge_deleted failed: {}", e);
+        anyhow::Error::from(e)
+    })?;
 
+    info!("purged {} deleted workout sessions", result.rows_affected());
     Ok(result.rows_affected())
 }
 
@@ -151,7 +268,7 @@ pub async fn get_in_progress_workout(pool: &SqlitePool) -> Result<Option<Workout
     let status = WorkoutStatus::InProgress;
     let result = sqlx::query_as::<_, WorkoutSession>(
         "SELECT id, user_id, name, datetime, duration_seconds, notes, status, summary, created_at, updated_at
-         FROM workout_sessions WHERE status = ?1 LIMIT 1",
+         FROM workout_sessions WHERE status = ?1 AND deleted_at IS NULL LIMIT 1",
     )
     .bind(&status)
     .fetch_optional(pool)
@@ -164,6 +281,73 @@ pub async fn get_in_progress_workout(pool: &SqlitePool) -> Result<Option<Workout
     Ok(result)
 }
 
+pub async fn get_in_progress_workout_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+) -> Result<Option<WorkoutSession>> {
+    debug!("get_in_progress_workout_tx called");
+
+    let status = WorkoutStatus::InProgress;
+    let result = sqlx::query_as::<_, WorkoutSession>(
+        "SELECT id, user_id, name, datetime, duration_seconds, notes, status, summary, created_at, updated_at
+         FROM workout_sessions WHERE status = ?1 AND deleted_at IS NULL LIMIT 1",
+    )
+    .bind(&status)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| {
+        warn!("get_in_progress_workout_tx failed: {}", e);
+        anyhow::Error::from(e)
+    })?;
+
+    Ok(result)
+}
+
+/// Completes in-progress workouts with no activity in the last `inactivity_seconds`. A
+/// workout's last activity is its newest set, or its creation time if it has none, and its
+/// duration is set to the span up to that point. Returns how many workouts were closed.
+pub async fn auto_complete_stale_workouts(
+    pool: &SqlitePool,
+    inactivity_seconds: i64,
+) -> Result<u64> {
+    debug!(
+        "auto_complete_stale_workouts called inactivity_seconds={}",
+        inactivity_seconds
+    );
+
+    let now = chrono::Utc::now().timestamp();
+    let cutoff = now - inactivity_seconds;
+    let in_progress = WorkoutStatus::InProgress;
+    let completed = WorkoutStatus::Completed;
+    let result = sqlx::query(
+        "UPDATE workout_sessions
+         SET status = ?1,
+             duration_seconds = MAX(
+                 duration_seconds,
+                 COALESCE((SELECT MAX(ws.created_at) FROM workout_sets ws WHERE ws.session_id = workout_sessions.id), created_at) - created_at
+             ),
+             updated_at = ?2
+         WHERE status = ?3
+           AND deleted_at IS NULL
+           AND COALESCE((SELECT MAX(ws.created_at) FROM workout_sets ws WHERE ws.session_id = workout_sessions.id), created_at) < ?4",
+    )
+    .bind(&completed)
+    .bind(now)
+    .bind(&in_progress)
+    .bind(cutoff)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!("auto_complete_stale_workouts failed: {}", e);
+        anyhow::Error::from(e)
+    })?;
+
+    info!(
+        "auto-completed {} stale workout sessions",
+        result.rows_affected()
+    );
+    Ok(result.rows_affected())
+}
+
 pub async fn complete_workout_session(
     pool: &SqlitePool,
     session_id: i64,
@@ -200,18 +384,109 @@ pub async fn complete_workout_session(
     Ok(())
 }
 
+pub async fn complete_workout_session_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    session_id: i64,
+    duration_seconds: i64,
+) -> Result<()> {
+    debug!(
+        "complete_workout_session_tx called session_id={} duration_seconds={}",
+        session_id, duration_seconds
+    );
+
+    let now = chrono::Utc::now().timestamp();
+    let status = WorkoutStatus::Completed;
+    sqlx::query(
+        "UPDATE workout_sessions SET status = ?1, duration_seconds = ?2, updated_at = ?3 WHERE id = ?4",
+    )
+    .bind(&status)
+    .bind(duration_seconds)
+    .bind(now)
+    .bind(session_id)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        error!(
+            "complete_workout_session_tx failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    Ok(())
+}
+
+/// Seconds between the first and last set logged in a workout; 0 with fewer than two sets.
+pub async fn compute_workout_duration(pool: &SqlitePool, session_id: i64) -> Result<i64> {
+    debug!("compute_workout_duration called session_id={}", session_id);
+
+    let duration: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(MAX(created_at) - MIN(created_at), 0) FROM workout_sets WHERE session_id = ?1",
+    )
+    .bind(session_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "compute_workout_duration failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    Ok(duration)
+}
+
+/// Puts a completed workout back in progress. Fails if the workout is missing, deleted or
+/// not completed.
+pub async fn reopen_workout_session(pool: &SqlitePool, session_id: i64) -> Result<()> {
+    debug!("reopen_workout_session called session_id={}", session_id);
+
+    let now = chrono::Utc::now().timestamp();
+    let in_progress = WorkoutStatus::InProgress;
+    let completed = WorkoutStatus::Completed;
+    let result = sqlx::query(
+        "UPDATE workout_sessions SET status = ?1, updated_at = ?2
+         WHERE id = ?3 AND status = ?4 AND deleted_at IS NULL",
+    )
+    .bind(&in_progress)
+    .bind(now)
+    .bind(session_id)
+    .bind(&completed)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "reopen_workout_session failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(anyhow::anyhow!(
+            "No completed workout session with id {}",
+            session_id
+        ));
+    }
+    info!("reopened workout session id={}", session_id);
+    Ok(())
+}
+
 pub async fn check_in_progress_workout_exists(pool: &SqlitePool) -> Result<bool> {
     debug!("check_in_progress_workout_exists called");
 
     let status = WorkoutStatus::InProgress;
-    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workout_sessions WHERE status = ?1")
-        .bind(&status)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| {
-            warn!("check_in_progress_workout_exists failed: {}", e);
-            anyhow::Error::from(e)
-        })?;
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM workout_sessions WHERE status = ?1 AND deleted_at IS NULL",
+    )
+    .bind(&status)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        warn!("check_in_progress_workout_exists failed: {}", e);
+        anyhow::Error::from(e)
+    })?;
 
     Ok(count > 0)
 }
@@ -248,6 +523,42 @@ pub async fn update_workout_duration(
     Ok(())
 }
 
+/// Replaces a workout's notes; blank notes are stored as NULL.
+pub async fn update_workout_notes(
+    pool: &SqlitePool,
+    session_id: i64,
+    notes: Option<String>,
+) -> Result<WorkoutSession> {
+    debug!(
+        "update_workout_notes called session_id={} notes_present={}",
+        session_id,
+        notes.is_some()
+    );
+
+    let notes = notes
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query_as::<_, WorkoutSession>(
+        "UPDATE workout_sessions SET notes = ?1, updated_at = ?2
+         WHERE id = ?3 AND deleted_at IS NULL
+         RETURNING id, user_id, name, duration_seconds, notes, status, summary, created_at, updated_at",
+    )
+    .bind(notes)
+    .bind(now)
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "update_workout_notes failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })?
+    .ok_or_else(|| anyhow::anyhow!("No workout session with id {}", session_id))
+}
+
 pub async fn update_workout_summary(
     pool: &SqlitePool,
     session_id: i64,
@@ -278,27 +589,292 @@ pub async fn update_workout_summary(
     Ok(())
 }
 
-pub async fn get_exercise(pool: &SqlitePool, exercise_id: i64) -> Result<Exercise> {
-    debug!("get_exercise called exercise_id={}", exercise_id);
+pub async fn update_workout_summary_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    session_id: i64,
+    summary: String,
+) -> Result<()> {
+    debug!(
+        "update_workout_summary_tx called session_id={} summary_len={}",
+        session_id,
+        summary.len()
+    );
 
-    sqlx::query_as::<_, Exercise>(
-        "SELECT id, slug, name, description, created_at, updated_at
-         FROM exercises WHERE id = ?1",
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query("UPDATE workout_sessions SET summary = ?1, updated_at = ?2 WHERE id = ?3")
+        .bind(summary)
+        .bind(now)
+        .bind(session_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
+            error!(
+                "update_workout_summary_tx failed for session_id {}: {}",
+                session_id, e
+            );
+            anyhow::Error::from(e)
+        })?;
+
+    info!(
+        "updated workout summary for session_id={} (in transaction)",
+        session_id
+    );
+    Ok(())
+}
+
+/// Totals across every workout that hasn't been deleted.
+pub async fn get_workout_stats(pool: &SqlitePool) -> Result<WorkoutStats> {
+    debug!("get_workout_stats called");
+
+    let completed = WorkoutStatus::Completed;
+    sqlx::query_as::<_, WorkoutStats>(
+        "SELECT
+             (SELECT COUNT(*) FROM workout_sessions WHERE status = ?1 AND deleted_at IS NULL) AS completed_workouts,
+             (SELECT COUNT(*) FROM workout_sets ws
+              JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL) AS total_sets,
+             (SELECT COALESCE(SUM(ws.weight * ws.reps), 0.0) FROM workout_sets ws
+              JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL) AS total_volume",
     )
-    .bind(exercise_id)
+    .bind(&completed)
     .fetch_one(pool)
     .await
     .map_err(|e| {
-        warn!("get_exercise failed for id {}: {}", exercise_id, e);
+        warn!("get_workout_stats failed: {}", e);
         anyhow::Error::from(e)
     })
 }
 
-pub async fn get_all_exercises(pool: &SqlitePool) -> Result<Vec<Exercise>> {
-    debug!("get_all_exercises called");
-    let exercises = sqlx::query_as::<_, Exercise>(
-        "SELECT id, slug, name, description, created_at, updated_at FROM exercises",
-    )
+/// Creates a template with its exercises in the given order. Either the whole template is
+/// written or nothing is.
+pub async fn create_workout_template(
+    pool: &SqlitePool,
+    name: &str,
+    exercises: &[NewTemplateExercise],
+) -> Result<WorkoutTemplate> {
+    debug!(
+        "create_workout_template called name={} exercises={}",
+        name,
+        exercises.len()
+    );
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow::anyhow!("Template name cannot be empty"));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let template = sqlx::query_as::<_, WorkoutTemplate>(
+        "INSERT INTO workout_templates (name) VALUES (?1)
+         RETURNING id, name, created_at, updated_at",
+    )
+    .bind(name)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| {
+        error!("create_workout_template failed for name {}: {}", name, e);
+        anyhow::Error::from(e)
+    })?;
+
+    for (position, exercise) in exercises.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO template_exercises
+             (template_id, exercise_id, position, target_sets, target_reps)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(template.id)
+        .bind(exercise.exercise_id)
+        .bind(position as i64)
+        .bind(exercise.target_sets)
+        .bind(exercise.target_reps)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            error!(
+                "create_workout_template failed adding exercise_id={} to template {}: {}",
+                exercise.exercise_id, template.id, e
+            );
+            anyhow::Error::from(e)
+        })?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        error!(
+            "create_workout_template failed committing template {}: {}",
+            template.id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    info!(
+        "created workout template id={} name={} exercises={}",
+        template.id,
+        template.name,
+        exercises.len()
+    );
+    Ok(template)
+}
+
+pub async fn get_workout_template(pool: &SqlitePool, template_id: i64) -> Result<WorkoutTemplate> {
+    debug!("get_workout_template called template_id={}", template_id);
+    sqlx::query_as::<_, WorkoutTemplate>(
+        "SELECT id, name, created_at, updated_at FROM workout_templates WHERE id = ?1",
+    )
+    .bind(template_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        warn!("get_workout_template failed for id {}: {}", template_id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn get_workout_templates(pool: &SqlitePool) -> Result<Vec<WorkoutTemplate>> {
+    debug!("get_workout_templates called");
+    sqlx::query_as::<_, WorkoutTemplate>(
+        "SELECT id, name, created_at, updated_at FROM workout_templates ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("get_workout_templates failed: {}", e);
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn get_template_exercises(
+    pool: &SqlitePool,
+    template_id: i64,
+) -> Result<Vec<TemplateExercise>> {
+    debug!("get_template_exercises called template_id={}", template_id);
+    sqlx::query_as::<_, TemplateExercise>(
+        "SELECT template_id, exercise_id, position, target_sets, target_reps
+         FROM template_exercises WHERE template_id = ?1 ORDER BY position",
+    )
+    .bind(template_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "get_template_exercises failed for template {}: {}",
+            template_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn get_exercise(pool: &SqlitePool, exercise_id: i64) -> Result<Exercise> {
+    debug!("get_exercise called exercise_id={}", exercise_id);
+
+    sqlx::query_as::<_, Exercise>(
+        "SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at
+         FROM exercises WHERE id = ?1",
+    )
+    .bind(exercise_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        warn!("get_exercise failed for id {}: {}", exercise_id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn get_exercise_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    exercise_id: i64,
+) -> Result<Exercise> {
+    debug!("get_exercise_tx called exercise_id={}", exercise_id);
+
+    sqlx::query_as::<_, Exercise>(
+        "SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at
+         FROM exercises WHERE id = ?1",
+    )
+    .bind(exercise_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        warn!("get_exercise_tx failed for id {}: {}", exercise_id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+fn normalize_alias(alias: &str) -> String {
+    alias
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Makes `alias` resolve to the given exercise, repointing it if it already named another.
+/// Aliases match case-insensitively.
+pub async fn add_exercise_alias(pool: &SqlitePool, exercise_id: i64, alias: &str) -> Result<()> {
+    debug!(
+        "add_exercise_alias called exercise_id={} alias={}",
+        exercise_id, alias
+    );
+    let alias = normalize_alias(alias);
+    if alias.is_empty() {
+        return Err(anyhow::anyhow!("Exercise alias cannot be empty"));
+    }
+    sqlx::query(
+        "INSERT INTO exercise_aliases (alias, exercise_id) VALUES (?1, ?2)
+         ON CONFLICT(alias) DO UPDATE SET exercise_id = excluded.exercise_id",
+    )
+    .bind(&alias)
+    .bind(exercise_id)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "add_exercise_alias failed for exercise_id {} alias {}: {}",
+            exercise_id, alias, e
+        );
+        anyhow::Error::from(e)
+    })?;
+    Ok(())
+}
+
+pub async fn resolve_alias(pool: &SqlitePool, alias: &str) -> Result<Option<Exercise>> {
+    debug!("resolve_alias called alias={}", alias);
+    sqlx::query_as::<_, Exercise>(
+        "SELECT e.id, e.slug, e.name, e.description, e.is_favorite, e.experience_level, e.created_at, e.updated_at
+         FROM exercise_aliases a JOIN exercises e ON e.id = a.exercise_id
+         WHERE a.alias = ?1",
+    )
+    .bind(normalize_alias(alias))
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        warn!("resolve_alias failed for alias {}: {}", alias, e);
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn resolve_alias_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    alias: &str,
+) -> Result<Option<Exercise>> {
+    debug!("resolve_alias_tx called alias={}", alias);
+    sqlx::query_as::<_, Exercise>(
+        "SELECT e.id, e.slug, e.name, e.description, e.is_favorite, e.experience_level, e.created_at, e.updated_at
+         FROM exercise_aliases a JOIN exercises e ON e.id = a.exercise_id
+         WHERE a.alias = ?1",
+    )
+    .bind(normalize_alias(alias))
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| {
+        warn!("resolve_alias_tx failed for alias {}: {}", alias, e);
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn get_all_exercises(pool: &SqlitePool) -> Result<Vec<Exercise>> {
+    debug!("get_all_exercises called");
+    let exercises = sqlx::query_as::<_, Exercise>(
+        "SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at FROM exercises",
+    )
     .fetch_all(pool)
     .await
     .map_err(|e| {
@@ -309,6 +885,51 @@ pub async fn get_all_exercises(pool: &SqlitePool) -> Result<Vec<Exercise>> {
     Ok(exercises)
 }
 
+/// Exercises whose name starts with `prefix` (case-insensitive), followed by those that only
+/// contain it, each group ordered by name. A blank prefix matches nothing, and `limit` must be
+/// positive since SQLite reads a negative limit as no limit at all.
+pub async fn search_exercises(
+    pool: &SqlitePool,
+    prefix: &str,
+    limit: i64,
+) -> Result<Vec<Exercise>> {
+    debug!(
+        "search_exercises called prefix={:?} limit={}",
+        prefix, limit
+    );
+
+    if limit <= 0 {
+        return Err(anyhow::anyhow!(
+            "search limit must be positive, got {}",
+            limit
+        ));
+    }
+    let prefix = prefix.trim();
+    if prefix.is_empty() {
+        return Ok(vec![]);
+    }
+    let escaped = prefix
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    sqlx::query_as::<_, Exercise>(
+        "SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at
+         FROM exercises
+         WHERE name LIKE '%' || ?1 || '%' ESCAPE '\\'
+         ORDER BY name LIKE ?1 || '%' ESCAPE '\\' DESC, name COLLATE NOCASE ASC
+         LIMIT ?2",
+    )
+    .bind(escaped)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("search_exercises failed for prefix {:?}: {}", prefix, e);
+        anyhow::Error::from(e)
+    })
+}
+
 pub async fn get_all_exercises_except(
     pool: &SqlitePool,
     avoid_exercise_ids: &[i64],
@@ -323,7 +944,7 @@ pub async fn get_all_exercises_except(
         .join(",");
 
     let sql = format!(
-        r#"SELECT id, slug, name, description, created_at, updated_at
+        r#"SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at
         FROM exercises
         WHERE id NOT IN ({})
         "#,
@@ -339,732 +960,3574 @@ pub async fn get_all_exercises_except(
     })
 }
 
-pub async fn get_or_create_exercise(pool: &SqlitePool, exercise_name: &str) -> Result<Exercise> {
-    debug!("get_or_create_exercise called name={}", exercise_name);
-    let slug = slugify(exercise_name);
+/// Renames an exercise and regenerates its slug. Fails if another exercise already has
+/// the slug the new name produces.
+pub async fn rename_exercise(
+    pool: &SqlitePool,
+    exercise_id: i64,
+    new_name: &str,
+) -> Result<Exercise> {
+    debug!(
+        "rename_exercise called exercise_id={} new_name={}",
+        exercise_id, new_name
+    );
+    let new_name = new_name.trim();
+    let slug = slugify(new_name);
+    if slug.is_empty() {
+        return Err(anyhow::anyhow!("Exercise name cannot be empty"));
+    }
 
-    if let Some(exercise) = sqlx::query_as::<_, Exercise>(
-        "SELECT id, slug, name, description, created_at, updated_at
-         FROM exercises WHERE slug = ?1",
-    )
-    .bind(&slug)
-    .fetch_optional(pool)
-    .await?
-    {
-        debug!(
-            "found existing exercise id={} name={}",
-            exercise.id, exercise.name
-        );
-        return Ok(exercise);
+    let existing: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM exercises WHERE slug = ?1 AND id != ?2")
+            .bind(&slug)
+            .bind(exercise_id)
+            .fetch_optional(pool)
+            .await?;
+    if let Some(existing_id) = existing {
+        return Err(anyhow::anyhow!(
+            "Cannot rename exercise {} to '{}': exercise {} already uses that name",
+            exercise_id,
+            new_name,
+            existing_id
+        ));
     }
 
     let now = chrono::Utc::now().timestamp();
-
-    let created = sqlx::query_as::<_, Exercise>(
-        "INSERT INTO exercises (slug, name, description, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?4)
-         RETURNING id, slug, name, description, created_at, updated_at",
+    let renamed = sqlx::query_as::<_, Exercise>(
+        "UPDATE exercises SET name = ?1, slug = ?2, updated_at = ?3 WHERE id = ?4
+         RETURNING id, slug, name, description, is_favorite, experience_level, created_at, updated_at",
     )
-    .bind(slug)
-    .bind(exercise_name)
-    .bind(None::<String>)
+    .bind(new_name)
+    .bind(&slug)
     .bind(now)
+    .bind(exercise_id)
     .fetch_one(pool)
     .await
     .map_err(|e| {
-        error!(
-            "get_or_create_exercise failed inserting {}: {}",
-            exercise_name, e
-        );
+        error!("rename_exercise failed for exercise_id {}: {}", exercise_id, e);
         anyhow::Error::from(e)
     })?;
 
-    info!("created exercise id={} name={}", created.id, created.name);
-    Ok(created)
+    info!("renamed exercise id={} to {}", renamed.id, renamed.name);
+    Ok(renamed)
 }
 
-pub async fn get_or_create_muscle(pool: &SqlitePool, muscle_name: &str) -> Result<Muscle> {
-    debug!("get_or_create_muscle called name={}", muscle_name);
-
-    if let Some(muscle) = sqlx::query_as::<_, Muscle>(
-        "SELECT id, name, created_at, updated_at
-         FROM muscles WHERE name = ?1",
-    )
-    .bind(muscle_name)
-    .fetch_optional(pool)
-    .await?
-    {
-        debug!(
-            "found existing muscle id={} name={}",
-            muscle.id, muscle.name
-        );
-        return Ok(muscle);
-    }
+pub async fn set_favorite(
+    pool: &SqlitePool,
+    exercise_id: i64,
+    is_favorite: bool,
+) -> Result<Exercise> {
+    debug!(
+        "set_favorite called exercise_id={} is_favorite={}",
+        exercise_id, is_favorite
+    );
 
     let now = chrono::Utc::now().timestamp();
-
-    let created = sqlx::query_as::<_, Muscle>(
-        "INSERT INTO muscles (name, created_at, updated_at)
-         VALUES (?1, ?2, ?2)
-         RETURNING id, name, created_at, updated_at",
+    let updated = sqlx::query_as::<_, Exercise>(
+        "UPDATE exercises SET is_favorite = ?1, updated_at = ?2 WHERE id = ?3
+         RETURNING id, slug, name, description, is_favorite, experience_level, created_at, updated_at",
     )
-    .bind(muscle_name)
+    .bind(is_favorite)
     .bind(now)
+    .bind(exercise_id)
     .fetch_one(pool)
     .await
     .map_err(|e| {
-        error!(
-            "get_or_create_muscle failed inserting {}: {}",
-            muscle_name, e
-        );
+        error!("set_favorite failed for exercise_id {}: {}", exercise_id, e);
         anyhow::Error::from(e)
     })?;
 
-    info!("created muscle id={} name={}", created.id, created.name);
-    Ok(created)
+    info!(
+        "set exercise id={} is_favorite={}",
+        updated.id, updated.is_favorite
+    );
+    Ok(updated)
 }
 
-pub async fn get_or_create_equipment(pool: &SqlitePool, equipment_name: &str) -> Result<Equipment> {
-    debug!("get_or_create_equipment called name={}", equipment_name);
-
-    if let Some(equipment) = sqlx::query_as::<_, Equipment>(
-        "SELECT id, name, created_at, updated_at
-         FROM equipment WHERE name = ?1",
+pub async fn get_favorites(pool: &SqlitePool) -> Result<Vec<Exercise>> {
+    debug!("get_favorites called");
+    let exercises = sqlx::query_as::<_, Exercise>(
+        "SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at
+         FROM exercises WHERE is_favorite = 1 ORDER BY name ASC",
     )
-    .bind(equipment_name)
-    .fetch_optional(pool)
-    .await?
-    {
-        debug!(
-            "found existing equipment id={} name={}",
-            equipment.id, equipment.name
-        );
-        return Ok(equipment);
-    }
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!("get_favorites failed: {}", e);
+        anyhow::Error::from(e)
+    })?;
+    debug!("get_favorites returned {} exercises", exercises.len());
+    Ok(exercises)
+}
 
-    let now = chrono::Utc::now().timestamp();
+pub async fn set_exercise_experience_level(
+    pool: &SqlitePool,
+    exercise_id: i64,
+    level: ExperienceLevel,
+) -> Result<Exercise> {
+    debug!(
+        "set_exercise_experience_level called exercise_id={} level={}",
+        exercise_id, level
+    );
 
-    let created = sqlx::query_as::<_, Equipment>(
-        "INSERT INTO equipment (name, created_at, updated_at)
-         VALUES (?1, ?2, ?2)
-         RETURNING id, name, created_at, updated_at",
+    let now = chrono::Utc::now().timestamp();
+    let updated = sqlx::query_as::<_, Exercise>(
+        "UPDATE exercises SET experience_level = ?1, updated_at = ?2 WHERE id = ?3
+         RETURNING id, slug, name, description, is_favorite, experience_level, created_at, updated_at",
     )
-    .bind(equipment_name)
+    .bind(level)
     .bind(now)
+    .bind(exercise_id)
     .fetch_one(pool)
     .await
     .map_err(|e| {
         error!(
-            "get_or_create_equipment failed inserting {}: {}",
-            equipment_name, e
+            "set_exercise_experience_level failed for exercise_id {}: {}",
+            exercise_id, e
         );
         anyhow::Error::from(e)
     })?;
 
-    info!("created equipment id={} name={}", created.id, created.name);
-    Ok(created)
+    info!(
+        "set exercise id={} experience_level={}",
+        updated.id, updated.experience_level
+    );
+    Ok(updated)
 }
 
-pub async fn get_or_create_user(pool: &SqlitePool, username: &str) -> Result<User> {
-    debug!("get_or_create_user called username={}", username);
+pub async fn get_exercises_for_level(
+    pool: &SqlitePool,
+    level: ExperienceLevel,
+) -> Result<Vec<Exercise>> {
+    debug!("get_exercises_for_level called level={}", level);
 
-    if let Some(u) = sqlx::query_as::<_, User>(
-        "SELECT id, username, created_at, updated_at
-         FROM users WHERE username = ?1",
-    )
-    .bind(username)
-    .fetch_optional(pool)
-    .await?
-    {
-        debug!("found existing user id={} username={}", u.id, u.username);
-        return Ok(u);
-    }
+    let levels = level.accessible_levels();
+    let placeholders = std::iter::repeat("?")
+        .take(levels.len())
+        .collect::<Vec<_>>()
+        .join(",");
 
-    let now = chrono::Utc::now().timestamp();
+    let sql = format!(
+        r#"SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at
+        FROM exercises
+        WHERE experience_level IN ({})
+        ORDER BY name ASC
+        "#,
+        placeholders
+    );
+    let mut query = sqlx::query_as::<_, Exercise>(&sql);
+    for level in levels {
+        query = query.bind(*level);
+    }
+    let exercises = query.fetch_all(pool).await.map_err(|e| {
+        error!("get_exercises_for_level failed: {}", e);
+        anyhow::Error::from(e)
+    })?;
+    debug!(
+        "get_exercises_for_level returned {} exercises",
+        exercises.len()
+    );
+    Ok(exercises)
+}
 
-    let created = sqlx::query_as::<_, User>(
-        "INSERT INTO users (username, created_at, updated_at)
-         VALUES (?1, ?2, ?2)
-         RETURNING id, username, created_at, updated_at",
+pub async fn get_recent_exercises(pool: &SqlitePool, limit: i64) -> Result<Vec<Exercise>> {
+    debug!("get_recent_exercises called limit={}", limit);
+    let exercises = sqlx::query_as::<_, Exercise>(
+        "SELECT e.id, e.slug, e.name, e.description, e.is_favorite, e.experience_level, e.created_at, e.updated_at
+         FROM exercises e
+         JOIN (
+             SELECT exercise_id, MAX(created_at) AS last_used, MAX(id) AS last_set_id
+             FROM workout_sets GROUP BY exercise_id
+         ) recent ON recent.exercise_id = e.id
+         ORDER BY recent.last_used DESC, recent.last_set_id DESC
+         LIMIT ?1",
     )
-    .bind(username)
-    .bind(now)
-    .fetch_one(pool)
+    .bind(limit)
+    .fetch_all(pool)
     .await
     .map_err(|e| {
-        error!("get_or_create_user failed inserting {}: {}", username, e);
+        warn!("get_recent_exercises failed: {}", e);
         anyhow::Error::from(e)
     })?;
-
-    info!(
-        "created user id={} username={}",
-        created.id, created.username
+    debug!(
+        "get_recent_exercises returned {} exercises",
+        exercises.len()
     );
-    Ok(created)
+    Ok(exercises)
 }
 
-pub async fn create_request_string(
-    pool: &SqlitePool,
-    user_id: i64,
-    input: String,
-) -> Result<RequestString> {
+const ORPHAN_EXERCISE_CONDITION: &str =
+    "NOT EXISTS (SELECT 1 FROM workout_sets ws WHERE ws.exercise_id = exercises.id)
+     AND NOT EXISTS (SELECT 1 FROM exercise_muscles em WHERE em.exercise_id = exercises.id)
+     AND NOT EXISTS (SELECT 1 FROM exercise_equipment ee WHERE ee.exercise_id = exercises.id)
+     AND NOT EXISTS (SELECT 1 FROM template_exercises te WHERE te.exercise_id = exercises.id)";
+
+/// Exercises that no set or template references and that have no muscle or equipment links,
+/// typically left behind by typos.
+pub async fn get_orphan_exercises(pool: &SqlitePool) -> Result<Vec<Exercise>> {
+    debug!("get_orphan_exercises called");
+    let exercises = sqlx::query_as::<_, Exercise>(&format!(
+        "SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at
+         FROM exercises WHERE {} ORDER BY name ASC",
+        ORPHAN_EXERCISE_CONDITION
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!("get_orphan_exercises failed: {}", e);
+        anyhow::Error::from(e)
+    })?;
     debug!(
-        "create_request_string called user_id={} input_len={}",
-        user_id,
-        input.len()
+        "get_orphan_exercises returned {} exercises",
+        exercises.len()
     );
+    Ok(exercises)
+}
 
-    let now = chrono::Utc::now().timestamp();
+/// Deletes orphan exercises and returns them. With `dry_run` nothing is deleted and the
+/// exercises that would be pruned are returned instead.
+pub async fn prune_orphan_exercises(pool: &SqlitePool, dry_run: bool) -> Result<Vec<Exercise>> {
+    debug!("prune_orphan_exercises called dry_run={}", dry_run);
+    if dry_run {
+        return get_orphan_exercises(pool).await;
+    }
 
-    sqlx::query_as::<_, RequestString>(
-        "INSERT INTO request_strings (user_id, string, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?3)
-         RETURNING id, user_id, string, created_at, updated_at",
-    )
-    .bind(user_id)
-    .bind(input)
-    .bind(now)
-    .fetch_one(pool)
+    let mut pruned = sqlx::query_as::<_, Exercise>(&format!(
+        "DELETE FROM exercises WHERE {}
+         RETURNING id, slug, name, description, is_favorite, experience_level, created_at, updated_at",
+        ORPHAN_EXERCISE_CONDITION
+    ))
+    .fetch_all(pool)
     .await
     .map_err(|e| {
-        error!(
-            "create_request_string failed for user_id {}: {}",
-            user_id, e
-        );
+        error!("prune_orphan_exercises failed: {}", e);
         anyhow::Error::from(e)
-    })
-}
+    })?;
+    pruned.sort_by(|a, b| a.name.cmp(&b.name));
 
-pub async fn create_request_string_for_username(
-    pool: &SqlitePool,
-    username: &str,
-    input: String,
-) -> Result<RequestString> {
-    debug!(
-        "create_request_string_for_username called username={}",
-        username
-    );
-    let user = get_or_create_user(pool, username).await?;
-    create_request_string(pool, user.id, input).await
+    info!("pruned {} orphan exercises", pruned.len());
+    Ok(pruned)
 }
 
-pub async fn add_workout_set(
-    pool: &SqlitePool,
-    session_id: &i64,
-    exercise_id: &i64,
-    request_string_id: &i64,
-    weight: &f64,
-    reps: &i64,
-    rpe: Option<f64>,
-) -> Result<WorkoutSet> {
+/// Folds a duplicate exercise into `keep_id`: its sets, template entries, muscle and
+/// equipment links and aliases move over, its name becomes an alias of the survivor, and the duplicate row is
+/// deleted. Returns the number of sets moved.
+pub async fn merge_exercises(pool: &SqlitePool, keep_id: i64, remove_id: i64) -> Result<u64> {
     debug!(
-        "add_workout_set called session_id={} exercise_id={} weight={} reps={} rpe={:?}",
-        session_id, exercise_id, weight, reps, rpe
+        "merge_exercises called keep_id={} remove_id={}",
+        keep_id, remove_id
     );
+    if keep_id == remove_id {
+        return Err(anyhow::anyhow!(
+            "Cannot merge exercise {} into itself",
+            keep_id
+        ));
+    }
+    let keep = get_exercise(pool, keep_id).await?;
+    let remove = get_exercise(pool, remove_id).await?;
+    let now = chrono::Utc::now().timestamp();
 
-    let max_index: Option<i64> = sqlx::query_scalar::<_, i64>(
-        "SELECT MAX(set_index) FROM workout_sets WHERE session_id = ?1 AND exercise_id = ?2",
+    let mut tx = pool.begin().await?;
+
+    let moved = sqlx::query(
+        "UPDATE workout_sets SET exercise_id = ?1, updated_at = ?2 WHERE exercise_id = ?3",
     )
-    .bind(session_id)
-    .bind(exercise_id)
-    .fetch_optional(pool)
-    .await?;
+    .bind(keep_id)
+    .bind(now)
+    .bind(remove_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
 
-    let next_index = max_index.map(|n| n + 1).unwrap_or(1);
-    let now = chrono::Utc::now().timestamp();
+    sqlx::query(
+        "INSERT OR IGNORE INTO exercise_muscles (exercise_id, muscle_id, relation_type, strength)
+         SELECT ?1, muscle_id, relation_type, strength FROM exercise_muscles WHERE exercise_id = ?2",
+    )
+    .bind(keep_id)
+    .bind(remove_id)
+    .execute(&mut *tx)
+    .await?;
 
-    let created = sqlx::query_as::<_, WorkoutSet>(
-        "INSERT INTO workout_sets (session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
-         RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at"
+    sqlx::query(
+        "INSERT OR IGNORE INTO exercise_equipment (exercise_id, equipment_id, relation_type)
+         SELECT ?1, equipment_id, relation_type FROM exercise_equipment WHERE exercise_id = ?2",
     )
-    .bind(session_id)
-    .bind(exercise_id)
-    .bind(request_string_id)
-    .bind(weight)
-    .bind(reps)
-    .bind(next_index)
-    .bind(rpe)
-    .bind(None::<String>)
-    .bind(now)
-    .fetch_one(pool)
-    .await
-    .map_err(|e| {
+    .bind(keep_id)
+    .bind(remove_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("UPDATE template_exercises SET exercise_id = ?1 WHERE exercise_id = ?2")
+        .bind(keep_id)
+        .bind(remove_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE exercise_aliases SET exercise_id = ?1 WHERE exercise_id = ?2")
+        .bind(keep_id)
+        .bind(remove_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("INSERT OR IGNORE INTO exercise_aliases (alias, exercise_id) VALUES (?1, ?2)")
+        .bind(normalize_alias(&remove.name))
+        .bind(keep_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM exercises WHERE id = ?1")
+        .bind(remove_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await.map_err(|e| {
         error!(
-            "add_workout_set failed inserting session_id={} exercise_id={}: {}",
-            session_id, exercise_id, e
+            "merge_exercises failed committing keep_id={} remove_id={}: {}",
+            keep_id, remove_id, e
         );
         anyhow::Error::from(e)
     })?;
 
     info!(
-        "added workout set id={} session_id={} exercise_id={} set_index={}",
-        created.id, created.session_id, created.exercise_id, created.set_index
+        "merged exercise {} into {}, moved {} sets",
+        remove.name, keep.name, moved
     );
-    Ok(created)
+    Ok(moved)
 }
 
-pub async fn add_multiple_sets_to_workout(
-    pool: &SqlitePool,
-    session_id: &i64,
-    exercise_id: &i64,
-    request_string_id: &i64,
-    weight: &f64,
-    reps: &i64,
-    rpe: Option<f64>,
-    set_count: i64,
-) -> Result<Vec<WorkoutSet>> {
-    debug!(
-        "add_multiple_sets_to_workout called session_id={} exercise_id={} set_count={}",
-        session_id, exercise_id, set_count
-    );
-
-    let max_index: Option<i64> = sqlx::query_scalar::<_, i64>(
-        "SELECT MAX(set_index) FROM workout_sets WHERE session_id = ?1 AND exercise_id = ?2",
-    )
-    .bind(session_id)
-    .bind(exercise_id)
-    .fetch_optional(pool)
-    .await?;
+pub async fn get_or_create_exercise(pool: &SqlitePool, exercise_name: &str) -> Result<Exercise> {
+    debug!("get_or_create_exercise called name={}", exercise_name);
+    let mut tx = pool.begin().await?;
+    let exercise = get_or_create_exercise_tx(&mut tx, exercise_name).await?;
+    tx.commit().await?;
+    Ok(exercise)
+}
 
-    let starting_index = max_index.map(|n| n + 1).unwrap_or(1);
-    let now = chrono::Utc::now().timestamp();
+/// Looks up an exercise by alias or name, creating it if neither matches.
+pub async fn get_or_create_exercise_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    exercise_name: &str,
+) -> Result<Exercise> {
+    debug!("get_or_create_exercise_tx called name={}", exercise_name);
+    if let Some(exercise) = resolve_alias_tx(tx, exercise_name).await? {
+        debug!(
+            "resolved alias {} to exercise id={} name={}",
+            exercise_name, exercise.id, exercise.name
+        );
+        return Ok(exercise);
+    }
+    let slug = slugify(exercise_name);
 
-    let mut created = Vec::new();
-    for i in 0..set_count {
-        let set_index = starting_index + i;
-        let set = sqlx::query_as::<_, WorkoutSet>(
-            "INSERT INTO workout_sets (session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
-             RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at"
-        )
-        .bind(session_id)
-        .bind(exercise_id)
-        .bind(request_string_id)
-        .bind(weight)
-        .bind(reps)
-        .bind(set_index)
-        .bind(rpe)
-        .bind(None::<String>)
-        .bind(now)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| {
-            error!(
-                "add_multiple_sets_to_workout failed inserting session_id={} exercise_id={}: {}",
-                session_id, exercise_id, e
-            );
-            anyhow::Error::from(e)
-        })?;
-        created.push(set);
+    if let Some(exercise) = sqlx::query_as::<_, Exercise>(
+        "SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at
+         FROM exercises WHERE slug = ?1",
+    )
+    .bind(&slug)
+    .fetch_optional(&mut **tx)
+    .await?
+    {
+        debug!(
+            "found existing exercise id={} name={}",
+            exercise.id, exercise.name
+        );
+        return Ok(exercise);
     }
 
-    info!(
-        "added {} workout sets starting_index={} session_id={} exercise_id={}",
-        created.len(),
-        starting_index,
-        session_id,
-        exercise_id
-    );
-    Ok(created)
-}
+    let now = chrono::Utc::now().timestamp();
 
-pub async fn get_sets_for_session(pool: &SqlitePool, session_id: i64) -> Result<Vec<WorkoutSet>> {
-    debug!("get_sets_for_session called session_id={}", session_id);
-    sqlx::query_as::<_, WorkoutSet>(
-        "SELECT id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at
-         FROM workout_sets WHERE session_id = ?1 ORDER BY set_index ASC"
+    let created = sqlx::query_as::<_, Exercise>(
+        "INSERT INTO exercises (slug, name, description, experience_level, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+         RETURNING id, slug, name, description, is_favorite, experience_level, created_at, updated_at",
     )
-    .bind(session_id)
-    .fetch_all(pool)
+    .bind(slug)
+    .bind(exercise_name)
+    .bind(None::<String>)
+    .bind(ExperienceLevel::default_for_exercise(exercise_name))
+    .bind(now)
+    .fetch_one(&mut **tx)
     .await
     .map_err(|e| {
-        warn!(
-            "get_sets_for_session failed for session_id {}: {}",
-            session_id, e
+        error!(
+            "get_or_create_exercise_tx failed inserting {}: {}",
+            exercise_name, e
         );
         anyhow::Error::from(e)
-    })
+    })?;
+
+    info!("created exercise id={} name={}", created.id, created.name);
+    Ok(created)
 }
 
-pub async fn update_workout_set(
-    pool: &SqlitePool,
-    set_id: i64,
-    update: &UpdateWorkoutSet,
-) -> Result<WorkoutSet> {
-    debug!("update_workout_set called set_id={}", set_id);
+pub async fn get_all_muscles(pool: &SqlitePool) -> Result<Vec<Muscle>> {
+    debug!("get_all_muscles called");
+    sqlx::query_as::<_, Muscle>("SELECT id, name, created_at, updated_at FROM muscles")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            warn!("get_all_muscles failed: {}", e);
+            anyhow::Error::from(e)
+        })
+}
 
-    let now = chrono::Utc::now().timestamp();
-    sqlx::query_as::<_, WorkoutSet>(
-        "UPDATE workout_sets SET
-         session_id = CASE WHEN ?1 IS NOT NULL THEN ?1 ELSE session_id END,
-         exercise_id = CASE WHEN ?2 IS NOT NULL THEN ?2 ELSE exercise_id END,
-         request_string_id = CASE WHEN ?3 IS NOT NULL THEN ?3 ELSE request_string_id END,
-         weight = CASE WHEN ?4 IS NOT NULL THEN ?4 ELSE weight END,
-         reps = CASE WHEN ?5 IS NOT NULL THEN ?5 ELSE reps END,
-         set_index = CASE WHEN ?6 IS NOT NULL THEN ?6 ELSE set_index END,
-         rpe = CASE WHEN ?7 IS NOT NULL THEN ?7 ELSE rpe END,
-         notes = ?8,
-         updated_at = ?9
-         WHERE id = ?10
-         RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at"
+pub async fn get_muscle(pool: &SqlitePool, muscle_id: i64) -> Result<Muscle> {
+    debug!("get_muscle called muscle_id={}", muscle_id);
+
+    sqlx::query_as::<_, Muscle>(
+        "SELECT id, name, created_at, updated_at FROM muscles WHERE id = ?1",
     )
-    .bind(update.session_id)
-    .bind(update.exercise_id)
-    .bind(update.request_string_id)
-    .bind(update.weight)
-    .bind(update.reps)
-    .bind(update.set_index)
-    .bind(update.rpe)
-    .bind(update.notes.clone())
-    .bind(now)
-    .bind(set_id)
+    .bind(muscle_id)
     .fetch_one(pool)
     .await
     .map_err(|e| {
-        warn!("update_workout_set failed for set_id {}: {}", set_id, e);
+        warn!("get_muscle failed for id {}: {}", muscle_id, e);
         anyhow::Error::from(e)
     })
 }
 
-async fn get_workout_set_by_id(pool: &SqlitePool, set_id: i64) -> Result<WorkoutSet> {
-    sqlx::query_as::<_, WorkoutSet>(
-        "SELECT id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at
-         FROM workout_sets WHERE id = ?1"
+pub async fn get_or_create_muscle(pool: &SqlitePool, muscle_name: &str) -> Result<Muscle> {
+    debug!("get_or_create_muscle called name={}", muscle_name);
+
+    if let Some(muscle) = sqlx::query_as::<_, Muscle>(
+        "SELECT id, name, created_at, updated_at
+         FROM muscles WHERE name = ?1",
     )
-    .bind(set_id)
+    .bind(muscle_name)
+    .fetch_optional(pool)
+    .await?
+    {
+        debug!(
+            "found existing muscle id={} name={}",
+            muscle.id, muscle.name
+        );
+        return Ok(muscle);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+
+    let created = sqlx::query_as::<_, Muscle>(
+        "INSERT INTO muscles (name, created_at, updated_at)
+         VALUES (?1, ?2, ?2)
+         RETURNING id, name, created_at, updated_at",
+    )
+    .bind(muscle_name)
+    .bind(now)
     .fetch_one(pool)
     .await
-    .map_err(|e| anyhow::Error::from(e))
+    .map_err(|e| {
+        error!(
+            "get_or_create_muscle failed inserting {}: {}",
+            muscle_name, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    info!("created muscle id={} name={}", created.id, created.name);
+    Ok(created)
 }
 
-pub async fn update_workout_set_from_parsed(
+pub async fn link_exercise_to_muscle(
     pool: &SqlitePool,
-    set_id: i64,
-    parsed: &ParsedSet,
-) -> Result<WorkoutSet> {
+    link: &NewExerciseMuscle,
+) -> Result<ExerciseMuscle> {
     debug!(
-        "update_workout_set_from_parsed called set_id={} parsed={:?}",
-        set_id, parsed
+        "link_exercise_to_muscle called exercise_id={} muscle_id={} relation_type={}",
+        link.exercise_id, link.muscle_id, link.relation_type
     );
-    let original = get_workout_set_by_id(pool, set_id).await.map_err(|e| {
-        error!("failed to load original set id {}: {}", set_id, e);
+    let now = chrono::Utc::now().timestamp();
+
+    sqlx::query_as::<_, ExerciseMuscle>(
+        "INSERT INTO exercise_muscles (exercise_id, muscle_id, relation_type, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT(exercise_id, muscle_id) DO UPDATE SET relation_type = excluded.relation_type, updated_at = excluded.updated_at
+         RETURNING exercise_id, muscle_id, relation_type, created_at, updated_at",
+    )
+    .bind(link.exercise_id)
+    .bind(link.muscle_id)
+    .bind(&link.relation_type)
+    .bind(now)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "link_exercise_to_muscle failed for exercise_id {} muscle_id {}: {}",
+            link.exercise_id, link.muscle_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Muscles linked to an exercise with each link's relation type, primary muscles first and
+/// then by name.
+pub async fn get_muscles_for_exercise(
+    pool: &SqlitePool,
+    exercise_id: i64,
+) -> Result<Vec<(Muscle, String)>> {
+    debug!(
+        "get_muscles_for_exercise called exercise_id={}",
+        exercise_id
+    );
+    let rows = sqlx::query_as::<_, (i64, String, i64, i64, String)>(
+        "SELECT m.id, m.name, m.created_at, m.updated_at, em.relation_type
+         FROM exercise_muscles em
+         JOIN muscles m ON m.id = em.muscle_id
+         WHERE em.exercise_id = ?1
+         ORDER BY em.relation_type != 'primary', m.name ASC",
+    )
+    .bind(exercise_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_muscles_for_exercise failed for exercise_id {}: {}",
+            exercise_id, e
+        );
         anyhow::Error::from(e)
     })?;
 
-    let exercise_id_opt = if !parsed.exercise.is_empty() {
-        let exercise = get_or_create_exercise(pool, &parsed.exercise).await?;
-        if exercise.id != original.exercise_id {
-            Some(exercise.id)
-        } else {
-            None
+    Ok(rows
+        .into_iter()
+        .map(|(id, name, created_at, updated_at, relation_type)| {
+            (
+                Muscle {
+                    id,
+                    name,
+                    created_at,
+                    updated_at,
+                },
+                relation_type,
+            )
+        })
+        .collect())
+}
+
+/// Number of sets in a workout per primary muscle, most-worked first.
+pub async fn get_muscle_set_counts(
+    pool: &SqlitePool,
+    session_id: i64,
+) -> Result<Vec<(Muscle, i64)>> {
+    debug!("get_muscle_set_counts called session_id={}", session_id);
+    let rows = sqlx::query_as::<_, (i64, String, i64, i64, i64)>(
+        "SELECT m.id, m.name, m.created_at, m.updated_at, COUNT(ws.id) AS set_count
+         FROM workout_sets ws
+         JOIN exercise_muscles em ON em.exercise_id = ws.exercise_id AND em.relation_type = 'primary'
+         JOIN muscles m ON m.id = em.muscle_id
+         WHERE ws.session_id = ?1
+         GROUP BY m.id
+         ORDER BY set_count DESC, m.name ASC",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_muscle_set_counts failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, name, created_at, updated_at, set_count)| {
+            (
+                Muscle {
+                    id,
+                    name,
+                    created_at,
+                    updated_at,
+                },
+                set_count,
+            )
+        })
+        .collect())
+}
+
+pub async fn get_or_create_equipment(pool: &SqlitePool, equipment_name: &str) -> Result<Equipment> {
+    debug!("get_or_create_equipment called name={}", equipment_name);
+
+    if let Some(equipment) = sqlx::query_as::<_, Equipment>(
+        "SELECT id, name, created_at, updated_at
+         FROM equipment WHERE name = ?1",
+    )
+    .bind(equipment_name)
+    .fetch_optional(pool)
+    .await?
+    {
+        debug!(
+            "found existing equipment id={} name={}",
+            equipment.id, equipment.name
+        );
+        return Ok(equipment);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+
+    let created = sqlx::query_as::<_, Equipment>(
+        "INSERT INTO equipment (name, created_at, updated_at)
+         VALUES (?1, ?2, ?2)
+         RETURNING id, name, created_at, updated_at",
+    )
+    .bind(equipment_name)
+    .bind(now)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "get_or_create_equipment failed inserting {}: {}",
+            equipment_name, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    info!("created equipment id={} name={}", created.id, created.name);
+    Ok(created)
+}
+
+pub async fn list_equipment(pool: &SqlitePool) -> Result<Vec<Equipment>> {
+    debug!("list_equipment called");
+    sqlx::query_as::<_, Equipment>(
+        "SELECT id, name, created_at, updated_at FROM equipment ORDER BY name ASC",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!("list_equipment failed: {}", e);
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn link_exercise_to_equipment(
+    pool: &SqlitePool,
+    link: &NewExerciseEquipment,
+) -> Result<()> {
+    debug!(
+        "link_exercise_to_equipment called exercise_id={} equipment_id={} relation_type={}",
+        link.exercise_id, link.equipment_id, link.relation_type
+    );
+    let now = chrono::Utc::now().timestamp();
+
+    sqlx::query(
+        "INSERT INTO exercise_equipment (exercise_id, equipment_id, relation_type, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT(exercise_id, equipment_id) DO UPDATE SET relation_type = excluded.relation_type, updated_at = excluded.updated_at",
+    )
+    .bind(link.exercise_id)
+    .bind(link.equipment_id)
+    .bind(&link.relation_type)
+    .bind(now)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "link_exercise_to_equipment failed for exercise_id {} equipment_id {}: {}",
+            link.exercise_id, link.equipment_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+    Ok(())
+}
+
+/// Starter exercises for a fresh database: name, primary muscles, required equipment. Names
+/// slugify to the targets of the seeded aliases, so "squat" or "ohp" resolve to them.
+const DEFAULT_EXERCISES: &[(&str, &[&str], &[&str])] = &[
+    ("Bench Press", &["Chest", "Triceps"], &["Barbell", "Bench"]),
+    (
+        "Barbell Back Squat",
+        &["Quadriceps", "Glutes"],
+        &["Barbell", "Squat Rack"],
+    ),
+    ("Deadlift", &["Hamstrings", "Glutes"], &["Barbell"]),
+    ("Overhead Press", &["Shoulders", "Triceps"], &["Barbell"]),
+    ("Barbell Row", &["Lats", "Biceps"], &["Barbell"]),
+    ("Pull-Up", &["Lats", "Biceps"], &["Pull-up Bar"]),
+    ("Romanian Deadlift", &["Hamstrings"], &["Barbell"]),
+    ("Dumbbell Curl", &["Biceps"], &["Dumbbells"]),
+    ("Tricep Pushdown", &["Triceps"], &["Cable Machine"]),
+    ("Lateral Raise", &["Shoulders"], &["Dumbbells"]),
+    ("Lunge", &["Quadriceps", "Glutes"], &[]),
+    ("Calf Raise", &["Calves"], &[]),
+    ("Plank", &["Abs"], &[]),
+];
+
+/// Inserts the curated starter exercises with their muscles and equipment. Everything goes
+/// through the `get_or_create_*` and upserting link operations, so running it again on a
+/// seeded database adds nothing.
+pub async fn seed_defaults(pool: &SqlitePool) -> Result<()> {
+    debug!("seed_defaults called");
+    for (exercise_name, muscles, equipment) in DEFAULT_EXERCISES {
+        let exercise = get_or_create_exercise(pool, exercise_name).await?;
+        for muscle_name in *muscles {
+            let muscle = get_or_create_muscle(pool, muscle_name).await?;
+            link_exercise_to_muscle(
+                pool,
+                &NewExerciseMuscle {
+                    exercise_id: exercise.id,
+                    muscle_id: muscle.id,
+                    relation_type: "primary".to_string(),
+                },
+            )
+            .await?;
+        }
+        for equipment_name in *equipment {
+            let item = get_or_create_equipment(pool, equipment_name).await?;
+            link_exercise_to_equipment(
+                pool,
+                &NewExerciseEquipment {
+                    exercise_id: exercise.id,
+                    equipment_id: item.id,
+                    relation_type: "requires".to_string(),
+                },
+            )
+            .await?;
         }
+    }
+    info!("seeded {} default exercises", DEFAULT_EXERCISES.len());
+    Ok(())
+}
+
+pub async fn set_equipment_owned(
+    pool: &SqlitePool,
+    user_id: i64,
+    equipment_id: i64,
+    owned: bool,
+) -> Result<()> {
+    debug!(
+        "set_equipment_owned called user_id={} equipment_id={} owned={}",
+        user_id, equipment_id, owned
+    );
+    let query = if owned {
+        sqlx::query(
+            "INSERT OR IGNORE INTO user_equipment (user_id, equipment_id, created_at)
+             VALUES (?1, ?2, ?3)",
+        )
+        .bind(user_id)
+        .bind(equipment_id)
+        .bind(chrono::Utc::now().timestamp())
     } else {
-        None
+        sqlx::query("DELETE FROM user_equipment WHERE user_id = ?1 AND equipment_id = ?2")
+            .bind(user_id)
+            .bind(equipment_id)
     };
 
-    let weight_opt = parsed.weight.map(|w| w as f64);
-    let rpe_opt = parsed.rpe.map(|r| r as f64);
+    query.execute(pool).await.map_err(|e| {
+        error!(
+            "set_equipment_owned failed for user_id {} equipment_id {}: {}",
+            user_id, equipment_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+    Ok(())
+}
 
-    let update = UpdateWorkoutSet {
-        session_id: None,
-        exercise_id: exercise_id_opt,
-        request_string_id: None,
-        weight: weight_opt,
-        reps: parsed.reps.map(|r| r as i64),
-        set_index: None,
-        rpe: rpe_opt,
-        notes: None,
-    };
+pub async fn get_owned_equipment(pool: &SqlitePool, user_id: i64) -> Result<Vec<Equipment>> {
+    debug!("get_owned_equipment called user_id={}", user_id);
+    sqlx::query_as::<_, Equipment>(
+        "SELECT e.id, e.name, e.created_at, e.updated_at
+         FROM equipment e
+         JOIN user_equipment ue ON ue.equipment_id = e.id
+         WHERE ue.user_id = ?1
+         ORDER BY e.name ASC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!("get_owned_equipment failed for user_id {}: {}", user_id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+/// Returns the exercises a user can perform with the equipment they own: every piece of
+/// equipment linked to the exercise must be owned. Exercises with no equipment links
+/// (bodyweight) are always included.
+pub async fn get_exercises_for_owned_equipment(
+    pool: &SqlitePool,
+    user_id: i64,
+) -> Result<Vec<Exercise>> {
+    debug!(
+        "get_exercises_for_owned_equipment called user_id={}",
+        user_id
+    );
+    sqlx::query_as::<_, Exercise>(
+        "SELECT id, slug, name, description, is_favorite, experience_level, created_at, updated_at
+         FROM exercises
+         WHERE NOT EXISTS (
+             SELECT 1 FROM exercise_equipment ee
+             WHERE ee.exercise_id = exercises.id
+               AND ee.equipment_id NOT IN (SELECT equipment_id FROM user_equipment WHERE user_id = ?1)
+         )
+         ORDER BY name ASC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_exercises_for_owned_equipment failed for user_id {}: {}",
+            user_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn get_or_create_user(pool: &SqlitePool, username: &str) -> Result<User> {
+    debug!("get_or_create_user called username={}", username);
+    let mut tx = pool.begin().await?;
+    let user = get_or_create_user_tx(&mut tx, username).await?;
+    tx.commit().await?;
+    Ok(user)
+}
+
+pub async fn get_or_create_user_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    username: &str,
+) -> Result<User> {
+    debug!("get_or_create_user_tx called username={}", username);
+
+    if let Some(u) = sqlx::query_as::<_, User>(
+        "SELECT id, username, created_at, updated_at
+         FROM users WHERE username = ?1",
+    )
+    .bind(username)
+    .fetch_optional(&mut **tx)
+    .await?
+    {
+        debug!("found existing user id={} username={}", u.id, u.username);
+        return Ok(u);
+    }
+
+    let now = chrono::Utc::now().timestamp();
+
+    let created = sqlx::query_as::<_, User>(
+        "INSERT INTO users (username, created_at, updated_at)
+         VALUES (?1, ?2, ?2)
+         RETURNING id, username, created_at, updated_at",
+    )
+    .bind(username)
+    .bind(now)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        error!("get_or_create_user_tx failed inserting {}: {}", username, e);
+        anyhow::Error::from(e)
+    })?;
+
+    info!(
+        "created user id={} username={}",
+        created.id, created.username
+    );
+    Ok(created)
+}
+
+/// Records a bodyweight measurement taken at `recorded_at` (unix seconds).
+pub async fn add_bodyweight(
+    pool: &SqlitePool,
+    user_id: i64,
+    weight: f64,
+    recorded_at: i64,
+) -> Result<BodyweightEntry> {
+    debug!(
+        "add_bodyweight called user_id={} weight={} recorded_at={}",
+        user_id, weight, recorded_at
+    );
+
+    if !weight.is_finite() || weight <= 0.0 {
+        return Err(anyhow::anyhow!(
+            "Bodyweight must be a positive number, got {}",
+            weight
+        ));
+    }
+
+    let entry = sqlx::query_as::<_, BodyweightEntry>(
+        "INSERT INTO bodyweight_entries (user_id, weight, recorded_at) VALUES (?1, ?2, ?3)
+         RETURNING id, user_id, weight, recorded_at, created_at",
+    )
+    .bind(user_id)
+    .bind(weight)
+    .bind(recorded_at)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!("add_bodyweight failed for user_id {}: {}", user_id, e);
+        anyhow::Error::from(e)
+    })?;
+
+    info!(
+        "recorded bodyweight id={} user_id={} weight={}",
+        entry.id, entry.user_id, entry.weight
+    );
+    Ok(entry)
+}
+
+/// Bodyweight entries for a user, oldest first, optionally only those recorded at or after
+/// `since`.
+pub async fn get_bodyweight_series(
+    pool: &SqlitePool,
+    user_id: i64,
+    since: Option<i64>,
+) -> Result<Vec<BodyweightEntry>> {
+    debug!(
+        "get_bodyweight_series called user_id={} since={:?}",
+        user_id, since
+    );
+    sqlx::query_as::<_, BodyweightEntry>(
+        "SELECT id, user_id, weight, recorded_at, created_at FROM bodyweight_entries
+         WHERE user_id = ?1 AND (?2 IS NULL OR recorded_at >= ?2)
+         ORDER BY recorded_at ASC, id ASC",
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "get_bodyweight_series failed for user_id {}: {}",
+            user_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn delete_bodyweight_entry(pool: &SqlitePool, entry_id: i64) -> Result<u64> {
+    debug!("delete_bodyweight_entry called entry_id={}", entry_id);
+    let result = sqlx::query("DELETE FROM bodyweight_entries WHERE id = ?1")
+        .bind(entry_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!("delete_bodyweight_entry failed for id {}: {}", entry_id, e);
+            anyhow::Error::from(e)
+        })?;
+    Ok(result.rows_affected())
+}
+
+pub async fn create_request_string(
+    pool: &SqlitePool,
+    user_id: i64,
+    input: String,
+) -> Result<RequestString> {
+    debug!(
+        "create_request_string called user_id={} input_len={}",
+        user_id,
+        input.len()
+    );
+
+    let now = chrono::Utc::now().timestamp();
+
+    sqlx::query_as::<_, RequestString>(
+        "INSERT INTO request_strings (user_id, string, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?3)
+         RETURNING id, user_id, string, created_at, updated_at",
+    )
+    .bind(user_id)
+    .bind(input)
+    .bind(now)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "create_request_string failed for user_id {}: {}",
+            user_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn create_request_string_for_username(
+    pool: &SqlitePool,
+    username: &str,
+    input: String,
+) -> Result<RequestString> {
+    debug!(
+        "create_request_string_for_username called username={}",
+        username
+    );
+    let mut tx = pool.begin().await?;
+    let request = create_request_string_for_username_tx(&mut tx, username, input).await?;
+    tx.commit().await?;
+    Ok(request)
+}
+
+pub async fn create_request_string_for_username_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    username: &str,
+    input: String,
+) -> Result<RequestString> {
+    debug!(
+        "create_request_string_for_username_tx called username={}",
+        username
+    );
+    let user = get_or_create_user_tx(tx, username).await?;
+    let now = chrono::Utc::now().timestamp();
+
+    sqlx::query_as::<_, RequestString>(
+        "INSERT INTO request_strings (user_id, string, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?3)
+         RETURNING id, user_id, string, created_at, updated_at",
+    )
+    .bind(user.id)
+    .bind(input)
+    .bind(now)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        error!(
+            "create_request_string_for_username_tx failed for user_id {}: {}",
+            user.id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Most reps accepted for a single set; anything above is taken to be a bad parse.
+const MAX_REPS: i64 = 1000;
+
+/// Rejects set values no real set has: negative weight, negative or more than [`MAX_REPS`]
+/// reps, and an RPE outside 0 to 10.
+fn validate_set_values(weight: f64, reps: i64, rpe: Option<f64>) -> Result<()> {
+    if weight.is_nan() || weight < 0.0 {
+        return Err(anyhow::anyhow!(
+            "Invalid weight {}: must not be negative",
+            weight
+        ));
+    }
+    if !(0..=MAX_REPS).contains(&reps) {
+        return Err(anyhow::anyhow!(
+            "Invalid reps {}: must be between 0 and {}",
+            reps,
+            MAX_REPS
+        ));
+    }
+    if let Some(rpe) = rpe.filter(|rpe| !(0.0..=10.0).contains(rpe)) {
+        return Err(anyhow::anyhow!(
+            "Invalid RPE {}: must be between 0 and 10",
+            rpe
+        ));
+    }
+    Ok(())
+}
+
+pub async fn add_workout_set(
+    pool: &SqlitePool,
+    session_id: &i64,
+    exercise_id: &i64,
+    request_string_id: &i64,
+    weight: &f64,
+    reps: &i64,
+    rpe: Option<f64>,
+) -> Result<WorkoutSet> {
+    debug!(
+        "add_workout_set called session_id={} exercise_id={} weight={} reps={} rpe={:?}",
+        session_id, exercise_id, weight, reps, rpe
+    );
+    validate_set_values(*weight, *reps, rpe)?;
+
+    let max_index: Option<i64> = sqlx::query_scalar::<_, i64>(
+        "SELECT MAX(set_index) FROM workout_sets WHERE session_id = ?1 AND exercise_id = ?2",
+    )
+    .bind(session_id)
+    .bind(exercise_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let next_index = max_index.map(|n| n + 1).unwrap_or(1);
+    let now = chrono::Utc::now().timestamp();
+
+    let created = sqlx::query_as::<_, WorkoutSet>(
+        "INSERT INTO workout_sets (session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)
+         RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at"
+    )
+    .bind(session_id)
+    .bind(exercise_id)
+    .bind(request_string_id)
+    .bind(weight)
+    .bind(reps)
+    .bind(next_index)
+    .bind(rpe)
+    .bind(None::<String>)
+    .bind(now)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "add_workout_set failed inserting session_id={} exercise_id={}: {}",
+            session_id, exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    info!(
+        "added workout set id={} session_id={} exercise_id={} set_index={}",
+        created.id, created.session_id, created.exercise_id, created.set_index
+    );
+    Ok(created)
+}
+
+pub async fn insert_workout_set_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    new_set: &NewWorkoutSet,
+) -> Result<WorkoutSet> {
+    debug!(
+        "insert_workout_set_tx called session_id={} exercise_id={} set_index={}",
+        new_set.session_id, new_set.exercise_id, new_set.set_index
+    );
+
+    let now = chrono::Utc::now().timestamp();
+    let created = sqlx::query_as::<_, WorkoutSet>(
+        "INSERT INTO workout_sets (session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?12)
+         RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at"
+    )
+    .bind(new_set.session_id)
+    .bind(new_set.exercise_id)
+    .bind(new_set.request_string_id)
+    .bind(new_set.weight)
+    .bind(new_set.reps)
+    .bind(new_set.set_index)
+    .bind(new_set.rpe)
+    .bind(new_set.notes.clone())
+    .bind(new_set.rest_style)
+    .bind(new_set.duration_seconds)
+    .bind(new_set.distance_meters)
+    .bind(now)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        error!(
+            "insert_workout_set_tx failed inserting session_id={} exercise_id={}: {}",
+            new_set.session_id, new_set.exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    info!(
+        "inserted workout set id={} session_id={} exercise_id={} set_index={} (in transaction)",
+        created.id, created.session_id, created.exercise_id, created.set_index
+    );
+    Ok(created)
+}
+
+/// Writes `set` back exactly as it was, NULL fields included, re-inserting it under its old
+/// id if it has been deleted since.
+pub async fn restore_workout_set(pool: &SqlitePool, set: &WorkoutSet) -> Result<WorkoutSet> {
+    debug!("restore_workout_set called set_id={}", set.id);
+
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query_as::<_, WorkoutSet>(
+        "INSERT INTO workout_sets (id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+         ON CONFLICT(id) DO UPDATE SET
+         session_id = excluded.session_id,
+         exercise_id = excluded.exercise_id,
+         request_string_id = excluded.request_string_id,
+         weight = excluded.weight,
+         reps = excluded.reps,
+         set_index = excluded.set_index,
+         rpe = excluded.rpe,
+         notes = excluded.notes,
+         rest_style = excluded.rest_style,
+         duration_seconds = excluded.duration_seconds,
+         distance_meters = excluded.distance_meters,
+         superset_group = excluded.superset_group,
+         updated_at = excluded.updated_at
+         RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at"
+    )
+    .bind(set.id)
+    .bind(set.session_id)
+    .bind(set.exercise_id)
+    .bind(set.request_string_id)
+    .bind(set.weight)
+    .bind(set.reps)
+    .bind(set.set_index)
+    .bind(set.rpe)
+    .bind(set.notes.clone())
+    .bind(set.rest_style)
+    .bind(set.duration_seconds)
+    .bind(set.distance_meters)
+    .bind(set.superset_group)
+    .bind(set.created_at)
+    .bind(now)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        warn!("restore_workout_set failed for set_id {}: {}", set.id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn add_multiple_sets_to_workout(
+    pool: &SqlitePool,
+    session_id: &i64,
+    exercise_id: &i64,
+    request_string_id: &i64,
+    weight: &f64,
+    reps: &i64,
+    rpe: Option<f64>,
+    set_count: i64,
+) -> Result<Vec<WorkoutSet>> {
+    debug!(
+        "add_multiple_sets_to_workout called session_id={} exercise_id={} set_count={}",
+        session_id, exercise_id, set_count
+    );
+    let mut tx = pool.begin().await?;
+    let new_set = NewWorkoutSet {
+        session_id: *session_id,
+        exercise_id: *exercise_id,
+        request_string_id: *request_string_id,
+        weight: *weight,
+        reps: *reps,
+        set_index: 0,
+        rpe,
+        notes: None,
+        rest_style: None,
+        duration_seconds: None,
+        distance_meters: None,
+    };
+    let created = add_workout_sets_tx(&mut tx, &new_set, set_count).await?;
+
+    tx.commit().await.map_err(|e| {
+        error!(
+            "add_multiple_sets_to_workout failed committing session_id={} exercise_id={}: {}",
+            session_id, exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    info!(
+        "added {} workout sets session_id={} exercise_id={}",
+        created.len(),
+        session_id,
+        exercise_id
+    );
+    Ok(created)
+}
+
+/// Appends `set_count` copies of `new_set` after the exercise's last set in the workout.
+/// Their set indices continue from that set, so `new_set.set_index` is ignored.
+pub async fn add_workout_sets_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    new_set: &NewWorkoutSet,
+    set_count: i64,
+) -> Result<Vec<WorkoutSet>> {
+    debug!(
+        "add_workout_sets_tx called session_id={} exercise_id={} set_count={}",
+        new_set.session_id, new_set.exercise_id, set_count
+    );
+    validate_set_values(new_set.weight, new_set.reps, new_set.rpe)?;
+
+    let max_index: Option<i64> = sqlx::query_scalar::<_, i64>(
+        "SELECT MAX(set_index) FROM workout_sets WHERE session_id = ?1 AND exercise_id = ?2",
+    )
+    .bind(new_set.session_id)
+    .bind(new_set.exercise_id)
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let starting_index = max_index.map(|n| n + 1).unwrap_or(1);
+
+    let mut created = Vec::with_capacity(set_count.max(0) as usize);
+    for i in 0..set_count {
+        let new_set = NewWorkoutSet {
+            set_index: starting_index + i,
+            ..new_set.clone()
+        };
+        created.push(insert_workout_set_tx(tx, &new_set).await?);
+    }
+    Ok(created)
+}
+
+/// Lists a session's sets in logged order. `limit` and `offset` page through them; with both
+/// `None` every set is returned.
+pub async fn get_sets_for_session(
+    pool: &SqlitePool,
+    session_id: i64,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<WorkoutSet>> {
+    debug!(
+        "get_sets_for_session called session_id={} limit={:?} offset={:?}",
+        session_id, limit, offset
+    );
+    sqlx::query_as::<_, WorkoutSet>(
+        "SELECT id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at
+         FROM workout_sets WHERE session_id = ?1 ORDER BY set_index ASC, id ASC
+         LIMIT ?2 OFFSET ?3"
+    )
+    .bind(session_id)
+    .bind(limit.unwrap_or(-1))
+    .bind(offset.unwrap_or(0))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_sets_for_session failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Every set of a session, in logged order.
+pub async fn get_sets_for_session_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    session_id: i64,
+) -> Result<Vec<WorkoutSet>> {
+    debug!("get_sets_for_session_tx called session_id={}", session_id);
+    sqlx::query_as::<_, WorkoutSet>(
+        "SELECT id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at
+         FROM workout_sets WHERE session_id = ?1 ORDER BY set_index ASC, id ASC"
+    )
+    .bind(session_id)
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_sets_for_session_tx failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Stores the tags parsed for a set. Tags are trimmed and lowercased; blank and duplicate
+/// tags are skipped.
+pub async fn add_tags_to_set(pool: &SqlitePool, set_id: i64, tags: &[String]) -> Result<()> {
+    debug!("add_tags_to_set called set_id={} tags={:?}", set_id, tags);
+    let mut tx = pool.begin().await?;
+    add_tags_to_set_tx(&mut tx, set_id, tags).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn add_tags_to_set_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    set_id: i64,
+    tags: &[String],
+) -> Result<()> {
+    debug!(
+        "add_tags_to_set_tx called set_id={} tags={:?}",
+        set_id, tags
+    );
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() {
+            continue;
+        }
+        sqlx::query("INSERT OR IGNORE INTO set_tags (set_id, tag) VALUES (?1, ?2)")
+            .bind(set_id)
+            .bind(&tag)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| {
+                error!(
+                    "add_tags_to_set_tx failed for set_id {} tag '{}': {}",
+                    set_id, tag, e
+                );
+                anyhow::Error::from(e)
+            })?;
+    }
+    Ok(())
+}
+
+pub async fn get_tags_for_set(pool: &SqlitePool, set_id: i64) -> Result<Vec<String>> {
+    debug!("get_tags_for_set called set_id={}", set_id);
+    sqlx::query_scalar::<_, String>("SELECT tag FROM set_tags WHERE set_id = ?1 ORDER BY tag ASC")
+        .bind(set_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            warn!("get_tags_for_set failed for set_id {}: {}", set_id, e);
+            anyhow::Error::from(e)
+        })
+}
+
+/// Stores the area of interest parsed for a set, replacing any earlier one. Blank values are
+/// skipped.
+pub async fn set_aoi_for_set(pool: &SqlitePool, set_id: i64, aoi: &str) -> Result<()> {
+    debug!("set_aoi_for_set called set_id={} aoi={}", set_id, aoi);
+    let mut tx = pool.begin().await?;
+    set_aoi_for_set_tx(&mut tx, set_id, aoi).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn set_aoi_for_set_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    set_id: i64,
+    aoi: &str,
+) -> Result<()> {
+    debug!("set_aoi_for_set_tx called set_id={} aoi={}", set_id, aoi);
+    let aoi = aoi.trim();
+    if aoi.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        "INSERT INTO set_aoi (set_id, aoi) VALUES (?1, ?2)
+         ON CONFLICT(set_id) DO UPDATE SET aoi = excluded.aoi",
+    )
+    .bind(set_id)
+    .bind(aoi)
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        error!("set_aoi_for_set_tx failed for set_id {}: {}", set_id, e);
+        anyhow::Error::from(e)
+    })?;
+    Ok(())
+}
+
+pub async fn get_aoi_for_set(pool: &SqlitePool, set_id: i64) -> Result<Option<String>> {
+    debug!("get_aoi_for_set called set_id={}", set_id);
+    sqlx::query_scalar::<_, String>("SELECT aoi FROM set_aoi WHERE set_id = ?1")
+        .bind(set_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            warn!("get_aoi_for_set failed for set_id {}: {}", set_id, e);
+            anyhow::Error::from(e)
+        })
+}
+
+/// Tags of every set in a workout as `(set_id, tag)` pairs, ordered by set and then tag.
+pub async fn get_tags_for_session(
+    pool: &SqlitePool,
+    session_id: i64,
+) -> Result<Vec<(i64, String)>> {
+    debug!("get_tags_for_session called session_id={}", session_id);
+    sqlx::query_as::<_, (i64, String)>(
+        "SELECT t.set_id, t.tag FROM set_tags t
+         JOIN workout_sets s ON s.id = t.set_id
+         WHERE s.session_id = ?1
+         ORDER BY t.set_id ASC, t.tag ASC",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_tags_for_session failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Areas of interest of the sets in a workout that have one, as `(set_id, aoi)` pairs.
+pub async fn get_aoi_for_session(pool: &SqlitePool, session_id: i64) -> Result<Vec<(i64, String)>> {
+    debug!("get_aoi_for_session called session_id={}", session_id);
+    sqlx::query_as::<_, (i64, String)>(
+        "SELECT a.set_id, a.aoi FROM set_aoi a
+         JOIN workout_sets s ON s.id = a.set_id
+         WHERE s.session_id = ?1",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_aoi_for_session failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn get_workout_set(pool: &SqlitePool, set_id: i64) -> Result<WorkoutSet> {
+    debug!("get_workout_set called set_id={}", set_id);
+    sqlx::query_as::<_, WorkoutSet>(
+        "SELECT id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at
+         FROM workout_sets WHERE id = ?1",
+    )
+    .bind(set_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        warn!("get_workout_set failed for id {}: {}", set_id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn get_workout_set_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    set_id: i64,
+) -> Result<WorkoutSet> {
+    debug!("get_workout_set_tx called set_id={}", set_id);
+    sqlx::query_as::<_, WorkoutSet>(
+        "SELECT id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at
+         FROM workout_sets WHERE id = ?1",
+    )
+    .bind(set_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        warn!("get_workout_set_tx failed for id {}: {}", set_id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+/// Backdates a set, e.g. when importing history logged elsewhere.
+pub async fn set_workout_set_created_at(
+    pool: &SqlitePool,
+    set_id: i64,
+    created_at: i64,
+) -> Result<()> {
+    debug!(
+        "set_workout_set_created_at called set_id={} created_at={}",
+        set_id, created_at
+    );
+    sqlx::query("UPDATE workout_sets SET created_at = ?1, updated_at = ?1 WHERE id = ?2")
+        .bind(created_at)
+        .bind(set_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            error!(
+                "set_workout_set_created_at failed for set_id {}: {}",
+                set_id, e
+            );
+            anyhow::Error::from(e)
+        })?;
+    Ok(())
+}
+
+/// Records the duration and distance of a timed or cardio set.
+pub async fn set_workout_set_cardio(
+    pool: &SqlitePool,
+    set_id: i64,
+    duration_seconds: Option<i64>,
+    distance_meters: Option<f64>,
+) -> Result<WorkoutSet> {
+    debug!(
+        "set_workout_set_cardio called set_id={} duration_seconds={:?} distance_meters={:?}",
+        set_id, duration_seconds, distance_meters
+    );
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query_as::<_, WorkoutSet>(
+        "UPDATE workout_sets SET duration_seconds = ?1, distance_meters = ?2, updated_at = ?3
+         WHERE id = ?4
+         RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at"
+    )
+    .bind(duration_seconds)
+    .bind(distance_meters)
+    .bind(now)
+    .bind(set_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        warn!("set_workout_set_cardio failed for set_id {}: {}", set_id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+pub async fn set_workout_set_index_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    set_id: i64,
+    set_index: i64,
+) -> Result<WorkoutSet> {
+    debug!(
+        "set_workout_set_index_tx called set_id={} set_index={}",
+        set_id, set_index
+    );
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query_as::<_, WorkoutSet>(
+        "UPDATE workout_sets SET set_index = ?1, updated_at = ?2 WHERE id = ?3
+         RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at"
+    )
+    .bind(set_index)
+    .bind(now)
+    .bind(set_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        warn!("set_workout_set_index_tx failed for set_id {}: {}", set_id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+/// Returns an unused superset group id.
+pub async fn next_superset_group_tx(tx: &mut Transaction<'_, Sqlite>) -> Result<i64> {
+    debug!("next_superset_group_tx called");
+    sqlx::query_scalar::<_, i64>("SELECT COALESCE(MAX(superset_group), 0) + 1 FROM workout_sets")
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| {
+            warn!("next_superset_group_tx failed: {}", e);
+            anyhow::Error::from(e)
+        })
+}
+
+pub async fn set_workout_set_superset_group_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    set_id: i64,
+    superset_group: Option<i64>,
+) -> Result<WorkoutSet> {
+    debug!(
+        "set_workout_set_superset_group_tx called set_id={} superset_group={:?}",
+        set_id, superset_group
+    );
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query_as::<_, WorkoutSet>(
+        "UPDATE workout_sets SET superset_group = ?1, updated_at = ?2 WHERE id = ?3
+         RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at"
+    )
+    .bind(superset_group)
+    .bind(now)
+    .bind(set_id)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        warn!(
+            "set_workout_set_superset_group_tx failed for set_id {}: {}",
+            set_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Removes every set from a superset group, returning how many sets were ungrouped.
+pub async fn clear_superset_group(pool: &SqlitePool, superset_group: i64) -> Result<u64> {
+    debug!(
+        "clear_superset_group called superset_group={}",
+        superset_group
+    );
+    let now = chrono::Utc::now().timestamp();
+    let result = sqlx::query(
+        "UPDATE workout_sets SET superset_group = NULL, updated_at = ?1 WHERE superset_group = ?2",
+    )
+    .bind(now)
+    .bind(superset_group)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "clear_superset_group failed for superset_group {}: {}",
+            superset_group, e
+        );
+        anyhow::Error::from(e)
+    })?;
+    Ok(result.rows_affected())
+}
+
+pub async fn update_workout_set(
+    pool: &SqlitePool,
+    set_id: i64,
+    update: &UpdateWorkoutSet,
+) -> Result<WorkoutSet> {
+    debug!("update_workout_set called set_id={}", set_id);
+    let mut tx = pool.begin().await?;
+    let updated = update_workout_set_tx(&mut tx, set_id, update).await?;
+    tx.commit().await?;
+    Ok(updated)
+}
+
+pub async fn update_workout_set_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    set_id: i64,
+    update: &UpdateWorkoutSet,
+) -> Result<WorkoutSet> {
+    debug!("update_workout_set_tx called set_id={}", set_id);
+
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query_as::<_, WorkoutSet>(
+        "UPDATE workout_sets SET
+         session_id = CASE WHEN ?1 IS NOT NULL THEN ?1 ELSE session_id END,
+         exercise_id = CASE WHEN ?2 IS NOT NULL THEN ?2 ELSE exercise_id END,
+         request_string_id = CASE WHEN ?3 IS NOT NULL THEN ?3 ELSE request_string_id END,
+         weight = CASE WHEN ?4 IS NOT NULL THEN ?4 ELSE weight END,
+         reps = CASE WHEN ?5 IS NOT NULL THEN ?5 ELSE reps END,
+         set_index = CASE WHEN ?6 IS NOT NULL THEN ?6 ELSE set_index END,
+         rpe = CASE WHEN ?7 IS NOT NULL THEN ?7 ELSE rpe END,
+         notes = CASE WHEN ?8 IS NULL THEN notes ELSE NULLIF(?8, '') END,
+         rest_style = CASE WHEN ?11 IS NOT NULL THEN ?11 ELSE rest_style END,
+         updated_at = ?9
+         WHERE id = ?10
+         RETURNING id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at"
+    )
+    .bind(update.session_id)
+    .bind(update.exercise_id)
+    .bind(update.request_string_id)
+    .bind(update.weight)
+    .bind(update.reps)
+    .bind(update.set_index)
+    .bind(update.rpe)
+    .bind(update.notes.clone())
+    .bind(now)
+    .bind(set_id)
+    .bind(update.rest_style)
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|e| {
+        warn!("update_workout_set_tx failed for set_id {}: {}", set_id, e);
+        anyhow::Error::from(e)
+    })
+}
+
+async fn get_workout_set_by_id(pool: &SqlitePool, set_id: i64) -> Result<WorkoutSet> {
+    sqlx::query_as::<_, WorkoutSet>(
+        "SELECT id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, rest_style, duration_seconds, distance_meters, superset_group, created_at, updated_at
+         FROM workout_sets WHERE id = ?1"
+    )
+    .bind(set_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow::Error::from(e))
+}
+
+pub async fn update_workout_set_from_parsed(
+    pool: &SqlitePool,
+    set_id: i64,
+    parsed: &ParsedSet,
+) -> Result<WorkoutSet> {
+    debug!(
+        "update_workout_set_from_parsed called set_id={} parsed={:?}",
+        set_id, parsed
+    );
+    let original = get_workout_set_by_id(pool, set_id).await.map_err(|e| {
+        error!("failed to load original set id {}: {}", set_id, e);
+        anyhow::Error::from(e)
+    })?;
+
+    let exercise_id_opt = if !parsed.exercise.is_empty() {
+        let exercise = get_or_create_exercise(pool, &parsed.exercise).await?;
+        if exercise.id != original.exercise_id {
+            Some(exercise.id)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let weight_opt = parsed.weight_in_kg().map(|w| w as f64);
+    let rpe_opt = parsed.rpe.map(|r| r as f64);
+
+    let update = UpdateWorkoutSet {
+        session_id: None,
+        exercise_id: exercise_id_opt,
+        request_string_id: None,
+        weight: weight_opt,
+        reps: parsed.reps.map(|r| r as i64),
+        set_index: None,
+        rpe: rpe_opt,
+        notes: None,
+        rest_style: None,
+    };
+
+    update_workout_set(pool, set_id, &update)
+        .await
+        .map_err(|e| {
+            error!("failed to update set id {}: {}", set_id, e);
+            anyhow::Error::from(e)
+        })
+}
+
+pub async fn delete_workout_set(pool: &SqlitePool, set_id: i64) -> Result<u64> {
+    debug!("delete_workout_set called set_id={}", set_id);
+    let result = sqlx::query("DELETE FROM workout_sets WHERE id = ?1")
+        .bind(set_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            warn!("delete_workout_set failed for set_id {}: {}", set_id, e);
+            anyhow::Error::from(e)
+        })?;
+    Ok(result.rows_affected())
+}
+
+pub async fn delete_workout_set_tx(tx: &mut Transaction<'_, Sqlite>, set_id: i64) -> Result<u64> {
+    debug!("delete_workout_set_tx called set_id={}", set_id);
+    let result = sqlx::query("DELETE FROM workout_sets WHERE id = ?1")
+        .bind(set_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| {
+            warn!("delete_workout_set_tx failed for set_id {}: {}", set_id, e);
+            anyhow::Error::from(e)
+        })?;
+    Ok(result.rows_affected())
+}
+
+/// Sets logged for an exercise in workouts that haven't been deleted, oldest first. With a
+/// `limit`, only the most recent `limit` sets are returned, still oldest first.
+pub async fn get_exercise_entries(
+    pool: &SqlitePool,
+    exercise_id: i64,
+    limit: Option<i64>,
+) -> Result<Vec<WorkoutSet>> {
+    debug!(
+        "get_exercise_entries called exercise_id={:?} limit={:?}",
+        exercise_id, limit
+    );
+
+    let sets = if let Some(limit) = limit {
+        sqlx::query_as::<_, WorkoutSet>(
+            "SELECT ws.id, ws.session_id, ws.exercise_id, ws.request_string_id, ws.weight, ws.reps, ws.set_index, ws.rpe, ws.notes, ws.rest_style, ws.duration_seconds, ws.distance_meters, ws.superset_group, ws.created_at, ws.updated_at
+             FROM workout_sets ws
+             JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+             WHERE ws.exercise_id = ?1 ORDER BY ws.created_at DESC, ws.id DESC LIMIT ?2"
+        )
+        .bind(exercise_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+        .map(|mut sets| {
+            sets.reverse();
+            sets
+        })
+    } else {
+        sqlx::query_as::<_, WorkoutSet>(
+            "SELECT ws.id, ws.session_id, ws.exercise_id, ws.request_string_id, ws.weight, ws.reps, ws.set_index, ws.rpe, ws.notes, ws.rest_style, ws.duration_seconds, ws.distance_meters, ws.superset_group, ws.created_at, ws.updated_at
+             FROM workout_sets ws
+             JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+             WHERE ws.exercise_id = ?1 ORDER BY ws.created_at ASC, ws.id ASC"
+        )
+        .bind(exercise_id)
+        .fetch_all(pool)
+        .await
+    }
+    .map_err(|e| {
+        error!(
+            "failed to load exercise entries for exercise id {}: {}",
+            exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    Ok(sets)
+}
+
+/// Sets logged for an exercise, newest first, each paired with the workout it belongs to.
+/// Workouts are loaded in one query rather than per set.
+pub async fn get_exercise_history(
+    pool: &SqlitePool,
+    exercise_id: i64,
+    limit: Option<i64>,
+) -> Result<Vec<(WorkoutSet, WorkoutSession)>> {
+    debug!(
+        "get_exercise_history called exercise_id={} limit={:?}",
+        exercise_id, limit
+    );
+
+    let sets = sqlx::query_as::<_, WorkoutSet>(
+        "SELECT ws.id, ws.session_id, ws.exercise_id, ws.request_string_id, ws.weight, ws.reps, ws.set_index, ws.rpe, ws.notes, ws.rest_style, ws.duration_seconds, ws.distance_meters, ws.superset_group, ws.created_at, ws.updated_at
+         FROM workout_sets ws
+         JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+         WHERE ws.exercise_id = ?1 ORDER BY ws.created_at DESC, ws.id DESC LIMIT ?2",
+    )
+    .bind(exercise_id)
+    .bind(limit.unwrap_or(-1))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "failed to load history for exercise id {}: {}",
+            exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+
+    let sessions = sqlx::query_as::<_, WorkoutSession>(
+        "SELECT id, user_id, name, duration_seconds, notes, status, summary, created_at, updated_at
+         FROM workout_sessions
+         WHERE id IN (SELECT session_id FROM workout_sets WHERE exercise_id = ?1)
+           AND deleted_at IS NULL",
+    )
+    .bind(exercise_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "failed to load sessions for exercise id {}: {}",
+            exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })?;
+    let sessions: std::collections::HashMap<i64, WorkoutSession> =
+        sessions.into_iter().map(|s| (s.id, s)).collect();
+
+    Ok(sets
+        .into_iter()
+        .filter_map(|set| {
+            let session = sessions.get(&set.session_id)?.clone();
+            Some((set, session))
+        })
+        .collect())
+}
+
+/// Sets whose notes or original request string contain `query` (case-insensitive), newest
+/// first. A blank query matches nothing.
+pub async fn search_sets(
+    pool: &SqlitePool,
+    query: &str,
+    limit: Option<i64>,
+) -> Result<Vec<WorkoutSet>> {
+    debug!("search_sets called query={:?} limit={:?}", query, limit);
+
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+    let pattern = format!(
+        "%{}%",
+        query
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+
+    sqlx::query_as::<_, WorkoutSet>(
+        "SELECT ws.id, ws.session_id, ws.exercise_id, ws.request_string_id, ws.weight, ws.reps, ws.set_index, ws.rpe, ws.notes, ws.rest_style, ws.duration_seconds, ws.distance_meters, ws.superset_group, ws.created_at, ws.updated_at
+         FROM workout_sets ws
+         JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+         LEFT JOIN request_strings rs ON rs.id = ws.request_string_id
+         WHERE (ws.notes LIKE ?1 ESCAPE '\\' OR rs.string LIKE ?1 ESCAPE '\\')
+         ORDER BY ws.created_at DESC, ws.id DESC
+         LIMIT ?2",
+    )
+    .bind(pattern)
+    .bind(limit.unwrap_or(-1))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("search_sets failed for query {:?}: {}", query, e);
+        anyhow::Error::from(e)
+    })
+}
+
+/// Per-day `(YYYY-MM-DD, set_count, tonnage)` for the last `days` days (UTC), oldest first.
+/// Days without any sets are included with zero counts.
+pub async fn get_training_calendar(
+    pool: &SqlitePool,
+    days: i64,
+) -> Result<Vec<(String, i64, f64)>> {
+    debug!("get_training_calendar called days={}", days);
+    if days <= 0 {
+        return Ok(vec![]);
+    }
+
+    let today = chrono::Utc::now().date_naive();
+    let first_day = today - chrono::Duration::days(days - 1);
+    let since = first_day
+        .and_hms_opt(0, 0, 0)
+        .map(|dt| dt.and_utc().timestamp())
+        .unwrap_or_default();
+
+    let rows = sqlx::query_as::<_, (String, i64, f64)>(
+        "SELECT date(ws.created_at, 'unixepoch') AS day, COUNT(*), COALESCE(SUM(ws.weight * ws.reps), 0.0)
+         FROM workout_sets ws
+         JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+         WHERE ws.created_at >= ?1
+         GROUP BY day",
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        warn!("get_training_calendar failed for days {}: {}", days, e);
+        anyhow::Error::from(e)
+    })?;
+    let by_day: std::collections::HashMap<String, (i64, f64)> = rows
+        .into_iter()
+        .map(|(day, set_count, tonnage)| (day, (set_count, tonnage)))
+        .collect();
+
+    Ok(first_day
+        .iter_days()
+        .take(days as usize)
+        .map(|day| {
+            let key = day.format("%Y-%m-%d").to_string();
+            let (set_count, tonnage) = by_day.get(&key).copied().unwrap_or((0, 0.0));
+            (key, set_count, tonnage)
+        })
+        .collect())
+}
+
+/// Returns the heaviest weight logged for an exercise in any session other than
+/// `excluding_session_id`.
+pub async fn get_previous_best_weight(
+    pool: &SqlitePool,
+    exercise_id: i64,
+    excluding_session_id: i64,
+) -> Result<Option<f64>> {
+    debug!(
+        "get_previous_best_weight called exercise_id={} excluding_session_id={}",
+        exercise_id, excluding_session_id
+    );
+    sqlx::query_scalar::<_, Option<f64>>(
+        "SELECT MAX(ws.weight) FROM workout_sets ws
+         JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+         WHERE ws.exercise_id = ?1 AND ws.session_id != ?2",
+    )
+    .bind(exercise_id)
+    .bind(excluding_session_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_previous_best_weight failed for exercise_id {}: {}",
+            exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Finds the completed session sharing the most exercises with `session_id`, preferring
+/// the most recent one on ties.
+pub async fn get_most_similar_completed_session(
+    pool: &SqlitePool,
+    session_id: i64,
+) -> Result<Option<i64>> {
+    debug!(
+        "get_most_similar_completed_session called session_id={}",
+        session_id
+    );
+    sqlx::query_scalar::<_, i64>(
+        "SELECT ws.session_id
+         FROM workout_sets ws
+         JOIN workout_sessions s ON s.id = ws.session_id
+         WHERE s.status = 'completed'
+           AND s.deleted_at IS NULL
+           AND ws.session_id != ?1
+           AND ws.exercise_id IN (SELECT exercise_id FROM workout_sets WHERE session_id = ?1)
+         GROUP BY ws.session_id
+         ORDER BY COUNT(DISTINCT ws.exercise_id) DESC, MAX(s.created_at) DESC, ws.session_id DESC
+         LIMIT 1",
+    )
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_most_similar_completed_session failed for session_id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Returns the heaviest set ever logged for an exercise. Ties go to the set with more reps,
+/// then to the most recent one.
+pub async fn get_personal_record(
+    pool: &SqlitePool,
+    exercise_id: i64,
+) -> Result<Option<WorkoutSet>> {
+    debug!("get_personal_record called exercise_id={}", exercise_id);
+    sqlx::query_as::<_, WorkoutSet>(
+        "SELECT ws.id, ws.session_id, ws.exercise_id, ws.request_string_id, ws.weight, ws.reps, ws.set_index, ws.rpe, ws.notes, ws.rest_style, ws.duration_seconds, ws.distance_meters, ws.superset_group, ws.created_at, ws.updated_at
+         FROM workout_sets ws
+         JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+         WHERE ws.exercise_id = ?1
+         ORDER BY ws.weight DESC, ws.reps DESC, ws.created_at DESC, ws.id DESC
+         LIMIT 1",
+    )
+    .bind(exercise_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        warn!(
+            "get_personal_record failed for exercise_id {}: {}",
+            exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Estimates a one-rep max with the Epley formula.
+pub fn estimate_one_rep_max(weight: f64, reps: i64) -> f64 {
+    weight * (1.0 + reps as f64 / 30.0)
+}
+
+/// Returns the highest Epley one-rep max estimate across every set logged for an exercise,
+/// or `None` if it has never been logged.
+pub async fn get_best_estimated_1rm(pool: &SqlitePool, exercise_id: i64) -> Result<Option<f64>> {
+    debug!("get_best_estimated_1rm called exercise_id={}", exercise_id);
+    let sets = get_exercise_entries(pool, exercise_id, None).await?;
+    Ok(sets
+        .iter()
+        .map(|s| estimate_one_rep_max(s.weight, s.reps))
+        .max_by(|a, b| a.total_cmp(b)))
+}
+
+/// Weight of the latest set of every exercise logged at or after `since` (unix seconds), as
+/// `(exercise_id, weight)`.
+pub async fn get_latest_weights_since(pool: &SqlitePool, since: i64) -> Result<Vec<(i64, f64)>> {
+    debug!("get_latest_weights_since called since={}", since);
+    sqlx::query_as::<_, (i64, f64)>(
+        "SELECT exercise_id, weight FROM (
+             SELECT ws.exercise_id, ws.weight,
+                    ROW_NUMBER() OVER (PARTITION BY ws.exercise_id ORDER BY ws.created_at DESC, ws.id DESC) AS recency
+             FROM workout_sets ws
+             JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+             WHERE ws.created_at >= ?1
+         ) WHERE recency = 1",
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        error!("get_latest_weights_since failed: {}", e);
+        anyhow::Error::from(e)
+    })
+}
+
+/// Best Epley one-rep max estimate of each of the `limit` latest workouts an exercise was
+/// logged in, as `(session_id, estimate)` from oldest to newest. Sets without reps are
+/// ignored.
+pub async fn get_session_best_estimated_1rms(
+    pool: &SqlitePool,
+    exercise_id: i64,
+    limit: i64,
+) -> Result<Vec<(i64, f64)>> {
+    debug!(
+        "get_session_best_estimated_1rms called exercise_id={} limit={}",
+        exercise_id, limit
+    );
+    sqlx::query_as::<_, (i64, f64)>(
+        "SELECT ws.session_id, MAX(ws.weight * (1.0 + ws.reps / 30.0)) FROM workout_sets ws
+         JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+         WHERE ws.exercise_id = ?1 AND ws.reps > 0
+         GROUP BY ws.session_id
+         ORDER BY s.created_at DESC, s.id DESC LIMIT ?2",
+    )
+    .bind(exercise_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map(|mut bests| {
+        bests.reverse();
+        bests
+    })
+    .map_err(|e| {
+        error!(
+            "get_session_best_estimated_1rms failed for exercise id {}: {}",
+            exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Total tonnage (weight x reps) logged for an exercise, optionally only sets created at or
+/// after `since` (unix seconds).
+pub async fn get_exercise_volume(
+    pool: &SqlitePool,
+    exercise_id: i64,
+    since: Option<i64>,
+) -> Result<f64> {
+    debug!(
+        "get_exercise_volume called exercise_id={} since={:?}",
+        exercise_id, since
+    );
+    sqlx::query_scalar::<_, f64>(
+        "SELECT CAST(COALESCE(SUM(ws.weight * ws.reps), 0) AS REAL) FROM workout_sets ws
+         JOIN workout_sessions s ON s.id = ws.session_id AND s.deleted_at IS NULL
+         WHERE ws.exercise_id = ?1 AND (?2 IS NULL OR ws.created_at >= ?2)",
+    )
+    .bind(exercise_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "get_exercise_volume failed for exercise id {}: {}",
+            exercise_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+/// Total tonnage (weight x reps) logged in a workout session.
+pub async fn get_session_volume(pool: &SqlitePool, session_id: i64) -> Result<f64> {
+    debug!("get_session_volume called session_id={}", session_id);
+    sqlx::query_scalar::<_, f64>(
+        "SELECT CAST(COALESCE(SUM(weight * reps), 0) AS REAL) FROM workout_sets
+         WHERE session_id = ?1",
+    )
+    .bind(session_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        error!(
+            "get_session_volume failed for session id {}: {}",
+            session_id, e
+        );
+        anyhow::Error::from(e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_database;
+    use crate::db::models::RestStyle;
+    use crate::llm::{ParsedSet, WeightUnit};
+    use sqlx::SqlitePool;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    async fn setup_test_db() -> SqlitePool {
+        INIT.call_once(|| {
+            env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug"))
+                .init();
+        });
+
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+        sqlx::query("PRAGMA journal_mode = WAL")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("PRAGMA synchronous = NORMAL")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("PRAGMA busy_timeout = 5000")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        init_database(&pool).await.unwrap();
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_create_workout_session() {
+        let pool = setup_test_db().await;
+
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+
+        assert!(session.id > 0);
+        assert_eq!(session.user_id, None);
+        assert_eq!(session.name, None);
+        assert_eq!(session.duration_seconds, 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_workout_session_with_fields() {
+        let pool = setup_test_db().await;
+
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let session = create_workout_session(
+            &pool,
+            Some(user.id),
+            Some("Test Workout".to_string()),
+            Some("Test notes".to_string()),
+            Some(3600),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(session.id > 0);
+        assert_eq!(session.user_id, Some(user.id));
+        assert_eq!(session.name, Some("Test Workout".to_string()));
+        assert_eq!(session.notes, Some("Test notes".to_string()));
+        assert_eq!(session.duration_seconds, 3600);
+    }
+
+    #[tokio::test]
+    async fn test_get_workout_session() {
+        let pool = setup_test_db().await;
+
+        let created = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let retrieved = get_workout_session(&pool, created.id).await.unwrap();
+
+        assert_eq!(created.id, retrieved.id);
+        assert_eq!(created.user_id, retrieved.user_id);
+        assert_eq!(created.name, retrieved.name);
+    }
+
+    #[tokio::test]
+    async fn test_get_workout_session_not_found() {
+        let pool = setup_test_db().await;
+
+        let result = get_workout_session(&pool, 99999).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_all_workout_sessions() {
+        let pool = setup_test_db().await;
+
+        create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+
+        let sessions = get_all_workout_sessions(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(sessions.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_workout_sessions_in_range() {
+        let pool = setup_test_db().await;
+
+        let mut ids = Vec::new();
+        for (created_at, status) in [
+            (1_000, WorkoutStatus::Completed),
+            (2_000, WorkoutStatus::Completed),
+            (3_000, WorkoutStatus::InProgress),
+            (4_000, WorkoutStatus::Completed),
+        ] {
+            let session = create_workout_session(&pool, None, None, None, None, Some(status))
+                .await
+                .unwrap();
+            sqlx::query("UPDATE workout_sessions SET created_at = ?1 WHERE id = ?2")
+                .bind(created_at)
+                .bind(session.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+            ids.push(session.id);
+        }
+        let ids_for = |sessions: Vec<WorkoutSession>| {
+            let mut found: Vec<i64> = sessions.into_iter().map(|s| s.id).collect();
+            found.sort();
+            found
+        };
+
+        let in_range = get_all_workout_sessions(&pool, None, Some(2_000), Some(4_000), None, None)
+            .await
+            .unwrap();
+        assert_eq!(ids_for(in_range), vec![ids[1], ids[2]]);
+
+        let completed = get_all_workout_sessions(
+            &pool,
+            Some(WorkoutStatus::Completed),
+            Some(2_000),
+            Some(4_001),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids_for(completed), vec![ids[1], ids[3]]);
+
+        let before = get_all_workout_sessions(&pool, None, None, Some(1_000), None, None)
+            .await
+            .unwrap();
+        assert!(before.is_empty());
+
+        let after = get_all_workout_sessions(&pool, None, Some(4_000), None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(ids_for(after), vec![ids[3]]);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_workout_sessions_page() {
+        let pool = setup_test_db().await;
+
+        let mut ids = Vec::new();
+        for created_at in [1_000, 2_000, 3_000, 4_000, 5_000, 6_000, 7_000] {
+            let session = create_workout_session(&pool, None, None, None, None, None)
+                .await
+                .unwrap();
+            sqlx::query("UPDATE workout_sessions SET created_at = ?1 WHERE id = ?2")
+                .bind(created_at)
+                .bind(session.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+            ids.push(session.id);
+        }
+
+        let page = get_all_workout_sessions(&pool, None, None, None, Some(3), Some(3))
+            .await
+            .unwrap();
+        assert_eq!(
+            page.iter().map(|s| s.id).collect::<Vec<_>>(),
+            vec![ids[3], ids[2], ids[1]]
+        );
+
+        let first = get_all_workout_sessions(&pool, None, None, None, Some(3), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            first.iter().map(|s| s.id).collect::<Vec<_>>(),
+            vec![ids[6], ids[5], ids[4]]
+        );
+
+        let all = get_all_workout_sessions(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_delete_workout_session() {
+        let pool = setup_test_db().await;
+
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let rows = delete_workout_session(&pool, session.id).await.unwrap();
+        assert_eq!(rows, 1);
+
+        let result = get_workout_session(&pool, session.id).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_update_workout_notes() {
+        let pool = setup_test_db().await;
+
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let updated = update_workout_notes(&pool, session.id, Some(" Left knee sore ".into()))
+            .await
+            .unwrap();
+        assert_eq!(updated.notes.as_deref(), Some("Left knee sore"));
+        assert_eq!(
+            get_workout_session(&pool, session.id)
+                .await
+                .unwrap()
+                .notes
+                .as_deref(),
+            Some("Left knee sore")
+        );
+
+        let cleared = update_workout_notes(&pool, session.id, None).await.unwrap();
+        assert_eq!(cleared.notes, None);
+        let blank = update_workout_notes(&pool, session.id, Some("  ".into()))
+            .await
+            .unwrap();
+        assert_eq!(blank.notes, None);
+        assert_eq!(
+            get_workout_session(&pool, session.id).await.unwrap().notes,
+            None
+        );
+
+        assert!(update_workout_notes(&pool, 9999, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_soft_deleted_session_can_be_restored() {
+        let pool = setup_test_db().await;
+
+        let kept = create_workout_session(
+            &pool,
+            None,
+            None,
+            None,
+            None,
+            Some(WorkoutStatus::Completed),
+        )
+        .await
+        .unwrap();
+        let deleted = create_workout_session(
+            &pool,
+            None,
+            None,
+            None,
+            None,
+            Some(WorkoutStatus::InProgress),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(delete_workout_session(&pool, deleted.id).await.unwrap(), 1);
+        assert_eq!(delete_workout_session(&pool, deleted.id).await.unwrap(), 0);
+
+        let listed = get_all_workout_sessions(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            listed.iter().map(|s| s.id).collect::<Vec<_>>(),
+            vec![kept.id]
+        );
+        assert!(get_in_progress_workout(&pool).await.unwrap().is_none());
+        assert!(restore_workout_session(&pool, kept.id).await.is_err());
+
+        restore_workout_session(&pool, deleted.id).await.unwrap();
+        let listed = get_all_workout_sessions(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(
+            get_in_progress_workout(&pool).await.unwrap().map(|s| s.id),
+            Some(deleted.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_purge_deleted_removes_only_old_deletions() {
+        let pool = setup_test_db().await;
+
+        let old = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let recent = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        delete_workout_session(&pool, old.id).await.unwrap();
+        delete_workout_session(&pool, recent.id).await.unwrap();
+        sqlx::query("UPDATE workout_sessions SET deleted_at = 1000 WHERE id = ?1")
+            .bind(old.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(purge_deleted(&pool, 2000).await.unwrap(), 1);
+        assert!(restore_workout_session(&pool, old.id).await.is_err());
+        restore_workout_session(&pool, recent.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_exercise() {
+        let pool = setup_test_db().await;
+
+        let created = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let retrieved = get_exercise(&pool, created.id).await.unwrap();
+
+        assert_eq!(created.id, retrieved.id);
+        assert_eq!(created.name, retrieved.name);
+        assert_eq!(created.name, "Bench Press");
+    }
+
+    #[tokio::test]
+    async fn test_get_exercise_not_found() {
+        let pool = setup_test_db().await;
+
+        let result = get_exercise(&pool, 99999).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_all_exercises() {
+        let pool = setup_test_db().await;
+
+        get_or_create_exercise(&pool, "Squat").await.unwrap();
+        get_or_create_exercise(&pool, "Deadlift").await.unwrap();
+
+        let exercises = get_all_exercises(&pool).await.unwrap();
+        assert_eq!(exercises.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_exercises() {
+        let pool = setup_test_db().await;
+
+        for name in [
+            "Incline Bench Press",
+            "Bench Press",
+            "Bent Over Row",
+            "Squat",
+        ] {
+            get_or_create_exercise(&pool, name).await.unwrap();
+        }
+
+        let names = |exercises: Vec<Exercise>| -> Vec<String> {
+            exercises.into_iter().map(|e| e.name).collect()
+        };
+        assert_eq!(
+            names(search_exercises(&pool, "ben", 10).await.unwrap()),
+            vec!["Bench Press", "Bent Over Row", "Incline Bench Press"]
+        );
+        assert_eq!(
+            names(search_exercises(&pool, "BENCH", 1).await.unwrap()),
+            vec!["Bench Press"]
+        );
+        assert_eq!(search_exercises(&pool, "ben", 2).await.unwrap().len(), 2);
+        assert!(search_exercises(&pool, "  ", 10).await.unwrap().is_empty());
+        assert!(search_exercises(&pool, "%", 10).await.unwrap().is_empty());
+        assert!(search_exercises(&pool, "ben", 0).await.is_err());
+        assert!(search_exercises(&pool, "ben", -1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_or_create_exercise_new() {
+        let pool = setup_test_db().await;
+
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        assert_eq!(exercise.name, "Bench Press");
+        assert!(!exercise.slug.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_or_create_exercise_existing() {
+        let pool = setup_test_db().await;
+
+        let first = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let second = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+
+        assert_eq!(first.id, second.id);
+        assert_eq!(first.name, second.name);
+    }
+
+    #[tokio::test]
+    async fn test_set_favorite_toggle() {
+        let pool = setup_test_db().await;
+
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        assert!(!exercise.is_favorite);
+
+        let pinned = set_favorite(&pool, exercise.id, true).await.unwrap();
+        assert!(pinned.is_favorite);
+        assert!(get_exercise(&pool, exercise.id).await.unwrap().is_favorite);
+
+        let unpinned = set_favorite(&pool, exercise.id, false).await.unwrap();
+        assert!(!unpinned.is_favorite);
+    }
+
+    #[tokio::test]
+    async fn test_get_favorites() {
+        let pool = setup_test_db().await;
+
+        let squat = get_or_create_exercise(&pool, "Squat").await.unwrap();
+        let bench = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        get_or_create_exercise(&pool, "Deadlift").await.unwrap();
+
+        assert!(get_favorites(&pool).await.unwrap().is_empty());
+
+        set_favorite(&pool, squat.id, true).await.unwrap();
+        set_favorite(&pool, bench.id, true).await.unwrap();
+
+        let favorites = get_favorites(&pool).await.unwrap();
+        let names: Vec<_> = favorites.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Bench Press", "Squat"]);
+
+        set_favorite(&pool, squat.id, false).await.unwrap();
+        let favorites = get_favorites(&pool).await.unwrap();
+        assert_eq!(favorites.len(), 1);
+        assert_eq!(favorites[0].id, bench.id);
+    }
+
+    #[tokio::test]
+    async fn test_new_exercise_default_experience_level() {
+        let pool = setup_test_db().await;
+
+        let bench = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        assert_eq!(bench.experience_level, ExperienceLevel::Intermediate);
+
+        let snatch = get_or_create_exercise(&pool, "Power Snatch").await.unwrap();
+        assert_eq!(snatch.experience_level, ExperienceLevel::Advanced);
+
+        let clean = get_or_create_exercise(&pool, "Clean and Jerk")
+            .await
+            .unwrap();
+        assert_eq!(clean.experience_level, ExperienceLevel::Advanced);
+    }
+
+    #[tokio::test]
+    async fn test_get_exercises_for_level() {
+        let pool = setup_test_db().await;
+
+        let push_up = get_or_create_exercise(&pool, "Push Up").await.unwrap();
+        set_exercise_experience_level(&pool, push_up.id, ExperienceLevel::Beginner)
+            .await
+            .unwrap();
+        get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        get_or_create_exercise(&pool, "Power Clean").await.unwrap();
+
+        let names =
+            |exercises: Vec<Exercise>| exercises.into_iter().map(|e| e.name).collect::<Vec<_>>();
+
+        let beginner = get_exercises_for_level(&pool, ExperienceLevel::Beginner)
+            .await
+            .unwrap();
+        assert_eq!(names(beginner), vec!["Push Up"]);
+
+        let intermediate = get_exercises_for_level(&pool, ExperienceLevel::Intermediate)
+            .await
+            .unwrap();
+        assert_eq!(names(intermediate), vec!["Bench Press", "Push Up"]);
+
+        let advanced = get_exercises_for_level(&pool, ExperienceLevel::Advanced)
+            .await
+            .unwrap();
+        assert_eq!(
+            names(advanced),
+            vec!["Bench Press", "Power Clean", "Push Up"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_recent_exercises() {
+        let pool = setup_test_db().await;
+
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "log".to_string())
+            .await
+            .unwrap();
+        let squat = get_or_create_exercise(&pool, "Squat").await.unwrap();
+        let bench = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        get_or_create_exercise(&pool, "Deadlift").await.unwrap();
+
+        for exercise_id in [squat.id, bench.id, squat.id] {
+            add_workout_set(
+                &pool,
+                &session.id,
+                &exercise_id,
+                &request.id,
+                &100.0,
+                &5,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let recent = get_recent_exercises(&pool, 10).await.unwrap();
+        assert_eq!(
+            recent.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![squat.id, bench.id]
+        );
+
+        let limited = get_recent_exercises(&pool, 1).await.unwrap();
+        assert_eq!(limited.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_workout_stats() {
+        let pool = setup_test_db().await;
+
+        let empty = get_workout_stats(&pool).await.unwrap();
+        assert_eq!(empty.completed_workouts, 0);
+        assert_eq!(empty.total_sets, 0);
+        assert_eq!(empty.total_volume, 0.0);
+
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+            .await
+            .unwrap();
+        add_multiple_sets_to_workout(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            None,
+            2,
+        )
+        .await
+        .unwrap();
+        complete_workout_session(&pool, session.id, 1800)
+            .await
+            .unwrap();
+
+        let stats = get_workout_stats(&pool).await.unwrap();
+        assert_eq!(stats.completed_workouts, 1);
+        assert_eq!(stats.total_sets, 2);
+        assert_eq!(stats.total_volume, 1000.0);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_workout_drops_out_of_stats_and_records() {
+        let pool = setup_test_db().await;
+
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "bench".to_string())
+            .await
+            .unwrap();
+        let kept = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        add_workout_set(&pool, &kept.id, &exercise.id, &request.id, &100.0, &5, None)
+            .await
+            .unwrap();
+        let deleted = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        add_workout_set(
+            &pool,
+            &deleted.id,
+            &exercise.id,
+            &request.id,
+            &140.0,
+            &3,
+            None,
+        )
+        .await
+        .unwrap();
+        delete_workout_session(&pool, deleted.id).await.unwrap();
+
+        let stats = get_workout_stats(&pool).await.unwrap();
+        assert_eq!(stats.total_sets, 1);
+        assert_eq!(stats.total_volume, 500.0);
+
+        let pr = get_personal_record(&pool, exercise.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pr.weight, 100.0);
+        assert_eq!(
+            get_best_estimated_1rm(&pool, exercise.id).await.unwrap(),
+            Some(estimate_one_rep_max(100.0, 5))
+        );
+        assert_eq!(
+            get_previous_best_weight(&pool, exercise.id, kept.id)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            get_exercise_volume(&pool, exercise.id, None).await.unwrap(),
+            500.0
+        );
+        let entries = get_exercise_entries(&pool, exercise.id, None)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].session_id, kept.id);
+    }
+
+    #[tokio::test]
+    async fn test_prune_orphan_exercises() {
+        let pool = setup_test_db().await;
+
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "squat 100kg x 5".to_string())
+            .await
+            .unwrap();
+        let used = get_or_create_exercise(&pool, "Squat").await.unwrap();
+        let unused = get_or_create_exercise(&pool, "Sqaut").await.unwrap();
+        add_workout_set(&pool, &session.id, &used.id, &request.id, &100.0, &5, None)
+            .await
+            .unwrap();
 
-    update_workout_set(pool, set_id, &update)
-        .await
-        .map_err(|e| {
-            error!("failed to update set id {}: {}", set_id, e);
-            anyhow::Error::from(e)
-        })
-}
+        let preview = prune_orphan_exercises(&pool, true).await.unwrap();
+        assert_eq!(
+            preview.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![unused.id]
+        );
+        assert!(get_exercise(&pool, unused.id).await.is_ok());
 
-pub async fn delete_workout_set(pool: &SqlitePool, set_id: i64) -> Result<u64> {
-    debug!("delete_workout_set called set_id={}", set_id);
-    let result = sqlx::query("DELETE FROM workout_sets WHERE id = ?1")
-        .bind(set_id)
-        .execute(pool)
+        let pruned = prune_orphan_exercises(&pool, false).await.unwrap();
+        assert_eq!(
+            pruned.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![unused.id]
+        );
+        assert!(get_exercise(&pool, unused.id).await.is_err());
+        assert!(get_exercise(&pool, used.id).await.is_ok());
+        assert!(get_orphan_exercises(&pool).await.unwrap().is_empty());
+
+        let planned = get_or_create_exercise(&pool, "Front Squat").await.unwrap();
+        create_workout_template(
+            &pool,
+            "Legs",
+            &[NewTemplateExercise {
+                exercise_id: planned.id,
+                target_sets: 3,
+                target_reps: 5,
+            }],
+        )
         .await
-        .map_err(|e| {
-            warn!("delete_workout_set failed for set_id {}: {}", set_id, e);
-            anyhow::Error::from(e)
-        })?;
-    Ok(result.rows_affected())
-}
+        .unwrap();
+        assert!(
+            prune_orphan_exercises(&pool, false)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(get_exercise(&pool, planned.id).await.is_ok());
+    }
 
-pub async fn get_exercise_entries(
-    pool: &SqlitePool,
-    exercise_id: i64,
-    limit: Option<i64>,
-) -> Result<Vec<WorkoutSet>> {
-    debug!(
-        "get_exercise_entries called exercise_id={:?} limit={:?}",
-        exercise_id, limit
-    );
+    #[tokio::test]
+    async fn test_set_tags() {
+        let pool = setup_test_db().await;
 
-    let sets = if let Some(limit) = limit {
-        sqlx::query_as::<_, WorkoutSet>(
-            "SELECT id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at
-             FROM workout_sets WHERE exercise_id = ?1 ORDER BY created_at ASC LIMIT ?2"
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Squat").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "paused squat".to_string())
+            .await
+            .unwrap();
+        let tagged = add_workout_set(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            None,
         )
-        .bind(exercise_id)
-        .bind(limit)
-        .fetch_all(pool)
         .await
-    } else {
-        sqlx::query_as::<_, WorkoutSet>(
-            "SELECT id, session_id, exercise_id, request_string_id, weight, reps, set_index, rpe, notes, created_at, updated_at
-             FROM workout_sets WHERE exercise_id = ?1 ORDER BY created_at ASC"
+        .unwrap();
+        let untagged = add_workout_set(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            None,
+        )
+        .await
+        .unwrap();
+
+        add_tags_to_set(
+            &pool,
+            tagged.id,
+            &[
+                "Paused".to_string(),
+                " belt ".to_string(),
+                "paused".to_string(),
+            ],
         )
-        .bind(exercise_id)
-        .fetch_all(pool)
         .await
+        .unwrap();
+        add_tags_to_set(&pool, untagged.id, &[]).await.unwrap();
+
+        assert_eq!(
+            get_tags_for_set(&pool, tagged.id).await.unwrap(),
+            vec!["belt".to_string(), "paused".to_string()]
+        );
+        assert!(
+            get_tags_for_set(&pool, untagged.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        set_aoi_for_set(&pool, untagged.id, "lockout")
+            .await
+            .unwrap();
+        assert_eq!(
+            get_tags_for_session(&pool, session.id).await.unwrap(),
+            vec![
+                (tagged.id, "belt".to_string()),
+                (tagged.id, "paused".to_string())
+            ]
+        );
+        assert_eq!(
+            get_aoi_for_session(&pool, session.id).await.unwrap(),
+            vec![(untagged.id, "lockout".to_string())]
+        );
+
+        delete_workout_set(&pool, tagged.id).await.unwrap();
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM set_tags")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
     }
-    .map_err(|e| {
-        error!(
-            "failed to load exercise entries for exercise id {}: {}",
-            exercise_id, e
+
+    #[tokio::test]
+    async fn test_add_multiple_sets_contiguous_indexes() {
+        let pool = setup_test_db().await;
+
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Squat").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "10x3 squat".to_string())
+            .await
+            .unwrap();
+
+        let created = add_multiple_sets_to_workout(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &120.0,
+            &3,
+            None,
+            10,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            created.iter().map(|s| s.set_index).collect::<Vec<_>>(),
+            (1..=10).collect::<Vec<i64>>()
         );
-        anyhow::Error::from(e)
-    })?;
+        let stored = get_sets_for_session(&pool, session.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            stored.iter().map(|s| s.set_index).collect::<Vec<_>>(),
+            (1..=10).collect::<Vec<i64>>()
+        );
+    }
 
-    Ok(sets)
-}
+    #[tokio::test]
+    async fn test_compute_workout_duration_spans_sets() {
+        let pool = setup_test_db().await;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::db::init_database;
-    use crate::llm::ParsedSet;
-    use sqlx::SqlitePool;
-    use std::sync::Once;
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "squat".to_string())
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Squat").await.unwrap();
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            compute_workout_duration(&pool, session.id).await.unwrap(),
+            0
+        );
 
-    static INIT: Once = Once::new();
+        let mut set_ids = Vec::new();
+        for _ in 0..2 {
+            let set = add_workout_set(
+                &pool,
+                &session.id,
+                &exercise.id,
+                &request.id,
+                &100.0,
+                &5,
+                None,
+            )
+            .await
+            .unwrap();
+            set_ids.push(set.id);
+        }
+        let now = chrono::Utc::now().timestamp();
+        for (set_id, created_at) in set_ids.iter().zip([now - 600, now]) {
+            sqlx::query("UPDATE workout_sets SET created_at = ?1 WHERE id = ?2")
+                .bind(created_at)
+                .bind(set_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
 
-    async fn setup_test_db() -> SqlitePool {
-        INIT.call_once(|| {
-            env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("debug"))
-                .init();
-        });
+        assert_eq!(
+            compute_workout_duration(&pool, session.id).await.unwrap(),
+            600
+        );
+    }
 
-        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+    #[tokio::test]
+    async fn test_auto_complete_stale_workouts() {
+        let pool = setup_test_db().await;
 
-        sqlx::query("PRAGMA journal_mode = WAL")
-            .execute(&pool)
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "squat".to_string())
             .await
             .unwrap();
-        sqlx::query("PRAGMA synchronous = NORMAL")
+        let exercise = get_or_create_exercise(&pool, "Squat").await.unwrap();
+        let stale = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let fresh = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        for session_id in [stale.id, fresh.id] {
+            add_workout_set(
+                &pool,
+                &session_id,
+                &exercise.id,
+                &request.id,
+                &100.0,
+                &5,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query("UPDATE workout_sessions SET created_at = ?1 WHERE id = ?2")
+            .bind(now - 3 * 3600)
+            .bind(stale.id)
             .execute(&pool)
             .await
             .unwrap();
-        sqlx::query("PRAGMA busy_timeout = 5000")
+        sqlx::query("UPDATE workout_sets SET created_at = ?1 WHERE session_id = ?2")
+            .bind(now - 2 * 3600)
+            .bind(stale.id)
             .execute(&pool)
             .await
             .unwrap();
 
-        init_database(&pool).await.unwrap();
+        let closed = auto_complete_stale_workouts(&pool, 3600).await.unwrap();
+        assert_eq!(closed, 1);
 
-        pool
+        let stale = get_workout_session(&pool, stale.id).await.unwrap();
+        assert_eq!(stale.status, WorkoutStatus::Completed);
+        assert_eq!(stale.duration_seconds, 3600);
+        let fresh = get_workout_session(&pool, fresh.id).await.unwrap();
+        assert_eq!(fresh.status, WorkoutStatus::InProgress);
+
+        assert_eq!(auto_complete_stale_workouts(&pool, 3600).await.unwrap(), 0);
     }
 
     #[tokio::test]
-    async fn test_create_workout_session() {
+    async fn test_get_or_create_muscle() {
+        let pool = setup_test_db().await;
+
+        let muscle = get_or_create_muscle(&pool, "Chest").await.unwrap();
+        assert_eq!(muscle.name, "Chest");
+        assert!(muscle.id > 0);
+
+        let same = get_or_create_muscle(&pool, "Chest").await.unwrap();
+        assert_eq!(muscle.id, same.id);
+    }
+
+    #[tokio::test]
+    async fn test_link_and_read_exercise_muscles() {
+        let pool = setup_test_db().await;
+
+        let bench = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let squat = get_or_create_exercise(&pool, "Squat").await.unwrap();
+        assert!(
+            get_muscles_for_exercise(&pool, bench.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        for (muscle, relation_type) in [
+            ("Triceps", "secondary"),
+            ("Chest", "primary"),
+            ("Anterior Deltoid", "secondary"),
+        ] {
+            let muscle = get_or_create_muscle(&pool, muscle).await.unwrap();
+            let link = link_exercise_to_muscle(
+                &pool,
+                &NewExerciseMuscle {
+                    exercise_id: bench.id,
+                    muscle_id: muscle.id,
+                    relation_type: relation_type.to_string(),
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(link.relation_type, relation_type);
+        }
+
+        let read_back: Vec<(String, String)> = get_muscles_for_exercise(&pool, bench.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(muscle, relation_type)| (muscle.name, relation_type))
+            .collect();
+        assert_eq!(
+            read_back,
+            vec![
+                ("Chest".to_string(), "primary".to_string()),
+                ("Anterior Deltoid".to_string(), "secondary".to_string()),
+                ("Triceps".to_string(), "secondary".to_string()),
+            ]
+        );
+        assert!(
+            get_muscles_for_exercise(&pool, squat.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_muscle_set_counts() {
         let pool = setup_test_db().await;
 
         let session = create_workout_session(&pool, None, None, None, None, None)
             .await
             .unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "push day".to_string())
+            .await
+            .unwrap();
+        let chest = get_or_create_muscle(&pool, "Chest").await.unwrap();
+        let triceps = get_or_create_muscle(&pool, "Triceps").await.unwrap();
+        let bench = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let pushdown = get_or_create_exercise(&pool, "Triceps Pushdown")
+            .await
+            .unwrap();
 
-        assert!(session.id > 0);
-        assert_eq!(session.user_id, None);
-        assert_eq!(session.name, None);
-        assert_eq!(session.duration_seconds, 0);
+        for (exercise_id, muscle_id, relation_type) in [
+            (bench.id, chest.id, "primary"),
+            (bench.id, triceps.id, "secondary"),
+            (pushdown.id, triceps.id, "primary"),
+        ] {
+            link_exercise_to_muscle(
+                &pool,
+                &NewExerciseMuscle {
+                    exercise_id,
+                    muscle_id,
+                    relation_type: relation_type.to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        add_multiple_sets_to_workout(
+            &pool,
+            &session.id,
+            &bench.id,
+            &request.id,
+            &100.0,
+            &5,
+            None,
+            3,
+        )
+        .await
+        .unwrap();
+        add_workout_set(
+            &pool,
+            &session.id,
+            &pushdown.id,
+            &request.id,
+            &30.0,
+            &12,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let counts = get_muscle_set_counts(&pool, session.id).await.unwrap();
+        assert_eq!(
+            counts
+                .iter()
+                .map(|(m, count)| (m.name.as_str(), *count))
+                .collect::<Vec<_>>(),
+            vec![("Chest", 3), ("Triceps", 1)]
+        );
+
+        let other = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert!(
+            get_muscle_set_counts(&pool, other.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
     }
 
     #[tokio::test]
-    async fn test_create_workout_session_with_fields() {
+    async fn test_get_or_create_user() {
         let pool = setup_test_db().await;
 
         let user = get_or_create_user(&pool, "testuser").await.unwrap();
-        let session = create_workout_session(
-            &pool,
-            Some(user.id),
-            Some("Test Workout".to_string()),
-            Some("Test notes".to_string()),
-            Some(3600),
-            None,
-        )
-        .await
-        .unwrap();
+        assert_eq!(user.username, "testuser");
+        assert!(user.id > 0);
 
-        assert!(session.id > 0);
-        assert_eq!(session.user_id, Some(user.id));
-        assert_eq!(session.name, Some("Test Workout".to_string()));
-        assert_eq!(session.notes, Some("Test notes".to_string()));
-        assert_eq!(session.duration_seconds, 3600);
+        let same = get_or_create_user(&pool, "testuser").await.unwrap();
+        assert_eq!(user.id, same.id);
     }
 
     #[tokio::test]
-    async fn test_get_workout_session() {
+    async fn test_create_request_string() {
         let pool = setup_test_db().await;
 
-        let created = create_workout_session(&pool, None, None, None, None, None)
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
             .await
             .unwrap();
-        let retrieved = get_workout_session(&pool, created.id).await.unwrap();
 
-        assert_eq!(created.id, retrieved.id);
-        assert_eq!(created.user_id, retrieved.user_id);
-        assert_eq!(created.name, retrieved.name);
+        assert_eq!(request.user_id, user.id);
+        assert_eq!(request.string, "100kg x 5");
+        assert!(request.id > 0);
     }
 
     #[tokio::test]
-    async fn test_get_workout_session_not_found() {
+    async fn test_create_request_string_for_username() {
         let pool = setup_test_db().await;
 
-        let result = get_workout_session(&pool, 99999).await;
-        assert!(result.is_err());
+        let request =
+            create_request_string_for_username(&pool, "testuser", "100kg x 5".to_string())
+                .await
+                .unwrap();
+
+        assert_eq!(request.string, "100kg x 5");
+        assert!(request.id > 0);
     }
 
     #[tokio::test]
-    async fn test_get_all_workout_sessions() {
+    async fn test_add_workout_set() {
         let pool = setup_test_db().await;
 
-        create_workout_session(&pool, None, None, None, None, None)
+        let session = create_workout_session(&pool, None, None, None, None, None)
             .await
             .unwrap();
-        create_workout_session(&pool, None, None, None, None, None)
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
             .await
             .unwrap();
 
-        let sessions = get_all_workout_sessions(&pool, None).await.unwrap();
-        assert_eq!(sessions.len(), 2);
+        let set = add_workout_set(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            Some(8.0),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(set.session_id, session.id);
+        assert_eq!(set.exercise_id, exercise.id);
+        assert_eq!(set.weight, 100.0);
+        assert_eq!(set.reps, 5);
+        assert_eq!(set.set_index, 1);
+        assert_eq!(set.rpe, Some(8.0));
     }
 
     #[tokio::test]
-    async fn test_delete_workout_session() {
+    async fn test_add_workout_set_index_increment() {
         let pool = setup_test_db().await;
 
         let session = create_workout_session(&pool, None, None, None, None, None)
             .await
             .unwrap();
-        let rows = delete_workout_session(&pool, session.id).await.unwrap();
-        assert_eq!(rows, 1);
-
-        let result = get_workout_session(&pool, session.id).await;
-        assert!(result.is_err());
-    }
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+            .await
+            .unwrap();
 
-    #[tokio::test]
-    async fn test_get_exercise() {
-        let pool = setup_test_db().await;
+        let set1 = add_workout_set(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            None,
+        )
+        .await
+        .unwrap();
 
-        let created = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
-        let retrieved = get_exercise(&pool, created.id).await.unwrap();
+        let set2 = add_workout_set(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            None,
+        )
+        .await
+        .unwrap();
 
-        assert_eq!(created.id, retrieved.id);
-        assert_eq!(created.name, retrieved.name);
-        assert_eq!(created.name, "Bench Press");
+        assert_eq!(set1.set_index, 1);
+        assert_eq!(set2.set_index, 2);
     }
 
     #[tokio::test]
-    async fn test_get_exercise_not_found() {
+    async fn test_add_multiple_sets_to_workout() {
         let pool = setup_test_db().await;
 
-        let result = get_exercise(&pool, 99999).await;
-        assert!(result.is_err());
-    }
-
-    #[tokio::test]
-    async fn test_get_all_exercises() {
-        let pool = setup_test_db().await;
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+            .await
+            .unwrap();
 
-        get_or_create_exercise(&pool, "Squat").await.unwrap();
-        get_or_create_exercise(&pool, "Deadlift").await.unwrap();
+        let sets = add_multiple_sets_to_workout(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            Some(8.0),
+            3,
+        )
+        .await
+        .unwrap();
 
-        let exercises = get_all_exercises(&pool).await.unwrap();
-        assert_eq!(exercises.len(), 2);
+        assert_eq!(sets.len(), 3);
+        assert_eq!(sets[0].set_index, 1);
+        assert_eq!(sets[1].set_index, 2);
+        assert_eq!(sets[2].set_index, 3);
+        assert_eq!(sets[0].weight, 100.0);
+        assert_eq!(sets[0].reps, 5);
     }
 
     #[tokio::test]
-    async fn test_get_or_create_exercise_new() {
+    async fn test_add_workout_set_rejects_invalid_values() {
         let pool = setup_test_db().await;
 
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
         let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
-        assert_eq!(exercise.name, "Bench Press");
-        assert!(!exercise.slug.is_empty());
-    }
-
-    #[tokio::test]
-    async fn test_get_or_create_exercise_existing() {
-        let pool = setup_test_db().await;
-
-        let first = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
-        let second = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+            .await
+            .unwrap();
 
-        assert_eq!(first.id, second.id);
-        assert_eq!(first.name, second.name);
-    }
+        // Bodyweight, the rep cap and both RPE bounds are all accepted.
+        for (weight, reps, rpe) in [
+            (0.0, 5, Some(0.0)),
+            (100.0, MAX_REPS, Some(10.0)),
+            (60.0, 0, None),
+        ] {
+            add_workout_set(
+                &pool,
+                &session.id,
+                &exercise.id,
+                &request.id,
+                &weight,
+                &reps,
+                rpe,
+            )
+            .await
+            .unwrap();
+        }
 
-    #[tokio::test]
-    async fn test_get_or_create_muscle() {
-        let pool = setup_test_db().await;
+        for (weight, reps, rpe, message) in [
+            (-20.0, 5, None, "weight"),
+            (f64::NAN, 5, None, "weight"),
+            (100.0, -1, None, "reps"),
+            (100.0, MAX_REPS + 1, None, "reps"),
+            (100.0, 5, Some(-0.5), "RPE"),
+            (100.0, 5, Some(11.0), "RPE"),
+        ] {
+            let single = add_workout_set(
+                &pool,
+                &session.id,
+                &exercise.id,
+                &request.id,
+                &weight,
+                &reps,
+                rpe,
+            )
+            .await
+            .unwrap_err();
+            assert!(single.to_string().contains(message), "{}", single);
 
-        let muscle = get_or_create_muscle(&pool, "Chest").await.unwrap();
-        assert_eq!(muscle.name, "Chest");
-        assert!(muscle.id > 0);
+            let multiple = add_multiple_sets_to_workout(
+                &pool,
+                &session.id,
+                &exercise.id,
+                &request.id,
+                &weight,
+                &reps,
+                rpe,
+                3,
+            )
+            .await
+            .unwrap_err();
+            assert!(multiple.to_string().contains(message), "{}", multiple);
+        }
 
-        let same = get_or_create_muscle(&pool, "Chest").await.unwrap();
-        assert_eq!(muscle.id, same.id);
+        let stored = get_sets_for_session(&pool, session.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 3);
     }
 
     #[tokio::test]
-    async fn test_get_or_create_user() {
+    async fn test_get_sets_for_session() {
         let pool = setup_test_db().await;
 
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
         let user = get_or_create_user(&pool, "testuser").await.unwrap();
-        assert_eq!(user.username, "testuser");
-        assert!(user.id > 0);
+        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+            .await
+            .unwrap();
 
-        let same = get_or_create_user(&pool, "testuser").await.unwrap();
-        assert_eq!(user.id, same.id);
+        add_workout_set(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            None,
+        )
+        .await
+        .unwrap();
+
+        add_workout_set(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let sets = get_sets_for_session(&pool, session.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(sets.len(), 2);
+        assert_eq!(sets[0].set_index, 1);
+        assert_eq!(sets[1].set_index, 2);
     }
 
     #[tokio::test]
-    async fn test_create_request_string() {
+    async fn test_get_sets_for_session_page() {
         let pool = setup_test_db().await;
 
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
         let user = get_or_create_user(&pool, "testuser").await.unwrap();
-        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+        let request = create_request_string(&pool, user.id, "7x5 bench 100kg".to_string())
             .await
             .unwrap();
+        add_multiple_sets_to_workout(
+            &pool,
+            &session.id,
+            &exercise.id,
+            &request.id,
+            &100.0,
+            &5,
+            None,
+            7,
+        )
+        .await
+        .unwrap();
 
-        assert_eq!(request.user_id, user.id);
-        assert_eq!(request.string, "100kg x 5");
-        assert!(request.id > 0);
-    }
-
-    #[tokio::test]
-    async fn test_create_request_string_for_username() {
-        let pool = setup_test_db().await;
+        let page = get_sets_for_session(&pool, session.id, Some(3), Some(3))
+            .await
+            .unwrap();
+        assert_eq!(
+            page.iter().map(|s| s.set_index).collect::<Vec<_>>(),
+            vec![4, 5, 6]
+        );
 
-        let request =
-            create_request_string_for_username(&pool, "testuser", "100kg x 5".to_string())
-                .await
-                .unwrap();
+        let last = get_sets_for_session(&pool, session.id, Some(3), Some(6))
+            .await
+            .unwrap();
+        assert_eq!(last.len(), 1);
 
-        assert_eq!(request.string, "100kg x 5");
-        assert!(request.id > 0);
+        let all = get_sets_for_session(&pool, session.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 7);
     }
 
     #[tokio::test]
-    async fn test_add_workout_set() {
+    async fn test_update_workout_set() {
         let pool = setup_test_db().await;
 
         let session = create_workout_session(&pool, None, None, None, None, None)
@@ -1083,62 +4546,154 @@ mod tests {
             &request.id,
             &100.0,
             &5,
-            Some(8.0),
+            None,
         )
         .await
         .unwrap();
 
-        assert_eq!(set.session_id, session.id);
-        assert_eq!(set.exercise_id, exercise.id);
-        assert_eq!(set.weight, 100.0);
-        assert_eq!(set.reps, 5);
-        assert_eq!(set.set_index, 1);
-        assert_eq!(set.rpe, Some(8.0));
+        let update = UpdateWorkoutSet {
+            weight: Some(105.0),
+            reps: Some(6),
+            rpe: Some(9.0),
+            notes: Some("Updated set".to_string()),
+            ..Default::default()
+        };
+
+        let updated = update_workout_set(&pool, set.id, &update).await.unwrap();
+
+        assert_eq!(updated.weight, 105.0);
+        assert_eq!(updated.reps, 6);
+        assert_eq!(updated.rpe, Some(9.0));
+        assert_eq!(updated.notes, Some("Updated set".to_string()));
+        assert_eq!(updated.session_id, set.session_id);
+        assert_eq!(updated.exercise_id, set.exercise_id);
+
+        // Leaving notes out keeps them; an empty note clears them.
+        let update = UpdateWorkoutSet {
+            weight: Some(110.0),
+            ..Default::default()
+        };
+        let updated = update_workout_set(&pool, set.id, &update).await.unwrap();
+        assert_eq!(updated.weight, 110.0);
+        assert_eq!(updated.notes, Some("Updated set".to_string()));
+
+        let update = UpdateWorkoutSet {
+            notes: Some(String::new()),
+            ..Default::default()
+        };
+        let updated = update_workout_set(&pool, set.id, &update).await.unwrap();
+        assert_eq!(updated.notes, None);
     }
 
     #[tokio::test]
-    async fn test_add_workout_set_index_increment() {
+    async fn test_rest_style_round_trip() {
         let pool = setup_test_db().await;
 
         let session = create_workout_session(&pool, None, None, None, None, None)
             .await
             .unwrap();
-        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let exercise = get_or_create_exercise(&pool, "Squat").await.unwrap();
         let user = get_or_create_user(&pool, "testuser").await.unwrap();
-        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+        let request = create_request_string(&pool, user.id, "squat 5x3".to_string())
             .await
             .unwrap();
 
-        let set1 = add_workout_set(
+        let plain = add_workout_set(
             &pool,
             &session.id,
             &exercise.id,
             &request.id,
-            &100.0,
-            &5,
+            &140.0,
+            &3,
             None,
         )
         .await
         .unwrap();
+        assert_eq!(plain.rest_style, None);
+        assert_eq!(plain.rest_style.unwrap_or_default(), RestStyle::Hypertrophy);
+
+        let mut tx = pool.begin().await.unwrap();
+        let heavy = insert_workout_set_tx(
+            &mut tx,
+            &NewWorkoutSet {
+                session_id: session.id,
+                exercise_id: exercise.id,
+                request_string_id: request.id,
+                weight: 160.0,
+                reps: 2,
+                set_index: 2,
+                rpe: None,
+                notes: None,
+                rest_style: Some(RestStyle::Strength),
+                duration_seconds: None,
+                distance_meters: None,
+            },
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+        let stored = get_workout_set(&pool, heavy.id).await.unwrap();
+        assert_eq!(stored.rest_style, Some(RestStyle::Strength));
 
-        let set2 = add_workout_set(
+        let update = UpdateWorkoutSet {
+            rest_style: Some(RestStyle::Conditioning),
+            ..Default::default()
+        };
+        let updated = update_workout_set(&pool, heavy.id, &update).await.unwrap();
+        assert_eq!(updated.rest_style, Some(RestStyle::Conditioning));
+
+        let untouched = update_workout_set(
+            &pool,
+            heavy.id,
+            &UpdateWorkoutSet {
+                reps: Some(3),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(untouched.rest_style, Some(RestStyle::Conditioning));
+    }
+
+    #[tokio::test]
+    async fn test_update_workout_set_partial() {
+        let pool = setup_test_db().await;
+
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+            .await
+            .unwrap();
+
+        let set = add_workout_set(
             &pool,
             &session.id,
             &exercise.id,
             &request.id,
             &100.0,
             &5,
-            None,
+            Some(8.0),
         )
         .await
         .unwrap();
 
-        assert_eq!(set1.set_index, 1);
-        assert_eq!(set2.set_index, 2);
+        let update = UpdateWorkoutSet {
+            weight: Some(105.0),
+            ..Default::default()
+        };
+
+        let updated = update_workout_set(&pool, set.id, &update).await.unwrap();
+
+        assert_eq!(updated.weight, 105.0);
+        assert_eq!(updated.reps, set.reps);
+        assert_eq!(updated.rpe, set.rpe);
     }
 
     #[tokio::test]
-    async fn test_add_multiple_sets_to_workout() {
+    async fn test_delete_workout_set() {
         let pool = setup_test_db().await;
 
         let session = create_workout_session(&pool, None, None, None, None, None)
@@ -1150,45 +4705,51 @@ mod tests {
             .await
             .unwrap();
 
-        let sets = add_multiple_sets_to_workout(
+        let set = add_workout_set(
             &pool,
             &session.id,
             &exercise.id,
             &request.id,
             &100.0,
             &5,
-            Some(8.0),
-            3,
+            None,
         )
         .await
         .unwrap();
 
-        assert_eq!(sets.len(), 3);
-        assert_eq!(sets[0].set_index, 1);
-        assert_eq!(sets[1].set_index, 2);
-        assert_eq!(sets[2].set_index, 3);
-        assert_eq!(sets[0].weight, 100.0);
-        assert_eq!(sets[0].reps, 5);
+        let rows = delete_workout_set(&pool, set.id).await.unwrap();
+        assert_eq!(rows, 1);
+
+        let sets = get_sets_for_session(&pool, session.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(sets.len(), 0);
     }
 
     #[tokio::test]
-    async fn test_get_sets_for_session() {
+    async fn test_get_exercise_entries() {
         let pool = setup_test_db().await;
 
-        let session = create_workout_session(&pool, None, None, None, None, None)
+        let session1 = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let session2 = create_workout_session(&pool, None, None, None, None, None)
             .await
             .unwrap();
         let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
         let user = get_or_create_user(&pool, "testuser").await.unwrap();
-        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+        let request1 = create_request_string(&pool, user.id, "100kg x 5".to_string())
+            .await
+            .unwrap();
+        let request2 = create_request_string(&pool, user.id, "100kg x 5".to_string())
             .await
             .unwrap();
 
         add_workout_set(
             &pool,
-            &session.id,
+            &session1.id,
             &exercise.id,
-            &request.id,
+            &request1.id,
             &100.0,
             &5,
             None,
@@ -1198,9 +4759,9 @@ mod tests {
 
         add_workout_set(
             &pool,
-            &session.id,
+            &session2.id,
             &exercise.id,
-            &request.id,
+            &request2.id,
             &100.0,
             &5,
             None,
@@ -1208,57 +4769,166 @@ mod tests {
         .await
         .unwrap();
 
-        let sets = get_sets_for_session(&pool, session.id).await.unwrap();
-        assert_eq!(sets.len(), 2);
-        assert_eq!(sets[0].set_index, 1);
-        assert_eq!(sets[1].set_index, 2);
+        let entries = get_exercise_entries(&pool, exercise.id, None)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 2);
     }
 
     #[tokio::test]
-    async fn test_update_workout_set() {
+    async fn test_search_sets_matches_notes_and_request_strings() {
         let pool = setup_test_db().await;
 
         let session = create_workout_session(&pool, None, None, None, None, None)
             .await
             .unwrap();
-        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let squat = get_or_create_exercise(&pool, "Squat").await.unwrap();
         let user = get_or_create_user(&pool, "testuser").await.unwrap();
-        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+        let paused = create_request_string(&pool, user.id, "3x5 Paused Squats 100kg".to_string())
+            .await
+            .unwrap();
+        let plain = create_request_string(&pool, user.id, "squat 120 for 3".to_string())
             .await
             .unwrap();
 
-        let set = add_workout_set(
+        let paused_set =
+            add_workout_set(&pool, &session.id, &squat.id, &paused.id, &100.0, &5, None)
+                .await
+                .unwrap();
+        let plain_set = add_workout_set(&pool, &session.id, &squat.id, &plain.id, &120.0, &3, None)
+            .await
+            .unwrap();
+        update_workout_set(
             &pool,
-            &session.id,
-            &exercise.id,
-            &request.id,
-            &100.0,
-            &5,
-            None,
+            plain_set.id,
+            &UpdateWorkoutSet {
+                session_id: None,
+                exercise_id: None,
+                request_string_id: None,
+                weight: None,
+                reps: None,
+                rpe: None,
+                set_index: None,
+                notes: Some("100% effort".to_string()),
+                rest_style: None,
+            },
         )
         .await
         .unwrap();
 
-        let update = UpdateWorkoutSet {
-            weight: Some(105.0),
-            reps: Some(6),
-            rpe: Some(9.0),
-            notes: Some("Updated set".to_string()),
-            ..Default::default()
-        };
+        let found = search_sets(&pool, "paused squat", None).await.unwrap();
+        assert_eq!(
+            found.iter().map(|s| s.id).collect::<Vec<_>>(),
+            vec![paused_set.id]
+        );
 
-        let updated = update_workout_set(&pool, set.id, &update).await.unwrap();
+        let found = search_sets(&pool, "100%", None).await.unwrap();
+        assert_eq!(
+            found.iter().map(|s| s.id).collect::<Vec<_>>(),
+            vec![plain_set.id]
+        );
 
-        assert_eq!(updated.weight, 105.0);
-        assert_eq!(updated.reps, 6);
-        assert_eq!(updated.rpe, Some(9.0));
-        assert_eq!(updated.notes, Some("Updated set".to_string()));
-        assert_eq!(updated.session_id, set.session_id);
-        assert_eq!(updated.exercise_id, set.exercise_id);
+        let found = search_sets(&pool, "squat", Some(1)).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert!(search_sets(&pool, "  ", None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_exercise_history_pairs_sets_with_sessions() {
+        let pool = setup_test_db().await;
+
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "bench".to_string())
+            .await
+            .unwrap();
+
+        let mut expected = Vec::new();
+        for (created_at, weight) in [(1_700_000_000, 80.0), (1_700_600_000, 85.0)] {
+            let session = create_workout_session(&pool, None, None, None, None, None)
+                .await
+                .unwrap();
+            sqlx::query("UPDATE workout_sessions SET created_at = ?1 WHERE id = ?2")
+                .bind(created_at)
+                .bind(session.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+            let set = add_workout_set(
+                &pool,
+                &session.id,
+                &exercise.id,
+                &request.id,
+                &weight,
+                &5,
+                None,
+            )
+            .await
+            .unwrap();
+            set_workout_set_created_at(&pool, set.id, created_at + 60)
+                .await
+                .unwrap();
+            expected.push((set.id, session.id, created_at));
+        }
+
+        let history = get_exercise_history(&pool, exercise.id, None)
+            .await
+            .unwrap();
+        let got: Vec<(i64, i64, i64)> = history
+            .iter()
+            .map(|(set, session)| (set.id, session.id, session.created_at))
+            .collect();
+        expected.reverse();
+        assert_eq!(got, expected);
+        assert_eq!(history[0].0.weight, 85.0);
+
+        let limited = get_exercise_history(&pool, exercise.id, Some(1))
+            .await
+            .unwrap();
+        assert_eq!(limited.len(), 1);
+        assert_eq!(limited[0].1.created_at, 1_700_600_000);
+    }
+
+    #[tokio::test]
+    async fn test_get_exercise_entries_with_limit() {
+        let pool = setup_test_db().await;
+
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+
+        for i in 0..5 {
+            let session = create_workout_session(&pool, None, None, None, None, None)
+                .await
+                .unwrap();
+            let request = create_request_string(&pool, user.id, format!("100kg x 5 {}", i))
+                .await
+                .unwrap();
+
+            let set = add_workout_set(
+                &pool,
+                &session.id,
+                &exercise.id,
+                &request.id,
+                &(100.0 + i as f64),
+                &5,
+                None,
+            )
+            .await
+            .unwrap();
+            set_workout_set_created_at(&pool, set.id, 1_700_000_000 + i * 86_400)
+                .await
+                .unwrap();
+        }
+
+        let entries = get_exercise_entries(&pool, exercise.id, Some(3))
+            .await
+            .unwrap();
+        let weights: Vec<f64> = entries.iter().map(|s| s.weight).collect();
+        assert_eq!(weights, vec![102.0, 103.0, 104.0]);
     }
 
     #[tokio::test]
-    async fn test_update_workout_set_partial() {
+    async fn test_update_workout_set_from_parsed() {
         let pool = setup_test_db().await;
 
         let session = create_workout_session(&pool, None, None, None, None, None)
@@ -1277,25 +4947,53 @@ mod tests {
             &request.id,
             &100.0,
             &5,
-            Some(8.0),
+            None,
         )
         .await
         .unwrap();
 
-        let update = UpdateWorkoutSet {
-            weight: Some(105.0),
-            ..Default::default()
+        let parsed = ParsedSet {
+            exercise: "Squat".to_string(),
+            weight: Some(150.0),
+            unit: None,
+            reps: Some(3),
+            rpe: Some(9.0),
+            set_count: None,
+            duration_seconds: None,
+            distance_meters: None,
+            tags: vec![],
+            aoi: None,
+            original_string: "150kg x 3 @9".to_string(),
         };
 
-        let updated = update_workout_set(&pool, set.id, &update).await.unwrap();
+        let updated = update_workout_set_from_parsed(&pool, set.id, &parsed)
+            .await
+            .unwrap();
 
-        assert_eq!(updated.weight, 105.0);
-        assert_eq!(updated.reps, set.reps);
-        assert_eq!(updated.rpe, set.rpe);
+        let new_exercise = get_exercise(&pool, updated.exercise_id).await.unwrap();
+        assert_eq!(new_exercise.name, "Squat");
+        assert_eq!(updated.weight, 150.0);
+        assert_eq!(updated.reps, 3);
+        assert_eq!(updated.rpe, Some(9.0));
+
+        let in_pounds = ParsedSet {
+            exercise: String::new(),
+            weight: Some(225.0),
+            unit: Some(WeightUnit::Lb),
+            reps: None,
+            rpe: None,
+            original_string: "225lb".to_string(),
+            ..parsed
+        };
+        let updated = update_workout_set_from_parsed(&pool, set.id, &in_pounds)
+            .await
+            .unwrap();
+        assert!((updated.weight - 102.06).abs() < 0.01);
+        assert_eq!(updated.reps, 3);
     }
 
     #[tokio::test]
-    async fn test_delete_workout_set() {
+    async fn test_get_personal_record_and_estimated_1rm() {
         let pool = setup_test_db().await;
 
         let session = create_workout_session(&pool, None, None, None, None, None)
@@ -1303,157 +5001,437 @@ mod tests {
             .unwrap();
         let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
         let user = get_or_create_user(&pool, "testuser").await.unwrap();
-        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+        let request = create_request_string(&pool, user.id, "bench".to_string())
             .await
             .unwrap();
 
-        let set = add_workout_set(
-            &pool,
-            &session.id,
-            &exercise.id,
-            &request.id,
-            &100.0,
-            &5,
-            None,
-        )
-        .await
-        .unwrap();
+        assert!(
+            get_personal_record(&pool, exercise.id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            get_best_estimated_1rm(&pool, exercise.id).await.unwrap(),
+            None
+        );
 
-        let rows = delete_workout_set(&pool, set.id).await.unwrap();
-        assert_eq!(rows, 1);
+        let mut ids = Vec::new();
+        for (weight, reps) in [(100.0, 5), (110.0, 2), (80.0, 12), (110.0, 3), (110.0, 3)] {
+            let set = add_workout_set(
+                &pool,
+                &session.id,
+                &exercise.id,
+                &request.id,
+                &weight,
+                &reps,
+                None,
+            )
+            .await
+            .unwrap();
+            ids.push(set.id);
+        }
 
-        let sets = get_sets_for_session(&pool, session.id).await.unwrap();
-        assert_eq!(sets.len(), 0);
+        let pr = get_personal_record(&pool, exercise.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pr.weight, 110.0);
+        assert_eq!(pr.reps, 3);
+        assert_eq!(pr.id, ids[4]);
+
+        // 110kg x 3 estimates 121kg, ahead of 100kg x 5 (~116.7) and 80kg x 12 (112).
+        let best = get_best_estimated_1rm(&pool, exercise.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((best - 121.0).abs() < 1e-9);
+        assert!((estimate_one_rep_max(100.0, 5) - 116.666_666).abs() < 1e-3);
     }
 
     #[tokio::test]
-    async fn test_get_exercise_entries() {
+    async fn test_exercise_and_session_volume() {
         let pool = setup_test_db().await;
 
-        let session1 = create_workout_session(&pool, None, None, None, None, None)
+        let first = create_workout_session(&pool, None, None, None, None, None)
             .await
             .unwrap();
-        let session2 = create_workout_session(&pool, None, None, None, None, None)
+        let second = create_workout_session(&pool, None, None, None, None, None)
             .await
             .unwrap();
-        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let bench = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let row = get_or_create_exercise(&pool, "Barbell Row").await.unwrap();
         let user = get_or_create_user(&pool, "testuser").await.unwrap();
-        let request1 = create_request_string(&pool, user.id, "100kg x 5".to_string())
+        let request = create_request_string(&pool, user.id, "volume".to_string())
             .await
             .unwrap();
-        let request2 = create_request_string(&pool, user.id, "100kg x 5".to_string())
+
+        let old = add_workout_set(&pool, &first.id, &bench.id, &request.id, &100.0, &5, None)
+            .await
+            .unwrap();
+        set_workout_set_created_at(&pool, old.id, 1_000)
+            .await
+            .unwrap();
+        add_workout_set(&pool, &first.id, &row.id, &request.id, &60.0, &10, None)
+            .await
+            .unwrap();
+        let recent = add_workout_set(&pool, &second.id, &bench.id, &request.id, &102.5, &4, None)
+            .await
+            .unwrap();
+        set_workout_set_created_at(&pool, recent.id, 2_000)
             .await
             .unwrap();
 
-        add_workout_set(
-            &pool,
-            &session1.id,
-            &exercise.id,
-            &request1.id,
-            &100.0,
-            &5,
-            None,
-        )
-        .await
-        .unwrap();
+        assert_eq!(get_session_volume(&pool, first.id).await.unwrap(), 1100.0);
+        assert_eq!(get_session_volume(&pool, second.id).await.unwrap(), 410.0);
+        assert_eq!(
+            get_exercise_volume(&pool, bench.id, None).await.unwrap(),
+            910.0
+        );
+        assert_eq!(
+            get_exercise_volume(&pool, bench.id, Some(1_500))
+                .await
+                .unwrap(),
+            410.0
+        );
+        assert_eq!(
+            get_exercise_volume(&pool, bench.id, Some(3_000))
+                .await
+                .unwrap(),
+            0.0
+        );
+    }
 
-        add_workout_set(
+    #[tokio::test]
+    async fn test_bodyweight_entries() {
+        let pool = setup_test_db().await;
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let other = get_or_create_user(&pool, "other").await.unwrap();
+
+        let later = add_bodyweight(&pool, user.id, 81.2, 2_000).await.unwrap();
+        let earlier = add_bodyweight(&pool, user.id, 80.5, 1_000).await.unwrap();
+        add_bodyweight(&pool, other.id, 60.0, 1_500).await.unwrap();
+        assert_eq!(later.weight, 81.2);
+        assert_eq!(later.recorded_at, 2_000);
+
+        assert!(add_bodyweight(&pool, user.id, 0.0, 3_000).await.is_err());
+        assert!(add_bodyweight(&pool, user.id, -75.0, 3_000).await.is_err());
+        assert!(
+            add_bodyweight(&pool, user.id, f64::NAN, 3_000)
+                .await
+                .is_err()
+        );
+
+        let series = get_bodyweight_series(&pool, user.id, None).await.unwrap();
+        assert_eq!(
+            series.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![earlier.id, later.id]
+        );
+        let recent = get_bodyweight_series(&pool, user.id, Some(1_500))
+            .await
+            .unwrap();
+        assert_eq!(
+            recent.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![later.id]
+        );
+
+        assert_eq!(delete_bodyweight_entry(&pool, earlier.id).await.unwrap(), 1);
+        assert_eq!(delete_bodyweight_entry(&pool, earlier.id).await.unwrap(), 0);
+        let series = get_bodyweight_series(&pool, user.id, None).await.unwrap();
+        assert_eq!(series.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_owned_equipment_filters_exercises() {
+        let pool = setup_test_db().await;
+
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let barbell = get_or_create_equipment(&pool, "Barbell").await.unwrap();
+        let dumbbell = get_or_create_equipment(&pool, "Dumbbell").await.unwrap();
+        assert_eq!(
+            get_or_create_equipment(&pool, "Barbell").await.unwrap().id,
+            barbell.id
+        );
+
+        let squat = get_or_create_exercise(&pool, "Squat").await.unwrap();
+        let curl = get_or_create_exercise(&pool, "Dumbbell Curl")
+            .await
+            .unwrap();
+        get_or_create_exercise(&pool, "Push Up").await.unwrap();
+        for (exercise_id, equipment_id) in [(squat.id, barbell.id), (curl.id, dumbbell.id)] {
+            link_exercise_to_equipment(
+                &pool,
+                &NewExerciseEquipment {
+                    exercise_id,
+                    equipment_id,
+                    relation_type: "requires".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let all: Vec<String> = list_equipment(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(all, vec!["Barbell", "Dumbbell"]);
+
+        set_equipment_owned(&pool, user.id, barbell.id, true)
+            .await
+            .unwrap();
+        set_equipment_owned(&pool, user.id, barbell.id, true)
+            .await
+            .unwrap();
+        let owned = get_owned_equipment(&pool, user.id).await.unwrap();
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].id, barbell.id);
+
+        let available: Vec<String> = get_exercises_for_owned_equipment(&pool, user.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(available, vec!["Push Up", "Squat"]);
+
+        set_equipment_owned(&pool, user.id, barbell.id, false)
+            .await
+            .unwrap();
+        assert!(
+            get_owned_equipment(&pool, user.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let available: Vec<String> = get_exercises_for_owned_equipment(&pool, user.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(available, vec!["Push Up"]);
+    }
+
+    #[tokio::test]
+    async fn test_set_workout_set_created_at() {
+        let pool = setup_test_db().await;
+
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Deadlift").await.unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "deadlift".to_string())
+            .await
+            .unwrap();
+        let set = add_workout_set(
             &pool,
-            &session2.id,
+            &session.id,
             &exercise.id,
-            &request2.id,
-            &100.0,
-            &5,
+            &request.id,
+            &180.0,
+            &3,
             None,
         )
         .await
         .unwrap();
 
-        let entries = get_exercise_entries(&pool, exercise.id, None)
+        set_workout_set_created_at(&pool, set.id, 1_700_000_000)
             .await
             .unwrap();
-        assert_eq!(entries.len(), 2);
+        let fetched = get_workout_set(&pool, set.id).await.unwrap();
+        assert_eq!(fetched.created_at, 1_700_000_000);
+        assert_eq!(fetched.updated_at, 1_700_000_000);
     }
 
     #[tokio::test]
-    async fn test_get_exercise_entries_with_limit() {
+    async fn test_get_training_calendar_fills_gaps() {
         let pool = setup_test_db().await;
 
-        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let exercise = get_or_create_exercise(&pool, "Squat").await.unwrap();
         let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "squat".to_string())
+            .await
+            .unwrap();
 
-        for i in 0..5 {
-            let session = create_workout_session(&pool, None, None, None, None, None)
-                .await
-                .unwrap();
-            let request = create_request_string(&pool, user.id, format!("100kg x 5 {}", i))
-                .await
-                .unwrap();
-
-            add_workout_set(
+        let now = chrono::Utc::now().timestamp();
+        for (days_ago, weight, reps) in [(0, 100.0, 5), (0, 100.0, 3), (2, 80.0, 10), (9, 60.0, 5)]
+        {
+            let set = add_workout_set(
                 &pool,
                 &session.id,
                 &exercise.id,
                 &request.id,
-                &100.0,
-                &5,
+                &weight,
+                &reps,
                 None,
             )
             .await
             .unwrap();
+            set_workout_set_created_at(&pool, set.id, now - days_ago * 24 * 60 * 60)
+                .await
+                .unwrap();
         }
 
-        let entries = get_exercise_entries(&pool, exercise.id, Some(3))
+        let calendar = get_training_calendar(&pool, 5).await.unwrap();
+        assert_eq!(calendar.len(), 5);
+        let today = chrono::Utc::now().date_naive();
+        assert_eq!(calendar[4].0, today.format("%Y-%m-%d").to_string());
+        assert_eq!(
+            calendar
+                .iter()
+                .map(|(_, count, _)| *count)
+                .collect::<Vec<_>>(),
+            vec![0, 0, 1, 0, 2]
+        );
+        assert_eq!(calendar[2].2, 800.0);
+        assert_eq!(calendar[4].2, 800.0);
+        assert_eq!(calendar[3].2, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_exercise_aliases() {
+        let pool = setup_test_db().await;
+
+        let bench = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let seeded = resolve_alias(&pool, "Bench").await.unwrap().unwrap();
+        assert_eq!(seeded.id, bench.id);
+        assert!(resolve_alias(&pool, "press").await.unwrap().is_none());
+
+        add_exercise_alias(&pool, bench.id, "  Flat   BB Bench ")
+            .await
+            .unwrap();
+        let resolved = get_or_create_exercise(&pool, "flat bb bench")
             .await
             .unwrap();
-        assert_eq!(entries.len(), 3);
+        assert_eq!(resolved.id, bench.id);
+        assert_eq!(get_all_exercises(&pool).await.unwrap().len(), 1);
     }
 
     #[tokio::test]
-    async fn test_update_workout_set_from_parsed() {
+    async fn test_merge_exercises() {
         let pool = setup_test_db().await;
-
         let session = create_workout_session(&pool, None, None, None, None, None)
             .await
             .unwrap();
-        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
-        let user = get_or_create_user(&pool, "testuser").await.unwrap();
-        let request = create_request_string(&pool, user.id, "100kg x 5".to_string())
+        let request = create_request_string_for_username(&pool, "test", "bench".to_string())
             .await
             .unwrap();
-
-        let set = add_workout_set(
+        let keep = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let duplicate = get_or_create_exercise(&pool, "Barbell Bench Pres")
+            .await
+            .unwrap();
+        add_workout_set(&pool, &session.id, &keep.id, &request.id, &100.0, &5, None)
+            .await
+            .unwrap();
+        add_multiple_sets_to_workout(
             &pool,
             &session.id,
-            &exercise.id,
+            &duplicate.id,
             &request.id,
             &100.0,
             &5,
             None,
+            2,
         )
         .await
         .unwrap();
 
-        let parsed = ParsedSet {
-            exercise: "Squat".to_string(),
-            weight: Some(150.0),
-            reps: Some(3),
-            rpe: Some(9.0),
-            set_count: None,
-            tags: vec![],
-            aoi: None,
-            original_string: "150kg x 3 @9".to_string(),
-        };
+        let template = create_workout_template(
+            &pool,
+            "Push",
+            &[NewTemplateExercise {
+                exercise_id: duplicate.id,
+                target_sets: 3,
+                target_reps: 5,
+            }],
+        )
+        .await
+        .unwrap();
 
-        let updated = update_workout_set_from_parsed(&pool, set.id, &parsed)
+        let moved = merge_exercises(&pool, keep.id, duplicate.id).await.unwrap();
+        assert_eq!(moved, 2);
+        let planned = get_template_exercises(&pool, template.id).await.unwrap();
+        assert_eq!(planned.len(), 1);
+        assert_eq!(planned[0].exercise_id, keep.id);
+        let sets = get_sets_for_session(&pool, session.id, None, None)
             .await
             .unwrap();
+        assert_eq!(sets.len(), 3);
+        assert!(sets.iter().all(|s| s.exercise_id == keep.id));
+        assert!(get_exercise(&pool, duplicate.id).await.is_err());
+        assert_eq!(
+            get_or_create_exercise(&pool, "Barbell Bench Pres")
+                .await
+                .unwrap()
+                .id,
+            keep.id
+        );
+        assert!(merge_exercises(&pool, keep.id, keep.id).await.is_err());
+    }
 
-        let new_exercise = get_exercise(&pool, updated.exercise_id).await.unwrap();
-        assert_eq!(new_exercise.name, "Squat");
-        assert_eq!(updated.weight, 150.0);
-        assert_eq!(updated.reps, 3);
-        assert_eq!(updated.rpe, Some(9.0));
+    #[tokio::test]
+    async fn test_rename_exercise() {
+        let pool = setup_test_db().await;
+        let typo = get_or_create_exercise(&pool, "Bench Pres").await.unwrap();
+        get_or_create_exercise(&pool, "Squat").await.unwrap();
+
+        let renamed = rename_exercise(&pool, typo.id, "Bench Press")
+            .await
+            .unwrap();
+        assert_eq!(renamed.id, typo.id);
+        assert_eq!(renamed.name, "Bench Press");
+        assert_eq!(renamed.slug, "bench-press");
+        assert_eq!(
+            get_or_create_exercise(&pool, "bench press")
+                .await
+                .unwrap()
+                .id,
+            typo.id
+        );
+
+        assert!(rename_exercise(&pool, typo.id, " squat ").await.is_err());
+        assert_eq!(
+            get_exercise(&pool, typo.id).await.unwrap().name,
+            "Bench Press"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_seed_defaults_is_rerunnable() {
+        let pool = setup_test_db().await;
+
+        async fn row_counts(pool: &SqlitePool) -> Vec<i64> {
+            let mut counts = Vec::new();
+            for table in [
+                "exercises",
+                "muscles",
+                "equipment",
+                "exercise_muscles",
+                "exercise_equipment",
+            ] {
+                let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+                    .fetch_one(pool)
+                    .await
+                    .unwrap();
+                counts.push(count);
+            }
+            counts
+        }
+
+        seed_defaults(&pool).await.unwrap();
+        let seeded = row_counts(&pool).await;
+        assert_eq!(seeded[0], DEFAULT_EXERCISES.len() as i64);
+        assert!(seeded.iter().all(|count| *count > 0));
+
+        seed_defaults(&pool).await.unwrap();
+        assert_eq!(row_counts(&pool).await, seeded);
     }
 
     #[tokio::test]
@@ -1483,6 +5461,7 @@ impl Default for UpdateWorkoutSet {
             rpe: None,
             set_index: None,
             notes: None,
+            rest_style: None,
         }
     }
 }
@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use chrono::Utc;
-use neo4rs::{Graph, query};
+use neo4rs::{BoltList, BoltMap, BoltString, BoltType, Graph, query};
 use tokio::sync::OnceCell;
 
 use log::{debug, error, info};
@@ -143,6 +143,58 @@ impl GraphManager {
         Ok(())
     }
 
+    /// Upserts an exercise and all of its muscle links in a single round-trip by unwinding
+    /// one row per `(muscle, relation_type)`.
+    pub async fn upsert_exercise_muscles_batch(
+        &self,
+        exercise: &Exercise,
+        links: &[(Muscle, String)],
+    ) -> Result<()> {
+        debug!(
+            "upsert_exercise_muscles_batch called exercise={} links={}",
+            exercise.slug,
+            links.len()
+        );
+        if links.is_empty() {
+            return Ok(());
+        }
+        let now = Utc::now().to_rfc3339();
+
+        let q = query(
+            "MERGE (e:Exercise { slug: $slug }) \
+             ON CREATE SET e.name = $ename, e.created_at = $now, e.updated_at = $now \
+             ON MATCH SET e.updated_at = $now \
+             WITH e \
+             UNWIND $rows AS row \
+             MERGE (m:Muscle { name: row.mname }) \
+             ON CREATE SET m.created_at = $now, m.updated_at = $now \
+             ON MATCH SET m.updated_at = $now \
+             MERGE (e)-[r:WORKS_MUSCLE]->(m) \
+             ON CREATE SET r.relation_type = row.relation_type, r.created_at = $now, r.updated_at = $now \
+             ON MATCH SET r.relation_type = row.relation_type, r.updated_at = $now \
+             RETURN e.slug AS slug, m.name AS muscle",
+        )
+        .param("slug", exercise.slug.clone())
+        .param("ename", exercise.name.clone())
+        .param("rows", exercise_muscle_rows(links))
+        .param("now", now);
+
+        let mut result = self.graph.execute(q).await.map_err(|e| {
+            error!(
+                "upsert_exercise_muscles_batch execute failed for exercise={}: {}",
+                exercise.slug, e
+            );
+            anyhow::Error::from(e)
+        })?;
+        while let Ok(Some(_row)) = result.next().await {}
+        info!(
+            "upsert_exercise_muscles_batch completed exercise={} links={}",
+            exercise.slug,
+            links.len()
+        );
+        Ok(())
+    }
+
     pub async fn upsert_equipment_and_link(
         &self,
         exercise: &Exercise,
@@ -290,3 +342,61 @@ impl GraphManager {
         Ok(())
     }
 }
+
+/// The `$rows` parameter of [`GraphManager::upsert_exercise_muscles_batch`]: one map with
+/// `mname` and `relation_type` per link.
+fn exercise_muscle_rows(links: &[(Muscle, String)]) -> BoltType {
+    let mut rows = BoltList::with_capacity(links.len());
+    for (muscle, relation_type) in links {
+        let mut row = BoltMap::with_capacity(2);
+        row.put(
+            BoltString::from("mname"),
+            BoltType::from(muscle.name.clone()),
+        );
+        row.put(
+            BoltString::from("relation_type"),
+            BoltType::from(relation_type.clone()),
+        );
+        rows.push(BoltType::Map(row));
+    }
+    BoltType::List(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn muscle(id: i64, name: &str) -> Muscle {
+        Muscle {
+            id,
+            name: name.to_string(),
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_exercise_muscle_rows_has_one_map_per_link() {
+        let links = vec![
+            (muscle(1, "Chest"), "primary".to_string()),
+            (muscle(2, "Triceps"), "secondary".to_string()),
+        ];
+
+        let BoltType::List(rows) = exercise_muscle_rows(&links) else {
+            panic!("rows should be a list");
+        };
+        assert_eq!(rows.value.len(), 2);
+
+        let BoltType::Map(second) = &rows.value[1] else {
+            panic!("each row should be a map");
+        };
+        assert_eq!(
+            second.value.get(&BoltString::from("mname")),
+            Some(&BoltType::from("Triceps"))
+        );
+        assert_eq!(
+            second.value.get(&BoltString::from("relation_type")),
+            Some(&BoltType::from("secondary"))
+        );
+    }
+}
@@ -1,6 +1,8 @@
 pub mod db;
 pub mod graph;
 pub mod llm;
+pub mod matching;
+pub mod metrics;
 pub mod recommendation;
 pub mod runtime;
 pub mod session;
@@ -1,17 +1,21 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
 
 use anyhow::{Result, anyhow};
+use futures::StreamExt;
 use ollama_rs::generation::parameters::TimeUnit;
-use openai::{Credentials, chat::*};
+use openai::{Credentials, OpenAiError, chat::*};
+use regex::Regex;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Deserializer, Serialize};
-use tokio::sync::OnceCell;
+use tokio::sync::{OnceCell, Semaphore};
 use tokio::time::sleep;
 
 use log::{debug, error, info, warn};
 
+use crate::db::models::ExercisePatternType;
+
 fn deserialize_reps<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
 where
     D: Deserializer<'de>,
@@ -38,15 +42,52 @@ where
     }
 }
 
+fn deserialize_exercise<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<String>::deserialize(deserializer)?
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default())
+}
+
+const KG_PER_LB: f32 = 0.453592;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeightUnit {
+    #[serde(alias = "kgs")]
+    Kg,
+    #[serde(alias = "lbs")]
+    Lb,
+}
+
+impl WeightUnit {
+    /// `weight` given in this unit, in kilograms.
+    pub fn to_kg(self, weight: f64) -> f64 {
+        match self {
+            WeightUnit::Kg => weight,
+            WeightUnit::Lb => weight * KG_PER_LB as f64,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParsedSet {
+    #[serde(default, deserialize_with = "deserialize_exercise")]
     pub exercise: String,
     pub weight: Option<f32>,
+    #[serde(default)]
+    pub unit: Option<WeightUnit>,
     #[serde(deserialize_with = "deserialize_reps")]
     pub reps: Option<i32>,
     pub rpe: Option<f32>,
     #[serde(deserialize_with = "deserialize_reps")]
     pub set_count: Option<i32>,
+    #[serde(default)]
+    pub duration_seconds: Option<i64>,
+    #[serde(default)]
+    pub distance_meters: Option<f64>,
     pub tags: Vec<String>,
     pub aoi: Option<String>,
     #[serde(skip_deserializing)]
@@ -58,6 +99,14 @@ impl ParsedSet {
         p.original_string = original;
         p
     }
+
+    /// The parsed weight in kilograms; weights without a unit are assumed to be kg.
+    pub fn weight_in_kg(&self) -> Option<f32> {
+        match self.unit {
+            Some(WeightUnit::Lb) => self.weight.map(|w| w * KG_PER_LB),
+            Some(WeightUnit::Kg) | None => self.weight,
+        }
+    }
 }
 
 fn strip_code_fences(s: &str) -> &str {
@@ -73,23 +122,207 @@ fn strip_code_fences(s: &str) -> &str {
     trimmed.trim()
 }
 
-type MockFn = Arc<dyn Fn(&str, &str) -> String + Send + Sync>;
+const NON_RETRYABLE_MARKERS: &[&str] = &[
+    "unauthorized",
+    "forbidden",
+    "invalid api key",
+    "incorrect api key",
+    "bad request",
+    "invalid_request",
+    "model not found",
+];
+
+/// Returned when the backend does not answer within [`LlmInterface`]'s timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LlmTimeout(pub Duration);
+
+impl std::fmt::Display for LlmTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LLM call timed out after {}ms", self.0.as_millis())
+    }
+}
+
+impl std::error::Error for LlmTimeout {}
+
+/// Returned when the backend cannot be reached or rejects the request; the underlying
+/// error stays in the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LlmUnavailable;
+
+impl std::fmt::Display for LlmUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LLM backend unavailable")
+    }
+}
+
+impl std::error::Error for LlmUnavailable {}
+
+/// Returned by [`LlmInterface::call_json`] when the reply is empty or doesn't parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlmBadJson {
+    /// The reply with any code fences stripped.
+    pub raw: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for LlmBadJson {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Cannot parse LLM JSON output: {}\nError: {}",
+            self.raw, self.message
+        )
+    }
+}
+
+impl std::error::Error for LlmBadJson {}
+
+/// HTTP status written out in an error message: either after "status", or followed by its
+/// reason phrase as in "429 Too Many Requests". Other numbers in the message are ignored.
+static STATUS_IN_MESSAGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\bstatus(?: code)?:?\s*([45]\d{2})\b|\b([45]\d{2}) ([a-z][a-z' -]*)")
+        .expect("status regex is valid")
+});
+
+fn status_in_message(message: &str) -> Option<u16> {
+    STATUS_IN_MESSAGE.captures_iter(message).find_map(|caps| {
+        if let Some(code) = caps.get(1) {
+            return code.as_str().parse().ok();
+        }
+        let status = reqwest::StatusCode::from_bytes(caps[2].as_bytes()).ok()?;
+        let reason = status.canonical_reason()?.to_lowercase();
+        caps[3].starts_with(&reason).then_some(status.as_u16())
+    })
+}
+
+fn is_retryable_status(status: u16) -> bool {
+    status == 408 || status == 429 || status >= 500
+}
+
+/// Whether a failed LLM call is worth retrying. Timeouts, rate limiting (429), server
+/// errors (5xx) and dropped connections are transient; authentication, validation and
+/// parse failures will fail the same way again. Unrecognised errors are retried.
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    for cause in error.chain() {
+        if cause.is::<LlmTimeout>() {
+            return true;
+        }
+        if cause.is::<serde_json::Error>() || cause.is::<LlmBadJson>() {
+            return false;
+        }
+        if let Some(http) = cause.downcast_ref::<reqwest::Error>() {
+            if let Some(status) = http.status() {
+                return is_retryable_status(status.as_u16());
+            }
+            if http.is_timeout() || http.is_connect() {
+                return true;
+            }
+            if http.is_decode() || http.is_builder() {
+                return false;
+            }
+        }
+        // The openai crate keeps the API's error type but not the HTTP status.
+        if let Some(api) = cause.downcast_ref::<OpenAiError>() {
+            if api.code.as_deref() == Some("insufficient_quota") {
+                return false;
+            }
+            match api.error_type.as_str() {
+                "server_error" | "rate_limit_error" | "requests" | "tokens" => return true,
+                "invalid_request_error"
+                | "authentication_error"
+                | "permission_error"
+                | "not_found_error"
+                | "insufficient_quota" => return false,
+                _ => {}
+            }
+        }
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind;
+            return matches!(
+                io.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::Interrupted
+            );
+        }
+    }
+
+    let message = format!("{:#}", error).to_lowercase();
+    if let Some(status) = status_in_message(&message) {
+        return is_retryable_status(status);
+    }
+    !NON_RETRYABLE_MARKERS.iter().any(|m| message.contains(m))
+}
+
+type MockFn = Arc<dyn Fn(&str, &str) -> Result<String> + Send + Sync>;
 
 enum LlmBackend {
     OpenAi {
         model: String,
         api_key: Option<String>,
+        /// Server other than api.openai.com speaking the same chat-completions API.
+        base_url: Option<String>,
     },
     Ollama {
         model: String,
     },
+    Gemini {
+        model: String,
+        api_key: Option<String>,
+    },
     Mock {
         responder: MockFn,
+        delay: Duration,
     },
 }
 
 pub struct LlmInterface {
     backend: LlmBackend,
+    timeout: Duration,
+    /// Sampling for calls that don't pass their own; `None` leaves OpenAI on its defaults and
+    /// uses [`GenerationParams::default`] for the other backends.
+    params: Option<GenerationParams>,
+    /// Bounds how many calls reach the backend at once; callers beyond it queue.
+    permits: Arc<Semaphore>,
+}
+
+/// Sampling settings for a call. The default is near-greedy so parsing stays deterministic;
+/// raise the temperature for more varied suggestions. OpenAI only receives them when they
+/// are set explicitly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenerationParams {
+    pub temperature: f32,
+    pub top_p: Option<f32>,
+}
+
+impl Default for GenerationParams {
+    fn default() -> Self {
+        GenerationParams {
+            temperature: 0.001,
+            top_p: None,
+        }
+    }
+}
+
+fn ollama_options(params: &GenerationParams) -> ollama_rs::models::ModelOptions {
+    let options = ollama_rs::models::ModelOptions::default().temperature(params.temperature);
+    match params.top_p {
+        Some(top_p) => options.top_p(top_p),
+        None => options,
+    }
+}
+
+/// Token usage reported by the backend for a single call. Backends that don't report
+/// usage leave every count at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallStats {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
 }
 
 static OPENAI_CREDS: OnceCell<Credentials> = OnceCell::const_new();
@@ -98,12 +331,181 @@ const OPENAI_DEFAULT_MODEL: &str = "gpt-4o-mini";
 static OLLAMA_CLIENT: OnceCell<Arc<ollama_rs::Ollama>> = OnceCell::const_new();
 const OLLAMA_DEFAULT_MODEL: &str = "llama3.2:3b";
 
+static GEMINI_CLIENT: OnceCell<reqwest::Client> = OnceCell::const_new();
+const GEMINI_DEFAULT_MODEL: &str = "gemini-1.5-flash";
+const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<GeminiCandidate>,
+    usage_metadata: Option<GeminiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiCandidate {
+    content: Option<GeminiContent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiContent {
+    #[serde(default)]
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiPart {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsage {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
+    #[serde(default)]
+    total_token_count: u64,
+}
+
+/// Request body for `generateContent`. Gemini's chat turns have no system role here, so
+/// the system prompt is prepended to the user turn. `json` asks for a JSON reply.
+fn gemini_request_body(
+    system: &str,
+    user: &str,
+    params: &GenerationParams,
+    json: bool,
+) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "contents": [{
+            "role": "user",
+            "parts": [{ "text": format!("{}\n\n{}", system, user) }],
+        }],
+        "generationConfig": {
+            "temperature": params.temperature,
+        },
+    });
+    if json {
+        body["generationConfig"]["responseMimeType"] = serde_json::json!("application/json");
+    }
+    if let Some(top_p) = params.top_p {
+        body["generationConfig"]["topP"] = serde_json::json!(top_p);
+    }
+    body
+}
+
+/// Joins the text parts of the first candidate; usage is zero when not reported.
+fn gemini_reply(response: GeminiResponse) -> Result<(String, CallStats)> {
+    let candidate = response
+        .candidates
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("Gemini returned no candidates"))?;
+    let content: String = candidate
+        .content
+        .map(|c| c.parts.into_iter().map(|p| p.text).collect())
+        .unwrap_or_default();
+    let stats = response
+        .usage_metadata
+        .map(|usage| CallStats {
+            prompt_tokens: usage.prompt_token_count,
+            completion_tokens: usage.candidates_token_count,
+            total_tokens: usage.total_token_count,
+        })
+        .unwrap_or_default();
+    Ok((content.trim().to_string(), stats))
+}
+
+/// Chat completion request for the OpenAI backend. `json` turns on JSON mode, which
+/// OpenAI only accepts when the messages themselves mention JSON.
+fn openai_request(
+    model: &str,
+    system: &str,
+    user: &str,
+    params: Option<&GenerationParams>,
+    json: bool,
+) -> ChatCompletionBuilder {
+    let messages = vec![
+        ChatCompletionMessage {
+            role: ChatCompletionMessageRole::System,
+            content: Some(system.to_string()),
+            name: None,
+            function_call: None,
+            tool_call_id: None,
+            tool_calls: None,
+        },
+        ChatCompletionMessage {
+            role: ChatCompletionMessageRole::User,
+            content: Some(user.to_string()),
+            name: None,
+            function_call: None,
+            tool_call_id: None,
+            tool_calls: None,
+        },
+    ];
+    let mut builder = ChatCompletion::builder(model, messages);
+    if json {
+        builder = builder.response_format(ChatCompletionResponseFormat::json_object());
+    }
+    if let Some(params) = params {
+        builder = builder.temperature(params.temperature);
+        if let Some(top_p) = params.top_p {
+            builder = builder.top_p(top_p);
+        }
+    }
+    builder
+}
+
+const DEFAULT_LLM_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_CONCURRENT_CALLS: usize = 2;
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const HEALTH_CHECK_SYSTEM_PROMPT: &str =
+    "You are a health check. Reply with exactly the JSON {\"ok\":true} and nothing else.";
+
+const JSON_REPAIR_PROMPT: &str = "Your previous reply was not valid JSON for the requested format. Fix it: return ONLY the corrected JSON, keeping its content and matching the structure the original instructions below ask for.\n\nOriginal instructions:\n";
+
 impl LlmInterface {
     pub async fn new_openai(api_key: Option<String>, model: Option<String>) -> Result<Self> {
         let model = model.unwrap_or_else(|| OPENAI_DEFAULT_MODEL.to_string());
         info!("LlmInterface::new_openai selected model={}", model);
         Ok(Self {
-            backend: LlmBackend::OpenAi { model, api_key },
+            backend: LlmBackend::OpenAi {
+                model,
+                api_key,
+                base_url: None,
+            },
+            timeout: DEFAULT_LLM_TIMEOUT,
+            params: None,
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_CALLS)),
+        })
+    }
+
+    /// Any OpenAI-compatible server (llama.cpp, vLLM, LM Studio, ...) at `base_url`, e.g.
+    /// `http://localhost:8080/v1`. Local servers usually accept a missing API key.
+    pub async fn new_openai_compatible(
+        base_url: String,
+        api_key: Option<String>,
+        model: String,
+    ) -> Result<Self> {
+        if base_url.trim().is_empty() {
+            return Err(anyhow!("OpenAI-compatible backend needs a base URL"));
+        }
+        info!(
+            "LlmInterface::new_openai_compatible selected model={} base_url={}",
+            model, base_url
+        );
+        Ok(Self {
+            backend: LlmBackend::OpenAi {
+                model,
+                api_key,
+                base_url: Some(base_url),
+            },
+            timeout: DEFAULT_LLM_TIMEOUT,
+            params: None,
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_CALLS)),
         })
     }
 
@@ -112,18 +514,76 @@ impl LlmInterface {
         info!("LlmInterface::new_ollama selected model={}", model);
         Ok(Self {
             backend: LlmBackend::Ollama { model },
+            timeout: DEFAULT_LLM_TIMEOUT,
+            params: None,
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_CALLS)),
+        })
+    }
+
+    /// Google Gemini backend. Without an API key, `GEMINI_API_KEY` is read from the
+    /// environment on each call.
+    pub async fn new_gemini(api_key: Option<String>, model: Option<String>) -> Result<Self> {
+        let model = model.unwrap_or_else(|| GEMINI_DEFAULT_MODEL.to_string());
+        info!("LlmInterface::new_gemini selected model={}", model);
+        Ok(Self {
+            backend: LlmBackend::Gemini { model, api_key },
+            timeout: DEFAULT_LLM_TIMEOUT,
+            params: None,
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_CALLS)),
         })
     }
 
     pub fn new_mock_fn(f: impl Fn(&str, &str) -> String + Send + Sync + 'static) -> Self {
-        debug!("LlmInterface::new_mock_fn creating mock backend");
+        Self::new_mock_result_fn(move |system, user| Ok(f(system, user)))
+    }
+
+    /// Mock backend whose responder can fail, for exercising error handling.
+    pub fn new_mock_result_fn(
+        f: impl Fn(&str, &str) -> Result<String> + Send + Sync + 'static,
+    ) -> Self {
+        debug!("LlmInterface::new_mock_result_fn creating mock backend");
         Self {
             backend: LlmBackend::Mock {
                 responder: Arc::new(f),
+                delay: Duration::ZERO,
             },
+            timeout: DEFAULT_LLM_TIMEOUT,
+            params: None,
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_CALLS)),
         }
     }
 
+    /// Mock backend that waits `delay` before answering, for exercising timeouts.
+    pub fn new_mock_delayed_fn(
+        delay: Duration,
+        f: impl Fn(&str, &str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        let mut llm = Self::new_mock_fn(f);
+        if let LlmBackend::Mock { delay: d, .. } = &mut llm.backend {
+            *d = delay;
+        }
+        llm
+    }
+
+    /// Sets how long a call may take before failing with [`LlmTimeout`]. Defaults to 60s.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Caps how many calls run against the backend at once; further calls wait for a free
+    /// slot before their timeout starts. Defaults to 2. A cap of 0 is treated as 1.
+    pub fn with_max_concurrent_calls(mut self, max: usize) -> Self {
+        self.permits = Arc::new(Semaphore::new(max.max(1)));
+        self
+    }
+
+    /// Sets the sampling parameters used by every call that doesn't pass its own.
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = Some(params);
+        self
+    }
+
     pub fn new_mock_map(map: HashMap<String, String>) -> Self {
         debug!(
             "LlmInterface::new_mock_map creating mock map backend with {} entries",
@@ -139,11 +599,23 @@ impl LlmInterface {
         })
     }
 
-    async fn get_openai_creds(api_key: &Option<String>) -> Result<Credentials> {
+    /// Credentials for api.openai.com are built once and shared; a custom base URL gets its
+    /// own credentials so it never leaks into other backends.
+    async fn get_openai_creds(
+        api_key: &Option<String>,
+        base_url: &Option<String>,
+    ) -> Result<Credentials> {
         debug!(
-            "LlmInterface::get_openai_creds called; api_key provided={}",
-            api_key.is_some()
+            "LlmInterface::get_openai_creds called; api_key provided={} base_url={:?}",
+            api_key.is_some(),
+            base_url
         );
+        if let Some(base_url) = base_url {
+            return Ok(Credentials::new(
+                api_key.as_deref().unwrap_or_default(),
+                base_url.as_str(),
+            ));
+        }
         Ok(OPENAI_CREDS
             .get_or_init(|| async {
                 match api_key {
@@ -169,24 +641,301 @@ impl LlmInterface {
             .clone())
     }
 
+    async fn get_gemini_client() -> reqwest::Client {
+        debug!("LlmInterface::get_gemini_client called");
+        GEMINI_CLIENT
+            .get_or_init(|| async { reqwest::Client::new() })
+            .await
+            .clone()
+    }
+
+    async fn call_gemini(
+        &self,
+        model: &str,
+        api_key: &Option<String>,
+        system: &str,
+        user: &str,
+        params: &GenerationParams,
+        json: bool,
+    ) -> Result<(String, CallStats)> {
+        debug!(
+            "Gemini call using model={} api_key_present={}",
+            model,
+            api_key.is_some()
+        );
+        let api_key = match api_key {
+            Some(key) => key.clone(),
+            None => std::env::var("GEMINI_API_KEY")
+                .map_err(|_| anyhow!("GEMINI_API_KEY is not set").context(LlmUnavailable))?,
+        };
+        let url = format!("{}/{}:generateContent", GEMINI_API_BASE, model);
+        let response: GeminiResponse = Self::get_gemini_client()
+            .await
+            .post(url)
+            .query(&[("key", api_key)])
+            .json(&gemini_request_body(system, user, params, json))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| {
+                error!("Gemini generateContent failed: {}", e);
+                anyhow::Error::from(e).context(LlmUnavailable)
+            })?
+            .json()
+            .await
+            .map_err(|e| {
+                error!("Gemini response could not be decoded: {}", e);
+                anyhow::Error::from(e).context(LlmUnavailable)
+            })?;
+        let (content, stats) = gemini_reply(response)?;
+        debug!(
+            "Gemini response length={} total_tokens={}",
+            content.len(),
+            stats.total_tokens
+        );
+        Ok((content, stats))
+    }
+
     pub async fn call(&self, system: &str, user: &str) -> Result<String> {
+        let (content, _) = self.call_with_stats(system, user).await?;
+        Ok(content)
+    }
+
+    pub async fn call_with_stats(&self, system: &str, user: &str) -> Result<(String, CallStats)> {
+        self.call_sampled(system, user, self.params.as_ref(), false)
+            .await
+    }
+
+    /// Like [`call`](Self::call) with sampling for this call only, e.g. a higher temperature
+    /// for suggestions on an interface that otherwise parses near-greedily.
+    pub async fn call_with_params(
+        &self,
+        system: &str,
+        user: &str,
+        params: GenerationParams,
+    ) -> Result<String> {
+        let (content, _) = self
+            .call_sampled(system, user, Some(&params), false)
+            .await?;
+        Ok(content)
+    }
+
+    /// Makes one call; `json` asks backends that support it to constrain the reply to JSON.
+    async fn call_sampled(
+        &self,
+        system: &str,
+        user: &str,
+        params: Option<&GenerationParams>,
+        json: bool,
+    ) -> Result<(String, CallStats)> {
+        let _permit = self.permits.acquire().await?;
+        tokio::time::timeout(self.timeout, self.call_backend(system, user, params, json))
+            .await
+            .unwrap_or_else(|_| {
+                error!("LLM call timed out after {:?}", self.timeout);
+                Err(LlmTimeout(self.timeout).into())
+            })
+    }
+
+    /// Whether the backend answers a trivial prompt within a few seconds with the JSON it was
+    /// asked for. Mock backends only need their responder to succeed.
+    pub async fn health_check(&self) -> Result<bool> {
+        #[derive(Deserialize)]
+        struct HealthReply {
+            ok: bool,
+        }
+
+        // Waiting for a free slot counts against the timeout, so a backend tied up with long
+        // calls reports unhealthy rather than stalling the check.
+        let timeout = self.timeout.min(HEALTH_CHECK_TIMEOUT);
+        let reply = tokio::time::timeout(timeout, async {
+            let _permit = self.permits.acquire().await?;
+            self.call_backend(
+                HEALTH_CHECK_SYSTEM_PROMPT,
+                "ping",
+                self.params.as_ref(),
+                true,
+            )
+            .await
+        })
+        .await;
+        let content = match reply {
+            Ok(Ok((content, _))) => content,
+            Ok(Err(e)) => {
+                warn!("LLM health check failed: {}", e);
+                return Ok(false);
+            }
+            Err(_) => {
+                warn!("LLM health check timed out after {:?}", timeout);
+                return Ok(false);
+            }
+        };
+        if matches!(self.backend, LlmBackend::Mock { .. }) {
+            return Ok(true);
+        }
+        match serde_json::from_str::<HealthReply>(strip_code_fences(&content)) {
+            Ok(reply) => Ok(reply.ok),
+            Err(e) => {
+                warn!("LLM health check reply was not the expected JSON: {}", e);
+                Ok(false)
+            }
+        }
+    }
+
+    async fn call_backend(
+        &self,
+        system: &str,
+        user: &str,
+        params: Option<&GenerationParams>,
+        json: bool,
+    ) -> Result<(String, CallStats)> {
         debug!(
-            "LlmInterface::call invoked backend={}",
+            "LlmInterface::call_with_stats invoked backend={}",
             match &self.backend {
+                LlmBackend::OpenAi {
+                    model,
+                    base_url: Some(base_url),
+                    ..
+                } => format!("openai({} @ {})", model, base_url),
                 LlmBackend::OpenAi { model, .. } => format!("openai({})", model),
                 LlmBackend::Ollama { model } => format!("ollama({})", model),
+                LlmBackend::Gemini { model, .. } => format!("gemini({})", model),
                 LlmBackend::Mock { .. } => "mock".to_string(),
             }
         );
 
         match &self.backend {
-            LlmBackend::OpenAi { model, api_key } => {
+            LlmBackend::OpenAi {
+                model,
+                api_key,
+                base_url,
+            } => {
                 debug!(
                     "OpenAI call using model={} api_key_present={}",
                     model,
                     api_key.is_some()
                 );
-                let creds = Self::get_openai_creds(api_key).await?;
+                let creds = Self::get_openai_creds(api_key, base_url).await?;
+                let builder = openai_request(model, system, user, params, json).credentials(creds);
+                let result_completion = builder.create().await.map_err(|e| {
+                    error!("OpenAI ChatCompletion.create() failed: {}", e);
+                    anyhow::Error::from(e).context(LlmUnavailable)
+                })?;
+                let result_message = result_completion
+                    .choices
+                    .first()
+                    .ok_or_else(|| anyhow!("OpenAI returned no choices"))?
+                    .message
+                    .clone();
+                let content = result_message
+                    .content
+                    .unwrap_or_else(|| "".to_string())
+                    .trim()
+                    .to_string();
+                let stats = result_completion
+                    .usage
+                    .map(|usage| CallStats {
+                        prompt_tokens: usage.prompt_tokens as u64,
+                        completion_tokens: usage.completion_tokens as u64,
+                        total_tokens: usage.total_tokens as u64,
+                    })
+                    .unwrap_or_default();
+                debug!(
+                    "OpenAI response length={} total_tokens={}",
+                    content.len(),
+                    stats.total_tokens
+                );
+                Ok((content, stats))
+            }
+            LlmBackend::Ollama { model } => {
+                debug!("Ollama call using model={}", model);
+                let client = Self::get_ollama_client().await?;
+                let options = ollama_options(&params.copied().unwrap_or_default());
+                let res = client
+                    .generate(
+                        ollama_rs::generation::completion::request::GenerationRequest::new(
+                            model.clone(),
+                            user.to_string(),
+                        )
+                        .options(options)
+                        .system(system.to_string())
+                        .keep_alive(
+                            ollama_rs::generation::parameters::KeepAlive::Until {
+                                time: 30,
+                                unit: TimeUnit::Minutes,
+                            },
+                        ),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Ollama generate failed: {}", e);
+                        anyhow::Error::from(e).context(LlmUnavailable)
+                    })?;
+                let prompt_tokens = res.prompt_eval_count.unwrap_or(0);
+                let completion_tokens = res.eval_count.unwrap_or(0);
+                let stats = CallStats {
+                    prompt_tokens,
+                    completion_tokens,
+                    total_tokens: prompt_tokens + completion_tokens,
+                };
+                debug!(
+                    "Ollama response length={} total_tokens={}",
+                    res.response.len(),
+                    stats.total_tokens
+                );
+                Ok((res.response.trim().to_string(), stats))
+            }
+            LlmBackend::Gemini { model, api_key } => {
+                let params = params.copied().unwrap_or_default();
+                self.call_gemini(model, api_key, system, user, &params, json)
+                    .await
+            }
+            LlmBackend::Mock { responder, delay } => {
+                sleep(*delay).await;
+                debug!("Mock LLM responder invoked");
+                let r = responder(system, user)?;
+                debug!("Mock response length={}", r.len());
+                Ok((r.trim().to_string(), CallStats::default()))
+            }
+        }
+    }
+
+    /// Like [`call`](Self::call) but hands each chunk of the reply to `on_token` as the
+    /// backend produces it, for showing progress on long generations. Returns the
+    /// concatenation of every chunk. The Gemini and mock backends reply in a single chunk.
+    pub async fn call_streaming(
+        &self,
+        system: &str,
+        user: &str,
+        mut on_token: impl FnMut(&str),
+    ) -> Result<String> {
+        let _permit = self.permits.acquire().await?;
+        tokio::time::timeout(
+            self.timeout,
+            self.stream_backend(system, user, &mut on_token),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            error!("LLM streaming call timed out after {:?}", self.timeout);
+            Err(LlmTimeout(self.timeout).into())
+        })
+    }
+
+    async fn stream_backend(
+        &self,
+        system: &str,
+        user: &str,
+        on_token: &mut impl FnMut(&str),
+    ) -> Result<String> {
+        let mut content = String::new();
+        match &self.backend {
+            LlmBackend::OpenAi {
+                model,
+                api_key,
+                base_url,
+            } => {
+                debug!("OpenAI streaming call using model={}", model);
+                let creds = Self::get_openai_creds(api_key, base_url).await?;
                 let messages = vec![
                     ChatCompletionMessage {
                         role: ChatCompletionMessageRole::System,
@@ -205,35 +954,32 @@ impl LlmInterface {
                         tool_calls: None,
                     },
                 ];
-                let result_completion = ChatCompletion::builder(model, messages.clone())
-                    .response_format(ChatCompletionResponseFormat::json_object())
-                    .credentials(creds.clone())
-                    .create()
-                    .await
-                    .map_err(|e| {
-                        error!("OpenAI ChatCompletion.create() failed: {}", e);
-                        e
-                    })?;
-                let result_message = result_completion
-                    .choices
-                    .first()
-                    .ok_or_else(|| anyhow!("OpenAI returned no choices"))?
-                    .message
-                    .clone();
-                let content = result_message
-                    .content
-                    .unwrap_or_else(|| "".to_string())
-                    .trim()
-                    .to_string();
-                debug!("OpenAI response length={}", content.len());
-                Ok(content)
+                let mut builder = ChatCompletionDelta::builder(model, messages).credentials(creds);
+                if let Some(params) = &self.params {
+                    builder = builder.temperature(params.temperature);
+                    if let Some(top_p) = params.top_p {
+                        builder = builder.top_p(top_p);
+                    }
+                }
+                let mut stream = builder.create_stream().await.map_err(|e| {
+                    error!("OpenAI ChatCompletionDelta.create_stream() failed: {}", e);
+                    anyhow::Error::from(e).context(LlmUnavailable)
+                })?;
+                while let Some(delta) = stream.recv().await {
+                    for choice in &delta.choices {
+                        if let Some(chunk) = &choice.delta.content {
+                            on_token(chunk);
+                            content.push_str(chunk);
+                        }
+                    }
+                }
             }
             LlmBackend::Ollama { model } => {
-                debug!("Ollama call using model={}", model);
+                debug!("Ollama streaming call using model={}", model);
                 let client = Self::get_ollama_client().await?;
-                let options = ollama_rs::models::ModelOptions::default().temperature(0.001);
-                let res = client
-                    .generate(
+                let options = ollama_options(&self.params.unwrap_or_default());
+                let mut stream = client
+                    .generate_stream(
                         ollama_rs::generation::completion::request::GenerationRequest::new(
                             model.clone(),
                             user.to_string(),
@@ -249,19 +995,34 @@ impl LlmInterface {
                     )
                     .await
                     .map_err(|e| {
-                        error!("Ollama generate failed: {}", e);
-                        e
+                        error!("Ollama generate_stream failed: {}", e);
+                        anyhow::Error::from(e).context(LlmUnavailable)
                     })?;
-                debug!("Ollama response length={}", res.response.len());
-                Ok(res.response.trim().to_string())
+                while let Some(responses) = stream.next().await {
+                    for res in responses? {
+                        on_token(&res.response);
+                        content.push_str(&res.response);
+                    }
+                }
             }
-            LlmBackend::Mock { responder } => {
-                debug!("Mock LLM responder invoked");
-                let r = responder(system, user);
-                debug!("Mock response length={}", r.len());
-                Ok(r.trim().to_string())
+            LlmBackend::Gemini { model, api_key } => {
+                debug!("Gemini streaming call using model={}", model);
+                let params = self.params.unwrap_or_default();
+                content = self
+                    .call_gemini(model, api_key, system, user, &params, false)
+                    .await?
+                    .0;
+                on_token(&content);
+            }
+            LlmBackend::Mock { responder, delay } => {
+                sleep(*delay).await;
+                debug!("Mock LLM streaming responder invoked");
+                content = responder(system, user)?;
+                on_token(&content);
             }
         }
+        debug!("streaming response length={}", content.len());
+        Ok(content)
     }
 
     pub async fn call_json<T>(&self, system: &str, user: &str) -> Result<T>
@@ -269,23 +1030,57 @@ impl LlmInterface {
         T: DeserializeOwned,
     {
         debug!("call_json invoked; user_input_len={}", user.len());
-        let raw = self.call(system, user).await?;
+        let (raw, _) = self
+            .call_sampled(system, user, self.params.as_ref(), true)
+            .await?;
         debug!("raw LLM output len={}", raw.len());
         let stripped = strip_code_fences(&raw);
         if stripped.trim().is_empty() {
-            return Err(anyhow!("LLM returned empty response"));
+            return Err(LlmBadJson {
+                raw: String::new(),
+                message: "LLM returned empty response".to_string(),
+            }
+            .into());
         }
         let parsed: T = serde_json::from_str(stripped).map_err(|e| {
             error!("Cannot parse LLM JSON output: {} -- error: {}", stripped, e);
-            anyhow!(format!(
-                "Cannot parse LLM JSON output: {}\nError: {}",
-                stripped, e
-            ))
+            LlmBadJson {
+                raw: stripped.to_string(),
+                message: e.to_string(),
+            }
         })?;
         debug!("call_json parsed successfully");
         Ok(parsed)
     }
 
+    /// Like [`call_json`](Self::call_json), but when the reply fails to parse, makes one
+    /// more call asking the model to repair its output, passing back the bad reply and the
+    /// parse error. Fails if the repaired reply does not parse either.
+    pub async fn call_json_with_repair<T>(&self, system: &str, user: &str) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        debug!(
+            "call_json_with_repair invoked; user_input_len={}",
+            user.len()
+        );
+        let (raw, _) = self
+            .call_sampled(system, user, self.params.as_ref(), true)
+            .await?;
+        let stripped = strip_code_fences(&raw);
+        let error = match serde_json::from_str::<T>(stripped) {
+            Ok(parsed) => return Ok(parsed),
+            Err(e) => e,
+        };
+        warn!(
+            "LLM JSON output did not parse ({}), requesting a repair: {}",
+            error, stripped
+        );
+        let repair_system = format!("{}{}", JSON_REPAIR_PROMPT, system);
+        let repair_user = format!("Invalid reply:\n{}\n\nParse error: {}", stripped, error);
+        self.call_json(&repair_system, &repair_user).await
+    }
+
     pub async fn call_with_retry(
         &self,
         system: &str,
@@ -310,6 +1105,10 @@ impl LlmInterface {
                 }
                 Err(e) => {
                     warn!("call failed on attempt {}: {}", attempt, e);
+                    if !is_retryable(&e) {
+                        error!("call_with_retry giving up on non-retryable error: {}", e);
+                        return Err(e);
+                    }
                     if attempt >= max_attempts {
                         error!("call_with_retry exhausted attempts={}", attempt);
                         return Err(e);
@@ -368,6 +1167,7 @@ pub struct PromptContext {
     pub selected_set_backend_id: Option<i64>,
     pub visible_set_backend_ids: Vec<i64>,
     pub current_summary: Option<String>,
+    pub suggestion_count: usize,
 }
 
 impl Default for PromptContext {
@@ -384,6 +1184,7 @@ impl Default for PromptContext {
             selected_set_backend_id: None,
             visible_set_backend_ids: vec![],
             current_summary: None,
+            suggestion_count: 5,
         }
     }
 }
@@ -477,7 +1278,7 @@ impl PromptBuilder {
     }
 
     pub fn system_parse_prompt(&self) -> String {
-        "You are a precise workout set parser. Return only a single JSON object matching the schema: {\"exercise\": string|null, \"weight\": float|null, \"reps\": integer|null, \"rpe\": float|null, \"set_count\": integer|null, \"tags\": [string], \"aoi\": string|null, \"original_string\": string}. 'reps' and 'set_count' must be integers.".to_string()
+        "You are a precise workout set parser. Return only a single JSON object matching the schema: {\"exercise\": string|null, \"weight\": float|null, \"unit\": \"kg\"|\"lb\"|null, \"reps\": integer|null, \"rpe\": float|null, \"set_count\": integer|null, \"duration_seconds\": integer|null, \"distance_meters\": float|null, \"tags\": [string], \"aoi\": string|null, \"original_string\": string}. 'reps' and 'set_count' must be integers. Set 'unit' to \"lb\" when the weight is given in pounds (lb, lbs), \"kg\" when given in kilograms, and null when no unit is stated. For timed or cardio sets such as planks or runs, set 'duration_seconds' to the total time in seconds and 'distance_meters' to the distance in meters (5km is 5000), and leave 'weight' and 'reps' null unless they were given.".to_string()
     }
 
     pub fn user_parse_prompt(&self, input: &str) -> String {
@@ -550,9 +1351,12 @@ impl PromptBuilder {
 Return a JSON object with a "commands" array. Each command should be fully parsed with all fields extracted.
 
 Command types:
-1. "add_set" - Add one or more workout sets. Fields: exercise (string), weight (number|null), reps (integer|null), rpe (number|null), set_count (integer|null, defaults to 1), tags (array of strings), aoi (string|null), original_string (string)
+1. "add_set" - Add one or more workout sets. Fields: exercise (string), weight (number|null), unit ("kg"|"lb"|null), reps (integer|null), rpe (number|null), set_count (integer|null, defaults to 1), duration_seconds (integer|null), distance_meters (number|null), tags (array of strings), aoi (string|null), original_string (string), superset_group (integer|null)
    - If user says "add 3 sets of bench press 100kg x 5", return 3 separate add_set commands
+   - For supersets ("superset: bench 60x10 + row 50x10", "bench 60x10 supersetted with row 50x10"), return one add_set command per exercise and give them the same superset_group number (1 for the first superset in the input, 2 for the next, ...); otherwise leave superset_group null
    - Parse exercise names, weights, reps, RPE from natural language
+   - Give the weight as the user stated it and set unit to "lb" when it is in pounds (lb, lbs), "kg" when in kilograms, and null when no unit is stated; never convert between units yourself
+   - For timed or cardio sets ("plank 60s", "run 5km in 25min"), set duration_seconds and distance_meters (in meters) and leave weight and reps null
    - RPE is rate of perceived exersion 0 is No effort, 1 Very light, 2 to 3 Light, 4 to 6 Moderate, 7 to 8 Vigorous, 9 Very Hard, and 10 is Maximum Effort. The scale can also be interpreted as the number of reps in reserve, where one rep in reserve is 9 (10 minus 1), etc. The user may say "one rep max" indicating 0 reps in reserve and an RPE 10 for example.
    - Use known exercises from context when possible
 
@@ -563,10 +1367,12 @@ Command types:
    - If user says "this set" and a currently selected set ID is provided in context, use that set_id
    - If user says "all the sets I can see" or similar and visible set IDs are provided, use those set_ids (one command per set)
 
-3. "edit_set" - Edit an existing set. Fields: set_id (integer|null), description (string|null), exercise (string|null), weight (number|null), reps (integer|null), rpe (number|null)
+3. "edit_set" - Edit an existing set. Fields: set_id (integer|null), description (string|null), exercise (string|null), weight (number|null), reps (integer|null), rpe (number|null), weight_delta (number|null), reps_delta (integer|null), unit ("kg"|"lb"|null, applies to weight and weight_delta), notes (string|null)
    - Only include fields that should be changed
    - If user says "change last bench press to 105kg", include set_id or description pointing to the last bench press set, and weight=105.0
+   - If user asks for a relative change ("add 5kg", "bump by 2.5kg", "one less rep"), use weight_delta/reps_delta (negative to subtract) and leave weight/reps null; the backend applies the delta to the set's current value
    - If user says "no that should be 80kg" referring to most recent set, use description or set_id from recent sets
+   - If user wants to annotate a set ("add a note to that set: felt shaky", "note: left knee caved"), put the note text in notes and leave the other fields null
    - If user says "this set" and a currently selected set ID is provided in context, use that set_id
 
 4. "update_summary" - Refresh the workout summary when the current summary (provided in context) no longer reflects the workout, when the user explicitly asks for a new summary, or when major exercise changes occur. Fields: message (string, <= 30 characters, no ending period), emoji (string, single emoji character or empty string if unsure). Always trim whitespace.
@@ -575,9 +1381,13 @@ Command types:
 
 Examples:
 - "add 3 sets of bench press 100kg x 5" → [{"command_type": "add_set", "exercise": "Bench Press", "weight": 100.0, "reps": 5, "set_count": 1, "tags": [], "aoi": null, "original_string": "bench press 100kg x 5"}, ... (3 times)]
+- "superset: bench 60x10 + row 50x10" → [{"command_type": "add_set", "exercise": "Bench Press", "weight": 60.0, "reps": 10, "set_count": 1, "tags": [], "aoi": null, "original_string": "bench 60x10", "superset_group": 1}, {"command_type": "add_set", "exercise": "Barbell Row", "weight": 50.0, "reps": 10, "set_count": 1, "tags": [], "aoi": null, "original_string": "row 50x10", "superset_group": 1}]
 - "remove the last 2 sets" → [{"command_type": "remove_set", "set_id": null, "description": "last set"}, {"command_type": "remove_set", "set_id": null, "description": "second to last set"}]
 - "change last bench press to 105kg" → [{"command_type": "edit_set", "set_id": null, "description": "last bench press set", "weight": 105.0, "exercise": null, "reps": null, "rpe": null}]
+- "bench 225lb x 5" → [{"command_type": "add_set", "exercise": "Bench Press", "weight": 225.0, "unit": "lb", "reps": 5, "set_count": 1, "tags": [], "aoi": null, "original_string": "bench 225lb x 5"}]
 - "no that should be 80kg" → [{"command_type": "edit_set", "set_id": null, "description": "most recent set", "weight": 80.0, ...}]
+- "add 5kg to last set" → [{"command_type": "edit_set", "set_id": null, "description": "last set", "weight": null, "weight_delta": 5.0, "exercise": null, "reps": null, "rpe": null}]
+- "add a note to that set: felt shaky" → [{"command_type": "edit_set", "set_id": null, "description": "most recent set", "notes": "felt shaky", "exercise": null, "weight": null, "reps": null, "rpe": null}]
 - "rewrite the summary to highlight today's push focus 🔥" → [{"command_type": "update_summary", "message": "Push power finisher", "emoji": "🔥"}]
 
 Return only valid JSON: {"commands": [...]}"#.to_string()
@@ -632,21 +1442,22 @@ Return a JSON object with a 'suggestions' array. Each suggestion should have:
 - 'suggestion_type' (one of: 'exercise', 'progression', 'volume', 'accessory', 'completion')
 - 'exercise_name' (optional string): For exercise or progression suggestions, specify the exercise name
 - 'reasoning' (optional string): Brief explanation
+- 'confidence' (number from 0.0 to 1.0): How strongly you recommend it given the data; the most useful suggestion gets the highest value
 
 CRITICAL: Avoid vague suggestions like "do progressive overload" or "focus on form". Instead, provide specific, actionable recommendations.
 
 GOOD EXAMPLES:
 1. Exercise recommendation:
-   {"title": "Add Barbell Rows", "subtitle": "3 sets of 8-10 reps @7-8 RPE", "suggestion_type": "exercise", "exercise_name": "Barbell Row", "reasoning": "Balances the pressing work you've done"}
+   {"title": "Add Barbell Rows", "subtitle": "3 sets of 8-10 reps @7-8 RPE", "suggestion_type": "exercise", "exercise_name": "Barbell Row", "reasoning": "Balances the pressing work you've done", "confidence": 0.8}
 
 2. Specific progression:
-   {"title": "Increase Bench Press to 87.5kg", "subtitle": "You've been doing 85kg x 5, try 87.5kg x 4-5 @8 RPE", "suggestion_type": "progression", "exercise_name": "Bench Press", "reasoning": "2.5kg increase based on your recent performance"}
+   {"title": "Increase Bench Press to 87.5kg", "subtitle": "You've been doing 85kg x 5, try 87.5kg x 4-5 @8 RPE", "suggestion_type": "progression", "exercise_name": "Bench Press", "reasoning": "2.5kg increase based on your recent performance", "confidence": 0.9}
 
 3. Workout completion:
-   {"title": "Consider wrapping up", "subtitle": "You've done 4 heavy compounds and 3 accessories - good volume for today", "suggestion_type": "completion", "reasoning": "High volume and intensity already achieved"}
+   {"title": "Consider wrapping up", "subtitle": "You've done 4 heavy compounds and 3 accessories - good volume for today", "suggestion_type": "completion", "reasoning": "High volume and intensity already achieved", "confidence": 0.6}
 
 4. Volume adjustment:
-   {"title": "Add 1 more set to Squats", "subtitle": "You did 3 sets, add a 4th at 90% of your working weight", "suggestion_type": "volume", "exercise_name": "Barbell Back Squat", "reasoning": "Room for more volume based on RPE"}
+   {"title": "Add 1 more set to Squats", "subtitle": "You did 3 sets, add a 4th at 90% of your working weight", "suggestion_type": "volume", "exercise_name": "Barbell Back Squat", "reasoning": "Room for more volume based on RPE", "confidence": 0.7}
 
 BAD EXAMPLES (DO NOT DO THIS):
 - {"title": "Do progressive overload"} - Too vague
@@ -669,6 +1480,7 @@ Return only valid JSON."#.to_string()
         &self,
         current_exercises: &[(String, i64)],
         past_performance: &str,
+        trends: &[(String, ProgressTrend)],
     ) -> String {
         let exercises_list: String = current_exercises
             .iter()
@@ -686,11 +1498,34 @@ Return only valid JSON."#.to_string()
         };
 
         format!(
-            "Current workout:\n{}\nPast Performance Summary:\n{}\n{}\nProvide 3-5 SPECIFIC, ACTIONABLE suggestions. For each suggestion:\n\n1. EXERCISE RECOMMENDATIONS: If suggesting a new exercise, specify the exact exercise name, rep range, and RPE (e.g., \"Add Barbell Rows: 3 sets of 8-10 reps @7-8 RPE\")\n\n2. PROGRESSION SUGGESTIONS: If suggesting progression on an existing exercise, specify:\n   - Exact weight change (e.g., \"Increase Bench Press from 85kg to 87.5kg\")\n   - Rep range (e.g., \"Try 4-5 reps @8 RPE\")\n   - Base this on the past performance data provided\n\n3. COMPLETION SUGGESTIONS: If the workout is already very taxing (high volume, high intensity, or user appears fatigued), suggest wrapping up with a completion-type suggestion\n\n4. VOLUME SUGGESTIONS: If suggesting more volume, specify exactly how many sets/reps to add (e.g., \"Add 1 more set to Squats at 90% working weight\")\n\nBase all suggestions on the actual past performance data. Be specific with weights, reps, and RPE ranges. Avoid vague advice.\n\nReturn JSON with a 'suggestions' array.",
-            exercises_list, past_performance, workout_intensity_note
+            "Current workout:\n{}\nPast Performance Summary:\n{}\n{}{}\nProvide {} SPECIFIC, ACTIONABLE suggestions. For each suggestion:\n\n1. EXERCISE RECOMMENDATIONS: If suggesting a new exercise, specify the exact exercise name, rep range, and RPE (e.g., \"Add Barbell Rows: 3 sets of 8-10 reps @7-8 RPE\")\n\n2. PROGRESSION SUGGESTIONS: If suggesting progression on an existing exercise, specify:\n   - Exact weight change (e.g., \"Increase Bench Press from 85kg to 87.5kg\")\n   - Rep range (e.g., \"Try 4-5 reps @8 RPE\")\n   - Base this on the past performance data provided\n\n3. COMPLETION SUGGESTIONS: If the workout is already very taxing (high volume, high intensity, or user appears fatigued), suggest wrapping up with a completion-type suggestion\n\n4. VOLUME SUGGESTIONS: If suggesting more volume, specify exactly how many sets/reps to add (e.g., \"Add 1 more set to Squats at 90% working weight\")\n\nBase all suggestions on the actual past performance data. Be specific with weights, reps, and RPE ranges. Avoid vague advice.\n\nReturn JSON with a 'suggestions' array.",
+            exercises_list,
+            past_performance,
+            Self::trends_section(trends),
+            workout_intensity_note,
+            self.suggestion_count()
+        )
+    }
+
+    fn trends_section(trends: &[(String, ProgressTrend)]) -> String {
+        if trends.is_empty() {
+            return String::new();
+        }
+        let lines = trends
+            .iter()
+            .map(|(name, trend)| format!("- {}: {}", name, trend))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "Progress trends (computed from logged history, treat as ground truth):\n{}\n",
+            lines
         )
     }
 
+    pub fn suggestion_count(&self) -> usize {
+        self.ctx.suggestion_count.max(1)
+    }
+
     pub fn system_summary_prompt(&self) -> String {
         r#"You are an expert fitness coach. Analyze the workout data and generate a brief, insightful summary that captures the workout's character, intensity, and focus.
 
@@ -717,6 +1552,7 @@ Return ONLY the JSON object."#.to_string()
         &self,
         current_exercises: &[(String, i64)],
         detailed_exercises: &[(String, i64, String)],
+        trends: &[(String, ProgressTrend)],
     ) -> String {
         if current_exercises.is_empty() {
             return "No exercises added yet.".to_string();
@@ -764,8 +1600,24 @@ Return ONLY the JSON object."#.to_string()
         };
 
         format!(
-            "Workout Analysis:\n\nExercises performed:\n{}\n\nTotal: {} exercises, {} sets\nIntensity: {}\n\nAnalyze the workout pattern:\n- Exercise selection (compound vs isolation, movement patterns)\n- Volume (total sets: {} - indicates volume focus if >15, strength focus if <8)\n- Intensity (RPE patterns indicate training intent)\n- Exercise count (focused if 1-3, comprehensive if 4+)\n- Weight/rep ranges (heavy/low reps = strength, moderate = hypertrophy, light/high = endurance)\n\nGenerate an insightful summary that captures the workout's character, intensity focus, and training intent. Don't just list exercises - identify the underlying training pattern (e.g., 'Heavy strength focus', 'Volume accumulation', 'Power building', 'High intensity push').",
-            exercises_list, exercise_count, total_sets, intensity_note, total_sets
+            "Workout Analysis:\n\nExercises performed:\n{}\n\nTotal: {} exercises, {} sets\nIntensity: {}\n\n{}Analyze the workout pattern:\n- Exercise selection (compound vs isolation, movement patterns)\n- Volume (total sets: {} - indicates volume focus if >15, strength focus if <8)\n- Intensity (RPE patterns indicate training intent)\n- Exercise count (focused if 1-3, comprehensive if 4+)\n- Weight/rep ranges (heavy/low reps = strength, moderate = hypertrophy, light/high = endurance)\n\nGenerate an insightful summary that captures the workout's character, intensity focus, and training intent. Don't just list exercises - identify the underlying training pattern (e.g., 'Heavy strength focus', 'Volume accumulation', 'Power building', 'High intensity push').",
+            exercises_list,
+            exercise_count,
+            total_sets,
+            intensity_note,
+            Self::trends_section(trends),
+            total_sets
+        )
+    }
+
+    pub fn system_movement_pattern_prompt(&self) -> String {
+        "You classify strength exercises by movement pattern. Return only a JSON object {\"pattern\": string} where pattern is one of \"horizontal_push\", \"horizontal_pull\", \"vertical_push\", \"vertical_pull\", \"hip_hinge\", \"squat\", \"lunge\", \"carry\", \"rotation\", \"isolation\" or \"unknown\". Use \"isolation\" for single-joint exercises such as curls or lateral raises, and \"unknown\" when the name does not identify a movement.".to_string()
+    }
+
+    pub fn user_movement_pattern_prompt(&self, exercise: &str) -> String {
+        format!(
+            "Exercise: {}\nReturn only the JSON object with its movement pattern.",
+            exercise
         )
     }
 }
@@ -833,6 +1685,28 @@ pub async fn generate_exercise_to_equipment_and_muscles(
     Ok((res.equipment, res.muscles, res.related_exercises))
 }
 
+pub async fn classify_movement_pattern(
+    llm: &LlmInterface,
+    builder: &PromptBuilder,
+    exercise: &str,
+) -> Result<ExercisePatternType> {
+    debug!("classify_movement_pattern called exercise='{}'", exercise);
+    let system = builder.system_movement_pattern_prompt();
+    let user = builder.user_movement_pattern_prompt(exercise);
+    #[derive(Deserialize)]
+    struct ResShape {
+        pattern: String,
+    }
+    let res: ResShape = llm.call_json(&system, &user).await?;
+    let pattern = ExercisePatternType::from_str(res.pattern.trim());
+    info!(
+        "classify_movement_pattern classified '{}' as {}",
+        exercise,
+        pattern.as_str()
+    );
+    Ok(pattern)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkoutSuggestion {
     pub title: String,
@@ -840,6 +1714,9 @@ pub struct WorkoutSuggestion {
     pub suggestion_type: String,
     pub exercise_name: Option<String>,
     pub reasoning: Option<String>,
+    /// How strongly the model recommends this suggestion, from 0.0 to 1.0.
+    #[serde(default)]
+    pub confidence: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -848,6 +1725,45 @@ pub struct WorkoutSummary {
     pub emoji: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgressTrend {
+    Improving,
+    Plateau,
+    Declining,
+}
+
+impl ProgressTrend {
+    /// Compares the latest session's top weight with the average top weight of the earlier
+    /// sessions. A change within 2.5% either way counts as a plateau.
+    pub fn from_top_weights(previous: &[f64], latest: f64) -> Option<Self> {
+        if previous.is_empty() {
+            return None;
+        }
+        let baseline = previous.iter().sum::<f64>() / previous.len() as f64;
+        if baseline <= 0.0 {
+            return None;
+        }
+        let change = (latest - baseline) / baseline;
+        Some(if change > 0.025 {
+            ProgressTrend::Improving
+        } else if change < -0.025 {
+            ProgressTrend::Declining
+        } else {
+            ProgressTrend::Plateau
+        })
+    }
+}
+
+impl std::fmt::Display for ProgressTrend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgressTrend::Improving => write!(f, "Improving"),
+            ProgressTrend::Plateau => write!(f, "Plateau"),
+            ProgressTrend::Declining => write!(f, "Declining"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "command_type")]
 pub enum Command {
@@ -858,9 +1774,20 @@ pub enum Command {
         reps: Option<i64>,
         rpe: Option<f64>,
         set_count: Option<i64>,
+        #[serde(default)]
+        duration_seconds: Option<i64>,
+        #[serde(default)]
+        distance_meters: Option<f64>,
+        /// Unit `weight` was given in; `None` means kilograms.
+        #[serde(default)]
+        unit: Option<WeightUnit>,
         tags: Vec<String>,
         aoi: Option<String>,
         original_string: String,
+        /// Label shared by the sets of one superset in a single reply; sets with the same
+        /// label are grouped together after they are added.
+        #[serde(default)]
+        superset_group: Option<i64>,
     },
     #[serde(rename = "remove_set")]
     RemoveSet {
@@ -875,6 +1802,16 @@ pub enum Command {
         weight: Option<f64>,
         reps: Option<i64>,
         rpe: Option<f64>,
+        #[serde(default)]
+        weight_delta: Option<f64>,
+        #[serde(default)]
+        reps_delta: Option<i64>,
+        /// Unit `weight` and `weight_delta` were given in; `None` means kilograms.
+        #[serde(default)]
+        unit: Option<WeightUnit>,
+        /// Free-text note for the set; replaces any existing note.
+        #[serde(default)]
+        notes: Option<String>,
     },
     #[serde(rename = "update_summary")]
     UpdateSummary { message: String, emoji: String },
@@ -916,20 +1853,40 @@ pub async fn generate_workout_suggestions(
     builder: &PromptBuilder,
     current_exercises: &[(String, i64)],
     past_performance: &str,
+    trends: &[(String, ProgressTrend)],
 ) -> Result<Vec<WorkoutSuggestion>> {
     debug!(
         "generate_workout_suggestions called exercises={}",
         current_exercises.len()
     );
     let system = builder.system_suggestion_prompt();
-    let user = builder.user_suggestion_prompt(current_exercises, past_performance);
+    let user = builder.user_suggestion_prompt(current_exercises, past_performance, trends);
 
     #[derive(Deserialize)]
     struct ResShape {
         suggestions: Vec<WorkoutSuggestion>,
     }
 
-    let res: ResShape = llm.call_json(&system, &user).await?;
+    let mut res: ResShape = llm.call_json(&system, &user).await?;
+    for suggestion in &mut res.suggestions {
+        suggestion.confidence = suggestion.confidence.map(|c| c.clamp(0.0, 1.0));
+    }
+    // Most confident first; the sort is stable, so unscored suggestions keep the model's
+    // order after the scored ones.
+    res.suggestions.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let max_suggestions = builder.suggestion_count();
+    if res.suggestions.len() > max_suggestions {
+        warn!(
+            "generate_workout_suggestions received {} suggestions, truncating to {}",
+            res.suggestions.len(),
+            max_suggestions
+        );
+        res.suggestions.truncate(max_suggestions);
+    }
     info!(
         "generate_workout_suggestions returned {} suggestions",
         res.suggestions.len()
@@ -942,13 +1899,14 @@ pub async fn generate_workout_summary(
     builder: &PromptBuilder,
     current_exercises: &[(String, i64)],
     detailed_exercises: &[(String, i64, String)],
+    trends: &[(String, ProgressTrend)],
 ) -> Result<WorkoutSummary> {
     debug!(
         "generate_workout_summary called exercises={}",
         current_exercises.len()
     );
     let system = builder.system_summary_prompt();
-    let user = builder.user_summary_prompt(current_exercises, detailed_exercises);
+    let user = builder.user_summary_prompt(current_exercises, detailed_exercises, trends);
 
     let mut summary: WorkoutSummary = llm.call_json(&system, &user).await?;
     summary.message = summary.message.trim().to_string();
@@ -1006,4 +1964,468 @@ mod tests {
                 .any(|s| s.to_lowercase().contains("squat"))
         );
     }
+
+    #[tokio::test]
+    async fn mock_suggestion_count() {
+        let ctx = PromptContext {
+            suggestion_count: 2,
+            ..Default::default()
+        };
+        let builder = PromptBuilder::new(ctx);
+        let llm = LlmInterface::new_mock_fn(|_s, u| {
+            assert!(u.contains("Provide 2 SPECIFIC, ACTIONABLE suggestions"));
+            r#"{"suggestions": [
+                {"title": "Add Barbell Rows", "suggestion_type": "exercise", "exercise_name": "Barbell Row"},
+                {"title": "Increase Bench Press to 87.5kg", "suggestion_type": "progression", "exercise_name": "Bench Press"},
+                {"title": "Consider wrapping up", "suggestion_type": "completion"}
+            ]}"#
+            .to_string()
+        });
+        let current = vec![("Bench Press".to_string(), 3)];
+        let suggestions = generate_workout_suggestions(&llm, &builder, &current, "", &[])
+            .await
+            .unwrap();
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].title, "Add Barbell Rows");
+    }
+
+    #[tokio::test]
+    async fn mock_suggestions_sorted_by_confidence() {
+        let builder = PromptBuilder::new(PromptContext::default());
+        let llm = LlmInterface::new_mock_fn(|_s, _u| {
+            r#"{"suggestions": [
+                {"title": "Consider wrapping up", "suggestion_type": "completion"},
+                {"title": "Add Barbell Rows", "suggestion_type": "exercise", "confidence": 0.4},
+                {"title": "Increase Bench Press to 87.5kg", "suggestion_type": "progression", "confidence": 0.9},
+                {"title": "Add 1 more set to Squats", "suggestion_type": "volume", "confidence": 1.7}
+            ]}"#
+            .to_string()
+        });
+        let current = vec![("Bench Press".to_string(), 3)];
+        let suggestions = generate_workout_suggestions(&llm, &builder, &current, "", &[])
+            .await
+            .unwrap();
+        assert_eq!(
+            suggestions
+                .iter()
+                .map(|s| (s.title.as_str(), s.confidence))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Add 1 more set to Squats", Some(1.0)),
+                ("Increase Bench Press to 87.5kg", Some(0.9)),
+                ("Add Barbell Rows", Some(0.4)),
+                ("Consider wrapping up", None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_summary_prompt_includes_trends() {
+        let builder = PromptBuilder::new(PromptContext::default());
+        let llm = LlmInterface::new_mock_fn(|_s, u| {
+            assert!(u.contains("- Bench Press: Improving"));
+            assert!(u.contains("- Squat: Declining"));
+            r#"{"message": "Pressing up, squats dipping", "emoji": "📈"}"#.to_string()
+        });
+        let current = vec![("Bench Press".to_string(), 3), ("Squat".to_string(), 2)];
+        let detailed = vec![
+            (
+                "Bench Press".to_string(),
+                3,
+                "Bench Press: 3 sets, avg 100.0kg x 5 reps".to_string(),
+            ),
+            (
+                "Squat".to_string(),
+                2,
+                "Squat: 2 sets, avg 120.0kg x 5 reps".to_string(),
+            ),
+        ];
+        let trends = vec![
+            ("Bench Press".to_string(), ProgressTrend::Improving),
+            ("Squat".to_string(), ProgressTrend::Declining),
+        ];
+        let summary = generate_workout_summary(&llm, &builder, &current, &detailed, &trends)
+            .await
+            .unwrap();
+        assert_eq!(summary.emoji, "📈");
+    }
+
+    #[test]
+    fn progress_trend_from_top_weights() {
+        assert_eq!(ProgressTrend::from_top_weights(&[], 100.0), None);
+        assert_eq!(
+            ProgressTrend::from_top_weights(&[95.0, 97.5], 102.5),
+            Some(ProgressTrend::Improving)
+        );
+        assert_eq!(
+            ProgressTrend::from_top_weights(&[100.0, 100.0], 101.0),
+            Some(ProgressTrend::Plateau)
+        );
+        assert_eq!(
+            ProgressTrend::from_top_weights(&[100.0], 90.0),
+            Some(ProgressTrend::Declining)
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_call_with_stats_reports_zero_usage() {
+        let llm = LlmInterface::new_mock_fn(|_s, _u| "  {\"ok\": true}  ".to_string());
+        let (content, stats) = llm.call_with_stats("system", "user").await.unwrap();
+        assert_eq!(content, "{\"ok\": true}");
+        assert_eq!(stats, CallStats::default());
+        assert_eq!(llm.call("system", "user").await.unwrap(), content);
+    }
+
+    #[tokio::test]
+    async fn ollama_client_is_cached() {
+        let first = LlmInterface::get_ollama_client().await.unwrap();
+        let second = LlmInterface::get_ollama_client().await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn generation_params_reach_ollama_options() {
+        let default = serde_json::to_value(ollama_options(&GenerationParams::default())).unwrap();
+        assert!((default["temperature"].as_f64().unwrap() - 0.001).abs() < 1e-6);
+        assert!(default.get("top_p").is_none());
+
+        let llm = LlmInterface::new_mock_fn(|_, _| String::new()).with_params(GenerationParams {
+            temperature: 0.9,
+            top_p: Some(0.95),
+        });
+        let options = serde_json::to_value(ollama_options(&llm.params.unwrap())).unwrap();
+        assert!((options["temperature"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+        assert!((options["top_p"].as_f64().unwrap() - 0.95).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn openai_keeps_its_default_sampling_unless_configured() {
+        let llm = LlmInterface::new_openai(None, None).await.unwrap();
+        assert_eq!(llm.params, None);
+
+        let tuned = llm.with_params(GenerationParams::default());
+        assert_eq!(tuned.params, Some(GenerationParams::default()));
+    }
+
+    #[tokio::test]
+    async fn call_with_params_answers_like_call() {
+        let llm = LlmInterface::new_mock_fn(|_, user| format!("echo {}", user));
+        let params = GenerationParams {
+            temperature: 0.8,
+            top_p: None,
+        };
+        assert_eq!(
+            llm.call_with_params("system", "hi", params).await.unwrap(),
+            "echo hi"
+        );
+    }
+
+    #[tokio::test]
+    async fn openai_compatible_backend_keeps_base_url() {
+        let llm = LlmInterface::new_openai_compatible(
+            "http://localhost:8080/v1".into(),
+            None,
+            "llama-3.1-8b".into(),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            &llm.backend,
+            LlmBackend::OpenAi { model, api_key: None, base_url: Some(url) }
+                if model == "llama-3.1-8b" && url == "http://localhost:8080/v1"
+        ));
+
+        let llm = LlmInterface::new_openai(None, None).await.unwrap();
+        assert!(matches!(
+            &llm.backend,
+            LlmBackend::OpenAi { base_url: None, .. }
+        ));
+
+        assert!(
+            LlmInterface::new_openai_compatible(" ".into(), None, "m".into())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn gemini_backend_dispatch() {
+        let llm = LlmInterface::new_gemini(Some("key".into()), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            &llm.backend,
+            LlmBackend::Gemini { model, api_key }
+                if model == GEMINI_DEFAULT_MODEL && api_key.as_deref() == Some("key")
+        ));
+
+        let body = gemini_request_body("SYSTEM", "USER", &GenerationParams::default(), true);
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "SYSTEM\n\nUSER");
+        assert_eq!(
+            body["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        let body = gemini_request_body("SYSTEM", "USER", &GenerationParams::default(), false);
+        assert!(body["generationConfig"].get("responseMimeType").is_none());
+
+        let response: GeminiResponse = serde_json::from_str(
+            r#"{"candidates":[{"content":{"parts":[{"text":"```json\n{\"reps\": "},{"text":"5}\n```"}]}}],
+                "usageMetadata":{"promptTokenCount":7,"candidatesTokenCount":3,"totalTokenCount":10}}"#,
+        )
+        .unwrap();
+        let (content, stats) = gemini_reply(response).unwrap();
+        assert_eq!(stats.total_tokens, 10);
+        let parsed: serde_json::Value = serde_json::from_str(strip_code_fences(&content)).unwrap();
+        assert_eq!(parsed["reps"], 5);
+
+        let empty: GeminiResponse = serde_json::from_str(r#"{"candidates":[]}"#).unwrap();
+        assert!(gemini_reply(empty).is_err());
+    }
+
+    #[test]
+    fn parsed_set_weight_units() {
+        let kg: ParsedSet = serde_json::from_str(
+            r#"{"exercise":"Bench Press","weight":100.0,"unit":"kg","reps":5,"rpe":null,"set_count":1,"tags":[],"aoi":null}"#,
+        )
+        .unwrap();
+        assert_eq!(kg.unit, Some(WeightUnit::Kg));
+        assert_eq!(kg.weight_in_kg(), Some(100.0));
+
+        let lb: ParsedSet = serde_json::from_str(
+            r#"{"exercise":"Bench Press","weight":225.0,"unit":"lb","reps":5,"rpe":null,"set_count":1,"tags":[],"aoi":null}"#,
+        )
+        .unwrap();
+        assert_eq!(lb.unit, Some(WeightUnit::Lb));
+        assert!((lb.weight_in_kg().unwrap() - 102.0582).abs() < 1e-3);
+
+        let none: ParsedSet = serde_json::from_str(
+            r#"{"exercise":"Bench Press","weight":60.0,"unit":null,"reps":5,"rpe":null,"set_count":1,"tags":[],"aoi":null}"#,
+        )
+        .unwrap();
+        assert_eq!(none.unit, None);
+        assert_eq!(none.weight_in_kg(), Some(60.0));
+
+        let missing: ParsedSet = serde_json::from_str(
+            r#"{"exercise":"Bench Press","weight":60.0,"reps":5,"rpe":null,"set_count":1,"tags":[],"aoi":null}"#,
+        )
+        .unwrap();
+        assert_eq!(missing.unit, None);
+    }
+
+    #[test]
+    fn is_retryable_classifies_errors() {
+        assert!(is_retryable(&anyhow!("request timed out")));
+        assert!(is_retryable(&anyhow!("429 Too Many Requests")));
+        assert!(is_retryable(&anyhow!("503 Service Unavailable")));
+        assert!(is_retryable(&anyhow::Error::from(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset
+        ))));
+        assert!(!is_retryable(&anyhow!("401 Unauthorized: invalid api key")));
+        assert!(!is_retryable(&anyhow!("400 Bad Request")));
+        assert!(!is_retryable(&anyhow!("Unauthorized")));
+        assert!(is_retryable(&anyhow!("timed out after 4000ms")));
+        let parse_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert!(!is_retryable(&anyhow::Error::from(parse_error)));
+        assert!(is_retryable(&anyhow!("connection reset after 400 ms")));
+        assert!(!is_retryable(&anyhow!("upstream returned status: 401")));
+    }
+
+    #[test]
+    fn is_retryable_reads_openai_error_type() {
+        let api_error = |json: &str| {
+            let error: OpenAiError = serde_json::from_str(json).unwrap();
+            anyhow::Error::from(error).context(LlmUnavailable)
+        };
+        assert!(is_retryable(&api_error(
+            r#"{"message": "Slow down", "type": "requests", "param": null, "code": null}"#
+        )));
+        assert!(is_retryable(&api_error(
+            r#"{"message": "The server had an error", "type": "server_error", "param": null, "code": null}"#
+        )));
+        assert!(!is_retryable(&api_error(
+            r#"{"message": "Bad key", "type": "invalid_request_error", "param": null, "code": "invalid_api_key"}"#
+        )));
+        assert!(!is_retryable(&api_error(
+            r#"{"message": "Over quota", "type": "insufficient_quota", "param": null, "code": "insufficient_quota"}"#
+        )));
+    }
+
+    #[tokio::test]
+    async fn call_streaming_mock_emits_single_chunk() {
+        let llm = LlmInterface::new_mock_fn(|_, _| r#"{"message": "Solid session"}"#.to_string());
+        let mut chunks = Vec::new();
+        let content = llm
+            .call_streaming("system", "user", |chunk| chunks.push(chunk.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(chunks, vec![r#"{"message": "Solid session"}"#.to_string()]);
+        assert_eq!(content, chunks.concat());
+    }
+
+    #[tokio::test]
+    async fn call_json_with_repair_fixes_broken_reply() {
+        #[derive(Deserialize)]
+        struct Reply {
+            message: String,
+        }
+
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let llm = LlmInterface::new_mock_fn(move |system, user| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if system.starts_with(JSON_REPAIR_PROMPT) {
+                assert!(system.ends_with("Reply with a message."));
+                assert!(user.contains(r#"{"message": "Nice work""#));
+                assert!(user.contains("Parse error:"));
+                r#"{"message": "Nice work"}"#.to_string()
+            } else {
+                r#"{"message": "Nice work""#.to_string()
+            }
+        });
+
+        assert!(
+            llm.call_json::<Reply>("Reply with a message.", "hi")
+                .await
+                .is_err()
+        );
+        let reply: Reply = llm
+            .call_json_with_repair("Reply with a message.", "hi")
+            .await
+            .unwrap();
+        assert_eq!(reply.message, "Nice work");
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn call_times_out_on_slow_backend() {
+        let llm = LlmInterface::new_mock_delayed_fn(Duration::from_millis(200), |_, _| {
+            "late".to_string()
+        })
+        .with_timeout(Duration::from_millis(20));
+        let err = llm.call("system", "user").await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<LlmTimeout>(),
+            Some(&LlmTimeout(Duration::from_millis(20)))
+        );
+        assert!(is_retryable(&err));
+
+        let patient = LlmInterface::new_mock_delayed_fn(Duration::from_millis(5), |_, _| {
+            "on time".to_string()
+        })
+        .with_timeout(Duration::from_secs(5));
+        assert_eq!(patient.call("system", "user").await.unwrap(), "on time");
+    }
+
+    #[tokio::test]
+    async fn health_check_reports_failing_responder() {
+        let reachable = LlmInterface::new_mock_fn(|_, _| String::new());
+        assert!(reachable.health_check().await.unwrap());
+
+        let failing = LlmInterface::new_mock_result_fn(|_, _| Err(anyhow!("connection refused")));
+        assert!(!failing.health_check().await.unwrap());
+
+        let slow = LlmInterface::new_mock_delayed_fn(Duration::from_millis(200), |_, _| {
+            r#"{"ok":true}"#.to_string()
+        })
+        .with_timeout(Duration::from_millis(20));
+        assert!(!slow.health_check().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn health_check_gives_up_waiting_for_a_busy_backend() {
+        let llm = LlmInterface::new_mock_fn(|_, _| String::new())
+            .with_max_concurrent_calls(1)
+            .with_timeout(Duration::from_millis(20));
+        let _busy = llm.permits.acquire().await.unwrap();
+
+        let healthy = tokio::time::timeout(Duration::from_secs(1), llm.health_check())
+            .await
+            .expect("health check should not wait for the permit past its timeout");
+        assert!(!healthy.unwrap());
+    }
+
+    #[test]
+    fn openai_json_mode_requests_mention_json() {
+        let request = openai_request("gpt", HEALTH_CHECK_SYSTEM_PROMPT, "ping", None, true)
+            .build()
+            .unwrap();
+        let body = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["response_format"]["type"], "json_object");
+        let messages = body["messages"].as_array().unwrap();
+        assert!(messages.iter().any(|m| {
+            m["content"]
+                .as_str()
+                .is_some_and(|c| c.to_lowercase().contains("json"))
+        }));
+
+        let params = GenerationParams {
+            top_p: Some(0.5),
+            ..GenerationParams::default()
+        };
+        let request = openai_request("gpt", "SYSTEM", "USER", Some(&params), false)
+            .build()
+            .unwrap();
+        let body = serde_json::to_value(&request).unwrap();
+        assert!(body.get("response_format").is_none());
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "USER");
+        assert_eq!(body["top_p"], 0.5);
+    }
+
+    #[tokio::test]
+    async fn single_permit_serializes_concurrent_calls() {
+        let delay = Duration::from_millis(50);
+        let finished = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = finished.clone();
+        let llm = LlmInterface::new_mock_delayed_fn(delay, move |_, user| {
+            log.lock()
+                .unwrap()
+                .push((user.to_string(), std::time::Instant::now()));
+            "ok".to_string()
+        })
+        .with_max_concurrent_calls(1);
+
+        let (a, b) = tokio::join!(llm.call("system", "a"), llm.call("system", "b"));
+        assert_eq!(a.unwrap(), "ok");
+        assert_eq!(b.unwrap(), "ok");
+
+        let finished = finished.lock().unwrap();
+        assert_eq!(finished.len(), 2);
+        // The second call only starts its delay once the first has released its permit.
+        assert!(finished[1].1.duration_since(finished[0].1) >= delay);
+    }
+
+    #[tokio::test]
+    async fn call_with_retry_stops_on_non_retryable_error() {
+        let attempts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let llm = LlmInterface::new_mock_result_fn(move |_, _| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Err(anyhow!("401 Unauthorized"))
+        });
+        let result = llm
+            .call_with_retry("system", "user", 3, Duration::from_millis(1))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn call_with_retry_retries_transient_errors() {
+        let attempts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let llm = LlmInterface::new_mock_result_fn(move |_, _| {
+            if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                Err(anyhow!("503 Service Unavailable"))
+            } else {
+                Ok("ok".to_string())
+            }
+        });
+        let result = llm
+            .call_with_retry("system", "user", 3, Duration::from_millis(1))
+            .await
+            .unwrap();
+        assert_eq!(result, "ok");
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
 }
@@ -0,0 +1,175 @@
+//! Fuzzy matching of free-text exercise names against the ones already in the database.
+
+/// Similarity a name must reach before it is treated as an existing exercise. High enough that
+/// "Bench Dip" stays separate from "Bench Press" but "Barbell Bench" still finds
+/// "Barbell Bench Press".
+pub const DEFAULT_EXERCISE_MATCH_THRESHOLD: f64 = 0.92;
+
+/// Similarity two words must reach to count as the same word: plurals and small typos
+/// ("squats", "pres") pass, different lifts ("row" and "curl") don't.
+const WORD_MATCH_THRESHOLD: f64 = 0.9;
+
+fn normalize(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn jaro(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        for j in start..end {
+            if !b_matched[j] && b[j] == *ca {
+                b_matched[j] = true;
+                a_matches.push(*ca);
+                break;
+            }
+        }
+    }
+    if a_matches.is_empty() {
+        return 0.0;
+    }
+
+    let b_matches = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, matched)| **matched)
+        .map(|(c, _)| *c);
+    let transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|(ca, cb)| **ca != *cb)
+        .count()
+        / 2;
+
+    let m = a_matches.len() as f64;
+    (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m) / 3.0
+}
+
+/// Jaro-Winkler similarity of two names after lowercasing and collapsing whitespace, from
+/// 0.0 (nothing in common) to 1.0 (identical).
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = normalize(a).chars().collect();
+    let b: Vec<char> = normalize(b).chars().collect();
+    if a == b {
+        return 1.0;
+    }
+    let jaro = jaro(&a, &b);
+    let prefix = a
+        .iter()
+        .zip(&b)
+        .take(4)
+        .take_while(|(ca, cb)| ca == cb)
+        .count();
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+/// Whether every word of `name` has a close counterpart in `candidate`. Names that score
+/// high overall but differ in the word that tells the lifts apart, like "Dumbbell Row" and
+/// "Dumbbell Curl", don't agree.
+pub fn words_agree(name: &str, candidate: &str) -> bool {
+    let candidate = normalize(candidate);
+    let candidate_words: Vec<&str> = candidate.split(' ').collect();
+    normalize(name).split(' ').all(|word| {
+        candidate_words
+            .iter()
+            .any(|other| name_similarity(word, other) >= WORD_MATCH_THRESHOLD)
+    })
+}
+
+/// Returns the known name most similar to `name` if it scores at least `threshold` and every
+/// word of `name` matches one of its words. An exact match (ignoring case and spacing)
+/// always wins.
+pub fn fuzzy_match_exercise(name: &str, known: &[String], threshold: f64) -> Option<String> {
+    let mut best: Option<(&String, f64)> = None;
+    for candidate in known {
+        let score = name_similarity(name, candidate);
+        if score >= threshold
+            && best.is_none_or(|(_, best_score)| score > best_score)
+            && words_agree(name, candidate)
+        {
+            best = Some((candidate, score));
+        }
+    }
+    best.map(|(candidate, _)| candidate.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known() -> Vec<String> {
+        ["Barbell Bench Press", "Bench Dip", "Squat", "Lat Pulldown"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_exact_match_ignores_case_and_spacing() {
+        assert_eq!(
+            fuzzy_match_exercise("  squat ", &known(), DEFAULT_EXERCISE_MATCH_THRESHOLD),
+            Some("Squat".to_string())
+        );
+        assert_eq!(
+            fuzzy_match_exercise("barbell  bench press", &known(), 1.0),
+            Some("Barbell Bench Press".to_string())
+        );
+    }
+
+    #[test]
+    fn test_close_match_is_reused() {
+        assert_eq!(
+            fuzzy_match_exercise("Barbell Bench", &known(), DEFAULT_EXERCISE_MATCH_THRESHOLD),
+            Some("Barbell Bench Press".to_string())
+        );
+        assert_eq!(
+            fuzzy_match_exercise("Squats", &known(), DEFAULT_EXERCISE_MATCH_THRESHOLD),
+            Some("Squat".to_string())
+        );
+    }
+
+    #[test]
+    fn test_no_match_below_threshold() {
+        assert_eq!(
+            fuzzy_match_exercise("Bench Press", &known(), DEFAULT_EXERCISE_MATCH_THRESHOLD),
+            None
+        );
+        assert_eq!(
+            fuzzy_match_exercise("Lat Pullover", &known(), DEFAULT_EXERCISE_MATCH_THRESHOLD),
+            None
+        );
+        assert_eq!(
+            fuzzy_match_exercise("Deadlift", &known(), DEFAULT_EXERCISE_MATCH_THRESHOLD),
+            None
+        );
+        assert_eq!(fuzzy_match_exercise("Squat", &[], 0.0), None);
+    }
+
+    #[test]
+    fn test_close_names_of_different_lifts_are_not_merged() {
+        // Each pair scores above 0.9 overall.
+        for (name, existing) in [
+            ("Dumbbell Row", "Dumbbell Curl"),
+            ("Barbell Row", "Barbell Curl"),
+            ("Incline Bench Press", "Incline Dumbbell Press"),
+        ] {
+            assert!(name_similarity(name, existing) >= 0.9);
+            assert!(!words_agree(name, existing));
+            assert_eq!(
+                fuzzy_match_exercise(name, &[existing.to_string()], 0.9),
+                None
+            );
+        }
+        assert!(words_agree("Squats", "Squat"));
+        assert!(words_agree("Bench Pres", "Bench Press"));
+    }
+}
@@ -0,0 +1,42 @@
+//! Conversions between the effort scales sets are logged with.
+
+/// Top of the RPE scale, a set taken to failure with no reps left.
+const MAX_RPE: f64 = 10.0;
+
+/// Reps in reserve implied by an RPE: 10 − RPE, so RPE 10 is 0 RIR and RPE 7 is 3 RIR. The
+/// RPE is clamped to 0–10 first, keeping the result within 0–10 as well.
+pub fn rir_from_rpe(rpe: f64) -> f64 {
+    MAX_RPE - rpe.clamp(0.0, MAX_RPE)
+}
+
+/// RPE implied by a number of reps in reserve, the inverse of [`rir_from_rpe`]. More than 10
+/// reps in reserve reads as RPE 0.
+pub fn rpe_from_rir(rir: f64) -> f64 {
+    MAX_RPE - rir.clamp(0.0, MAX_RPE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rir_from_rpe_boundaries() {
+        assert_eq!(rir_from_rpe(10.0), 0.0);
+        assert_eq!(rir_from_rpe(7.0), 3.0);
+        assert_eq!(rir_from_rpe(8.5), 1.5);
+        assert_eq!(rir_from_rpe(0.0), 10.0);
+        assert_eq!(rir_from_rpe(11.0), 0.0);
+        assert_eq!(rir_from_rpe(-1.0), 10.0);
+    }
+
+    #[test]
+    fn test_rpe_from_rir_inverts_rir_from_rpe() {
+        assert_eq!(rpe_from_rir(0.0), 10.0);
+        assert_eq!(rpe_from_rir(3.0), 7.0);
+        assert_eq!(rpe_from_rir(15.0), 0.0);
+        assert_eq!(rpe_from_rir(-2.0), 10.0);
+        for rpe in [0.0, 6.5, 9.0, 10.0] {
+            assert_eq!(rpe_from_rir(rir_from_rpe(rpe)), rpe);
+        }
+    }
+}
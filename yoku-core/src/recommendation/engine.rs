@@ -1,13 +1,14 @@
-#![allow(dead_code)]
 use super::GraphManager;
+use super::graph::{GraphBackend, MuscleInvolvement};
 use crate::db::models::*;
-use crate::db::operations::get_all_exercises_except;
+use crate::db::operations::{
+    get_all_exercises, get_all_exercises_except, get_all_muscles, get_latest_weights_since,
+};
 use anyhow::Result;
 use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
 
-pub struct RecommendationEngine<T: indradb::Datastore> {
-    graph_manager: GraphManager<T>,
+pub struct RecommendationEngine {
+    graph_manager: GraphManager,
     db_pool: sqlx::SqlitePool,
 }
 
@@ -41,13 +42,52 @@ struct ExerciseInProgress {
     pub exercise_id: i64,
     pub exercise_name: String,
     pub movement_pattern: ExercisePatternType,
-    pub equipment: Vec<i64>,
     pub muscles: Vec<(i64, f64)>,
     pub joints: Vec<Joint>,
     pub sets_completed: usize,
     pub base_weight: f64,
 }
 
+const EXERCISE_SETUP_MINUTES: f64 = 2.0;
+const SECONDS_PER_REP: f64 = 3.0;
+const COMPOUND_FATIGUE_FACTOR: f64 = 1.5;
+const OVERSHOOT_PENALTY: f64 = 0.5;
+const OFF_TARGET_PENALTY: f64 = 0.25;
+const DURATION_PENALTY: f64 = 0.5;
+const FATIGUE_PENALTY: f64 = 0.05;
+
+/// Rep scheme, effort and rest used for every planned set of a session style.
+struct StyleParameters {
+    reps: i64,
+    intended_rpe: f64,
+    rest_seconds: i64,
+    max_sets_per_exercise: usize,
+}
+
+impl StyleParameters {
+    fn for_style(style: SessionStyle) -> Self {
+        let (reps, intended_rpe, rest_seconds, max_sets_per_exercise) = match style {
+            SessionStyle::Hypertrophy => (10, 8.0, 90, 4),
+            SessionStyle::Strength => (5, 8.5, 180, 5),
+            SessionStyle::Power => (3, 7.0, 180, 5),
+            SessionStyle::Conditioning => (15, 7.0, 45, 3),
+            SessionStyle::Rehab => (12, 6.0, 60, 3),
+            SessionStyle::SkillFocused => (5, 7.0, 120, 5),
+        };
+        StyleParameters {
+            reps,
+            intended_rpe,
+            rest_seconds,
+            max_sets_per_exercise,
+        }
+    }
+
+    /// Working time plus rest for one set, in minutes.
+    fn set_minutes(&self) -> f64 {
+        (self.reps as f64 * SECONDS_PER_REP + self.rest_seconds as f64) / 60.0
+    }
+}
+
 #[derive(Clone)]
 struct BeamState {
     pub sets: Vec<PlannedSet>,
@@ -60,14 +100,138 @@ struct BeamState {
     pub score: f64,
 }
 
-impl<T: indradb::Datastore> RecommendationEngine<T> {
-    pub fn new(graph_manager: GraphManager<T>, db_pool: sqlx::SqlitePool) -> Self {
+impl RecommendationEngine {
+    /// Opens the engine over the graph store `backend` picks.
+    pub fn new(backend: GraphBackend, db_pool: sqlx::SqlitePool) -> Result<Self> {
+        Ok(Self::with_graph_manager(
+            GraphManager::open(backend)?,
+            db_pool,
+        ))
+    }
+
+    pub fn with_graph_manager(graph_manager: GraphManager, db_pool: sqlx::SqlitePool) -> Self {
         RecommendationEngine {
             graph_manager,
             db_pool,
         }
     }
 
+    pub fn forget_exercise(&self, exercise_id: i64) -> Result<bool> {
+        self.graph_manager.remove_exercise_by_db_id(exercise_id)
+    }
+
+    /// Brings the graph's copy of a renamed exercise up to date.
+    pub fn rename_exercise(&self, exercise: &Exercise) -> Result<bool> {
+        self.graph_manager.rename_exercise(exercise)
+    }
+
+    /// Adds a vertex for every exercise and muscle in the database that the graph does not
+    /// have yet, so the two stores agree on `db_id`s. Safe to run repeatedly.
+    pub async fn sync_from_db(&self) -> Result<()> {
+        for muscle in get_all_muscles(&self.db_pool).await? {
+            self.graph_manager.get_muscle_vert(&muscle)?;
+        }
+        for exercise in get_all_exercises(&self.db_pool).await? {
+            self.graph_manager.get_exercise_vert(&exercise)?;
+        }
+        Ok(())
+    }
+
+    pub fn graph_manager(&self) -> &GraphManager {
+        &self.graph_manager
+    }
+
+    /// Muscles an exercise works as `(muscle db id, involvement)` pairs; empty when the
+    /// exercise has not been added to the graph.
+    pub fn muscles_for_exercise(&self, exercise_id: i64) -> Result<Vec<(i64, MuscleInvolvement)>> {
+        let Ok(vertex) = self.graph_manager.get_exercise_by_db_id(exercise_id) else {
+            return Ok(vec![]);
+        };
+        self.graph_manager
+            .get_muscles_for_exercise(vertex.id)?
+            .into_iter()
+            .map(|(muscle_vertex, involvement)| {
+                Ok((
+                    self.graph_manager.get_vertex_db_id(muscle_vertex)?,
+                    involvement,
+                ))
+            })
+            .collect()
+    }
+
+    /// Movement pattern linked to an exercise; Unknown when the exercise has no pattern or
+    /// has not been added to the graph.
+    pub fn movement_pattern_for_exercise(&self, exercise_id: i64) -> Result<ExercisePatternType> {
+        let Ok(vertex) = self.graph_manager.get_exercise_by_db_id(exercise_id) else {
+            return Ok(ExercisePatternType::Unknown);
+        };
+        self.graph_manager
+            .get_movement_pattern_for_exercise(vertex.id)
+    }
+
+    /// Finds substitutes for an exercise among those working any of its muscles, keeping
+    /// only exercises whose required equipment is all in `available_equipment_ids`. Ranked
+    /// by the number of shared muscles, then by the summed effective weight they share.
+    pub fn suggest_substitute(
+        &self,
+        exercise_id: i64,
+        available_equipment_ids: &[i64],
+    ) -> Result<Vec<i64>> {
+        let Ok(source) = self.graph_manager.get_exercise_by_db_id(exercise_id) else {
+            return Ok(vec![]);
+        };
+        let source_muscles: HashMap<i64, f64> = self
+            .graph_manager
+            .get_muscles_with_db_ids_for_exercise(source.id)?
+            .into_iter()
+            .map(|(muscle_id, involvement)| (muscle_id, involvement.effective_weight()))
+            .collect();
+
+        let mut candidates = HashSet::new();
+        for (muscle_vert, _) in self.graph_manager.get_muscles_for_exercise(source.id)? {
+            for (exercise_vert, _) in self.graph_manager.get_exercises_for_muscle(muscle_vert)? {
+                if exercise_vert != source.id {
+                    candidates.insert(exercise_vert);
+                }
+            }
+        }
+
+        let available: HashSet<i64> = available_equipment_ids.iter().copied().collect();
+        let mut ranked = Vec::new();
+        for candidate in candidates {
+            let required = self
+                .graph_manager
+                .get_required_equipment_db_ids_for_exercise(candidate)?;
+            if !required.iter().all(|id| available.contains(id)) {
+                continue;
+            }
+
+            let mut shared = 0usize;
+            let mut overlap = 0.0;
+            for (muscle_id, involvement) in self
+                .graph_manager
+                .get_muscles_with_db_ids_for_exercise(candidate)?
+            {
+                if let Some(weight) = source_muscles.get(&muscle_id) {
+                    shared += 1;
+                    overlap += weight.min(involvement.effective_weight());
+                }
+            }
+            ranked.push((
+                self.graph_manager.get_vertex_db_id(candidate)?,
+                shared,
+                overlap,
+            ));
+        }
+
+        ranked.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| b.2.total_cmp(&a.2))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        Ok(ranked.into_iter().map(|(id, _, _)| id).collect())
+    }
+
     pub fn expand_muscle_groups(&self, group_proportions: &[(&str, f64)]) -> Vec<(i64, f64)> {
         let mut result: HashMap<i64, f64> = HashMap::new();
 
@@ -113,13 +277,9 @@ impl<T: indradb::Datastore> RecommendationEngine<T> {
         }
     }
 
-    #[allow(unused, dead_code)]
     pub async fn plan_workout(
         &self,
 
-        // user's ID to get their history
-        user_id: i64,
-
         // muscle IDs to work in proportions $sum{x} = 1$
         target_muscle_id_proportions: HashMap<i64, f64>,
 
@@ -140,17 +300,28 @@ impl<T: indradb::Datastore> RecommendationEngine<T> {
 
         // the session style to use, e.g. hypertrophy, strength, etc.
         session_style: SessionStyle,
+
+        // the user's experience level, exercises above it are never planned
+        experience_level: ExperienceLevel,
     ) -> Result<WorkoutPlan> {
-        let possible_sql_exercises =
-            get_all_exercises_except(&self.db_pool, &avoid_exercises).await?;
+        let accessible_levels = experience_level.accessible_levels();
+        let possible_sql_exercises = get_all_exercises_except(&self.db_pool, &avoid_exercises)
+            .await?
+            .into_iter()
+            .filter(|ex| accessible_levels.contains(&ex.experience_level))
+            .collect::<Vec<_>>();
 
         let available_equipment_set: std::collections::HashSet<i64> =
             available_equipment_ids.iter().cloned().collect();
 
         let possible_exercises = possible_sql_exercises
             .iter()
-            .map(|ex| (ex, self.graph_manager.get_exercise_vert(ex).unwrap()))
-            .filter(|(ex, exercise_id)| {
+            // an exercise whose vertex can't be found or added is left out of the plan
+            .filter_map(|ex| {
+                let vert = self.graph_manager.get_exercise_vert(ex).ok()?;
+                Some((ex, vert))
+            })
+            .filter(|(_, exercise_id)| {
                 let required_equipment = self
                     .graph_manager
                     .get_required_equipment_db_ids_for_exercise(*exercise_id)
@@ -162,6 +333,44 @@ impl<T: indradb::Datastore> RecommendationEngine<T> {
             })
             .collect::<Vec<_>>();
 
+        let history_cutoff =
+            chrono::Utc::now().timestamp() - i64::from(history_window_days) * 24 * 60 * 60;
+        let base_weights: HashMap<i64, f64> =
+            get_latest_weights_since(&self.db_pool, history_cutoff)
+                .await?
+                .into_iter()
+                .collect();
+        let mut templates = Vec::with_capacity(possible_exercises.len());
+        for (sql_ex, ex_vert) in &possible_exercises {
+            let base_weight = base_weights.get(&sql_ex.id).copied().unwrap_or(0.0);
+            let movement_pattern = self
+                .graph_manager
+                .get_movement_pattern_for_exercise(*ex_vert)?;
+            templates.push(ExerciseInProgress {
+                exercise_id: sql_ex.id,
+                exercise_name: sql_ex.name.clone(),
+                movement_pattern,
+                muscles: self
+                    .graph_manager
+                    .get_muscles_with_db_ids_for_exercise(*ex_vert)?
+                    .into_iter()
+                    .map(|(muscle_id, involvement)| (muscle_id, involvement.effective_weight()))
+                    .collect(),
+                joints: Self::joints_for_pattern(movement_pattern),
+                sets_completed: 0,
+                base_weight,
+            });
+        }
+
+        let style = StyleParameters::for_style(session_style);
+        let target_duration = f64::from(target_duration_in_minutes);
+        let planned_sets = (target_duration / style.set_minutes()).max(1.0);
+        let desired_volume: HashMap<i64, f64> = target_muscle_id_proportions
+            .iter()
+            .map(|(&muscle_id, &proportion)| (muscle_id, proportion * planned_sets))
+            .collect();
+        let exercise_limit = maximum_exercise_count.map(|n| n.max(0) as usize);
+
         let max_sets = 30;
 
         let initial_state = BeamState {
@@ -180,40 +389,378 @@ impl<T: indradb::Datastore> RecommendationEngine<T> {
 
         for _step in 0..max_sets {
             let mut candidates: Vec<BeamState> = Vec::new();
+            let mut expanded = false;
             for state in &beam {
-                match &state.current_exercise {
-                    Some(exercise) => {
-                        // so we have a previous exercise, we can either
-                        // 1. do another set of this exercise
-                        // 2. do a different exercise
+                // stopping here is always an option, so a plan never has to take a set
+                // that makes it worse
+                candidates.push(state.clone());
+
+                // terminate if we've reached the maximum duration
+                if state.total_duration >= target_duration {
+                    continue;
+                }
+
+                let mut next_states = Vec::new();
+                let exercises_started =
+                    state.completed_exercises.len() + usize::from(state.current_exercise.is_some());
+                let can_start_new = exercise_limit.is_none_or(|limit| exercises_started < limit);
+
+                if let Some(exercise) = &state.current_exercise {
+                    // 1. do another set of this exercise
+                    if exercise.sets_completed < style.max_sets_per_exercise {
+                        next_states.push(Self::add_set(state, exercise.clone(), &style));
                     }
-                    None => {
-                        // no previous exercise, pick one to start
-                        for (sql_ex, ex_vert) in &possible_exercises {
-                            if !Self::is_exercise_allowed(sql_ex, state) {
-                                continue;
-                            }
+                }
+
+                // 2. start a new exercise, finishing the current one if there is one
+                if can_start_new {
+                    for template in &templates {
+                        if !Self::is_exercise_allowed(template.exercise_id, state) {
+                            continue;
                         }
+                        let mut next = state.clone();
+                        if let Some(previous) = next.current_exercise.take() {
+                            next.completed_exercises.insert(previous.exercise_id);
+                        }
+                        next.total_duration += EXERCISE_SETUP_MINUTES;
+                        next_states.push(Self::add_set(&next, template.clone(), &style));
                     }
                 }
+
+                for mut next in next_states {
+                    next.score = state.score
+                        + Self::score_transition(state, &next, &desired_volume, target_duration);
+                    candidates.push(next);
+                    expanded = true;
+                }
             }
-            if candidates.is_empty() {
+            if !expanded {
                 break;
             }
 
-            candidates.sort_unstable_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+            candidates.sort_unstable_by(|a, b| b.score.total_cmp(&a.score));
             beam = candidates.into_iter().take(BEAM_WIDTH).collect();
+        }
+
+        let best = beam
+            .into_iter()
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .map(|state| state.sets)
+            .unwrap_or_default();
+        Ok(WorkoutPlan { sets: best })
+    }
+
+    fn is_exercise_allowed(exercise_id: i64, state: &BeamState) -> bool {
+        !state.completed_exercises.contains(&exercise_id)
+            && state
+                .current_exercise
+                .as_ref()
+                .is_none_or(|current| current.exercise_id != exercise_id)
+    }
+
+    fn joints_for_pattern(pattern: ExercisePatternType) -> Vec<Joint> {
+        match pattern {
+            ExercisePatternType::Squat | ExercisePatternType::Lunge => vec![Joint::LeftKnee],
+            _ => vec![],
+        }
+    }
+
+    /// Performs one more set of `exercise` on top of `state`: its muscle contributions go
+    /// into the accumulator and its fatigue and duration into the running totals.
+    fn add_set(
+        state: &BeamState,
+        mut exercise: ExerciseInProgress,
+        style: &StyleParameters,
+    ) -> BeamState {
+        let mut next = state.clone();
+        exercise.sets_completed += 1;
+
+        for (muscle_id, contribution) in &exercise.muscles {
+            *next.muscle_accumulator.entry(*muscle_id).or_insert(0.0) += contribution;
+        }
 
-            // terminate if we've reached the maximum duration
+        let compound_factor = match exercise.movement_pattern {
+            ExercisePatternType::Isolation => 1.0,
+            _ => COMPOUND_FATIGUE_FACTOR,
+        };
+        let systemic_fatigue = style.intended_rpe / 10.0 * compound_factor;
+        let joint_fatigue: HashMap<Joint, f64> = exercise
+            .joints
+            .iter()
+            .map(|joint| (*joint, systemic_fatigue))
+            .collect();
+        next.total_systemic_fatigue += systemic_fatigue;
+        for (joint, fatigue) in &joint_fatigue {
+            *next.total_joint_fatigue.entry(*joint).or_insert(0.0) += fatigue;
+        }
+        next.total_duration += style.set_minutes();
+
+        next.sets.push(PlannedSet {
+            exercise_id: exercise.exercise_id,
+            exercise_name: exercise.exercise_name.clone(),
+            movement_pattern: exercise.movement_pattern,
+            set_number: exercise.sets_completed,
+            reps: style.reps,
+            weight: exercise.base_weight,
+            intended_rpe: style.intended_rpe,
+            rest_seconds: style.rest_seconds,
+            systemic_fatigue,
+            joint_fatigue,
+        });
+        next.current_exercise = Some(exercise);
+        next
+    }
+
+    /// How much better `next` is than `previous`. Volume counts towards each target
+    /// muscle's share of the session up to that share; volume beyond it, volume on
+    /// untargeted muscles, accumulated fatigue and time past the target duration all cost.
+    fn score_transition(
+        previous: &BeamState,
+        next: &BeamState,
+        desired_volume: &HashMap<i64, f64>,
+        target_duration: f64,
+    ) -> f64 {
+        Self::state_value(next, desired_volume, target_duration)
+            - Self::state_value(previous, desired_volume, target_duration)
+    }
+
+    fn state_value(
+        state: &BeamState,
+        desired_volume: &HashMap<i64, f64>,
+        target_duration: f64,
+    ) -> f64 {
+        let mut value = 0.0;
+        for (muscle_id, volume) in &state.muscle_accumulator {
+            match desired_volume.get(muscle_id) {
+                Some(desired) => {
+                    value += volume.min(*desired) - OVERSHOOT_PENALTY * (volume - desired).max(0.0)
+                }
+                None => value -= OFF_TARGET_PENALTY * volume,
+            }
+        }
+        value -= DURATION_PENALTY * (state.total_duration - target_duration).max(0.0);
+        value -= FATIGUE_PENALTY * state.total_systemic_fatigue;
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_database;
+    use crate::db::operations::{
+        add_workout_set, create_request_string, create_workout_session, get_or_create_exercise,
+        get_or_create_muscle, get_or_create_user,
+    };
+    use crate::recommendation::graph::MuscleUsageType;
+
+    async fn engine_with_exercises() -> (RecommendationEngine, i64, i64) {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        init_database(&pool).await.unwrap();
+        let graph = GraphManager::new_in_memory().unwrap();
+
+        let chest = get_or_create_muscle(&pool, "Chest").await.unwrap();
+        let quads = get_or_create_muscle(&pool, "Quadriceps").await.unwrap();
+        let (chest_id, quads_id) = (chest.id, quads.id);
+        let chest_vert = graph.add_muscle(chest).unwrap();
+        let quads_vert = graph.add_muscle(quads).unwrap();
+
+        for (name, muscle_vert, pattern) in [
+            (
+                "Bench Press",
+                chest_vert,
+                ExercisePatternType::HorizontalPush,
+            ),
+            ("Barbell Back Squat", quads_vert, ExercisePatternType::Squat),
+        ] {
+            let exercise = get_or_create_exercise(&pool, name).await.unwrap();
+            let exercise_vert = graph.add_exercise(&exercise).unwrap();
+            graph
+                .link_exercise_to_muscle(
+                    exercise_vert,
+                    muscle_vert,
+                    MuscleInvolvement::new(1.0, MuscleUsageType::Primary),
+                )
+                .unwrap();
+            graph
+                .link_exercise_to_movement_pattern(exercise_vert, pattern)
+                .unwrap();
         }
-        todo!()
+
+        (
+            RecommendationEngine::with_graph_manager(graph, pool),
+            chest_id,
+            quads_id,
+        )
+    }
+
+    async fn plan(engine: &RecommendationEngine, targets: HashMap<i64, f64>) -> WorkoutPlan {
+        engine
+            .plan_workout(
+                targets,
+                vec![],
+                30,
+                28,
+                Some(2),
+                vec![],
+                SessionStyle::Hypertrophy,
+                ExperienceLevel::Intermediate,
+            )
+            .await
+            .unwrap()
     }
 
-    fn is_exercise_allowed(sql_ex: &Exercise, state: &BeamState) -> bool {
-        true
+    #[tokio::test]
+    async fn test_sync_from_db_adds_missing_vertices_once() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        init_database(&pool).await.unwrap();
+        let graph = GraphManager::new_in_memory().unwrap();
+
+        let bench = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let squat = get_or_create_exercise(&pool, "Barbell Back Squat")
+            .await
+            .unwrap();
+        let chest = get_or_create_muscle(&pool, "Chest").await.unwrap();
+        let existing = graph.add_exercise(&bench).unwrap();
+
+        let engine = RecommendationEngine::with_graph_manager(graph, pool);
+        engine.sync_from_db().await.unwrap();
+        engine.sync_from_db().await.unwrap();
+
+        let graph = engine.graph_manager();
+        assert_eq!(graph.get_exercise_by_db_id(bench.id).unwrap().id, existing);
+        let squat_vert = graph.get_exercise_by_db_id(squat.id).unwrap();
+        assert_eq!(graph.get_vertex_db_id(squat_vert.id).unwrap(), squat.id);
+        let chest_vert = graph.get_muscle_by_db_id(chest.id).unwrap();
+        assert_eq!(graph.get_vertex_db_id(chest_vert.id).unwrap(), chest.id);
+
+        let exercise_vertices = graph
+            .export_dot()
+            .unwrap()
+            .lines()
+            .filter(|line| line.contains("shape=box"))
+            .count();
+        assert_eq!(exercise_vertices, 2);
+    }
+
+    #[tokio::test]
+    async fn test_suggest_substitute_respects_equipment() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        init_database(&pool).await.unwrap();
+        let graph = GraphManager::new_in_memory().unwrap();
+
+        let chest = graph
+            .add_muscle(get_or_create_muscle(&pool, "Chest").await.unwrap())
+            .unwrap();
+        let triceps = graph
+            .add_muscle(get_or_create_muscle(&pool, "Triceps").await.unwrap())
+            .unwrap();
+        let barbell = graph.add_equipment("Barbell", None, 1).unwrap();
+        let dumbbell = graph.add_equipment("Dumbbell", None, 2).unwrap();
+
+        let mut ids = HashMap::new();
+        for (name, equipment, muscles) in [
+            ("Barbell Bench Press", barbell, vec![chest, triceps]),
+            ("Dumbbell Bench Press", dumbbell, vec![chest, triceps]),
+            ("Dumbbell Fly", dumbbell, vec![chest]),
+            ("Close Grip Bench Press", barbell, vec![chest, triceps]),
+        ] {
+            let exercise = get_or_create_exercise(&pool, name).await.unwrap();
+            let vert = graph.add_exercise(&exercise).unwrap();
+            graph
+                .link_exercise_to_equipment(vert, equipment, true)
+                .unwrap();
+            for muscle in muscles {
+                graph
+                    .link_exercise_to_muscle(
+                        vert,
+                        muscle,
+                        MuscleInvolvement::new(1.0, MuscleUsageType::Primary),
+                    )
+                    .unwrap();
+            }
+            ids.insert(name, exercise.id);
+        }
+        let engine = RecommendationEngine::with_graph_manager(graph, pool);
+
+        let substitutes = engine
+            .suggest_substitute(ids["Barbell Bench Press"], &[2])
+            .unwrap();
+        assert_eq!(
+            substitutes,
+            vec![ids["Dumbbell Bench Press"], ids["Dumbbell Fly"]]
+        );
+
+        let with_barbell = engine
+            .suggest_substitute(ids["Barbell Bench Press"], &[1, 2])
+            .unwrap();
+        assert_eq!(with_barbell.len(), 3);
+        assert!(!with_barbell.contains(&ids["Barbell Bench Press"]));
     }
 
-    fn score_transition() -> f64 {
-        0.0
+    #[tokio::test]
+    async fn test_plan_workout_follows_target_muscles() {
+        let (engine, chest_id, quads_id) = engine_with_exercises().await;
+
+        let chest_only = plan(&engine, HashMap::from([(chest_id, 1.0)])).await;
+        assert!(!chest_only.sets.is_empty());
+        assert!(
+            chest_only
+                .sets
+                .iter()
+                .all(|set| set.exercise_name == "Bench Press")
+        );
+        assert_eq!(
+            chest_only
+                .sets
+                .iter()
+                .map(|set| set.set_number)
+                .collect::<Vec<_>>(),
+            (1..=chest_only.sets.len()).collect::<Vec<_>>()
+        );
+
+        let split = plan(&engine, HashMap::from([(chest_id, 0.5), (quads_id, 0.5)])).await;
+        let names: HashSet<&str> = split
+            .sets
+            .iter()
+            .map(|set| set.exercise_name.as_str())
+            .collect();
+        assert_eq!(names, HashSet::from(["Bench Press", "Barbell Back Squat"]));
+        assert!(
+            split
+                .sets
+                .iter()
+                .filter(|set| set.exercise_name == "Barbell Back Squat")
+                .all(|set| set.joint_fatigue.contains_key(&Joint::LeftKnee))
+        );
+        let minutes: f64 = split
+            .sets
+            .iter()
+            .map(|set| (set.reps as f64 * SECONDS_PER_REP + set.rest_seconds as f64) / 60.0)
+            .sum();
+        assert!(minutes <= 30.0);
+    }
+
+    #[tokio::test]
+    async fn test_plan_workout_starts_from_latest_weight() {
+        let (engine, chest_id, _) = engine_with_exercises().await;
+        let pool = &engine.db_pool;
+        let bench = get_or_create_exercise(pool, "Bench Press").await.unwrap();
+        let session = create_workout_session(pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let user = get_or_create_user(pool, "testuser").await.unwrap();
+        let request = create_request_string(pool, user.id, "bench".to_string())
+            .await
+            .unwrap();
+        for weight in [80.0, 85.0] {
+            add_workout_set(pool, &session.id, &bench.id, &request.id, &weight, &5, None)
+                .await
+                .unwrap();
+        }
+
+        let planned = plan(&engine, HashMap::from([(chest_id, 1.0)])).await;
+        assert!(!planned.sets.is_empty());
+        assert!(planned.sets.iter().all(|set| set.weight == 85.0));
     }
 }
@@ -3,9 +3,11 @@ use crate::db::models::ExercisePatternType;
 use crate::db::operations::slugify;
 use anyhow::{Result, anyhow};
 use indradb::QueryExt;
-use indradb::{Database, Datastore, MemoryDatastore, QueryOutputValue, RocksdbDatastore, ijson};
+use indradb::{Database, MemoryDatastore, QueryOutputValue, RocksdbDatastore, ijson};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MuscleUsageType {
@@ -60,29 +62,105 @@ impl MuscleInvolvement {
     }
 }
 
-pub struct GraphManager<T: Datastore> {
-    db: Database<T>,
+/// Where the recommendation graph is kept.
+#[derive(uniffi::Enum, Debug, Clone, PartialEq, Eq)]
+pub enum GraphBackend {
+    /// A RocksDB store at this path, kept across launches.
+    Rocksdb(PathBuf),
+    /// An in-memory store that starts empty and is lost with the session.
+    Memory,
 }
 
-impl GraphManager<RocksdbDatastore> {
-    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
-        let db = RocksdbDatastore::new_db(db_path)?;
-        let mut gm = Self { db };
-        gm.setup_indexed_properties()?;
-        Ok(gm)
+uniffi::custom_type!(PathBuf, String, {
+    remote,
+    lower: |path| path.to_string_lossy().into_owned(),
+    try_lift: |path| Ok(PathBuf::from(path)),
+});
+
+/// The indradb database behind a [`GraphManager`], one variant per [`GraphBackend`].
+enum GraphStore {
+    Rocksdb(Database<RocksdbDatastore>),
+    Memory(Database<MemoryDatastore>),
+}
+
+impl GraphStore {
+    fn get<Q: Into<indradb::Query>>(&self, q: Q) -> indradb::Result<Vec<QueryOutputValue>> {
+        match self {
+            GraphStore::Rocksdb(db) => db.get(q),
+            GraphStore::Memory(db) => db.get(q),
+        }
+    }
+
+    fn delete<Q: Into<indradb::Query>>(&self, q: Q) -> indradb::Result<()> {
+        match self {
+            GraphStore::Rocksdb(db) => db.delete(q),
+            GraphStore::Memory(db) => db.delete(q),
+        }
+    }
+
+    fn create_vertex_from_type(&self, t: indradb::Identifier) -> indradb::Result<uuid::Uuid> {
+        match self {
+            GraphStore::Rocksdb(db) => db.create_vertex_from_type(t),
+            GraphStore::Memory(db) => db.create_vertex_from_type(t),
+        }
+    }
+
+    fn create_edge(&self, edge: &indradb::Edge) -> indradb::Result<bool> {
+        match self {
+            GraphStore::Rocksdb(db) => db.create_edge(edge),
+            GraphStore::Memory(db) => db.create_edge(edge),
+        }
+    }
+
+    fn set_properties<Q: Into<indradb::Query>>(
+        &self,
+        q: Q,
+        name: indradb::Identifier,
+        value: &indradb::Json,
+    ) -> indradb::Result<()> {
+        match self {
+            GraphStore::Rocksdb(db) => db.set_properties(q, name, value),
+            GraphStore::Memory(db) => db.set_properties(q, name, value),
+        }
+    }
+
+    fn index_property(&mut self, name: indradb::Identifier) -> indradb::Result<()> {
+        match self {
+            GraphStore::Rocksdb(db) => db.index_property(name),
+            GraphStore::Memory(db) => db.index_property(name),
+        }
     }
 }
 
-impl GraphManager<MemoryDatastore> {
-    pub fn new() -> Result<Self> {
-        let db = MemoryDatastore::new_db();
+pub struct GraphManager {
+    db: GraphStore,
+}
+
+impl GraphManager {
+    /// Opens the graph kept in a RocksDB store at `db_path`.
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::with_store(GraphStore::Rocksdb(RocksdbDatastore::new_db(db_path)?))
+    }
+
+    /// An empty graph held in memory.
+    pub fn new_in_memory() -> Result<Self> {
+        Self::with_store(GraphStore::Memory(MemoryDatastore::new_db()))
+    }
+
+    /// Opens the graph on the store `backend` picks.
+    pub fn open(backend: GraphBackend) -> Result<Self> {
+        match backend {
+            GraphBackend::Rocksdb(db_path) => Self::new(db_path),
+            GraphBackend::Memory => Self::new_in_memory(),
+        }
+    }
+
+    fn with_store(db: GraphStore) -> Result<Self> {
         let mut gm = Self { db };
         gm.setup_indexed_properties()?;
         Ok(gm)
     }
-}
 
-impl<T: Datastore> GraphManager<T> {
     fn setup_indexed_properties(&mut self) -> Result<()> {
         self.db.index_property(indradb::Identifier::new("slug")?)?;
         self.db.index_property(indradb::Identifier::new("db_id")?)?;
@@ -111,6 +189,39 @@ impl<T: Datastore> GraphManager<T> {
         }
     }
 
+    /// The vertex of `vertex_type` with this slug, if any. Slugs are only unique within a
+    /// type: the exercise "Squat" and the squat movement pattern share one.
+    fn find_vertex_by_slug(
+        &self,
+        vertex_type: &str,
+        slug: &str,
+    ) -> Result<Option<indradb::Vertex>> {
+        let query = indradb::VertexWithPropertyValueQuery::new(
+            indradb::Identifier::new("slug")?,
+            ijson!(slug),
+        );
+        let t = indradb::Identifier::new(vertex_type)?;
+        match self.db.get(query)?.as_slice() {
+            [QueryOutputValue::Vertices(vertices)] => {
+                Ok(vertices.iter().find(|v| v.t == t).cloned())
+            }
+            _ => Err(anyhow!(
+                "Unexpected output type when querying slug {}",
+                slug
+            )),
+        }
+    }
+
+    /// Id of the `vertex_type` vertex with this slug, creating the vertex if there is none.
+    fn get_or_create_vertex(&self, vertex_type: &str, slug: &str) -> Result<uuid::Uuid> {
+        match self.find_vertex_by_slug(vertex_type, slug)? {
+            Some(vertex) => Ok(vertex.id),
+            None => Ok(self
+                .db
+                .create_vertex_from_type(indradb::Identifier::new(vertex_type)?)?),
+        }
+    }
+
     fn get_vertex_by_name(&self, name: &str) -> Result<indradb::Vertex> {
         let slug = slugify(name);
         self.get_vertex_by_slug(&slug)
@@ -153,10 +264,11 @@ impl<T: Datastore> GraphManager<T> {
         }
     }
 
+    /// The exercise's vertex, adding it to the graph if it is missing.
     pub fn get_exercise_vert(&self, ex: &dbm::Exercise) -> Result<uuid::Uuid> {
-        match self.get_exercise_by_db_id(ex.id) {
-            Ok(exercise_vert) => Ok(exercise_vert.id),
-            Err(_) => self.add_exercise(ex),
+        match self.find_vertex_by_db_id("exercise", ex.id)? {
+            Some(exercise_vert) => Ok(exercise_vert.id),
+            None => self.add_exercise(ex),
         }
     }
 
@@ -239,12 +351,11 @@ impl<T: Datastore> GraphManager<T> {
         Ok(MuscleInvolvement::new(scale_factor, usage_type))
     }
 
+    /// Adds a muscle vertex, or updates the muscle vertex with the same slug if there is one.
     pub fn add_muscle(&self, muscle: dbm::Muscle) -> Result<uuid::Uuid> {
-        let v_id = self
-            .db
-            .create_vertex_from_type(indradb::Identifier::new("muscle")?)?;
-        let q = indradb::SpecificVertexQuery::single(v_id);
         let slug = slugify(&muscle.name);
+        let v_id = self.get_or_create_vertex("muscle", &slug)?;
+        let q = indradb::SpecificVertexQuery::single(v_id);
         self.db
             .set_properties(q.clone(), indradb::Identifier::new("slug")?, &ijson!(slug))?;
         self.db.set_properties(
@@ -260,12 +371,11 @@ impl<T: Datastore> GraphManager<T> {
         Ok(v_id)
     }
 
+    /// Adds a muscle group vertex, reusing the group with the same slug if there is one.
     pub fn add_muscle_group(&self, group_name: &str) -> Result<uuid::Uuid> {
-        let v_id = self
-            .db
-            .create_vertex_from_type(indradb::Identifier::new("muscle_group")?)?;
-        let q = indradb::SpecificVertexQuery::single(v_id);
         let slug = slugify(group_name);
+        let v_id = self.get_or_create_vertex("muscle_group", &slug)?;
+        let q = indradb::SpecificVertexQuery::single(v_id);
         self.db
             .set_properties(q.clone(), indradb::Identifier::new("slug")?, &ijson!(slug))?;
         Ok(v_id)
@@ -320,6 +430,33 @@ impl<T: Datastore> GraphManager<T> {
         Ok(muscles)
     }
 
+    /// Cycles among `member_of` edges (e.g. group A in group B in group A), which
+    /// indicate bad data. Each cycle lists its vertices in edge order.
+    pub fn detect_group_cycles(&self) -> Result<Vec<Vec<uuid::Uuid>>> {
+        let member_of = indradb::Identifier::new("member_of")?;
+        let edges = match self.db.get(indradb::AllEdgeQuery)?.as_slice() {
+            [QueryOutputValue::Edges(edges)] => edges.clone(),
+            _ => vec![],
+        };
+
+        let mut parents: BTreeMap<uuid::Uuid, Vec<uuid::Uuid>> = BTreeMap::new();
+        for edge in edges.iter().filter(|e| e.t == member_of) {
+            parents
+                .entry(edge.outbound_id)
+                .or_default()
+                .push(edge.inbound_id);
+        }
+
+        let mut cycles = Vec::new();
+        let mut finished = HashSet::new();
+        for &start in parents.keys() {
+            if !finished.contains(&start) {
+                collect_cycles(start, &parents, &mut vec![], &mut finished, &mut cycles);
+            }
+        }
+        Ok(cycles)
+    }
+
     fn is_muscle(&self, vertex_id: uuid::Uuid) -> Result<bool> {
         let q = indradb::SpecificVertexQuery::single(vertex_id);
         match self.db.get(q)?.as_slice() {
@@ -353,17 +490,17 @@ impl<T: Datastore> GraphManager<T> {
         Ok(db_ids)
     }
 
+    /// Adds an equipment vertex, or updates the equipment vertex with the same slug if there
+    /// is one.
     pub fn add_equipment(
         &self,
         name: &str,
         description: Option<&str>,
         db_id: i64,
     ) -> Result<uuid::Uuid> {
-        let v_id = self
-            .db
-            .create_vertex_from_type(indradb::Identifier::new("equipment")?)?;
-        let q = indradb::SpecificVertexQuery::single(v_id);
         let slug = slugify(name);
+        let v_id = self.get_or_create_vertex("equipment", &slug)?;
+        let q = indradb::SpecificVertexQuery::single(v_id);
 
         self.db
             .set_properties(q.clone(), indradb::Identifier::new("slug")?, &ijson!(slug))?;
@@ -390,10 +527,10 @@ impl<T: Datastore> GraphManager<T> {
         self.get_vertex_by_name(name)
     }
 
+    /// Adds an exercise vertex, or updates the exercise vertex with the same slug if there is
+    /// one, so a missed [`GraphManager::get_exercise_vert`] lookup never duplicates it.
     pub fn add_exercise(&self, exercise: &dbm::Exercise) -> Result<uuid::Uuid> {
-        let v_id = self
-            .db
-            .create_vertex_from_type(indradb::Identifier::new("exercise")?)?;
+        let v_id = self.get_or_create_vertex("exercise", &exercise.slug)?;
         let q = indradb::SpecificVertexQuery::single(v_id);
 
         self.db.set_properties(
@@ -411,6 +548,11 @@ impl<T: Datastore> GraphManager<T> {
             indradb::Identifier::new("db_id")?,
             &ijson!(exercise.id),
         )?;
+        self.db.set_properties(
+            q.clone(),
+            indradb::Identifier::new("experience_level")?,
+            &ijson!(exercise.experience_level.as_str()),
+        )?;
 
         if let Some(ref desc) = exercise.description {
             self.db.set_properties(
@@ -428,24 +570,81 @@ impl<T: Datastore> GraphManager<T> {
     }
 
     pub fn get_exercise_by_db_id(&self, db_id: i64) -> Result<indradb::Vertex> {
+        self.get_vertex_by_db_id("exercise", db_id)
+    }
+
+    pub fn get_muscle_by_db_id(&self, db_id: i64) -> Result<indradb::Vertex> {
+        self.get_vertex_by_db_id("muscle", db_id)
+    }
+
+    /// The muscle's vertex, adding it to the graph if it is missing.
+    pub fn get_muscle_vert(&self, muscle: &dbm::Muscle) -> Result<uuid::Uuid> {
+        match self.find_vertex_by_db_id("muscle", muscle.id)? {
+            Some(muscle_vert) => Ok(muscle_vert.id),
+            None => self.add_muscle(muscle.clone()),
+        }
+    }
+
+    fn get_vertex_by_db_id(&self, vertex_type: &str, db_id: i64) -> Result<indradb::Vertex> {
+        self.find_vertex_by_db_id(vertex_type, db_id)?
+            .ok_or_else(|| anyhow!("No {} found with db_id {}", vertex_type, db_id))
+    }
+
+    /// The vertex of the given type for `db_id`, or `None` if there is none. Errors are
+    /// failures of the datastore itself.
+    fn find_vertex_by_db_id(
+        &self,
+        vertex_type: &str,
+        db_id: i64,
+    ) -> Result<Option<indradb::Vertex>> {
         let query = indradb::VertexWithPropertyValueQuery::new(
             indradb::Identifier::new("db_id")?,
             ijson!(db_id),
         );
+        let vertex_type = indradb::Identifier::new(vertex_type)?;
 
         match self.db.get(query)?.as_slice() {
-            [QueryOutputValue::Vertices(vertices)] => {
-                for vertex in vertices {
-                    if vertex.t == indradb::Identifier::new("exercise")? {
-                        return Ok(vertex.clone());
-                    }
-                }
-                Err(anyhow!("No exercise found with db_id {}", db_id))
+            [QueryOutputValue::Vertices(vertices)] => Ok(vertices
+                .iter()
+                .find(|vertex| vertex.t == vertex_type)
+                .cloned()),
+            _ => Err(anyhow!("Unexpected output type")),
+        }
+    }
+
+    /// Deletes the exercise vertex for `db_id` along with its edges. Returns `false` if the
+    /// exercise was never added to the graph.
+    pub fn remove_exercise_by_db_id(&self, db_id: i64) -> Result<bool> {
+        match self.get_exercise_by_db_id(db_id) {
+            Ok(vertex) => {
+                self.db
+                    .delete(indradb::SpecificVertexQuery::single(vertex.id))?;
+                Ok(true)
             }
-            _ => Err(anyhow!("No exercise found with db_id {}", db_id)),
+            Err(_) => Ok(false),
         }
     }
 
+    /// Copies a renamed exercise's name and slug onto its vertex. Returns `false` if the
+    /// exercise was never added to the graph.
+    pub fn rename_exercise(&self, exercise: &dbm::Exercise) -> Result<bool> {
+        let Some(vertex) = self.find_vertex_by_db_id("exercise", exercise.id)? else {
+            return Ok(false);
+        };
+        let q = indradb::SpecificVertexQuery::single(vertex.id);
+        self.db.set_properties(
+            q.clone(),
+            indradb::Identifier::new("slug")?,
+            &ijson!(exercise.slug),
+        )?;
+        self.db.set_properties(
+            q.clone(),
+            indradb::Identifier::new("name")?,
+            &ijson!(exercise.name),
+        )?;
+        Ok(true)
+    }
+
     pub fn link_exercise_to_equipment(
         &self,
         exercise_id: uuid::Uuid,
@@ -613,6 +812,35 @@ impl<T: Datastore> GraphManager<T> {
             .collect()
     }
 
+    /// How related two exercises are through the muscles they target, from 0.0 (no shared
+    /// muscle) to 1.0 (same muscles at the same weights): the weighted Jaccard index of their
+    /// `effective_weight`s, i.e. the summed minimum over the summed maximum per muscle.
+    /// Fails if either exercise is not in the graph.
+    pub fn exercise_relatedness(&self, a_db_id: i64, b_db_id: i64) -> Result<f64> {
+        let weights = |db_id: i64| -> Result<HashMap<uuid::Uuid, f64>> {
+            let exercise = self.get_exercise_by_db_id(db_id)?;
+            let mut weights: HashMap<uuid::Uuid, f64> = HashMap::new();
+            for (muscle, involvement) in self.get_muscles_for_exercise(exercise.id)? {
+                *weights.entry(muscle).or_default() += involvement.effective_weight();
+            }
+            Ok(weights)
+        };
+        let (a, b) = (weights(a_db_id)?, weights(b_db_id)?);
+
+        let muscles: HashSet<&uuid::Uuid> = a.keys().chain(b.keys()).collect();
+        let (mut shared, mut total) = (0.0, 0.0);
+        for muscle in muscles {
+            let a_weight = a.get(muscle).copied().unwrap_or(0.0);
+            let b_weight = b.get(muscle).copied().unwrap_or(0.0);
+            shared += a_weight.min(b_weight);
+            total += a_weight.max(b_weight);
+        }
+        if total <= 0.0 {
+            return Ok(0.0);
+        }
+        Ok(shared / total)
+    }
+
     /// Gets muscles for an exercise, returning SQL db_ids instead of graph UUIDs.
     /// This is useful for matching against target muscle distributions in workout planning.
     pub fn get_muscles_with_db_ids_for_exercise(
@@ -676,14 +904,12 @@ impl<T: Datastore> GraphManager<T> {
             .collect()
     }
 
-    /// Adds a movement pattern vertex to the graph.
-    /// Returns the UUID of the created vertex.
+    /// Adds a movement pattern vertex to the graph, reusing the existing one for the pattern.
+    /// Returns the UUID of the vertex.
     pub fn add_movement_pattern(&self, pattern: ExercisePatternType) -> Result<uuid::Uuid> {
-        let v_id = self
-            .db
-            .create_vertex_from_type(indradb::Identifier::new("movement_pattern")?)?;
-        let q = indradb::SpecificVertexQuery::single(v_id);
         let slug = pattern.as_str();
+        let v_id = self.get_or_create_vertex("movement_pattern", slug)?;
+        let q = indradb::SpecificVertexQuery::single(v_id);
 
         self.db
             .set_properties(q.clone(), indradb::Identifier::new("slug")?, &ijson!(slug))?;
@@ -700,7 +926,8 @@ impl<T: Datastore> GraphManager<T> {
     /// Returns the vertex if found.
     pub fn get_movement_pattern(&self, pattern: ExercisePatternType) -> Result<indradb::Vertex> {
         let slug = pattern.as_str();
-        self.get_vertex_by_slug(slug)
+        self.find_vertex_by_slug("movement_pattern", slug)?
+            .ok_or_else(|| anyhow!("No movement pattern vertex for {}", slug))
     }
 
     /// Gets or creates a movement pattern vertex.
@@ -741,6 +968,18 @@ impl<T: Datastore> GraphManager<T> {
         Ok(())
     }
 
+    /// Whether the exercise is linked to a movement pattern, Unknown included.
+    pub fn has_movement_pattern(&self, exercise_id: uuid::Uuid) -> Result<bool> {
+        let q = indradb::SpecificVertexQuery::single(exercise_id)
+            .outbound()?
+            .t(indradb::Identifier::new("has_movement_pattern")?);
+
+        match self.db.get(q)?.as_slice() {
+            [QueryOutputValue::Edges(edges)] => Ok(!edges.is_empty()),
+            _ => Ok(false),
+        }
+    }
+
     /// Gets the movement pattern for an exercise.
     /// Returns Unknown if no pattern is found.
     pub fn get_movement_pattern_for_exercise(
@@ -818,4 +1057,326 @@ impl<T: Datastore> GraphManager<T> {
             _ => Ok(vec![]),
         }
     }
+
+    /// Renders the whole graph as GraphViz DOT for debugging. Vertices are labelled by
+    /// their `name`, `slug` or `pattern_type` property and edges by their type; the
+    /// reverse edges kept for inbound lookups are skipped so each link is drawn once.
+    pub fn export_dot(&self) -> Result<String> {
+        const REVERSE_EDGE_TYPES: &[&str] =
+            &["used_by_exercise", "worked_by", "pattern_of_exercise"];
+
+        let mut vertices = match self.db.get(indradb::AllVertexQuery)?.as_slice() {
+            [QueryOutputValue::Vertices(vertices)] => vertices.clone(),
+            _ => vec![],
+        };
+
+        let mut labels: HashMap<uuid::Uuid, String> = HashMap::new();
+        if let [QueryOutputValue::VertexProperties(vert_props)] = self
+            .db
+            .get(indradb::AllVertexQuery.properties()?)?
+            .as_slice()
+        {
+            for vp in vert_props {
+                let prop = |name: &str| {
+                    vp.props
+                        .iter()
+                        .find(|p| p.name.as_str() == name)
+                        .and_then(|p| p.value.as_str().map(str::to_string))
+                };
+                if let Some(label) = prop("name")
+                    .or_else(|| prop("slug"))
+                    .or_else(|| prop("pattern_type"))
+                {
+                    labels.insert(vp.vertex.id, label);
+                }
+            }
+        }
+
+        let mut edges = match self.db.get(indradb::AllEdgeQuery)?.as_slice() {
+            [QueryOutputValue::Edges(edges)] => edges.clone(),
+            _ => vec![],
+        };
+        edges.retain(|e| !REVERSE_EDGE_TYPES.contains(&e.t.as_str()));
+
+        let label_of = |id: &uuid::Uuid, t: &indradb::Identifier| {
+            labels
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| t.as_str().to_string())
+        };
+        vertices.sort_by_key(|v| (v.t.as_str().to_string(), label_of(&v.id, &v.t)));
+        edges.sort_by_key(|e| (e.outbound_id, e.t.as_str().to_string(), e.inbound_id));
+
+        let mut dot = String::from("digraph recommendation {\n");
+        for vertex in &vertices {
+            let shape = match vertex.t.as_str() {
+                "exercise" => "box",
+                "equipment" => "hexagon",
+                "movement_pattern" => "diamond",
+                "muscle_group" => "doubleoctagon",
+                _ => "ellipse",
+            };
+            writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\", shape={}];",
+                vertex.id,
+                escape_dot(&label_of(&vertex.id, &vertex.t)),
+                shape
+            )?;
+        }
+        for edge in &edges {
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                edge.outbound_id,
+                edge.inbound_id,
+                escape_dot(edge.t.as_str())
+            )?;
+        }
+        dot.push_str("}\n");
+        Ok(dot)
+    }
+}
+
+/// Depth-first walk along `parents`, recording a cycle whenever an edge leads back to a
+/// vertex on the current `path`.
+fn collect_cycles(
+    vertex: uuid::Uuid,
+    parents: &BTreeMap<uuid::Uuid, Vec<uuid::Uuid>>,
+    path: &mut Vec<uuid::Uuid>,
+    finished: &mut HashSet<uuid::Uuid>,
+    cycles: &mut Vec<Vec<uuid::Uuid>>,
+) {
+    path.push(vertex);
+    for &parent in parents.get(&vertex).into_iter().flatten() {
+        if let Some(start) = path.iter().position(|v| *v == parent) {
+            cycles.push(path[start..].to_vec());
+        } else if !finished.contains(&parent) {
+            collect_cycles(parent, parents, path, finished, cycles);
+        }
+    }
+    path.pop();
+    finished.insert(vertex);
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::ExperienceLevel;
+
+    #[test]
+    fn test_export_dot_lists_linked_vertices() {
+        let graph = GraphManager::new_in_memory().unwrap();
+        let chest = graph
+            .add_muscle(dbm::Muscle {
+                id: 1,
+                name: "Chest".to_string(),
+                created_at: 0,
+                updated_at: 0,
+            })
+            .unwrap();
+        let bench = graph
+            .add_exercise(&dbm::Exercise {
+                id: 1,
+                slug: "bench-press".to_string(),
+                name: "Bench \"Flat\" Press".to_string(),
+                description: None,
+                is_favorite: false,
+                experience_level: ExperienceLevel::default_for_exercise("Bench Press"),
+                created_at: 0,
+                updated_at: 0,
+            })
+            .unwrap();
+        graph
+            .link_exercise_to_muscle(
+                bench,
+                chest,
+                MuscleInvolvement::new(1.0, MuscleUsageType::Primary),
+            )
+            .unwrap();
+        graph
+            .link_exercise_to_movement_pattern(bench, ExercisePatternType::HorizontalPush)
+            .unwrap();
+
+        let dot = graph.export_dot().unwrap();
+        assert!(dot.starts_with("digraph recommendation {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains(&format!(
+            "\"{}\" [label=\"Bench \\\"Flat\\\" Press\", shape=box];",
+            bench
+        )));
+        assert!(dot.contains(&format!("\"{}\" [label=\"chest\", shape=ellipse];", chest)));
+        assert!(dot.contains(&format!(
+            "\"{}\" -> \"{}\" [label=\"targets_muscle\"];",
+            bench, chest
+        )));
+        assert!(dot.contains("has_movement_pattern"));
+        assert!(!dot.contains("worked_by"));
+    }
+
+    #[test]
+    fn test_detect_group_cycles() {
+        let graph = GraphManager::new_in_memory().unwrap();
+        let chest = graph
+            .add_muscle(dbm::Muscle {
+                id: 1,
+                name: "Chest".to_string(),
+                created_at: 0,
+                updated_at: 0,
+            })
+            .unwrap();
+        let push = graph.add_muscle_group("Push").unwrap();
+        let upper = graph.add_muscle_group("Upper Body").unwrap();
+        graph.link_muscle_to_group(push, chest).unwrap();
+        graph.link_muscle_to_group(upper, push).unwrap();
+        assert!(graph.detect_group_cycles().unwrap().is_empty());
+
+        graph.link_muscle_to_group(push, upper).unwrap();
+        let cycles = graph.detect_group_cycles().unwrap();
+        assert_eq!(cycles.len(), 1);
+        let mut cycle = cycles[0].clone();
+        cycle.sort();
+        let mut expected = vec![push, upper];
+        expected.sort();
+        assert_eq!(cycle, expected);
+    }
+
+    fn vertex_count(graph: &GraphManager, vertex_type: &str) -> usize {
+        let t = indradb::Identifier::new(vertex_type).unwrap();
+        match graph.db.get(indradb::AllVertexQuery).unwrap().as_slice() {
+            [QueryOutputValue::Vertices(vertices)] => vertices.iter().filter(|v| v.t == t).count(),
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_adding_same_slug_twice_reuses_vertex() {
+        let graph = GraphManager::new_in_memory().unwrap();
+        let squat = dbm::Exercise {
+            id: 1,
+            slug: "squat".to_string(),
+            name: "Squat".to_string(),
+            description: None,
+            is_favorite: false,
+            experience_level: ExperienceLevel::default_for_exercise("Squat"),
+            created_at: 0,
+            updated_at: 0,
+        };
+        let first = graph.add_exercise(&squat).unwrap();
+        let recreated = dbm::Exercise { id: 2, ..squat };
+        assert_eq!(graph.add_exercise(&recreated).unwrap(), first);
+        assert_eq!(vertex_count(&graph, "exercise"), 1);
+        assert_eq!(graph.get_exercise_by_db_id(2).unwrap().id, first);
+
+        let quads = dbm::Muscle {
+            id: 1,
+            name: "Quadriceps".to_string(),
+            created_at: 0,
+            updated_at: 0,
+        };
+        let muscle = graph.add_muscle(quads.clone()).unwrap();
+        assert_eq!(graph.add_muscle(quads).unwrap(), muscle);
+        assert_eq!(vertex_count(&graph, "muscle"), 1);
+
+        let rack = graph.add_equipment("Squat Rack", None, 1).unwrap();
+        assert_eq!(
+            graph
+                .add_equipment("Squat Rack", Some("Power rack"), 1)
+                .unwrap(),
+            rack
+        );
+        assert_eq!(vertex_count(&graph, "equipment"), 1);
+
+        // The squat pattern shares the exercise's slug but is a separate vertex.
+        graph
+            .link_exercise_to_movement_pattern(first, ExercisePatternType::Squat)
+            .unwrap();
+        let pattern = graph
+            .get_movement_pattern(ExercisePatternType::Squat)
+            .unwrap();
+        assert_ne!(pattern.id, first);
+        assert_eq!(
+            graph
+                .add_movement_pattern(ExercisePatternType::Squat)
+                .unwrap(),
+            pattern.id
+        );
+        assert_eq!(vertex_count(&graph, "movement_pattern"), 1);
+        assert_eq!(
+            graph.get_movement_pattern_for_exercise(first).unwrap(),
+            ExercisePatternType::Squat
+        );
+    }
+
+    fn exercise(id: i64, name: &str) -> dbm::Exercise {
+        dbm::Exercise {
+            id,
+            slug: slugify(name),
+            name: name.to_string(),
+            description: None,
+            is_favorite: false,
+            experience_level: ExperienceLevel::default_for_exercise(name),
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_exercise_relatedness_weighs_shared_muscles() {
+        let graph = GraphManager::new_in_memory().unwrap();
+        let muscle = |id: i64, name: &str| {
+            graph
+                .add_muscle(dbm::Muscle {
+                    id,
+                    name: name.to_string(),
+                    created_at: 0,
+                    updated_at: 0,
+                })
+                .unwrap()
+        };
+        let (chest, triceps, lats) = (muscle(1, "Chest"), muscle(2, "Triceps"), muscle(3, "Lats"));
+        let primary = MuscleInvolvement::new(1.0, MuscleUsageType::Primary);
+        let synergist = MuscleInvolvement::new(1.0, MuscleUsageType::Synergist);
+
+        let link = |id: i64, name: &str, muscles: &[(uuid::Uuid, &MuscleInvolvement)]| {
+            let vertex = graph.add_exercise(&exercise(id, name)).unwrap();
+            for (muscle, involvement) in muscles {
+                graph
+                    .link_exercise_to_muscle(vertex, *muscle, (*involvement).clone())
+                    .unwrap();
+            }
+        };
+        link(
+            1,
+            "Bench Press",
+            &[(chest, &primary), (triceps, &synergist)],
+        );
+        link(
+            2,
+            "Dumbbell Press",
+            &[(chest, &primary), (triceps, &synergist)],
+        );
+        link(
+            3,
+            "Close Grip Bench",
+            &[(chest, &synergist), (triceps, &primary)],
+        );
+        link(4, "Pull-up", &[(lats, &primary)]);
+        link(5, "Plank", &[]);
+
+        assert_eq!(graph.exercise_relatedness(1, 2).unwrap(), 1.0);
+        // Shared: min(1, 0.5) + min(0.5, 1) = 1; total: max + max = 2.
+        assert_eq!(graph.exercise_relatedness(1, 3).unwrap(), 0.5);
+        assert_eq!(
+            graph.exercise_relatedness(1, 3).unwrap(),
+            graph.exercise_relatedness(3, 1).unwrap()
+        );
+        assert_eq!(graph.exercise_relatedness(1, 4).unwrap(), 0.0);
+        assert_eq!(graph.exercise_relatedness(5, 5).unwrap(), 0.0);
+        assert!(graph.exercise_relatedness(1, 99).is_err());
+    }
 }
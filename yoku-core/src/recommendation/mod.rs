@@ -1,7 +1,8 @@
 pub mod engine;
 pub mod graph;
 pub mod models;
+pub mod progression;
 pub mod seed;
 
 pub use self::engine::RecommendationEngine;
-pub use self::graph::GraphManager;
+pub use self::graph::{GraphBackend, GraphManager};
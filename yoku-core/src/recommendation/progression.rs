@@ -0,0 +1,204 @@
+use crate::db::operations::{get_exercise_entries, get_session_best_estimated_1rms};
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+/// How many of the latest sets are searched for an RPE to base the decision on.
+const RECENT_SETS: usize = 3;
+/// Smallest plate jump; increases and deloads land on multiples of it.
+const WEIGHT_STEP: f64 = 2.5;
+const EASY_RPE: f64 = 7.0;
+const GRINDING_RPE: f64 = 9.5;
+const DELOAD_FACTOR: f64 = 0.95;
+
+/// Load for the next set of an exercise, from the weight and RPE of its latest sets: add
+/// 2.5kg after an RPE of 7 or less, hold at RPE 8 to 9, and drop about 5% at 9.5 or more.
+/// Without a recent RPE the last weight is held. `None` when the exercise has no sets.
+pub async fn suggest_next_weight(pool: &SqlitePool, exercise_id: i64) -> Result<Option<f64>> {
+    let sets = get_exercise_entries(pool, exercise_id, Some(RECENT_SETS as i64)).await?;
+    let Some(last) = sets.last() else {
+        return Ok(None);
+    };
+    let rpe = sets.iter().rev().take(RECENT_SETS).find_map(|s| s.rpe);
+    Ok(Some(next_weight(last.weight, rpe)))
+}
+
+/// Whether an exercise has stalled: across its last `window` workouts, no session's best
+/// Epley estimated 1RM beats the first of them. `false` until it has been logged in at
+/// least `window` workouts, or when `window` is below 2.
+pub async fn detect_stall(pool: &SqlitePool, exercise_id: i64, window: usize) -> Result<bool> {
+    if window < 2 {
+        return Ok(false);
+    }
+    let session_bests =
+        get_session_best_estimated_1rms(pool, exercise_id, window as i64 + 1).await?;
+    Ok(is_stalled(&session_bests, window))
+}
+
+fn is_stalled(session_bests: &[(i64, f64)], window: usize) -> bool {
+    if session_bests.len() < window {
+        return false;
+    }
+    let recent = &session_bests[session_bests.len() - window..];
+    let baseline = recent[0].1;
+    recent[1..].iter().all(|(_, best)| *best <= baseline)
+}
+
+fn next_weight(weight: f64, rpe: Option<f64>) -> f64 {
+    match rpe {
+        Some(rpe) if rpe <= EASY_RPE => weight + WEIGHT_STEP,
+        Some(rpe) if rpe >= GRINDING_RPE => {
+            let deload = (weight * DELOAD_FACTOR / WEIGHT_STEP).floor() * WEIGHT_STEP;
+            deload.min(weight - WEIGHT_STEP).max(0.0)
+        }
+        _ => weight,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::init_database;
+    use crate::db::operations::{
+        add_workout_set, create_request_string, create_workout_session, get_or_create_exercise,
+        get_or_create_user,
+    };
+
+    async fn pool_with_sets(sets: &[(f64, Option<f64>)]) -> (SqlitePool, i64) {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        init_database(&pool).await.unwrap();
+        let exercise = get_or_create_exercise(&pool, "Bench Press").await.unwrap();
+        let session = create_workout_session(&pool, None, None, None, None, None)
+            .await
+            .unwrap();
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "bench".to_string())
+            .await
+            .unwrap();
+        for (weight, rpe) in sets {
+            add_workout_set(
+                &pool,
+                &session.id,
+                &exercise.id,
+                &request.id,
+                weight,
+                &5,
+                *rpe,
+            )
+            .await
+            .unwrap();
+        }
+        (pool, exercise.id)
+    }
+
+    /// One workout per entry, each with a single set of 5 reps at that weight.
+    async fn pool_with_sessions(weights: &[f64]) -> (SqlitePool, i64) {
+        let (pool, exercise_id) = pool_with_sets(&[]).await;
+        let user = get_or_create_user(&pool, "testuser").await.unwrap();
+        let request = create_request_string(&pool, user.id, "bench".to_string())
+            .await
+            .unwrap();
+        for weight in weights {
+            let session = create_workout_session(&pool, None, None, None, None, None)
+                .await
+                .unwrap();
+            add_workout_set(
+                &pool,
+                &session.id,
+                &exercise_id,
+                &request.id,
+                weight,
+                &5,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        (pool, exercise_id)
+    }
+
+    #[tokio::test]
+    async fn test_no_history_suggests_nothing() {
+        let (pool, exercise_id) = pool_with_sets(&[]).await;
+        assert_eq!(suggest_next_weight(&pool, exercise_id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_easy_set_adds_weight() {
+        let (pool, exercise_id) = pool_with_sets(&[(90.0, Some(8.0)), (100.0, Some(7.0))]).await;
+        assert_eq!(
+            suggest_next_weight(&pool, exercise_id).await.unwrap(),
+            Some(102.5)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hard_set_holds_weight() {
+        let (pool, exercise_id) = pool_with_sets(&[(100.0, Some(8.5))]).await;
+        assert_eq!(
+            suggest_next_weight(&pool, exercise_id).await.unwrap(),
+            Some(100.0)
+        );
+        let (pool, exercise_id) = pool_with_sets(&[(100.0, Some(9.0)), (100.0, None)]).await;
+        assert_eq!(
+            suggest_next_weight(&pool, exercise_id).await.unwrap(),
+            Some(100.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_grinding_set_deloads() {
+        let (pool, exercise_id) = pool_with_sets(&[(100.0, Some(10.0))]).await;
+        assert_eq!(
+            suggest_next_weight(&pool, exercise_id).await.unwrap(),
+            Some(95.0)
+        );
+        assert_eq!(next_weight(20.0, Some(9.5)), 17.5);
+        assert_eq!(next_weight(2.5, Some(9.5)), 0.0);
+    }
+
+    #[test]
+    fn test_missing_rpe_holds_weight() {
+        assert_eq!(next_weight(60.0, None), 60.0);
+        assert_eq!(next_weight(60.0, Some(7.5)), 60.0);
+    }
+
+    #[tokio::test]
+    async fn test_progressing_exercise_is_not_stalled() {
+        let (pool, exercise_id) = pool_with_sessions(&[100.0, 90.0, 95.0, 100.0]).await;
+        assert!(!detect_stall(&pool, exercise_id, 3).await.unwrap());
+        assert!(!detect_stall(&pool, exercise_id, 5).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_flat_or_declining_exercise_is_stalled() {
+        let (pool, exercise_id) = pool_with_sessions(&[90.0, 100.0, 100.0, 97.5]).await;
+        assert!(detect_stall(&pool, exercise_id, 3).await.unwrap());
+        assert!(!detect_stall(&pool, exercise_id, 4).await.unwrap());
+        assert!(!detect_stall(&pool, exercise_id, 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_long_workout_does_not_hide_earlier_ones() {
+        let (pool, exercise_id) = pool_with_sessions(&[100.0, 100.0]).await;
+        let (session_id, request_id): (i64, i64) = sqlx::query_as(
+            "SELECT session_id, request_string_id FROM workout_sets ORDER BY id DESC LIMIT 1",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        for _ in 0..100 {
+            add_workout_set(
+                &pool,
+                &session_id,
+                &exercise_id,
+                &request_id,
+                &60.0,
+                &5,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        assert!(detect_stall(&pool, exercise_id, 2).await.unwrap());
+    }
+}
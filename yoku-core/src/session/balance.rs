@@ -0,0 +1,130 @@
+use crate::db::models::WorkoutSet;
+use crate::db::operations::{get_muscle, get_sets_for_session};
+use crate::session::Session;
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Volume a set contributes to muscle balance. Sets without load count their reps, and
+/// timed or cardio sets count once, so bodyweight work still registers.
+fn set_volume(set: &WorkoutSet) -> f64 {
+    if set.weight > 0.0 && set.reps > 0 {
+        set.weight * set.reps as f64
+    } else if set.reps > 0 {
+        set.reps as f64
+    } else {
+        1.0
+    }
+}
+
+impl Session {
+    /// Splits a workout's volume across muscles using the recommendation graph's
+    /// exercise→muscle links, weighted by each link's effective weight. Returns each
+    /// muscle's share of the total as a percentage; exercises without links are ignored.
+    pub async fn analyze_muscle_balance(&self, session_id: i64) -> Result<HashMap<String, f64>> {
+        let sets = get_sets_for_session(&self.db_pool, session_id, None, None).await?;
+
+        let mut involvements = HashMap::new();
+        let mut contributions: HashMap<i64, f64> = HashMap::new();
+        for set in &sets {
+            if !involvements.contains_key(&set.exercise_id) {
+                let muscles = self
+                    .recommendation_engine
+                    .muscles_for_exercise(set.exercise_id)?;
+                involvements.insert(set.exercise_id, muscles);
+            }
+            let volume = set_volume(set);
+            for (muscle_id, involvement) in &involvements[&set.exercise_id] {
+                *contributions.entry(*muscle_id).or_default() +=
+                    volume * involvement.effective_weight();
+            }
+        }
+
+        let total: f64 = contributions.values().sum();
+        let mut balance = HashMap::with_capacity(contributions.len());
+        if total <= 0.0 {
+            return Ok(balance);
+        }
+        for (muscle_id, contribution) in contributions {
+            let muscle = get_muscle(&self.db_pool, muscle_id).await?;
+            *balance.entry(muscle.name).or_default() += contribution / total * 100.0;
+        }
+        Ok(balance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::operations::get_or_create_muscle;
+    use crate::llm::{LlmInterface, ParsedSet};
+    use crate::recommendation::graph::{MuscleInvolvement, MuscleUsageType};
+
+    async fn link(session: &Session, exercise: &str, muscles: &[(&str, MuscleInvolvement)]) {
+        let graph = session.recommendation_engine.graph_manager();
+        let exercise = session.get_or_create_exercise(exercise).await.unwrap();
+        let exercise_vert = graph.get_exercise_vert(&exercise).unwrap();
+        for (name, involvement) in muscles {
+            let muscle = get_or_create_muscle(&session.db_pool, name).await.unwrap();
+            let muscle_vert = match graph.get_muscle_by_name(name) {
+                Ok(vertex) => vertex.id,
+                Err(_) => graph.add_muscle(muscle).unwrap(),
+            };
+            graph
+                .link_exercise_to_muscle(exercise_vert, muscle_vert, involvement.clone())
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bench_contributes_mostly_to_chest() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        link(
+            &session,
+            "Bench Press",
+            &[
+                (
+                    "Chest",
+                    MuscleInvolvement::new(1.0, MuscleUsageType::Primary),
+                ),
+                (
+                    "Triceps",
+                    MuscleInvolvement::new(0.6, MuscleUsageType::Synergist),
+                ),
+                (
+                    "Front Delts",
+                    MuscleInvolvement::new(0.5, MuscleUsageType::Synergist),
+                ),
+            ],
+        )
+        .await;
+
+        session.new_workout().await.unwrap();
+        let workout_id = session.get_workout_id().await.unwrap();
+        session
+            .add_set_from_parsed(&ParsedSet {
+                exercise: "Bench Press".to_string(),
+                weight: Some(100.0),
+                unit: None,
+                reps: Some(5),
+                rpe: None,
+                set_count: Some(3),
+                duration_seconds: None,
+                distance_meters: None,
+                tags: vec![],
+                aoi: None,
+                original_string: "3x5 bench 100kg".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let balance = session.analyze_muscle_balance(workout_id).await.unwrap();
+        assert_eq!(balance.len(), 3);
+        assert!((balance.values().sum::<f64>() - 100.0).abs() < 1e-9);
+        let chest = balance["Chest"];
+        assert!(chest > 50.0);
+        assert!(balance.values().all(|share| *share <= chest));
+        assert!((balance["Triceps"] - 0.3 / 1.55 * 100.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::db::models::BodyweightEntry;
+use crate::db::operations::{
+    add_bodyweight, delete_bodyweight_entry, get_bodyweight_series, get_or_create_user,
+};
+use crate::session::Session;
+use anyhow::Result;
+
+/// Bodyweight is logged against the same local user that request strings are logged under.
+const BODYWEIGHT_OWNER: &str = "cli";
+
+impl Session {
+    /// Logs a bodyweight measurement, timestamped now unless `recorded_at` is given.
+    pub async fn add_bodyweight(
+        &self,
+        weight: f64,
+        recorded_at: Option<i64>,
+    ) -> Result<BodyweightEntry> {
+        let user = get_or_create_user(&self.db_pool, BODYWEIGHT_OWNER).await?;
+        let recorded_at = recorded_at.unwrap_or_else(|| chrono::Utc::now().timestamp());
+        add_bodyweight(&self.db_pool, user.id, weight, recorded_at).await
+    }
+
+    pub async fn get_bodyweight_series(&self, since: Option<i64>) -> Result<Vec<BodyweightEntry>> {
+        let user = get_or_create_user(&self.db_pool, BODYWEIGHT_OWNER).await?;
+        get_bodyweight_series(&self.db_pool, user.id, since).await
+    }
+
+    pub async fn delete_bodyweight_entry(&self, entry_id: i64) -> Result<u64> {
+        delete_bodyweight_entry(&self.db_pool, entry_id).await
+    }
+}
@@ -1,12 +1,21 @@
-use crate::db::models::{UpdateWorkoutSet, WorkoutSet};
-use crate::db::operations::{get_or_create_exercise, get_workout_session};
-use crate::llm::{Command, ParsedSet, PromptBuilder, PromptContext, classify_commands};
+use crate::db::models::{Exercise, UpdateWorkoutSet, WorkoutSet};
+use crate::db::operations::{
+    delete_workout_set_tx, get_workout_set_tx, next_superset_group_tx,
+    set_workout_set_superset_group_tx, update_workout_set_tx, update_workout_summary_tx,
+};
+use crate::llm::{
+    Command, LlmInterface, ParsedSet, PromptBuilder, PromptContext, classify_commands,
+};
 use crate::session::Session;
+use crate::session::context::resolve_set_reference;
+use crate::session::sets::{
+    AddedSets, ExerciseResolver, add_parsed_set_tx, removed_set_modification,
+};
 use crate::uniffi_interface::modifications::Modification;
 use anyhow::Result;
-use futures::future::try_join_all;
 use log::warn;
-use std::collections::HashMap;
+use sqlx::{Sqlite, Transaction};
+use std::collections::{BTreeMap, HashMap};
 
 impl Session {
     pub async fn process_user_input(
@@ -15,61 +24,145 @@ impl Session {
         selected_set_backend_id: Option<i64>,
         visible_set_backend_ids: Vec<i64>,
     ) -> Result<Vec<Modification>> {
-        let workout_id = self.get_workout_id().await;
-        if workout_id.is_none() {
-            return Err(anyhow::anyhow!("No active workout session"));
-        }
-        let workout_id = workout_id.unwrap();
-
-        let current_summary = get_workout_session(&self.db_pool, workout_id)
-            .await
-            .ok()
-            .and_then(|w| w.summary);
-
-        let exercises = self.get_all_exercises().await?;
-        let exercise_map: HashMap<i64, String> =
-            exercises.iter().map(|e| (e.id, e.name.clone())).collect();
-        let known_exercises: Vec<String> = exercises.iter().map(|e| e.name.clone()).collect();
+        self.process_user_input_with(
+            self.llm_backend.as_ref(),
+            input,
+            selected_set_backend_id,
+            visible_set_backend_ids,
+        )
+        .await
+    }
 
-        let workout_context = self.build_workout_context_string().await?;
+    /// Like [`Session::process_user_input`], but classifies the input with `llm` instead of
+    /// the session's own backend. The input's commands run in one transaction, so if any of
+    /// them fails nothing it asked for is kept.
+    pub async fn process_user_input_with(
+        &self,
+        llm: &LlmInterface,
+        input: &str,
+        selected_set_backend_id: Option<i64>,
+        visible_set_backend_ids: Vec<i64>,
+    ) -> Result<Vec<Modification>> {
+        let context = self.build_workout_context().await?;
+        let exercise_map = context.exercise_map();
+        let known_exercises: Vec<String> =
+            context.exercises.iter().map(|e| e.name.clone()).collect();
 
         let ctx = PromptContext {
             known_exercises,
             selected_set_backend_id,
             visible_set_backend_ids,
-            current_summary,
+            current_summary: context.workout.summary.clone(),
             ..Default::default()
         };
         let builder = PromptBuilder::new(ctx);
 
-        let commands =
-            classify_commands(self.llm_backend.as_ref(), &builder, input, &workout_context).await?;
+        let commands = classify_commands(llm, &builder, input, &context.to_prompt_string()).await?;
 
         if commands.is_empty() {
             warn!("LLM returned empty command array for input: {}", input);
             return Ok(vec![]);
         }
 
-        let sets = self.get_all_sets().await?;
+        let batch = CommandBatch {
+            session_id: context.workout.id,
+            sets: context.sets,
+            exercise_map,
+            resolver: self.exercise_resolver().await?,
+        };
+        let outcomes = self
+            .with_transaction(move |tx| {
+                Box::pin(async move { batch.execute_tx(tx, commands).await })
+            })
+            .await?;
 
-        let modification_futures: Vec<_> = commands
-            .into_iter()
-            .map(|command| self.execute_command(command, &sets, &exercise_map))
+        let mut all_modifications = Vec::with_capacity(outcomes.len());
+        for outcome in outcomes {
+            match outcome {
+                CommandOutcome::Added(added) => {
+                    all_modifications.push(self.added_sets_modification(added).await?);
+                }
+                CommandOutcome::Modified { set, exercise } => {
+                    if let Some(exercise) = &exercise {
+                        self.track_exercise(exercise).await?;
+                    }
+                    all_modifications.push(self.updated_set_modification(&set).await?);
+                }
+                CommandOutcome::Removed {
+                    set_id,
+                    exercise_id,
+                } => all_modifications.push(removed_set_modification(set_id, exercise_id)),
+                CommandOutcome::Unreported => {}
+            }
+        }
+
+        self.notify_modifications(&all_modifications).await;
+        Ok(all_modifications)
+    }
+}
+
+/// What one command wrote inside its input's transaction, reported once it has committed.
+enum CommandOutcome {
+    Added(AddedSets),
+    /// An edited set, with the exercise the command named, if any.
+    Modified {
+        set: WorkoutSet,
+        exercise: Option<Exercise>,
+    },
+    Removed {
+        set_id: i64,
+        exercise_id: Option<i64>,
+    },
+    /// A change with no modification to report, such as a new summary.
+    Unreported,
+}
+
+/// One input's commands with the session state they need, taken before the transaction
+/// they run in.
+struct CommandBatch {
+    session_id: i64,
+    /// The workout's sets when the input was classified, which set descriptions refer to.
+    sets: Vec<WorkoutSet>,
+    exercise_map: HashMap<i64, String>,
+    resolver: ExerciseResolver,
+}
+
+impl CommandBatch {
+    /// Runs the commands in order and groups any labelled supersets. Stops at the first
+    /// failure, leaving the caller to roll back whatever the earlier commands wrote.
+    async fn execute_tx(
+        mut self,
+        tx: &mut Transaction<'_, Sqlite>,
+        commands: Vec<Command>,
+    ) -> Result<Vec<CommandOutcome>> {
+        let superset_labels: Vec<Option<i64>> = commands
+            .iter()
+            .map(|command| match command {
+                Command::AddSet { superset_group, .. } => *superset_group,
+                _ => None,
+            })
             .collect();
 
-        let modification_results = try_join_all(modification_futures).await?;
-        let all_modifications: Vec<Modification> =
-            modification_results.into_iter().flatten().collect();
+        let mut outcomes = Vec::with_capacity(commands.len());
+        for command in commands {
+            outcomes.push(self.execute_command_tx(tx, command).await?);
+        }
+        group_labelled_supersets_tx(tx, &superset_labels, &mut outcomes).await?;
+        Ok(outcomes)
+    }
 
-        Ok(all_modifications)
+    fn resolve_set_id(&self, set_id: Option<i64>, description: Option<String>) -> Option<i64> {
+        set_id.or_else(|| {
+            description
+                .and_then(|desc| resolve_set_reference(&desc, &self.sets, &self.exercise_map))
+        })
     }
 
-    async fn execute_command(
-        &self,
+    async fn execute_command_tx(
+        &mut self,
+        tx: &mut Transaction<'_, Sqlite>,
         command: Command,
-        sets: &[WorkoutSet],
-        exercise_map: &HashMap<i64, String>,
-    ) -> Result<Vec<Modification>> {
+    ) -> Result<CommandOutcome> {
         match command {
             Command::AddSet {
                 exercise,
@@ -77,41 +170,46 @@ impl Session {
                 reps,
                 rpe,
                 set_count,
-                tags: _,
-                aoi: _,
+                duration_seconds,
+                distance_meters,
+                unit,
+                tags,
+                aoi,
                 original_string,
+                superset_group: _,
             } => {
                 let parsed = ParsedSet {
                     exercise,
                     weight: weight.map(|w| w as f32),
+                    unit,
                     reps: reps.map(|r| r as i32),
                     rpe: rpe.map(|r| r as f32),
                     set_count: set_count.map(|c| c as i32),
-                    tags: vec![],
-                    aoi: None,
+                    duration_seconds,
+                    distance_meters,
+                    tags,
+                    aoi,
                     original_string,
                 };
-                self.add_set_from_parsed_with_modifications(&parsed).await
+                let added =
+                    add_parsed_set_tx(tx, &mut self.resolver, self.session_id, &parsed).await?;
+                Ok(CommandOutcome::Added(added))
             }
             Command::RemoveSet {
                 set_id,
                 description,
             } => {
-                let resolved_id = if let Some(id) = set_id {
-                    Some(id)
-                } else if let Some(desc) = description {
-                    self.resolve_set_id_from_description(&desc, sets, exercise_map)
-                } else {
-                    None
-                };
-
-                if let Some(id) = resolved_id {
-                    self.delete_set_with_modifications(id).await
-                } else {
-                    Err(anyhow::anyhow!(
+                let Some(id) = self.resolve_set_id(set_id, description) else {
+                    return Err(anyhow::anyhow!(
                         "Could not resolve set_id for remove_set command"
-                    ))
-                }
+                    ));
+                };
+                let exercise_id = self.sets.iter().find(|s| s.id == id).map(|s| s.exercise_id);
+                delete_workout_set_tx(tx, id).await?;
+                Ok(CommandOutcome::Removed {
+                    set_id: id,
+                    exercise_id,
+                })
             }
             Command::EditSet {
                 set_id,
@@ -120,76 +218,449 @@ impl Session {
                 weight,
                 reps,
                 rpe,
+                weight_delta,
+                reps_delta,
+                unit,
+                notes,
             } => {
-                let resolved_id = if let Some(id) = set_id {
-                    Some(id)
-                } else if let Some(desc) = description {
-                    self.resolve_set_id_from_description(&desc, sets, exercise_map)
-                } else {
-                    None
+                let Some(id) = self.resolve_set_id(set_id, description) else {
+                    return Err(anyhow::anyhow!(
+                        "Could not resolve set_id for edit_set command"
+                    ));
+                };
+                let exercise = match exercise.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+                    Some(exercise_name) => {
+                        Some(self.resolver.resolve_name_tx(tx, exercise_name).await?)
+                    }
+                    None => None,
                 };
 
-                if let Some(id) = resolved_id {
-                    let exercise_id = if let Some(exercise_name) = exercise {
-                        let ex = get_or_create_exercise(&self.db_pool, &exercise_name).await?;
-                        Some(ex.id)
-                    } else {
-                        None
-                    };
-
-                    let update = UpdateWorkoutSet {
-                        session_id: None,
-                        exercise_id,
-                        request_string_id: None,
-                        weight,
-                        reps,
-                        rpe,
-                        set_index: None,
-                        notes: None,
-                    };
-                    let (_, modifications) = self
-                        .update_workout_set_with_modifications(id, &update)
-                        .await?;
-                    Ok(modifications)
-                } else {
-                    Err(anyhow::anyhow!(
-                        "Could not resolve set_id for edit_set command"
-                    ))
-                }
+                // Read inside the transaction so deltas build on earlier commands' edits.
+                let current = get_workout_set_tx(tx, id).await?;
+                let to_kg = |w: f64| unit.map_or(w, |unit| unit.to_kg(w));
+                let weight = weight
+                    .map(to_kg)
+                    .or(weight_delta.map(|d| (current.weight + to_kg(d)).max(0.0)));
+                let reps = reps.or(reps_delta.map(|d| (current.reps + d).max(0)));
+                let notes = notes
+                    .map(|n| n.trim().to_string())
+                    .filter(|n| !n.is_empty());
+
+                let update = UpdateWorkoutSet {
+                    session_id: None,
+                    exercise_id: exercise.as_ref().map(|e| e.id),
+                    request_string_id: None,
+                    weight,
+                    reps,
+                    rpe,
+                    set_index: None,
+                    notes,
+                    rest_style: None,
+                };
+                let set = update_workout_set_tx(tx, id, &update).await?;
+                Ok(CommandOutcome::Modified { set, exercise })
             }
             Command::UpdateSummary { message, emoji } => {
-                let session_id = self
-                    .get_workout_id()
-                    .await
-                    .ok_or_else(|| anyhow::anyhow!("No active workout in session"))?;
-
                 let summary_json = serde_json::json!({
                     "message": message.trim(),
                     "emoji": emoji.trim()
                 });
-
-                crate::db::operations::update_workout_summary(
-                    &self.db_pool,
-                    session_id,
-                    summary_json.to_string(),
-                )
-                .await?;
-                Ok(vec![])
+                update_workout_summary_tx(tx, self.session_id, summary_json.to_string()).await?;
+                Ok(CommandOutcome::Unreported)
             }
             Command::Unknown { input } => {
                 warn!("Unknown command for input: {}", input);
                 let parsed = ParsedSet {
                     exercise: input.clone(),
                     weight: None,
+                    unit: None,
                     reps: None,
                     rpe: None,
                     set_count: Some(1),
+                    duration_seconds: None,
+                    distance_meters: None,
                     tags: vec![],
                     aoi: None,
                     original_string: input,
                 };
-                self.add_set_from_parsed_with_modifications(&parsed).await
+                let added =
+                    add_parsed_set_tx(tx, &mut self.resolver, self.session_id, &parsed).await?;
+                Ok(CommandOutcome::Added(added))
             }
         }
     }
 }
+
+/// Groups the sets added by commands sharing a superset label into one superset each,
+/// updating those outcomes so they carry the assigned group.
+async fn group_labelled_supersets_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    labels: &[Option<i64>],
+    outcomes: &mut [CommandOutcome],
+) -> Result<()> {
+    let mut groups: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for (index, label) in labels.iter().enumerate() {
+        if let Some(label) = label {
+            groups.entry(*label).or_default().push(index);
+        }
+    }
+
+    for indexes in groups.into_values() {
+        let set_count: usize = indexes
+            .iter()
+            .map(|&i| match &outcomes[i] {
+                CommandOutcome::Added(added) => added.sets.len(),
+                _ => 0,
+            })
+            .sum();
+        if set_count < 2 {
+            continue;
+        }
+
+        let group_id = next_superset_group_tx(tx).await?;
+        for &i in &indexes {
+            if let CommandOutcome::Added(added) = &mut outcomes[i] {
+                for set in &mut added.sets {
+                    *set = set_workout_set_superset_group_tx(tx, set.id, Some(group_id)).await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::operations::{
+        add_workout_set, create_request_string_for_username, create_workout_session,
+        get_aoi_for_set, get_tags_for_set, get_workout_session, get_workout_set,
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_edit_set_applies_weight_delta() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| {
+            r#"{"commands": [{"command_type": "edit_set", "set_id": null, "description": "last set", "exercise": null, "weight": null, "reps": null, "rpe": null, "weight_delta": 5.0}]}"#
+                .to_string()
+        }))
+        .await
+        .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&ParsedSet {
+                exercise: "Bench Press".to_string(),
+                weight: Some(100.0),
+                unit: None,
+                reps: Some(5),
+                rpe: None,
+                set_count: None,
+                duration_seconds: None,
+                distance_meters: None,
+                tags: vec![],
+                aoi: None,
+                original_string: "bench 100kg x 5".to_string(),
+            })
+            .await
+            .unwrap();
+
+        session
+            .process_user_input("add 5kg to last set", None, vec![])
+            .await
+            .unwrap();
+
+        let sets = session.get_all_sets().await.unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].weight, 105.0);
+        assert_eq!(sets[0].reps, 5);
+    }
+
+    #[tokio::test]
+    async fn test_edit_set_matches_exercise_names_like_added_sets() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| {
+            r#"{"commands": [{"command_type": "edit_set", "set_id": null, "description": "last set", "exercise": "Bench Pres", "weight": null, "reps": null, "rpe": null}]}"#
+                .to_string()
+        }))
+        .await
+        .unwrap();
+        session.new_workout().await.unwrap();
+        let bench = session.get_or_create_exercise("Bench Press").await.unwrap();
+        session
+            .add_set_from_parsed(&ParsedSet {
+                exercise: "Squat".to_string(),
+                weight: Some(140.0),
+                unit: None,
+                reps: Some(3),
+                rpe: None,
+                set_count: None,
+                duration_seconds: None,
+                distance_meters: None,
+                tags: vec![],
+                aoi: None,
+                original_string: "squat 140kg x 3".to_string(),
+            })
+            .await
+            .unwrap();
+
+        session
+            .process_user_input("that was bench pres actually", None, vec![])
+            .await
+            .unwrap();
+
+        let sets = session.get_all_sets().await.unwrap();
+        assert_eq!(sets[0].exercise_id, bench.id);
+        assert_eq!(session.get_all_exercises().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pounds_are_stored_as_kilograms() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, user| {
+            if user.contains("225lb") {
+                r#"{"commands": [{"command_type": "add_set", "exercise": "Bench Press", "weight": 225.0, "unit": "lb", "reps": 5, "rpe": null, "set_count": 1, "tags": [], "aoi": null, "original_string": "bench 225lb x 5"}]}"#
+                    .to_string()
+            } else if user.contains("10lb") {
+                r#"{"commands": [{"command_type": "edit_set", "set_id": null, "description": "last set", "exercise": null, "weight": null, "reps": null, "rpe": null, "weight_delta": 10.0, "unit": "lb"}]}"#
+                    .to_string()
+            } else {
+                String::new()
+            }
+        }))
+        .await
+        .unwrap();
+        session.new_workout().await.unwrap();
+
+        session
+            .process_user_input("bench 225lb x 5", None, vec![])
+            .await
+            .unwrap();
+        let sets = session.get_all_sets().await.unwrap();
+        assert_eq!(sets.len(), 1);
+        assert!((sets[0].weight - 102.06).abs() < 0.01);
+
+        session
+            .process_user_input("add 10lb to last set", None, vec![])
+            .await
+            .unwrap();
+        let sets = session.get_all_sets().await.unwrap();
+        assert!((sets[0].weight - 106.59).abs() < 0.01);
+    }
+
+    #[tokio::test]
+    async fn test_edit_set_notes_persist() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, user| {
+            if user.contains("felt shaky") {
+                r#"{"commands": [{"command_type": "edit_set", "set_id": null, "description": "most recent set", "exercise": null, "weight": null, "reps": null, "rpe": null, "notes": "felt shaky"}]}"#
+                    .to_string()
+            } else if user.contains("add 5kg") {
+                r#"{"commands": [{"command_type": "edit_set", "set_id": null, "description": "last set", "exercise": null, "weight": null, "reps": null, "rpe": null, "weight_delta": 5.0}]}"#
+                    .to_string()
+            } else {
+                String::new()
+            }
+        }))
+        .await
+        .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&ParsedSet {
+                exercise: "Squat".to_string(),
+                weight: Some(140.0),
+                unit: None,
+                reps: Some(3),
+                rpe: None,
+                set_count: None,
+                duration_seconds: None,
+                distance_meters: None,
+                tags: vec![],
+                aoi: None,
+                original_string: "squat 140kg x 3".to_string(),
+            })
+            .await
+            .unwrap();
+
+        session
+            .process_user_input("add a note to that set: felt shaky", None, vec![])
+            .await
+            .unwrap();
+        let sets = session.get_all_sets().await.unwrap();
+        assert_eq!(sets[0].notes.as_deref(), Some("felt shaky"));
+        assert_eq!(sets[0].weight, 140.0);
+
+        // A later edit without a note keeps it.
+        session
+            .process_user_input("add 5kg to last set", None, vec![])
+            .await
+            .unwrap();
+        let sets = session.get_all_sets().await.unwrap();
+        assert_eq!(sets[0].weight, 145.0);
+        assert_eq!(sets[0].notes.as_deref(), Some("felt shaky"));
+    }
+
+    #[tokio::test]
+    async fn test_failing_command_undoes_the_whole_input() {
+        let response = Arc::new(Mutex::new(String::new()));
+        let responder = response.clone();
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(move |_, user| {
+            if user.contains("999999") {
+                responder.lock().unwrap().clone()
+            } else {
+                String::new()
+            }
+        }))
+        .await
+        .unwrap();
+        session.new_workout().await.unwrap();
+        let logged = |exercise: &str, weight: f32, tags: Vec<String>| ParsedSet {
+            exercise: exercise.to_string(),
+            weight: Some(weight),
+            unit: None,
+            reps: Some(5),
+            rpe: None,
+            set_count: None,
+            duration_seconds: None,
+            distance_meters: None,
+            tags,
+            aoi: Some("lower back".to_string()),
+            original_string: format!("{} {}kg x 5", exercise, weight),
+        };
+        session
+            .add_set_from_parsed(&logged("Squat", 140.0, vec![]))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&logged("Barbell Row", 80.0, vec!["belt".to_string()]))
+            .await
+            .unwrap();
+        let before = session.get_all_sets().await.unwrap();
+        let (squat, row) = (before[0].id, before[1].id);
+
+        let earlier_workout =
+            create_workout_session(&session.db_pool, None, None, None, None, None)
+                .await
+                .unwrap();
+        let request = create_request_string_for_username(&session.db_pool, "cli", "squat".into())
+            .await
+            .unwrap();
+        let earlier_set = add_workout_set(
+            &session.db_pool,
+            &earlier_workout.id,
+            &before[0].exercise_id,
+            &request.id,
+            &120.0,
+            &5,
+            None,
+        )
+        .await
+        .unwrap();
+        let exercise_count = session.get_all_exercises().await.unwrap().len();
+        let pool = &session.db_pool;
+        let request_count = || async move {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM request_strings")
+                .fetch_one(pool)
+                .await
+                .unwrap()
+        };
+        let requests_before = request_count().await;
+
+        *response.lock().unwrap() = format!(
+            r#"{{"commands": [
+                {{"command_type": "add_set", "exercise": "Bench Press", "weight": 100.0, "reps": 5, "rpe": null, "set_count": 3, "tags": [], "aoi": null, "original_string": "bench 3x5 100kg"}},
+                {{"command_type": "edit_set", "set_id": {}, "description": null, "exercise": null, "weight": 150.0, "reps": null, "rpe": null, "notes": "easy"}},
+                {{"command_type": "remove_set", "set_id": {}, "description": null}},
+                {{"command_type": "update_summary", "message": "Heavy day", "emoji": "💪"}},
+                {{"command_type": "edit_set", "set_id": {}, "description": null, "exercise": "Front Squat", "weight": 60.0, "reps": null, "rpe": null}},
+                {{"command_type": "edit_set", "set_id": 999999, "description": null, "exercise": null, "weight": 50.0, "reps": null, "rpe": null}}
+            ]}}"#,
+            squat, row, earlier_set.id
+        );
+        assert!(
+            session
+                .process_user_input(
+                    "bench 3x5 100kg, squat was 150, drop the row, fix set 999999",
+                    None,
+                    vec![]
+                )
+                .await
+                .is_err()
+        );
+
+        let after = session.get_all_sets().await.unwrap();
+        assert_eq!(after.len(), 2);
+        assert_eq!((after[0].id, after[0].weight), (squat, 140.0));
+        assert_eq!(after[0].notes, None);
+        assert_eq!((after[1].id, after[1].weight), (row, 80.0));
+        assert_eq!(
+            get_tags_for_set(&session.db_pool, row).await.unwrap(),
+            vec!["belt"]
+        );
+        assert_eq!(
+            get_aoi_for_set(&session.db_pool, row)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("lower back")
+        );
+        let workout_id = session.get_workout_id().await.unwrap();
+        let workout = get_workout_session(&session.db_pool, workout_id)
+            .await
+            .unwrap();
+        assert_eq!(workout.summary, None);
+
+        let earlier_after = get_workout_set(&session.db_pool, earlier_set.id)
+            .await
+            .unwrap();
+        assert_eq!(earlier_after.weight, 120.0);
+        assert_eq!(earlier_after.exercise_id, before[0].exercise_id);
+        let exercises = session.get_all_exercises().await.unwrap();
+        assert_eq!(exercises.len(), exercise_count);
+        assert!(
+            exercises
+                .iter()
+                .all(|e| e.name != "Bench Press" && e.name != "Front Squat")
+        );
+        assert_eq!(request_count().await, requests_before);
+    }
+
+    #[tokio::test]
+    async fn test_superset_input_groups_added_sets() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| {
+            r#"{"commands": [
+                {"command_type": "add_set", "exercise": "Bench Press", "weight": 60.0, "reps": 10, "rpe": null, "set_count": 1, "tags": [], "aoi": null, "original_string": "bench 60x10", "superset_group": 1},
+                {"command_type": "add_set", "exercise": "Barbell Row", "weight": 50.0, "reps": 10, "rpe": null, "set_count": 1, "tags": [], "aoi": null, "original_string": "row 50x10", "superset_group": 1},
+                {"command_type": "add_set", "exercise": "Plank", "weight": null, "reps": null, "rpe": null, "set_count": 1, "tags": [], "aoi": null, "original_string": "plank"}
+            ]}"#
+            .to_string()
+        }))
+        .await
+        .unwrap();
+        session.new_workout().await.unwrap();
+
+        let modifications = session
+            .process_user_input(
+                "superset: bench 60x10 + row 50x10, then plank",
+                None,
+                vec![],
+            )
+            .await
+            .unwrap();
+        assert_eq!(modifications.len(), 3);
+        let group = modifications[0].set.as_ref().unwrap().superset_group;
+        assert!(group.is_some());
+        assert_eq!(modifications[1].set.as_ref().unwrap().superset_group, group);
+        assert_eq!(modifications[2].set.as_ref().unwrap().superset_group, None);
+
+        let exercises = session.exercise_map().await.unwrap();
+        let sets = session.get_all_sets().await.unwrap();
+        let grouped: Vec<&str> = sets
+            .iter()
+            .filter(|s| s.superset_group.is_some())
+            .map(|s| exercises[&s.exercise_id].as_str())
+            .collect();
+        assert_eq!(grouped.len(), 2);
+        assert!(grouped.contains(&"Bench Press") && grouped.contains(&"Barbell Row"));
+        assert!(
+            sets.iter()
+                .all(|s| s.superset_group.is_none() || s.superset_group == group)
+        );
+    }
+}
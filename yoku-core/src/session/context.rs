@@ -1,33 +1,69 @@
-use crate::db::models::WorkoutSet;
+use crate::db::models::{Exercise, WorkoutSession, WorkoutSet};
 use crate::db::operations::{get_exercise_entries, get_sets_for_session, get_workout_session};
-use crate::session::Session;
+use crate::metrics::rir_from_rpe;
+use crate::session::{NoActiveWorkout, Session};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-impl Session {
-    pub async fn build_workout_context_string(&self) -> Result<String> {
-        let workout_id = self.get_workout_id().await;
-        let Some(workout_id) = workout_id else {
-            return Ok("No active workout session.".to_string());
-        };
+#[derive(Debug, Clone)]
+pub struct ExerciseHistory {
+    pub exercise_id: i64,
+    pub exercise_name: String,
+    pub past_sets: Vec<WorkoutSet>,
+}
 
-        let workout = get_workout_session(&self.db_pool, workout_id).await?;
-        let sets = get_sets_for_session(&self.db_pool, workout_id).await?;
-        let exercises = self.get_all_exercises().await?;
-        let exercise_map: HashMap<i64, String> =
-            exercises.iter().map(|e| (e.id, e.name.clone())).collect();
+/// Snapshot of the active workout and the data the prompts are built from, so callers can
+/// use the structured data and the prompt text without querying twice.
+#[derive(Debug, Clone)]
+pub struct WorkoutContext {
+    pub workout: WorkoutSession,
+    /// Every known exercise, not only the ones in this workout.
+    pub exercises: Vec<Exercise>,
+    /// Sets in this workout ordered by set index.
+    pub sets: Vec<WorkoutSet>,
+    /// Up to ten sets in this workout, most recent first.
+    pub recent_sets: Vec<WorkoutSet>,
+    /// Up to ten past sets for each exercise in this workout, in order of first appearance.
+    pub history: Vec<ExerciseHistory>,
+}
 
-        let mut sorted_sets = sets.clone();
-        sorted_sets.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+impl WorkoutContext {
+    pub fn exercise_map(&self) -> HashMap<i64, String> {
+        self.exercises
+            .iter()
+            .map(|e| (e.id, e.name.clone()))
+            .collect()
+    }
+
+    pub fn to_prompt_string(&self) -> String {
+        let exercise_map = self.exercise_map();
+        let exercise_name = |exercise_id: i64| {
+            exercise_map
+                .get(&exercise_id)
+                .map(|s| s.as_str())
+                .unwrap_or("Unknown")
+        };
+        let rpe_str = |rpe: Option<f64>| {
+            rpe.map(|r| format!(" @{:.1}RPE ({:.1} RIR)", r, rir_from_rpe(r)))
+                .unwrap_or_default()
+        };
+        let superset_str = |group: Option<i64>| {
+            group
+                .map(|g| format!(", Superset={}", g))
+                .unwrap_or_default()
+        };
 
         let mut context = String::new();
 
         context.push_str(&format!(
             "Current Workout: ID={}, Name={:?}\n",
-            workout.id, workout.name
+            self.workout.id, self.workout.name
         ));
+        if let Some(notes) = &self.workout.notes {
+            context.push_str(&format!("Workout Notes: {}\n", notes));
+        }
 
-        if let Some(summary_json) = &workout.summary {
+        if let Some(summary_json) = &self.workout.summary {
             if !summary_json.trim().is_empty() {
                 if let Ok(summary_value) = serde_json::from_str::<serde_json::Value>(summary_json) {
                     let message = summary_value
@@ -54,133 +90,475 @@ impl Session {
         context.push_str("\n");
 
         context.push_str("=== RECENT SETS (Most Recent First) ===\n");
-        for (idx, set) in sorted_sets.iter().take(10).enumerate() {
-            let exercise_name = exercise_map
-                .get(&set.exercise_id)
-                .map(|s| s.as_str())
-                .unwrap_or("Unknown");
-            let rpe_str = set
-                .rpe
-                .map(|r| format!(" @{:.1}RPE", r))
-                .unwrap_or_default();
+        for (idx, set) in self.recent_sets.iter().enumerate() {
             context.push_str(&format!(
-                "  [{}] Set ID={}, Exercise={}, Weight={:.1}kg, Reps={}, Set Index={}{}\n",
+                "  [{}] Set ID={}, Exercise={}, Weight={:.1}kg, Reps={}, Set Index={}{}{}\n",
                 idx + 1,
                 set.id,
-                exercise_name,
+                exercise_name(set.exercise_id),
                 set.weight,
                 set.reps,
                 set.set_index,
-                rpe_str
+                rpe_str(set.rpe),
+                superset_str(set.superset_group)
             ));
         }
         context.push_str("\n");
 
         context.push_str("=== ALL SETS IN CURRENT WORKOUT ===\n");
-        for set in &sets {
-            let exercise_name = exercise_map
-                .get(&set.exercise_id)
-                .map(|s| s.as_str())
-                .unwrap_or("Unknown");
-            let rpe_str = set
-                .rpe
-                .map(|r| format!(" @{:.1}RPE", r))
-                .unwrap_or_default();
+        for set in &self.sets {
             context.push_str(&format!(
-                "  Set ID={}, Exercise={}, Weight={:.1}kg, Reps={}, Set Index={}{}, Created={}\n",
-                set.id, exercise_name, set.weight, set.reps, set.set_index, rpe_str, set.created_at
+                "  Set ID={}, Exercise={}, Weight={:.1}kg, Reps={}, Set Index={}{}{}, Created={}\n",
+                set.id,
+                exercise_name(set.exercise_id),
+                set.weight,
+                set.reps,
+                set.set_index,
+                rpe_str(set.rpe),
+                superset_str(set.superset_group),
+                set.created_at
             ));
         }
         context.push_str("\n");
 
+        let mut supersets: BTreeMap<i64, Vec<&str>> = BTreeMap::new();
+        for set in &self.sets {
+            if let Some(group) = set.superset_group {
+                let names = supersets.entry(group).or_default();
+                let name = exercise_name(set.exercise_id);
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        if !supersets.is_empty() {
+            context
+                .push_str("=== SUPERSETS (sets sharing a Superset id are done back to back) ===\n");
+            for (group, names) in &supersets {
+                context.push_str(&format!("  Superset {}: {}\n", group, names.join(" + ")));
+            }
+            context.push_str("\n");
+        }
+
         context.push_str("=== RECENT PERFORMANCE HISTORY (Past 10 sets per exercise) ===\n");
-        let exercise_ids: std::collections::HashSet<i64> =
-            sets.iter().map(|s| s.exercise_id).collect();
+        for entry in &self.history {
+            context.push_str(&format!("  {}:\n", entry.exercise_name));
+            for past_set in &entry.past_sets {
+                context.push_str(&format!(
+                    "    {:.1}kg x {} reps{}\n",
+                    past_set.weight,
+                    past_set.reps,
+                    rpe_str(past_set.rpe)
+                ));
+            }
+        }
+
+        context
+    }
+}
+
+impl Session {
+    pub async fn build_workout_context(&self) -> Result<WorkoutContext> {
+        let workout_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
+
+        let workout = get_workout_session(&self.db_pool, workout_id).await?;
+        let sets = get_sets_for_session(&self.db_pool, workout_id, None, None).await?;
+        let exercises = self.get_all_exercises().await?;
+        let exercise_map: HashMap<i64, String> =
+            exercises.iter().map(|e| (e.id, e.name.clone())).collect();
+
+        let mut recent_sets = sets.clone();
+        recent_sets.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        recent_sets.truncate(10);
+
+        let mut exercise_ids: Vec<i64> = Vec::new();
+        for set in &sets {
+            if !exercise_ids.contains(&set.exercise_id) {
+                exercise_ids.push(set.exercise_id);
+            }
+        }
+
+        let mut history = Vec::new();
         for exercise_id in exercise_ids {
-            if let Some(exercise_name) = exercise_map.get(&exercise_id) {
-                match get_exercise_entries(&self.db_pool, exercise_id, Some(10)).await {
-                    Ok(past_sets) if !past_sets.is_empty() => {
-                        context.push_str(&format!("  {}:\n", exercise_name));
-                        for past_set in past_sets.iter().take(10) {
-                            let rpe_str = past_set
-                                .rpe
-                                .map(|r| format!(" @{:.1}RPE", r))
-                                .unwrap_or_default();
-                            context.push_str(&format!(
-                                "    {:.1}kg x {} reps{}\n",
-                                past_set.weight, past_set.reps, rpe_str
-                            ));
-                        }
-                    }
-                    _ => {}
-                }
+            let Some(exercise_name) = exercise_map.get(&exercise_id) else {
+                continue;
+            };
+            match get_exercise_entries(&self.db_pool, exercise_id, Some(10)).await {
+                Ok(past_sets) if !past_sets.is_empty() => history.push(ExerciseHistory {
+                    exercise_id,
+                    exercise_name: exercise_name.clone(),
+                    past_sets,
+                }),
+                _ => {}
             }
         }
 
-        Ok(context)
+        Ok(WorkoutContext {
+            workout,
+            exercises,
+            sets,
+            recent_sets,
+            history,
+        })
+    }
+
+    pub async fn build_workout_context_string(&self) -> Result<String> {
+        if self.get_workout_id().await.is_none() {
+            return Ok("No active workout session.".to_string());
+        }
+        Ok(self.build_workout_context().await?.to_prompt_string())
     }
 
+    /// Finds the set in `sets` a natural-language description refers to, such as "last",
+    /// "the one before last", "the first squat set", "set 3 of bench" or "the 100kg bench
+    /// set". `None` when nothing in the description identifies a set.
     pub fn resolve_set_id_from_description(
         &self,
         description: &str,
         sets: &[WorkoutSet],
         exercise_map: &HashMap<i64, String>,
     ) -> Option<i64> {
-        let desc_lower = description.to_lowercase();
+        resolve_set_reference(description, sets, exercise_map)
+    }
+}
+
+/// Which of the matching sets a description points at, counting from 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SetPosition {
+    FromStart(usize),
+    FromEnd(usize),
+}
+
+const ORDINAL_WORDS: &[&str] = &[
+    "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth",
+];
+
+/// Words that on their own point at the latest matching set.
+const LATEST_WORDS: &[&str] = &["recent", "previous", "that", "this", "it"];
+
+/// Lower-cased words of `text`; punctuation separates words except inside decimals.
+fn reference_tokens(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || c == '.'))
+        .map(|t| t.trim_matches('.'))
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 1-based value of "third", "3rd" and the like.
+fn ordinal_value(token: &str) -> Option<usize> {
+    if let Some(i) = ORDINAL_WORDS.iter().position(|o| *o == token) {
+        return Some(i + 1);
+    }
+    ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| token.strip_suffix(suffix))
+        .and_then(|digits| digits.parse::<usize>().ok())
+        .filter(|n| *n > 0)
+}
 
-        let mut sorted_sets = sets.to_vec();
-        sorted_sets.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+/// Splits "100kg" into `(100.0, "kg")`; a bare number has an empty unit.
+fn split_number(token: &str) -> Option<(f64, &str)> {
+    let end = token
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(token.len());
+    let value = token[..end].parse::<f64>().ok()?;
+    Some((value, &token[end..]))
+}
+
+fn singular(word: &str) -> &str {
+    word.strip_suffix('s').unwrap_or(word)
+}
 
-        if desc_lower.contains("most recent") || desc_lower.contains("last") || desc_lower == "that"
-        {
-            return sorted_sets.first().map(|s| s.id);
+/// Exercise in `sets` the description names. Whole names beat partial ones, then more
+/// matched words, then shorter names, then the exercise logged most recently.
+fn mentioned_exercise(
+    tokens: &[String],
+    sets: &[WorkoutSet],
+    exercise_map: &HashMap<i64, String>,
+) -> Option<i64> {
+    let words: Vec<&str> = tokens.iter().map(|t| singular(t)).collect();
+    let mut best: Option<((bool, usize, std::cmp::Reverse<usize>, i64), i64)> = None;
+    for set in sets {
+        let Some(name) = exercise_map.get(&set.exercise_id) else {
+            continue;
+        };
+        let name_tokens = reference_tokens(name);
+        let matched = name_tokens
+            .iter()
+            .filter(|t| words.contains(&singular(t)))
+            .count();
+        if matched == 0 {
+            continue;
+        }
+        let key = (
+            matched == name_tokens.len(),
+            matched,
+            std::cmp::Reverse(name_tokens.len()),
+            set.created_at,
+        );
+        if best.as_ref().is_none_or(|(best_key, _)| key > *best_key) {
+            best = Some((key, set.exercise_id));
         }
+    }
+    best.map(|(_, exercise_id)| exercise_id)
+}
 
-        if desc_lower.contains("second to last") || desc_lower.contains("second last") {
-            return sorted_sets.get(1).map(|s| s.id);
+/// Position named by anchors such as "last", "second to last", "one before last" or
+/// "third", or by a bare set number.
+fn described_position(tokens: &[String]) -> Option<SetPosition> {
+    let word = |i: usize| tokens.get(i).map(String::as_str);
+    for (i, token) in tokens.iter().enumerate() {
+        match token.as_str() {
+            "penultimate" => return Some(SetPosition::FromEnd(1)),
+            "last" | "latest" => {
+                let before = |n: usize| i.checked_sub(n).and_then(word);
+                let back = match (before(2), before(1)) {
+                    (Some(ordinal), Some("to" | "from")) => ordinal_value(ordinal).unwrap_or(1),
+                    (_, Some("before")) => 2,
+                    (Some("before"), Some("the")) => 2,
+                    (_, Some(ordinal)) => ordinal_value(ordinal).unwrap_or(1),
+                    _ => 1,
+                };
+                return Some(SetPosition::FromEnd(back - 1));
+            }
+            _ => {}
         }
+    }
+    if let Some(n) = tokens.iter().find_map(|t| ordinal_value(t)) {
+        return Some(SetPosition::FromStart(n - 1));
+    }
+    for (i, token) in tokens.iter().enumerate() {
+        if let Some((value, "")) = split_number(token) {
+            let unit_follows = word(i + 1).is_some_and(|next| is_unit(next));
+            if !unit_follows && value.fract() == 0.0 && value >= 1.0 {
+                return Some(SetPosition::FromStart(value as usize - 1));
+            }
+        }
+    }
+    None
+}
 
-        for exercise_name in exercise_map.values() {
-            let ex_lower = exercise_name.to_lowercase();
-            if desc_lower.contains(&ex_lower) {
-                let exercise_sets: Vec<_> = sorted_sets
-                    .iter()
-                    .filter(|s| {
-                        exercise_map
-                            .get(&s.exercise_id)
-                            .map(|n| n == exercise_name)
-                            .unwrap_or(false)
-                    })
-                    .collect();
+fn is_unit(word: &str) -> bool {
+    matches!(word, "kg" | "kgs" | "lb" | "lbs" | "rep" | "reps")
+}
 
-                if desc_lower.contains("last") || desc_lower.contains("most recent") {
-                    return exercise_sets.first().map(|s| s.id);
-                }
+/// Weight in kg and reps mentioned with a unit, e.g. "100kg", "225 lbs" or "5 reps".
+fn described_load(tokens: &[String]) -> (Option<f64>, Option<i64>) {
+    const KG_PER_LB: f64 = 0.453592;
+    let (mut weight, mut reps) = (None, None);
+    for (i, token) in tokens.iter().enumerate() {
+        let Some((value, unit)) = split_number(token) else {
+            continue;
+        };
+        let unit = match unit {
+            "" => tokens.get(i + 1).map(String::as_str).unwrap_or(""),
+            unit => unit,
+        };
+        match unit {
+            "kg" | "kgs" => weight = Some(value),
+            "lb" | "lbs" => weight = Some(value * KG_PER_LB),
+            "rep" | "reps" => reps = Some(value as i64),
+            _ => {}
+        }
+    }
+    (weight, reps)
+}
 
-                if let Some(idx_str) = desc_lower
-                    .split_whitespace()
-                    .find(|s| s.parse::<usize>().is_ok())
-                {
-                    if let Ok(idx) = idx_str.parse::<usize>() {
-                        if idx > 0 && idx <= exercise_sets.len() {
-                            return Some(exercise_sets[idx - 1].id);
-                        }
-                    }
-                }
-            }
+pub(crate) fn resolve_set_reference(
+    description: &str,
+    sets: &[WorkoutSet],
+    exercise_map: &HashMap<i64, String>,
+) -> Option<i64> {
+    let tokens = reference_tokens(description);
+    let exercise_id = mentioned_exercise(&tokens, sets, exercise_map);
+    let (weight, reps) = described_load(&tokens);
+
+    let mut candidates: Vec<&WorkoutSet> = sets
+        .iter()
+        .filter(|s| exercise_id.is_none_or(|id| s.exercise_id == id))
+        .filter(|s| weight.is_none_or(|w| (s.weight - w).abs() < 0.5))
+        .filter(|s| reps.is_none_or(|r| s.reps == r))
+        .collect();
+    // Within one exercise sets are numbered by set index; across exercises by time logged.
+    if exercise_id.is_some() {
+        candidates.sort_by_key(|s| (s.set_index, s.created_at, s.id));
+    } else {
+        candidates.sort_by_key(|s| (s.created_at, s.id));
+    }
+
+    let filtered = exercise_id.is_some() || weight.is_some() || reps.is_some();
+    let position = described_position(&tokens).or_else(|| {
+        let latest = filtered || tokens.iter().any(|t| LATEST_WORDS.contains(&t.as_str()));
+        latest.then_some(SetPosition::FromEnd(0))
+    })?;
+
+    let set = match position {
+        SetPosition::FromStart(i) => candidates.get(i),
+        SetPosition::FromEnd(i) => candidates.len().checked_sub(i + 1).map(|i| &candidates[i]),
+    };
+    set.map(|s| s.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LlmInterface, ParsedSet};
+
+    fn parsed(exercise: &str, weight: f32, reps: i32, set_count: i32) -> ParsedSet {
+        ParsedSet {
+            exercise: exercise.to_string(),
+            weight: Some(weight),
+            unit: None,
+            reps: Some(reps),
+            rpe: None,
+            set_count: Some(set_count),
+            duration_seconds: None,
+            distance_meters: None,
+            tags: vec![],
+            aoi: None,
+            original_string: format!("{}x{} {} {}kg", set_count, reps, exercise, weight),
         }
+    }
 
-        if let Some(idx_str) = desc_lower
-            .split_whitespace()
-            .find(|s| s.parse::<usize>().is_ok())
-        {
-            if let Ok(idx) = idx_str.parse::<usize>() {
-                if idx > 0 && idx <= sorted_sets.len() {
-                    return Some(sorted_sets[idx - 1].id);
-                }
-            }
+    #[tokio::test]
+    async fn test_build_workout_context() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        assert!(session.build_workout_context().await.is_err());
+        assert_eq!(
+            session.build_workout_context_string().await.unwrap(),
+            "No active workout session."
+        );
+
+        session.new_workout_with_name("Push day").await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5, 2))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Overhead Press", 60.0, 8, 1))
+            .await
+            .unwrap();
+
+        let context = session.build_workout_context().await.unwrap();
+        assert_eq!(context.workout.name, Some("Push day".to_string()));
+        assert_eq!(context.sets.len(), 3);
+        assert_eq!(context.recent_sets.len(), 3);
+        assert_eq!(context.exercises.len(), 2);
+        assert_eq!(
+            context
+                .history
+                .iter()
+                .map(|h| h.exercise_name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Bench Press", "Overhead Press"]
+        );
+        assert_eq!(context.history[0].past_sets.len(), 2);
+
+        let prompt = context.to_prompt_string();
+        assert!(prompt.contains(&format!(
+            "Current Workout: ID={}, Name=Some(\"Push day\")",
+            context.workout.id
+        )));
+        assert!(prompt.contains("Cached Summary → (none)"));
+        assert!(!prompt.contains("Workout Notes:"));
+        let overhead_set = context.sets.iter().find(|s| s.reps == 8).unwrap();
+        assert!(prompt.contains(&format!(
+            "Set ID={}, Exercise=Overhead Press, Weight=60.0kg, Reps=8",
+            overhead_set.id
+        )));
+        assert!(prompt.contains("  Bench Press:\n    100.0kg x 5 reps\n"));
+        assert_eq!(
+            session.build_workout_context_string().await.unwrap(),
+            prompt
+        );
+
+        session
+            .set_notes(context.workout.id, Some("Shoulder felt tight".to_string()))
+            .await
+            .unwrap();
+        let prompt = session.build_workout_context_string().await.unwrap();
+        assert!(prompt.contains("Workout Notes: Shoulder felt tight\n"));
+    }
+
+    fn set(id: i64, exercise_id: i64, set_index: i64, weight: f64, reps: i64) -> WorkoutSet {
+        WorkoutSet {
+            id,
+            session_id: 1,
+            exercise_id,
+            request_string_id: 1,
+            weight,
+            reps,
+            set_index,
+            rpe: None,
+            notes: None,
+            rest_style: None,
+            duration_seconds: None,
+            distance_meters: None,
+            superset_group: None,
+            created_at: id * 10,
+            updated_at: id * 10,
         }
+    }
+
+    #[test]
+    fn test_resolve_set_reference_phrasings() {
+        // Logged in id order: bench 1, squat 2, bench 3, squat 4, bench 5, incline 6.
+        let sets = vec![
+            set(1, 1, 1, 100.0, 5),
+            set(2, 2, 1, 140.0, 5),
+            set(3, 1, 2, 100.0, 5),
+            set(4, 2, 2, 140.0, 5),
+            set(5, 1, 3, 102.5, 3),
+            set(6, 3, 1, 80.0, 8),
+        ];
+        let exercise_map = HashMap::from([
+            (1, "Bench Press".to_string()),
+            (2, "Barbell Back Squat".to_string()),
+            (3, "Incline Bench Press".to_string()),
+            (4, "Deadlift".to_string()),
+        ]);
 
-        None
+        let cases: &[(&str, Option<i64>)] = &[
+            ("last", Some(6)),
+            ("the last set", Some(6)),
+            ("most recent", Some(6)),
+            ("that", Some(6)),
+            ("first", Some(1)),
+            ("second to last", Some(5)),
+            ("second last set", Some(5)),
+            ("the one before last", Some(5)),
+            ("the set before the last", Some(5)),
+            ("penultimate set", Some(5)),
+            ("third from last", Some(4)),
+            ("set 2", Some(2)),
+            ("the 5th set", Some(5)),
+            ("the first squat set", Some(2)),
+            ("last squat", Some(4)),
+            ("squats", Some(4)),
+            ("set 3 of bench", Some(5)),
+            ("bench set 1", Some(1)),
+            ("2nd bench press set", Some(3)),
+            ("the last bench press", Some(5)),
+            ("incline bench", Some(6)),
+            ("the 102.5kg bench", Some(5)),
+            ("the 100 kg bench set", Some(3)),
+            ("the 3 rep set", Some(5)),
+            ("set 9", None),
+            ("fourth squat", None),
+            ("deadlift", None),
+            ("", None),
+        ];
+        for (description, expected) in cases {
+            assert_eq!(
+                resolve_set_reference(description, &sets, &exercise_map),
+                *expected,
+                "description {:?}",
+                description
+            );
+        }
     }
 }
@@ -0,0 +1,36 @@
+use crate::db::models::{Equipment, Exercise};
+use crate::db::operations::{
+    get_exercises_for_owned_equipment, get_or_create_equipment, get_or_create_user,
+    get_owned_equipment, list_equipment, set_equipment_owned,
+};
+use crate::session::Session;
+use anyhow::Result;
+
+/// Equipment ownership is recorded against the same local user that request strings are
+/// logged under.
+const EQUIPMENT_OWNER: &str = "cli";
+
+impl Session {
+    pub async fn list_equipment(&self) -> Result<Vec<Equipment>> {
+        list_equipment(&self.db_pool).await
+    }
+
+    pub async fn get_or_create_equipment(&self, name: &str) -> Result<Equipment> {
+        get_or_create_equipment(&self.db_pool, name.trim()).await
+    }
+
+    pub async fn set_equipment_owned(&self, equipment_id: i64, owned: bool) -> Result<()> {
+        let user = get_or_create_user(&self.db_pool, EQUIPMENT_OWNER).await?;
+        set_equipment_owned(&self.db_pool, user.id, equipment_id, owned).await
+    }
+
+    pub async fn get_owned_equipment(&self) -> Result<Vec<Equipment>> {
+        let user = get_or_create_user(&self.db_pool, EQUIPMENT_OWNER).await?;
+        get_owned_equipment(&self.db_pool, user.id).await
+    }
+
+    pub async fn get_exercises_for_owned_equipment(&self) -> Result<Vec<Exercise>> {
+        let user = get_or_create_user(&self.db_pool, EQUIPMENT_OWNER).await?;
+        get_exercises_for_owned_equipment(&self.db_pool, user.id).await
+    }
+}
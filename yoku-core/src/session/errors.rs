@@ -0,0 +1,12 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoActiveWorkout;
+
+impl fmt::Display for NoActiveWorkout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No active workout")
+    }
+}
+
+impl std::error::Error for NoActiveWorkout {}
@@ -0,0 +1,567 @@
+use crate::db::models::{Exercise, ExercisePatternType, ExperienceLevel};
+use crate::db::operations::{
+    get_exercise_volume, get_exercises_for_level, get_favorites, get_or_create_equipment,
+    get_or_create_exercise, get_or_create_muscle, get_recent_exercises, merge_exercises,
+    prune_orphan_exercises, rename_exercise, set_favorite,
+};
+use crate::llm::{
+    PromptBuilder, PromptContext, classify_movement_pattern,
+    generate_exercise_to_equipment_and_muscles,
+};
+use crate::matching::name_similarity;
+use crate::recommendation::graph::{MuscleInvolvement, MuscleUsageType};
+use crate::session::Session;
+use crate::session::prefix_index::ExercisePrefixIndex;
+use crate::uniffi_interface::modifications::SuggestedExercise;
+use anyhow::Result;
+use log::warn;
+use std::collections::HashMap;
+
+impl Session {
+    pub async fn set_exercise_favorite(
+        &self,
+        exercise_id: i64,
+        is_favorite: bool,
+    ) -> Result<Exercise> {
+        set_favorite(&self.db_pool, exercise_id, is_favorite).await
+    }
+
+    pub async fn get_favorite_exercises(&self) -> Result<Vec<Exercise>> {
+        get_favorites(&self.db_pool).await
+    }
+
+    pub async fn get_exercises_for_level(&self, level: ExperienceLevel) -> Result<Vec<Exercise>> {
+        get_exercises_for_level(&self.db_pool, level).await
+    }
+
+    pub async fn get_recent_exercises(&self, limit: i64) -> Result<Vec<Exercise>> {
+        get_recent_exercises(&self.db_pool, limit).await
+    }
+
+    /// Tonnage logged for an exercise, optionally only since a unix timestamp.
+    pub async fn get_exercise_volume(&self, exercise_id: i64, since: Option<i64>) -> Result<f64> {
+        get_exercise_volume(&self.db_pool, exercise_id, since).await
+    }
+
+    /// Recommended load for the next set of an exercise based on recent RPE, if it has
+    /// been logged before.
+    pub async fn suggest_next_weight(&self, exercise_id: i64) -> Result<Option<f64>> {
+        crate::recommendation::progression::suggest_next_weight(&self.db_pool, exercise_id).await
+    }
+
+    /// Whether an exercise's estimated 1RM has not improved over its last `window` workouts.
+    pub async fn detect_stall(&self, exercise_id: i64, window: usize) -> Result<bool> {
+        crate::recommendation::progression::detect_stall(&self.db_pool, exercise_id, window).await
+    }
+
+    pub async fn prune_orphan_exercises(&self, dry_run: bool) -> Result<Vec<Exercise>> {
+        let pruned = prune_orphan_exercises(&self.db_pool, dry_run).await?;
+        if !dry_run {
+            *self.exercise_names.lock().await = None;
+            let mut index = self.exercise_index.lock().await;
+            for exercise in &pruned {
+                self.recommendation_engine.forget_exercise(exercise.id)?;
+                if let Some(index) = index.as_mut() {
+                    index.remove(exercise.id);
+                }
+            }
+        }
+        Ok(pruned)
+    }
+
+    pub async fn rename_exercise(&self, exercise_id: i64, new_name: &str) -> Result<Exercise> {
+        let renamed = rename_exercise(&self.db_pool, exercise_id, new_name).await?;
+        self.recommendation_engine.rename_exercise(&renamed)?;
+        *self.exercise_names.lock().await = None;
+        if let Some(index) = self.exercise_index.lock().await.as_mut() {
+            index.insert(renamed.clone());
+        }
+        Ok(renamed)
+    }
+
+    /// Merges a duplicate exercise into the one to keep, returning the number of sets moved.
+    pub async fn merge_exercises(&self, keep_id: i64, remove_id: i64) -> Result<u64> {
+        let moved = merge_exercises(&self.db_pool, keep_id, remove_id).await?;
+        self.recommendation_engine.forget_exercise(remove_id)?;
+        *self.exercise_names.lock().await = None;
+        if let Some(index) = self.exercise_index.lock().await.as_mut() {
+            index.remove(remove_id);
+        }
+        Ok(moved)
+    }
+
+    /// Sets how similar a parsed exercise name must be to an existing exercise for that
+    /// exercise to be reused, from 0.0 to 1.0. Use 1.0 to only reuse exact matches.
+    pub async fn set_exercise_match_threshold(&self, threshold: f64) {
+        *self.exercise_match_threshold.lock().await = threshold.clamp(0.0, 1.0);
+    }
+
+    /// Looks up or creates an exercise by name, keeping the prefix index and name map in sync.
+    pub async fn get_or_create_exercise(&self, name: &str) -> Result<Exercise> {
+        let exercise = get_or_create_exercise(&self.db_pool, name).await?;
+        self.track_exercise(&exercise).await?;
+        Ok(exercise)
+    }
+
+    /// Brings the name map, prefix index and recommendation graph up to date with an
+    /// exercise that may have just been created. Exercises created inside a transaction are
+    /// passed here once it has committed, so a rolled-back one never reaches the graph.
+    pub(crate) async fn track_exercise(&self, exercise: &Exercise) -> Result<()> {
+        let mut names = self.exercise_names.lock().await;
+        if names
+            .as_ref()
+            .is_some_and(|names| !names.contains_key(&exercise.id))
+        {
+            *names = None;
+        }
+        drop(names);
+        if let Some(index) = self.exercise_index.lock().await.as_mut() {
+            index.insert(exercise.clone());
+        }
+        // Only the name is used here so logging never waits on the LLM; exercises the name
+        // doesn't place are classified when their pattern is first asked for.
+        let graph = self.recommendation_engine.graph_manager();
+        if graph.get_exercise_by_db_id(exercise.id).is_err() {
+            let vertex = graph.get_exercise_vert(exercise)?;
+            let pattern = ExercisePatternType::from_exercise_name(&exercise.name);
+            if pattern != ExercisePatternType::Unknown {
+                graph.link_exercise_to_movement_pattern(vertex, pattern)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks the LLM for an exercise's movement pattern; `None` when the call fails.
+    async fn classify_exercise_pattern(&self, name: &str) -> Option<ExercisePatternType> {
+        let builder = PromptBuilder::new(PromptContext::default());
+        classify_movement_pattern(&self.llm_backend, &builder, name)
+            .await
+            .map_err(|e| {
+                warn!(
+                    "movement pattern classification failed for '{}': {}",
+                    name, e
+                );
+            })
+            .ok()
+    }
+
+    /// The exercise's movement pattern in the recommendation graph. An exercise whose name
+    /// didn't give its pattern away is classified by the LLM on the first call and linked,
+    /// even when the model is unsure, so it is only asked once. Unknown while the model
+    /// can't be reached.
+    pub async fn get_movement_pattern_for_exercise(
+        &self,
+        exercise_id: i64,
+    ) -> Result<ExercisePatternType> {
+        let pattern = self
+            .recommendation_engine
+            .movement_pattern_for_exercise(exercise_id)?;
+        let graph = self.recommendation_engine.graph_manager();
+        if pattern != ExercisePatternType::Unknown {
+            return Ok(pattern);
+        }
+        let Ok(vertex) = graph.get_exercise_by_db_id(exercise_id) else {
+            return Ok(pattern);
+        };
+        if graph.has_movement_pattern(vertex.id)? {
+            return Ok(pattern);
+        }
+        let Some(name) = self.exercise_map().await?.remove(&exercise_id) else {
+            return Ok(pattern);
+        };
+        let Some(pattern) = self.classify_exercise_pattern(&name).await else {
+            return Ok(ExercisePatternType::Unknown);
+        };
+        graph.link_exercise_to_movement_pattern(vertex.id, pattern)?;
+        Ok(pattern)
+    }
+
+    /// Asks the LLM for the equipment and muscles of every known exercise and adds them to
+    /// the recommendation graph, calling `on_progress(done, total)` after each exercise.
+    /// Exercises are enriched one at a time; an exercise whose reply can't be used is
+    /// skipped with a warning. Re-running only refreshes existing links.
+    pub async fn enrich_all_exercises(
+        &self,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<()> {
+        let exercises = self.get_all_exercises().await?;
+        let total = exercises.len();
+        let builder = PromptBuilder::new(PromptContext::default());
+        for (done, exercise) in exercises.iter().enumerate() {
+            match generate_exercise_to_equipment_and_muscles(
+                &self.llm_backend,
+                &builder,
+                &exercise.name,
+            )
+            .await
+            {
+                Ok((equipment, muscles, _)) => {
+                    self.link_exercise_in_graph(exercise, &equipment, &muscles)
+                        .await?
+                }
+                Err(e) => warn!("could not enrich exercise '{}': {}", exercise.name, e),
+            }
+            on_progress(done + 1, total);
+        }
+        Ok(())
+    }
+
+    async fn link_exercise_in_graph(
+        &self,
+        exercise: &Exercise,
+        equipment: &[String],
+        muscles: &[(String, String, f32)],
+    ) -> Result<()> {
+        let graph = self.recommendation_engine.graph_manager();
+        let exercise_vert = graph.get_exercise_vert(exercise)?;
+        for name in equipment.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+            let row = get_or_create_equipment(&self.db_pool, name).await?;
+            let equipment_vert = graph.add_equipment(&row.name, None, row.id)?;
+            graph.link_exercise_to_equipment(exercise_vert, equipment_vert, true)?;
+        }
+        for (name, relation, strength) in muscles {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            let muscle = get_or_create_muscle(&self.db_pool, name).await?;
+            let muscle_vert = graph.get_muscle_vert(&muscle)?;
+            // Models often answer "secondary", which the graph calls a synergist.
+            let usage_type =
+                MuscleUsageType::from_str(relation.trim()).unwrap_or(MuscleUsageType::Synergist);
+            let involvement =
+                MuscleInvolvement::new(f64::from(strength.clamp(0.0, 1.0)), usage_type);
+            graph.link_exercise_to_muscle(exercise_vert, muscle_vert, involvement)?;
+        }
+        Ok(())
+    }
+
+    /// Exercise id to name for every known exercise. Loaded from the database once and
+    /// cached until an exercise is created, renamed, merged or pruned.
+    pub async fn exercise_map(&self) -> Result<HashMap<i64, String>> {
+        let mut names = self.exercise_names.lock().await;
+        if names.is_none() {
+            let exercises = self.get_all_exercises().await?;
+            *names = Some(exercises.into_iter().map(|e| (e.id, e.name)).collect());
+        }
+        Ok(names.clone().unwrap_or_default())
+    }
+
+    /// Type-ahead search over exercise names. The index is built from the database on the
+    /// first call and kept in memory afterwards, so later keystrokes never hit SQLite.
+    pub async fn prefix_search(&self, prefix: &str, limit: usize) -> Result<Vec<Exercise>> {
+        let mut index = self.exercise_index.lock().await;
+        if index.is_none() {
+            let exercises = self.get_all_exercises().await?;
+            *index = Some(ExercisePrefixIndex::build(exercises));
+        }
+        Ok(index
+            .as_ref()
+            .map(|index| index.search(prefix, limit))
+            .unwrap_or_default())
+    }
+
+    /// For an exercise being logged for the first time, finds an existing exercise within
+    /// two edits of its name, the likely intended exercise if the new one is a typo. Failing
+    /// that, an exercise whose name scores above the match threshold but differs in a word
+    /// ("Dumbbell Curl" for "Dumbbell Row") is offered instead of being reused silently.
+    pub async fn suggest_existing_exercise(
+        &self,
+        exercise: &Exercise,
+    ) -> Result<Option<SuggestedExercise>> {
+        let name = exercise.name.to_lowercase();
+        let others: Vec<Exercise> = self
+            .get_all_exercises()
+            .await?
+            .into_iter()
+            .filter(|other| other.id != exercise.id)
+            .collect();
+        let best = others
+            .iter()
+            .map(|other| {
+                let distance = edit_distance(&name, &other.name.to_lowercase());
+                (other, distance)
+            })
+            .filter(|(_, distance)| *distance > 0 && *distance <= MAX_SUGGESTION_DISTANCE)
+            .min_by_key(|(other, distance)| (*distance, other.id));
+        if let Some((other, distance)) = best {
+            let longest = name.chars().count().max(other.name.chars().count()).max(1);
+            return Ok(Some(SuggestedExercise {
+                exercise_id: other.id,
+                similarity: 1.0 - distance as f64 / longest as f64,
+                name: other.name.clone(),
+            }));
+        }
+
+        let threshold = *self.exercise_match_threshold.lock().await;
+        let closest = others
+            .into_iter()
+            .map(|other| (name_similarity(&exercise.name, &other.name), other))
+            .filter(|(score, _)| *score >= threshold && *score < 1.0)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
+        Ok(closest.map(|(similarity, other)| SuggestedExercise {
+            exercise_id: other.id,
+            similarity,
+            name: other.name,
+        }))
+    }
+}
+
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::LlmInterface;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn session_with_exercises(names: &[&str]) -> Session {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        for name in names {
+            session.get_or_create_exercise(name).await.unwrap();
+        }
+        session
+    }
+
+    fn names(exercises: &[Exercise]) -> Vec<&str> {
+        exercises.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_prefix_search_matches_word_prefixes() {
+        let session =
+            session_with_exercises(&["Bench Press", "Barbell Row", "Overhead Press", "Squat"])
+                .await;
+
+        let results = session.prefix_search("b", 10).await.unwrap();
+        assert_eq!(names(&results), vec!["Barbell Row", "Bench Press"]);
+
+        let results = session.prefix_search("PRESS", 10).await.unwrap();
+        assert_eq!(names(&results), vec!["Bench Press", "Overhead Press"]);
+
+        let results = session.prefix_search("b", 1).await.unwrap();
+        assert_eq!(names(&results), vec!["Barbell Row"]);
+
+        assert!(
+            session
+                .prefix_search("deadlift", 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(session.prefix_search("  ", 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prefix_search_does_not_query_database_once_built() {
+        let session = session_with_exercises(&["Bench Press", "Squat"]).await;
+        session.prefix_search("s", 10).await.unwrap();
+
+        session.db_pool.close().await;
+
+        let results = session.prefix_search("sq", 10).await.unwrap();
+        assert_eq!(names(&results), vec!["Squat"]);
+    }
+
+    #[tokio::test]
+    async fn test_prefix_index_updates_on_create() {
+        let session = session_with_exercises(&["Bench Press"]).await;
+        assert!(session.prefix_search("dead", 10).await.unwrap().is_empty());
+
+        session.get_or_create_exercise("Deadlift").await.unwrap();
+
+        let results = session.prefix_search("dead", 10).await.unwrap();
+        assert_eq!(names(&results), vec!["Deadlift"]);
+    }
+
+    #[tokio::test]
+    async fn test_movement_pattern_is_named_at_once_and_classified_lazily() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(move |system, user| {
+            if system.contains("movement pattern") {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            if system.contains("movement pattern") && user.contains("Landmine Press") {
+                r#"{"pattern": "vertical_push"}"#.to_string()
+            } else {
+                String::new()
+            }
+        }))
+        .await
+        .unwrap();
+
+        // The name places a squat without asking the model.
+        let squat = session.get_or_create_exercise("Front Squat").await.unwrap();
+        let landmine = session
+            .get_or_create_exercise("Landmine Press")
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            session
+                .get_movement_pattern_for_exercise(squat.id)
+                .await
+                .unwrap(),
+            ExercisePatternType::Squat
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // The model is asked once, the first time the pattern is needed.
+        for _ in 0..2 {
+            assert_eq!(
+                session
+                    .get_movement_pattern_for_exercise(landmine.id)
+                    .await
+                    .unwrap(),
+                ExercisePatternType::VerticalPush
+            );
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_movement_pattern_is_classified_once() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(move |system, _| {
+            if system.contains("movement pattern") {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            r#"{"pattern": "unknown"}"#.to_string()
+        }))
+        .await
+        .unwrap();
+
+        let bench = session
+            .get_or_create_exercise("Barbell Bench Press")
+            .await
+            .unwrap();
+        assert_eq!(
+            session
+                .get_movement_pattern_for_exercise(bench.id)
+                .await
+                .unwrap(),
+            ExercisePatternType::HorizontalPush
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let juggling = session.get_or_create_exercise("Juggling").await.unwrap();
+        for _ in 0..2 {
+            assert_eq!(
+                session
+                    .get_movement_pattern_for_exercise(juggling.id)
+                    .await
+                    .unwrap(),
+                ExercisePatternType::Unknown
+            );
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_enrich_all_exercises_links_muscles_and_reports_progress() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|system, user| {
+            if !system.contains("related_exercises") {
+                String::new()
+            } else if user.contains("Bench Press") {
+                r#"{"equipment": ["Barbell"], "muscles": [["Chest", "primary", 0.9], ["Triceps", "secondary", 0.4]], "related_exercises": []}"#
+                    .to_string()
+            } else {
+                r#"{"equipment": ["Barbell"], "muscles": [["Quadriceps", "primary", 1.0]], "related_exercises": []}"#
+                    .to_string()
+            }
+        }))
+        .await
+        .unwrap();
+        let bench = session.get_or_create_exercise("Bench Press").await.unwrap();
+        let squat = session.get_or_create_exercise("Squat").await.unwrap();
+
+        let mut progress = Vec::new();
+        session
+            .enrich_all_exercises(|done, total| progress.push((done, total)))
+            .await
+            .unwrap();
+        assert_eq!(progress, vec![(1, 2), (2, 2)]);
+
+        let chest = get_or_create_muscle(&session.db_pool, "Chest")
+            .await
+            .unwrap();
+        let bench_muscles = session
+            .recommendation_engine
+            .muscles_for_exercise(bench.id)
+            .unwrap();
+        assert_eq!(bench_muscles.len(), 2);
+        let (_, chest_involvement) = bench_muscles
+            .iter()
+            .find(|(id, _)| *id == chest.id)
+            .unwrap();
+        assert_eq!(chest_involvement.usage_type, MuscleUsageType::Primary);
+        assert_eq!(
+            session
+                .recommendation_engine
+                .muscles_for_exercise(squat.id)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exercise_map_refreshes_on_create() {
+        let session = session_with_exercises(&["Bench Press"]).await;
+        let before = session.exercise_map().await.unwrap();
+        assert_eq!(before.len(), 1);
+
+        let deadlift = session.get_or_create_exercise("Deadlift").await.unwrap();
+
+        let after = session.exercise_map().await.unwrap();
+        assert_eq!(after.len(), 2);
+        assert_eq!(
+            after.get(&deadlift.id).map(String::as_str),
+            Some("Deadlift")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rename_exercise_renames_graph_vertex() {
+        let session = session_with_exercises(&["Bench"]).await;
+        let bench = session.get_or_create_exercise("Bench").await.unwrap();
+        let graph = session.recommendation_engine.graph_manager();
+        let vertex = graph.get_exercise_by_db_id(bench.id).unwrap();
+
+        session
+            .rename_exercise(bench.id, "Barbell Bench Press")
+            .await
+            .unwrap();
+
+        let renamed = graph.get_exercise_by_name("Barbell Bench Press").unwrap();
+        assert_eq!(renamed.id, vertex.id);
+        assert!(graph.get_exercise_by_name("Bench").is_err());
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("bench press", "bench press"), 0);
+        assert_eq!(edit_distance("bnech press", "bench press"), 2);
+        assert_eq!(edit_distance("squat", "squats"), 1);
+        assert_eq!(edit_distance("", "row"), 3);
+    }
+}
@@ -1,8 +1,21 @@
+mod balance;
+mod bodyweight;
 mod commands;
 mod context;
+mod equipment;
+mod errors;
+mod exercises;
+mod observers;
+mod prefix_index;
+mod review;
 mod session;
 mod sets;
 mod summary;
+mod templates;
 mod workout;
 
-pub use session::Session;
+pub use crate::recommendation::GraphBackend;
+pub use context::{ExerciseHistory, WorkoutContext};
+pub use errors::NoActiveWorkout;
+pub use review::{ReviewIssue, ReviewPersonalRecord, WorkoutReview};
+pub use session::{DEFAULT_POOL_MAX_CONNECTIONS, Session, SessionConfig};
@@ -0,0 +1,100 @@
+use crate::session::Session;
+use crate::uniffi_interface::modifications::{Modification, ModificationObserver};
+use std::sync::Arc;
+
+impl Session {
+    /// Adds an observer that receives every batch of modifications made through
+    /// [`Session::process_user_input`], set edits and deletions.
+    pub async fn register_modification_observer(&self, observer: Arc<dyn ModificationObserver>) {
+        self.modification_observers.lock().await.push(observer);
+    }
+
+    /// Hands `modifications` to every registered observer; empty batches are not sent.
+    pub(crate) async fn notify_modifications(&self, modifications: &[Modification]) {
+        if modifications.is_empty() {
+            return;
+        }
+        let observers = self.modification_observers.lock().await.clone();
+        for observer in observers {
+            observer.on_modifications(modifications.to_vec());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::UpdateWorkoutSet;
+    use crate::llm::{LlmInterface, ParsedSet};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingObserver {
+        batches: Mutex<Vec<usize>>,
+    }
+
+    impl ModificationObserver for RecordingObserver {
+        fn on_modifications(&self, modifications: Vec<Modification>) {
+            self.batches.lock().unwrap().push(modifications.len());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer_receives_one_batch_per_change() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| {
+            r#"{"commands": [{"command_type": "edit_set", "set_id": null, "description": "last set", "exercise": null, "weight": null, "reps": null, "rpe": null, "weight_delta": 5.0}]}"#
+                .to_string()
+        }))
+        .await
+        .unwrap();
+        let observer = Arc::new(RecordingObserver::default());
+        session
+            .register_modification_observer(observer.clone())
+            .await;
+
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&ParsedSet {
+                exercise: "Bench Press".to_string(),
+                weight: Some(100.0),
+                unit: None,
+                reps: Some(5),
+                rpe: None,
+                set_count: None,
+                duration_seconds: None,
+                distance_meters: None,
+                tags: vec![],
+                aoi: None,
+                original_string: "bench 100kg x 5".to_string(),
+            })
+            .await
+            .unwrap();
+        let set_id = session.get_all_sets().await.unwrap()[0].id;
+
+        session
+            .process_user_input("add 5kg to last set", None, vec![])
+            .await
+            .unwrap();
+        assert_eq!(*observer.batches.lock().unwrap(), vec![1]);
+
+        session
+            .update_workout_set_with_modifications(
+                set_id,
+                &UpdateWorkoutSet {
+                    session_id: None,
+                    exercise_id: None,
+                    request_string_id: None,
+                    weight: None,
+                    reps: Some(3),
+                    rpe: None,
+                    set_index: None,
+                    notes: None,
+                    rest_style: None,
+                },
+            )
+            .await
+            .unwrap();
+        session.delete_set_with_modifications(set_id).await.unwrap();
+        assert_eq!(*observer.batches.lock().unwrap(), vec![1, 1, 1]);
+    }
+}
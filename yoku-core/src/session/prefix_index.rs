@@ -0,0 +1,94 @@
+use crate::db::models::Exercise;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Default)]
+struct TrieNode {
+    children: BTreeMap<char, TrieNode>,
+    exercise_ids: Vec<i64>,
+}
+
+/// In-memory prefix trie over exercise names for type-ahead search. Every word of a name is
+/// indexed, so "press" matches "Bench Press" as well as "Press Around".
+#[derive(Default)]
+pub struct ExercisePrefixIndex {
+    root: TrieNode,
+    exercises: HashMap<i64, Exercise>,
+}
+
+impl ExercisePrefixIndex {
+    pub fn build(exercises: impl IntoIterator<Item = Exercise>) -> Self {
+        let mut index = Self::default();
+        for exercise in exercises {
+            index.insert(exercise);
+        }
+        index
+    }
+
+    /// Adds an exercise, replacing any previous entry with the same id so renames are
+    /// picked up.
+    pub fn insert(&mut self, exercise: Exercise) {
+        self.remove(exercise.id);
+        for key in Self::keys(&exercise.name) {
+            let mut node = &mut self.root;
+            for c in key.chars() {
+                node = node.children.entry(c).or_default();
+                node.exercise_ids.push(exercise.id);
+            }
+        }
+        self.exercises.insert(exercise.id, exercise);
+    }
+
+    pub fn remove(&mut self, exercise_id: i64) {
+        let Some(old) = self.exercises.remove(&exercise_id) else {
+            return;
+        };
+        for key in Self::keys(&old.name) {
+            let mut node = &mut self.root;
+            for c in key.chars() {
+                let Some(child) = node.children.get_mut(&c) else {
+                    break;
+                };
+                child.exercise_ids.retain(|id| *id != exercise_id);
+                node = child;
+            }
+        }
+    }
+
+    /// Returns up to `limit` exercises with a word starting with `prefix`, names that start
+    /// with the prefix first and then alphabetically.
+    pub fn search(&self, prefix: &str, limit: usize) -> Vec<Exercise> {
+        let prefix = prefix.trim().to_lowercase();
+        if prefix.is_empty() {
+            return vec![];
+        }
+
+        let mut node = &self.root;
+        for c in prefix.chars() {
+            match node.children.get(&c) {
+                Some(child) => node = child,
+                None => return vec![],
+            }
+        }
+
+        let ids: HashSet<i64> = node.exercise_ids.iter().copied().collect();
+        let mut matches: Vec<&Exercise> =
+            ids.iter().filter_map(|id| self.exercises.get(id)).collect();
+        matches.sort_by_key(|e| (!e.name.to_lowercase().starts_with(&prefix), e.name.clone()));
+        matches.into_iter().take(limit).cloned().collect()
+    }
+
+    fn keys(name: &str) -> Vec<String> {
+        let lower = name.trim().to_lowercase();
+        let mut keys = Vec::new();
+        let mut at_word_start = true;
+        for (i, c) in lower.char_indices() {
+            if c.is_whitespace() {
+                at_word_start = true;
+            } else if at_word_start {
+                keys.push(lower[i..].to_string());
+                at_word_start = false;
+            }
+        }
+        keys
+    }
+}
@@ -0,0 +1,304 @@
+use crate::db::models::{Muscle, WorkoutSet};
+use crate::db::operations::{
+    get_exercise, get_most_similar_completed_session, get_muscle_set_counts,
+    get_previous_best_weight, get_sets_for_session,
+};
+use crate::session::{NoActiveWorkout, Session};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewPersonalRecord {
+    pub exercise_id: i64,
+    pub exercise_name: String,
+    pub weight: f64,
+    pub reps: i64,
+    pub previous_best: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReviewIssue {
+    /// One side of an opposing muscle pair got far more sets than the other.
+    Imbalance {
+        dominant: String,
+        dominant_sets: i64,
+        lagging: String,
+        lagging_sets: i64,
+    },
+    /// A muscle trained in the previous similar session that this workout skipped.
+    MissedMuscle { muscle: String },
+}
+
+/// What changed in the active workout, shown before it is completed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkoutReview {
+    pub personal_records: Vec<ReviewPersonalRecord>,
+    pub tonnage: f64,
+    pub previous_session_id: Option<i64>,
+    pub previous_tonnage: Option<f64>,
+    pub issues: Vec<ReviewIssue>,
+}
+
+struct MuscleGroup {
+    name: &'static str,
+    muscles: &'static [&'static str],
+}
+
+/// Opposing muscle groups compared for imbalance; muscle names match case-insensitively.
+const ANTAGONIST_PAIRS: &[(MuscleGroup, MuscleGroup)] = &[
+    (
+        MuscleGroup {
+            name: "Chest",
+            muscles: &["chest", "pectorals"],
+        },
+        MuscleGroup {
+            name: "Back",
+            muscles: &[
+                "back",
+                "upper back",
+                "lats",
+                "latissimus dorsi",
+                "rhomboids",
+            ],
+        },
+    ),
+    (
+        MuscleGroup {
+            name: "Quadriceps",
+            muscles: &["quadriceps", "quads"],
+        },
+        MuscleGroup {
+            name: "Hamstrings",
+            muscles: &["hamstrings"],
+        },
+    ),
+    (
+        MuscleGroup {
+            name: "Biceps",
+            muscles: &["biceps"],
+        },
+        MuscleGroup {
+            name: "Triceps",
+            muscles: &["triceps"],
+        },
+    ),
+];
+
+const IMBALANCE_MIN_SETS: i64 = 3;
+const IMBALANCE_RATIO: i64 = 2;
+
+fn tonnage(sets: &[WorkoutSet]) -> f64 {
+    sets.iter().map(|s| s.weight * s.reps as f64).sum()
+}
+
+fn group_sets(group: &MuscleGroup, counts: &[(Muscle, i64)]) -> i64 {
+    counts
+        .iter()
+        .filter(|(muscle, _)| group.muscles.contains(&muscle.name.to_lowercase().as_str()))
+        .map(|(_, sets)| sets)
+        .sum()
+}
+
+fn imbalance_issues(counts: &[(Muscle, i64)]) -> Vec<ReviewIssue> {
+    let mut issues = Vec::new();
+    for (a, b) in ANTAGONIST_PAIRS {
+        let (a_sets, b_sets) = (group_sets(a, counts), group_sets(b, counts));
+        let ((dominant, dominant_sets), (lagging, lagging_sets)) = if a_sets >= b_sets {
+            ((a, a_sets), (b, b_sets))
+        } else {
+            ((b, b_sets), (a, a_sets))
+        };
+        if dominant_sets >= IMBALANCE_MIN_SETS && dominant_sets >= IMBALANCE_RATIO * lagging_sets {
+            issues.push(ReviewIssue::Imbalance {
+                dominant: dominant.name.to_string(),
+                dominant_sets,
+                lagging: lagging.name.to_string(),
+                lagging_sets,
+            });
+        }
+    }
+    issues
+}
+
+impl Session {
+    /// Summarises the active workout before completion: exercises whose top weight beats
+    /// every earlier session, tonnage against the most similar completed session, and
+    /// muscle imbalances or muscles skipped compared with that session.
+    pub async fn workout_review(&self) -> Result<WorkoutReview> {
+        let session_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
+        let sets = get_sets_for_session(&self.db_pool, session_id, None, None).await?;
+
+        let mut top_sets: BTreeMap<i64, &WorkoutSet> = BTreeMap::new();
+        for set in &sets {
+            let top = top_sets.entry(set.exercise_id).or_insert(set);
+            if (set.weight, set.reps) > (top.weight, top.reps) {
+                *top = set;
+            }
+        }
+
+        let mut personal_records = Vec::new();
+        for (exercise_id, top) in top_sets {
+            let Some(previous_best) =
+                get_previous_best_weight(&self.db_pool, exercise_id, session_id).await?
+            else {
+                continue;
+            };
+            if top.weight > previous_best {
+                let exercise = get_exercise(&self.db_pool, exercise_id).await?;
+                personal_records.push(ReviewPersonalRecord {
+                    exercise_id,
+                    exercise_name: exercise.name,
+                    weight: top.weight,
+                    reps: top.reps,
+                    previous_best,
+                });
+            }
+        }
+
+        let previous_session_id =
+            get_most_similar_completed_session(&self.db_pool, session_id).await?;
+        let muscle_counts = get_muscle_set_counts(&self.db_pool, session_id).await?;
+        let mut issues = imbalance_issues(&muscle_counts);
+
+        let mut previous_tonnage = None;
+        if let Some(previous_id) = previous_session_id {
+            previous_tonnage = Some(tonnage(
+                &get_sets_for_session(&self.db_pool, previous_id, None, None).await?,
+            ));
+
+            let trained: HashSet<i64> = muscle_counts.iter().map(|(m, _)| m.id).collect();
+            for (muscle, _) in get_muscle_set_counts(&self.db_pool, previous_id).await? {
+                if !trained.contains(&muscle.id) {
+                    issues.push(ReviewIssue::MissedMuscle {
+                        muscle: muscle.name,
+                    });
+                }
+            }
+        }
+
+        Ok(WorkoutReview {
+            personal_records,
+            tonnage: tonnage(&sets),
+            previous_session_id,
+            previous_tonnage,
+            issues,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::NewExerciseMuscle;
+    use crate::db::operations::{get_or_create_muscle, link_exercise_to_muscle};
+    use crate::llm::{LlmInterface, ParsedSet};
+
+    fn parsed(exercise: &str, weight: f32, reps: i32, set_count: i32) -> ParsedSet {
+        ParsedSet {
+            exercise: exercise.to_string(),
+            weight: Some(weight),
+            unit: None,
+            reps: Some(reps),
+            rpe: None,
+            set_count: Some(set_count),
+            duration_seconds: None,
+            distance_meters: None,
+            tags: vec![],
+            aoi: None,
+            original_string: String::new(),
+        }
+    }
+
+    async fn link_primary(session: &Session, exercise: &str, muscle: &str) {
+        let exercise = session.get_or_create_exercise(exercise).await.unwrap();
+        let muscle = get_or_create_muscle(&session.db_pool, muscle)
+            .await
+            .unwrap();
+        link_exercise_to_muscle(
+            &session.db_pool,
+            &NewExerciseMuscle {
+                exercise_id: exercise.id,
+                muscle_id: muscle.id,
+                relation_type: "primary".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_review_surfaces_personal_record() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5, 1))
+            .await
+            .unwrap();
+        let first_id = session.get_workout_id().await.unwrap();
+        session.complete_workout(1800).await.unwrap();
+
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 105.0, 5, 1))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Squat", 140.0, 5, 1))
+            .await
+            .unwrap();
+
+        let review = session.workout_review().await.unwrap();
+        assert_eq!(review.personal_records.len(), 1);
+        let pr = &review.personal_records[0];
+        assert_eq!(pr.exercise_name, "Bench Press");
+        assert_eq!(pr.weight, 105.0);
+        assert_eq!(pr.previous_best, 100.0);
+        assert_eq!(review.tonnage, 105.0 * 5.0 + 140.0 * 5.0);
+        assert_eq!(review.previous_session_id, Some(first_id));
+        assert_eq!(review.previous_tonnage, Some(500.0));
+    }
+
+    #[tokio::test]
+    async fn test_review_flags_imbalance_only_when_unbalanced() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        link_primary(&session, "Bench Press", "Chest").await;
+        link_primary(&session, "Barbell Row", "Lats").await;
+
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 80.0, 8, 3))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Barbell Row", 70.0, 8, 3))
+            .await
+            .unwrap();
+
+        let review = session.workout_review().await.unwrap();
+        assert!(
+            !review
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, ReviewIssue::Imbalance { .. }))
+        );
+
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 80.0, 8, 3))
+            .await
+            .unwrap();
+        let review = session.workout_review().await.unwrap();
+        assert_eq!(
+            review.issues,
+            vec![ReviewIssue::Imbalance {
+                dominant: "Chest".to_string(),
+                dominant_sets: 6,
+                lagging: "Back".to_string(),
+                lagging_sets: 3,
+            }]
+        );
+    }
+}
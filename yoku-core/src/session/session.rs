@@ -1,11 +1,15 @@
 use crate::db;
 use crate::llm::LlmInterface;
-use crate::recommendation::GraphManager;
-use crate::recommendation::RecommendationEngine;
+use crate::matching::DEFAULT_EXERCISE_MATCH_THRESHOLD;
+use crate::recommendation::{GraphBackend, RecommendationEngine};
+use crate::session::prefix_index::ExercisePrefixIndex;
+use crate::uniffi_interface::modifications::ModificationObserver;
 use anyhow::Result;
-use indradb::RocksdbDatastore;
-use sqlx::SqlitePool;
-use sqlx::sqlite::SqliteConnectOptions;
+use futures::future::BoxFuture;
+use log::warn;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -14,7 +18,66 @@ pub struct Session {
     pub workout_id: Mutex<Option<i64>>,
     pub db_pool: SqlitePool,
     pub llm_backend: Arc<LlmInterface>,
-    pub recommendation_engine: RecommendationEngine<RocksdbDatastore>,
+    pub recommendation_engine: RecommendationEngine,
+    pub(crate) exercise_index: Mutex<Option<ExercisePrefixIndex>>,
+    /// Exercise id to name, loaded on first use by [`Session::exercise_map`].
+    pub(crate) exercise_names: Mutex<Option<HashMap<i64, String>>>,
+    /// Planned number of sets, keyed by the workout it was set for.
+    pub(crate) target_set_count: Mutex<Option<(i64, i64)>>,
+    /// Minimum similarity for a parsed exercise name to reuse an existing exercise.
+    pub(crate) exercise_match_threshold: Mutex<f64>,
+    pub(crate) modification_observers: Mutex<Vec<Arc<dyn ModificationObserver>>>,
+}
+
+/// Token the caller must pass to [`Session::reset_database_confirmed`].
+pub const RESET_CONFIRMATION: &str = "RESET";
+
+/// Connections kept by the SQLite pool unless configured otherwise.
+pub const DEFAULT_POOL_MAX_CONNECTIONS: u32 = 5;
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct SessionConfig {
+    /// Upper bound on open SQLite connections; raise it if concurrent calls queue up.
+    pub pool_max_connections: u32,
+    pub graph_backend: GraphBackend,
+    /// Insert the starter exercises, muscles and equipment when opening the database.
+    pub seed_defaults: bool,
+}
+
+/// Defaults to an in-memory graph; pick [`GraphBackend::Rocksdb`] to keep it on disk.
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            pool_max_connections: DEFAULT_POOL_MAX_CONNECTIONS,
+            graph_backend: GraphBackend::Memory,
+            seed_defaults: false,
+        }
+    }
+}
+
+/// Opens (creating if needed) and migrates the database at `db_path`.
+async fn open_pool(db_path: &str, config: &SessionConfig) -> Result<SqlitePool> {
+    let options = SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(config.pool_max_connections.max(1))
+        .connect_with(options)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create DB pool: {}", e))?;
+
+    sqlx::query("PRAGMA journal_mode = WAL")
+        .execute(&pool)
+        .await?;
+    sqlx::query("PRAGMA synchronous = NORMAL")
+        .execute(&pool)
+        .await?;
+    sqlx::query("PRAGMA busy_timeout = 5000")
+        .execute(&pool)
+        .await?;
+
+    db::init_database_with(&pool, config.seed_defaults).await?;
+    Ok(pool)
 }
 
 const fn get_openai_api_key() -> &'static str {
@@ -23,43 +86,261 @@ const fn get_openai_api_key() -> &'static str {
 
 impl Session {
     pub async fn new(db_path: &str, model: String, graph_path: &str) -> Result<Self> {
-        let options = SqliteConnectOptions::new()
-            .filename(db_path)
-            .create_if_missing(true);
-        let pool = SqlitePool::connect_with(options)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to create DB pool: {}", e))?;
-
-        sqlx::query("PRAGMA journal_mode = WAL")
-            .execute(&pool)
-            .await?;
-        sqlx::query("PRAGMA synchronous = NORMAL")
-            .execute(&pool)
-            .await?;
-        sqlx::query("PRAGMA busy_timeout = 5000")
-            .execute(&pool)
-            .await?;
+        let config = SessionConfig {
+            graph_backend: GraphBackend::Rocksdb(graph_path.into()),
+            ..SessionConfig::default()
+        };
+        Self::new_with_config(db_path, Some(model), config).await
+    }
 
-        db::init_database(&pool).await?;
+    /// Opens a session whose LLM is OpenAI's `model`, or its default model with `None`.
+    pub async fn new_with_config(
+        db_path: &str,
+        model: Option<String>,
+        config: SessionConfig,
+    ) -> Result<Self> {
+        let pool = open_pool(db_path, &config).await?;
 
         let llm_backend = Arc::new(
-            LlmInterface::new_openai(Some(get_openai_api_key().to_string()), Some(model)).await?,
+            LlmInterface::new_openai(Some(get_openai_api_key().to_string()), model).await?,
         );
 
-        let recommendation_engine = RecommendationEngine::new(
-            GraphManager::<RocksdbDatastore>::new(graph_path)?,
-            pool.clone(),
-        );
+        let recommendation_engine = RecommendationEngine::new(config.graph_backend, pool.clone())?;
 
         Ok(Self {
             workout_id: Mutex::new(None),
             db_pool: pool,
             llm_backend,
             recommendation_engine,
+            exercise_index: Mutex::new(None),
+            exercise_names: Mutex::new(None),
+            target_set_count: Mutex::new(None),
+            exercise_match_threshold: Mutex::new(DEFAULT_EXERCISE_MATCH_THRESHOLD),
+            modification_observers: Mutex::new(Vec::new()),
         })
     }
 
     pub async fn get_workout_id(&self) -> Option<i64> {
         self.workout_id.lock().await.clone()
     }
+
+    /// Runs `f` inside a single database transaction, committing if it returns `Ok` and
+    /// rolling back every write it made otherwise.
+    pub async fn with_transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: for<'c> FnOnce(&'c mut Transaction<'static, Sqlite>) -> BoxFuture<'c, Result<T>>,
+    {
+        let mut tx = self.db_pool.begin().await?;
+        match f(&mut tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                warn!("rolling back transaction: {}", e);
+                tx.rollback().await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Deletes every workout, set and exercise, but only when `confirmation` is
+    /// [`RESET_CONFIRMATION`], so a stray call can't wipe the user's history.
+    pub async fn reset_database_confirmed(&self, confirmation: &str) -> Result<()> {
+        if confirmation != RESET_CONFIRMATION {
+            warn!("reset_database_confirmed called with wrong confirmation token");
+            return Err(anyhow::anyhow!(
+                "Refusing to reset the database: confirmation must be '{}'",
+                RESET_CONFIRMATION
+            ));
+        }
+        self.reset_database().await
+    }
+
+    pub(crate) async fn reset_database(&self) -> Result<()> {
+        db::drop_all_tables(&self.db_pool).await?;
+        db::init_database(&self.db_pool).await?;
+        *self.workout_id.lock().await = None;
+        *self.exercise_index.lock().await = None;
+        *self.exercise_names.lock().await = None;
+        *self.target_set_count.lock().await = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl Session {
+    pub(crate) async fn new_for_tests(llm_backend: LlmInterface) -> Result<Self> {
+        let pool = SqlitePool::connect("sqlite::memory:").await?;
+        db::init_database(&pool).await?;
+
+        let recommendation_engine = RecommendationEngine::new(GraphBackend::Memory, pool.clone())?;
+
+        Ok(Self {
+            workout_id: Mutex::new(None),
+            db_pool: pool,
+            llm_backend: Arc::new(llm_backend),
+            recommendation_engine,
+            exercise_index: Mutex::new(None),
+            exercise_names: Mutex::new(None),
+            target_set_count: Mutex::new(None),
+            exercise_match_threshold: Mutex::new(DEFAULT_EXERCISE_MATCH_THRESHOLD),
+            modification_observers: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::ExercisePatternType;
+    use crate::llm::ParsedSet;
+
+    async fn session_with_set() -> Session {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&ParsedSet {
+                exercise: "Bench Press".to_string(),
+                weight: Some(100.0),
+                unit: None,
+                reps: Some(5),
+                rpe: None,
+                set_count: None,
+                duration_seconds: None,
+                distance_meters: None,
+                tags: vec![],
+                aoi: None,
+                original_string: "bench 100x5".to_string(),
+            })
+            .await
+            .unwrap();
+        session
+    }
+
+    async fn set_count(session: &Session) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM workout_sets")
+            .fetch_one(&session.db_pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reset_with_wrong_token_keeps_data() {
+        let session = session_with_set().await;
+
+        assert!(session.reset_database_confirmed("reset").await.is_err());
+        assert!(session.reset_database_confirmed("").await.is_err());
+        assert_eq!(set_count(&session).await, 1);
+        assert!(session.get_workout_id().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_reset_with_token_clears_and_reinitialises() {
+        let session = session_with_set().await;
+        session.create_workout_template("Push", &[]).await.unwrap();
+        session.add_bodyweight(80.0, None).await.unwrap();
+        let rack = session.get_or_create_equipment("Squat Rack").await.unwrap();
+        session.set_equipment_owned(rack.id, true).await.unwrap();
+
+        session
+            .reset_database_confirmed(RESET_CONFIRMATION)
+            .await
+            .unwrap();
+        assert_eq!(set_count(&session).await, 0);
+        assert!(session.get_workout_id().await.is_none());
+        assert!(session.get_all_exercises().await.unwrap().is_empty());
+        assert!(session.get_workout_templates().await.unwrap().is_empty());
+        assert!(
+            session
+                .get_bodyweight_series(None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(session.list_equipment().await.unwrap().is_empty());
+        assert!(session.get_owned_equipment().await.unwrap().is_empty());
+
+        session.new_workout().await.unwrap();
+        assert!(session.get_workout_id().await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_open_pool_honours_max_connections() {
+        let db_path =
+            std::env::temp_dir().join(format!("yoku-pool-test-{}.db", std::process::id()));
+        let db_path = db_path.to_str().unwrap();
+
+        let pool = open_pool(
+            db_path,
+            &SessionConfig {
+                pool_max_connections: 3,
+                ..SessionConfig::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(pool.options().get_max_connections(), 3);
+        pool.close().await;
+
+        let pool = open_pool(db_path, &SessionConfig::default()).await.unwrap();
+        assert_eq!(
+            pool.options().get_max_connections(),
+            DEFAULT_POOL_MAX_CONNECTIONS
+        );
+        pool.close().await;
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[tokio::test]
+    async fn test_rocksdb_graph_backend_keeps_graph_at_configured_path() {
+        let dir = std::env::temp_dir();
+        let db_path = dir.join(format!("yoku-rocksdb-graph-{}.db", std::process::id()));
+        let graph_path = dir.join(format!("yoku-rocksdb-graph-{}", std::process::id()));
+        let config = SessionConfig {
+            graph_backend: GraphBackend::Rocksdb(graph_path.clone()),
+            ..SessionConfig::default()
+        };
+
+        let session = Session::new_with_config(
+            db_path.to_str().unwrap(),
+            Some("test-model".to_string()),
+            config.clone(),
+        )
+        .await
+        .unwrap();
+        let squat = crate::db::operations::get_or_create_exercise(&session.db_pool, "Squat")
+            .await
+            .unwrap();
+        let graph = session.recommendation_engine.graph_manager();
+        let vertex = graph.get_exercise_vert(&squat).unwrap();
+        graph
+            .link_exercise_to_movement_pattern(vertex, ExercisePatternType::Squat)
+            .unwrap();
+        session.db_pool.close().await;
+        drop(session);
+        assert!(graph_path.exists());
+
+        let session = Session::new_with_config(
+            db_path.to_str().unwrap(),
+            Some("test-model".to_string()),
+            config,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            session
+                .get_movement_pattern_for_exercise(squat.id)
+                .await
+                .unwrap(),
+            ExercisePatternType::Squat
+        );
+
+        session.db_pool.close().await;
+        drop(session);
+        let _ = std::fs::remove_file(&db_path);
+        let _ = std::fs::remove_dir_all(&graph_path);
+    }
 }
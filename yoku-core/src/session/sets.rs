@@ -1,17 +1,23 @@
-use crate::db::models::{Exercise, UpdateWorkoutSet, WorkoutSet};
+use crate::db::models::{Exercise, NewWorkoutSet, UpdateWorkoutSet, WorkoutSession, WorkoutSet};
 use crate::db::operations::{
-    add_multiple_sets_to_workout, add_workout_set, create_request_string_for_username,
-    delete_workout_set, get_exercise_entries, get_or_create_exercise, get_sets_for_session,
-    update_workout_set, update_workout_set_from_parsed,
+    add_tags_to_set_tx, add_workout_sets_tx, clear_superset_group,
+    create_request_string_for_username_tx, delete_workout_set, get_aoi_for_session,
+    get_aoi_for_set, get_exercise, get_exercise_entries, get_exercise_history, get_exercise_tx,
+    get_or_create_exercise_tx, get_sets_for_session, get_sets_for_session_tx, get_tags_for_session,
+    get_tags_for_set, get_workout_set, next_superset_group_tx, resolve_alias_tx,
+    restore_workout_set, search_sets, set_aoi_for_set_tx, set_workout_set_index_tx,
+    set_workout_set_superset_group_tx, update_workout_set, update_workout_set_from_parsed,
 };
 use crate::llm::ParsedSet;
-use crate::session::Session;
+use crate::matching::fuzzy_match_exercise;
+use crate::session::{NoActiveWorkout, Session};
 use crate::uniffi_interface::modifications::{Modification, ModificationType};
 use crate::uniffi_interface::objects::{
     Exercise as UniffiExercise, WorkoutSet as UniffiWorkoutSet,
 };
 use anyhow::Result;
-use sqlx;
+use sqlx::{self, Sqlite, Transaction};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 impl Session {
@@ -19,6 +25,12 @@ impl Session {
         delete_workout_set(&self.db_pool, set_id).await
     }
 
+    /// Puts a set back exactly as it was captured, re-creating it if it was deleted. Its tags
+    /// and area of interest are not restored.
+    pub async fn restore_set(&self, set: &WorkoutSet) -> Result<WorkoutSet> {
+        restore_workout_set(&self.db_pool, set).await
+    }
+
     pub async fn get_sets_for_exercise(
         &self,
         exercise_id: i64,
@@ -27,16 +39,62 @@ impl Session {
         get_exercise_entries(&self.db_pool, exercise_id, limit).await
     }
 
+    /// Past sets of an exercise, newest first, with the workout each came from.
+    pub async fn get_exercise_history(
+        &self,
+        exercise_id: i64,
+        limit: Option<i64>,
+    ) -> Result<Vec<(WorkoutSet, WorkoutSession)>> {
+        get_exercise_history(&self.db_pool, exercise_id, limit).await
+    }
+
+    /// Sets from any workout whose notes or original input mention `query`, newest first.
+    pub async fn search_sets(&self, query: &str, limit: Option<i64>) -> Result<Vec<WorkoutSet>> {
+        search_sets(&self.db_pool, query, limit).await
+    }
+
     pub async fn get_all_sets(&self) -> Result<Vec<WorkoutSet>> {
         let workout_id = self.get_workout_id().await;
         if let Some(workout_id) = workout_id {
-            get_sets_for_session(&self.db_pool, workout_id).await
+            get_sets_for_session(&self.db_pool, workout_id, None, None).await
         } else {
-            Err(anyhow::anyhow!("No active workout"))
+            Err(NoActiveWorkout.into())
         }
     }
 
+    pub async fn get_active_workout_grouped(&self) -> Result<Vec<(Exercise, Vec<WorkoutSet>)>> {
+        let mut sets = self.get_all_sets().await?;
+        sets.sort_by_key(|s| (s.created_at, s.id));
+
+        let mut exercise_order: Vec<i64> = Vec::new();
+        let mut sets_by_exercise: HashMap<i64, Vec<WorkoutSet>> = HashMap::new();
+        for set in sets {
+            if !sets_by_exercise.contains_key(&set.exercise_id) {
+                exercise_order.push(set.exercise_id);
+            }
+            sets_by_exercise
+                .entry(set.exercise_id)
+                .or_default()
+                .push(set);
+        }
+
+        let mut grouped = Vec::with_capacity(exercise_order.len());
+        for exercise_id in exercise_order {
+            let exercise = get_exercise(&self.db_pool, exercise_id).await?;
+            let mut exercise_sets = sets_by_exercise.remove(&exercise_id).unwrap_or_default();
+            exercise_sets.sort_by_key(|s| (s.set_index, s.id));
+            grouped.push((exercise, exercise_sets));
+        }
+
+        Ok(grouped)
+    }
+
+    /// Overwrites a set with a re-parsed one. A parse without an exercise name keeps the set's
+    /// exercise.
     pub async fn replace_set_from_parsed(&self, set_id: i64, parsed: &ParsedSet) -> Result<()> {
+        if !parsed.exercise.trim().is_empty() {
+            self.get_or_create_exercise(&parsed.exercise).await?;
+        }
         update_workout_set_from_parsed(&self.db_pool, set_id, parsed).await?;
         Ok(())
     }
@@ -50,230 +108,1002 @@ impl Session {
     }
 
     pub async fn add_set_from_parsed(&self, parsed: &ParsedSet) -> Result<()> {
-        let session_id = self
-            .get_workout_id()
-            .await
-            .ok_or_else(|| anyhow::anyhow!("No active workout in session"))?;
-
-        let request_str_content = if !parsed.original_string.is_empty() {
-            parsed.original_string.clone()
-        } else {
-            format!(
-                "{} {} reps rpe:{:?}",
-                parsed.exercise,
-                parsed.reps.unwrap_or(0),
-                parsed.rpe
-            )
-        };
-
-        let exercise_name = parsed.exercise.clone();
-        let exercise = get_or_create_exercise(&self.db_pool, &exercise_name).await?;
-
-        let weight = parsed.weight.unwrap_or(0.0) as f64;
-        let reps = parsed.reps.unwrap_or(0) as i64;
-        let set_count = parsed.set_count.unwrap_or(1).max(1) as i64;
-        let parsed_rpe = parsed.rpe.map(|r| r as f64);
-
-        let request =
-            create_request_string_for_username(&self.db_pool, "cli", request_str_content.clone())
-                .await?;
-
-        if set_count > 1 {
-            add_multiple_sets_to_workout(
-                &self.db_pool,
-                &session_id,
-                &exercise.id,
-                &request.id,
-                &weight,
-                &reps,
-                parsed_rpe,
-                set_count,
-            )
-            .await?;
-        } else {
-            add_workout_set(
-                &self.db_pool,
-                &session_id,
-                &exercise.id,
-                &request.id,
-                &weight,
-                &reps,
-                parsed_rpe,
-            )
-            .await?;
-        }
-
-        Ok(())
-    }
-
-    async fn is_exercise_new_for_session(&self, exercise_id: i64) -> Result<bool> {
-        let workout_id = self.get_workout_id().await;
-        if let Some(workout_id) = workout_id {
-            let existing_count: i64 = sqlx::query_scalar(
-                "SELECT COUNT(*) FROM workout_sets WHERE session_id = ?1 AND exercise_id = ?2",
-            )
-            .bind(workout_id)
-            .bind(exercise_id)
-            .fetch_one(&self.db_pool)
-            .await?;
-            Ok(existing_count == 0)
-        } else {
-            Ok(false)
-        }
+        let added = self.log_parsed_set(parsed).await?;
+        self.track_exercise(&added.exercise).await
     }
 
     pub async fn add_set_from_parsed_with_modifications(
         &self,
         parsed: &ParsedSet,
     ) -> Result<Vec<Modification>> {
-        let session_id = self
-            .get_workout_id()
-            .await
-            .ok_or_else(|| anyhow::anyhow!("No active workout in session"))?;
+        let added = self.log_parsed_set(parsed).await?;
+        Ok(vec![self.added_sets_modification(added).await?])
+    }
 
-        let request_str_content = if !parsed.original_string.is_empty() {
-            parsed.original_string.clone()
-        } else {
-            format!(
-                "{} {} reps rpe:{:?}",
-                parsed.exercise,
-                parsed.reps.unwrap_or(0),
-                parsed.rpe
-            )
-        };
+    /// Logs a parsed set in the active workout in a transaction of its own.
+    async fn log_parsed_set(&self, parsed: &ParsedSet) -> Result<AddedSets> {
+        let session_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
+        let mut resolver = self.exercise_resolver().await?;
+        let parsed = parsed.clone();
+        self.with_transaction(move |tx| {
+            Box::pin(async move { add_parsed_set_tx(tx, &mut resolver, session_id, &parsed).await })
+        })
+        .await
+    }
 
-        let exercise_name = parsed.exercise.clone();
-        let exercise = get_or_create_exercise(&self.db_pool, &exercise_name).await?;
-        let is_new_exercise = self.is_exercise_new_for_session(exercise.id).await?;
-        let uniffi_exercise = Arc::new(UniffiExercise::from(exercise.clone()));
-
-        let weight = parsed.weight.unwrap_or(0.0) as f64;
-        let reps = parsed.reps.unwrap_or(0) as i64;
-        let set_count = parsed.set_count.unwrap_or(1).max(1) as i64;
-        let parsed_rpe = parsed.rpe.map(|r| r as f64);
-
-        let request =
-            create_request_string_for_username(&self.db_pool, "cli", request_str_content.clone())
-                .await?;
-
-        let mut modifications = Vec::new();
-
-        if set_count > 1 {
-            let created_sets = add_multiple_sets_to_workout(
-                &self.db_pool,
-                &session_id,
-                &exercise.id,
-                &request.id,
-                &weight,
-                &reps,
-                parsed_rpe,
-                set_count,
-            )
-            .await?;
+    /// Matches exercise names the way the session would, for use inside a transaction.
+    pub(crate) async fn exercise_resolver(&self) -> Result<ExerciseResolver> {
+        let mut known: Vec<(i64, String)> = self.exercise_map().await?.into_iter().collect();
+        known.sort();
+        Ok(ExerciseResolver {
+            known,
+            threshold: *self.exercise_match_threshold.lock().await,
+        })
+    }
 
-            let set_ids: Vec<i64> = created_sets.iter().map(|s| s.id).collect();
-            let uniffi_sets: Vec<Arc<UniffiWorkoutSet>> = created_sets
-                .into_iter()
-                .map(|s| Arc::new(UniffiWorkoutSet::from(s)))
-                .collect();
+    /// Reports sets logged by [`add_parsed_set_tx`] once its transaction has committed, and
+    /// brings the exercise caches and recommendation graph up to date with their exercise.
+    pub(crate) async fn added_sets_modification(&self, added: AddedSets) -> Result<Modification> {
+        self.track_exercise(&added.exercise).await?;
+        let suggested_existing = if added.first_logged {
+            self.suggest_existing_exercise(&added.exercise).await?
+        } else {
+            None
+        };
 
-            let modification_type = if is_new_exercise {
-                ModificationType::ExerciseAdded
-            } else {
-                ModificationType::SetAdded
-            };
+        let set_ids: Vec<i64> = added.sets.iter().map(|s| s.id).collect();
+        let uniffi_sets = self.uniffi_sets(added.sets).await?;
 
-            modifications.push(Modification {
-                modification_type,
-                set_id: Some(set_ids[0]),
-                set_ids: set_ids.clone(),
-                exercise_id: Some(exercise.id),
-                set: Some(uniffi_sets[0].clone()),
-                sets: Some(uniffi_sets),
-                exercise: Some(uniffi_exercise.clone()),
-            });
+        let modification_type = if added.new_to_workout {
+            ModificationType::ExerciseAdded
         } else {
-            let created_set = add_workout_set(
-                &self.db_pool,
-                &session_id,
-                &exercise.id,
-                &request.id,
-                &weight,
-                &reps,
-                parsed_rpe,
-            )
-            .await?;
+            ModificationType::SetAdded
+        };
 
-            let uniffi_set = Arc::new(UniffiWorkoutSet::from(created_set.clone()));
+        Ok(Modification {
+            modification_type,
+            set_id: set_ids.first().copied(),
+            set_ids,
+            exercise_id: Some(added.exercise.id),
+            set: uniffi_sets.first().cloned(),
+            sets: Some(uniffi_sets),
+            exercise: Some(Arc::new(UniffiExercise::from(added.exercise))),
+            suggested_existing,
+        })
+    }
 
-            let modification_type = if is_new_exercise {
-                ModificationType::ExerciseAdded
-            } else {
-                ModificationType::SetAdded
-            };
+    /// Converts a set for the foreign interface, loading its tags and area of interest.
+    pub(crate) async fn uniffi_set(&self, set: WorkoutSet) -> Result<Arc<UniffiWorkoutSet>> {
+        let tags = get_tags_for_set(&self.db_pool, set.id).await?;
+        let aoi = get_aoi_for_set(&self.db_pool, set.id).await?;
+        Ok(Arc::new(UniffiWorkoutSet {
+            tags,
+            aoi,
+            ..UniffiWorkoutSet::from(set)
+        }))
+    }
 
-            modifications.push(Modification {
-                modification_type,
-                set_id: Some(created_set.id),
-                set_ids: vec![created_set.id],
-                exercise_id: Some(exercise.id),
-                set: Some(uniffi_set.clone()),
-                sets: Some(vec![uniffi_set]),
-                exercise: Some(uniffi_exercise),
-            });
+    /// Converts sets for the foreign interface like [`Session::uniffi_set`], loading tags and
+    /// areas of interest once per workout rather than once per set.
+    pub(crate) async fn uniffi_sets(
+        &self,
+        sets: Vec<WorkoutSet>,
+    ) -> Result<Vec<Arc<UniffiWorkoutSet>>> {
+        let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+        let mut aoi: HashMap<i64, String> = HashMap::new();
+        let mut session_ids: Vec<i64> = sets.iter().map(|s| s.session_id).collect();
+        session_ids.sort_unstable();
+        session_ids.dedup();
+        for session_id in session_ids {
+            for (set_id, tag) in get_tags_for_session(&self.db_pool, session_id).await? {
+                tags.entry(set_id).or_default().push(tag);
+            }
+            aoi.extend(get_aoi_for_session(&self.db_pool, session_id).await?);
         }
 
-        Ok(modifications)
+        Ok(sets
+            .into_iter()
+            .map(|set| {
+                Arc::new(UniffiWorkoutSet {
+                    tags: tags.remove(&set.id).unwrap_or_default(),
+                    aoi: aoi.remove(&set.id),
+                    ..UniffiWorkoutSet::from(set)
+                })
+            })
+            .collect())
     }
 
+    /// Applies `update` to a set and notifies modification observers.
     pub async fn update_workout_set_with_modifications(
         &self,
         set_id: i64,
         update: &UpdateWorkoutSet,
+    ) -> Result<(WorkoutSet, Vec<Modification>)> {
+        let (updated, modifications) = self.apply_set_update(set_id, update).await?;
+        self.notify_modifications(&modifications).await;
+        Ok((updated, modifications))
+    }
+
+    /// Like [`Session::update_workout_set_with_modifications`], without notifying observers.
+    pub(crate) async fn apply_set_update(
+        &self,
+        set_id: i64,
+        update: &UpdateWorkoutSet,
     ) -> Result<(WorkoutSet, Vec<Modification>)> {
         let updated = update_workout_set(&self.db_pool, set_id, update).await?;
-        let uniffi_set = Arc::new(UniffiWorkoutSet::from(updated.clone()));
+        let modification = self.updated_set_modification(&updated).await?;
+        Ok((updated, vec![modification]))
+    }
+
+    /// Reports an edited set, with its exercise.
+    pub(crate) async fn updated_set_modification(
+        &self,
+        updated: &WorkoutSet,
+    ) -> Result<Modification> {
+        let uniffi_set = self.uniffi_set(updated.clone()).await?;
 
-        let exercise_id = updated.exercise_id;
         let exercise_opt = sqlx::query_as::<_, Exercise>("SELECT * FROM exercises WHERE id = ?")
-            .bind(exercise_id)
+            .bind(updated.exercise_id)
             .fetch_optional(&self.db_pool)
             .await?;
 
         let uniffi_exercise = exercise_opt.map(|e| Arc::new(UniffiExercise::from(e)));
 
-        let modifications = vec![Modification {
+        Ok(Modification {
             modification_type: ModificationType::SetModified,
-            set_id: Some(set_id),
-            set_ids: vec![set_id],
+            set_id: Some(updated.id),
+            set_ids: vec![updated.id],
             exercise_id: Some(updated.exercise_id),
             set: Some(uniffi_set.clone()),
             sets: Some(vec![uniffi_set]),
             exercise: uniffi_exercise,
-        }];
+            suggested_existing: None,
+        })
+    }
 
-        Ok((updated, modifications))
+    /// Edits a set's reps, weight, RPE and exercise, leaving any `None` field unchanged. The
+    /// exercise is looked up by name and created if it doesn't exist yet.
+    pub async fn update_workout_set_full(
+        &self,
+        set_id: i64,
+        reps: Option<i64>,
+        weight: Option<f64>,
+        rpe: Option<f64>,
+        exercise_name: Option<&str>,
+    ) -> Result<(WorkoutSet, Vec<Modification>)> {
+        let exercise_id = match exercise_name.map(str::trim).filter(|n| !n.is_empty()) {
+            Some(name) => Some(self.get_or_create_exercise(name).await?.id),
+            None => None,
+        };
+        let update = UpdateWorkoutSet {
+            exercise_id,
+            reps,
+            weight,
+            rpe,
+            ..Default::default()
+        };
+        self.update_workout_set_with_modifications(set_id, &update)
+            .await
     }
 
+    /// Moves a set to `new_index` (1-based, clamped) among the sets of the same exercise in its
+    /// workout, shifting the others so indices stay contiguous. Returns a `SetModified` for
+    /// every set whose index changed.
+    pub async fn reorder_set(&self, set_id: i64, new_index: i64) -> Result<Vec<Modification>> {
+        let moved = get_workout_set(&self.db_pool, set_id).await?;
+        let exercise_id = moved.exercise_id;
+        let mut ordered: Vec<WorkoutSet> =
+            get_sets_for_session(&self.db_pool, moved.session_id, None, None)
+                .await?
+                .into_iter()
+                .filter(|s| s.exercise_id == exercise_id && s.id != set_id)
+                .collect();
+        let position = ((new_index.max(1) - 1) as usize).min(ordered.len());
+        ordered.insert(position, moved);
+
+        let changes: Vec<(i64, i64)> = ordered
+            .iter()
+            .zip(1..)
+            .filter(|(set, index)| set.set_index != *index)
+            .map(|(set, index)| (set.id, index))
+            .collect();
+
+        let updated = self
+            .with_transaction(|tx| {
+                Box::pin(async move {
+                    let mut updated = Vec::with_capacity(changes.len());
+                    for (id, index) in changes {
+                        updated.push(set_workout_set_index_tx(tx, id, index).await?);
+                    }
+                    Ok(updated)
+                })
+            })
+            .await?;
+
+        let uniffi_sets = self.uniffi_sets(updated.clone()).await?;
+        let mut modifications = Vec::with_capacity(updated.len());
+        for (set, uniffi_set) in updated.into_iter().zip(uniffi_sets) {
+            modifications.push(Modification {
+                modification_type: ModificationType::SetModified,
+                set_id: Some(set.id),
+                set_ids: vec![set.id],
+                exercise_id: Some(set.exercise_id),
+                set: Some(uniffi_set.clone()),
+                sets: Some(vec![uniffi_set]),
+                exercise: None,
+                suggested_existing: None,
+            });
+        }
+        Ok(modifications)
+    }
+
+    /// Groups sets that were performed back to back into a new superset and returns the
+    /// group id. Sets already in another superset are moved into the new one.
+    pub async fn group_sets_into_superset(&self, set_ids: Vec<i64>) -> Result<i64> {
+        if set_ids.len() < 2 {
+            return Err(anyhow::anyhow!("A superset needs at least two sets"));
+        }
+        self.with_transaction(|tx| {
+            Box::pin(async move {
+                let group_id = next_superset_group_tx(tx).await?;
+                for set_id in set_ids {
+                    set_workout_set_superset_group_tx(tx, set_id, Some(group_id)).await?;
+                }
+                Ok(group_id)
+            })
+        })
+        .await
+    }
+
+    /// Dissolves a superset, returning how many sets were ungrouped.
+    pub async fn ungroup_superset(&self, group_id: i64) -> Result<u64> {
+        clear_superset_group(&self.db_pool, group_id).await
+    }
+
+    /// Deletes a set from the active workout and notifies modification observers.
     pub async fn delete_set_with_modifications(&self, set_id: i64) -> Result<Vec<Modification>> {
-        let sets =
-            get_sets_for_session(&self.db_pool, self.get_workout_id().await.unwrap()).await?;
+        let modifications = self.remove_set(set_id).await?;
+        self.notify_modifications(&modifications).await;
+        Ok(modifications)
+    }
+
+    /// Like [`Session::delete_set_with_modifications`], without notifying observers.
+    pub(crate) async fn remove_set(&self, set_id: i64) -> Result<Vec<Modification>> {
+        let sets = get_sets_for_session(
+            &self.db_pool,
+            self.get_workout_id().await.ok_or(NoActiveWorkout)?,
+            None,
+            None,
+        )
+        .await?;
         let exercise_id = sets.iter().find(|s| s.id == set_id).map(|s| s.exercise_id);
 
         delete_workout_set(&self.db_pool, set_id).await?;
 
-        Ok(vec![Modification {
-            modification_type: ModificationType::SetRemoved,
-            set_id: Some(set_id),
-            set_ids: vec![set_id],
+        Ok(vec![removed_set_modification(set_id, exercise_id)])
+    }
+
+    /// Logs "same again" for an exercise: copies the most recent set of it in the active
+    /// workout, with its weight, reps, RPE, duration, distance, tags and area of interest,
+    /// as the next set. Fails if the exercise has no set in this workout yet.
+    pub async fn repeat_last_set(&self, exercise_id: i64) -> Result<Vec<Modification>> {
+        let session_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
+        let sets = get_sets_for_session(&self.db_pool, session_id, None, None).await?;
+        let Some(last) = sets
+            .into_iter()
+            .filter(|s| s.exercise_id == exercise_id)
+            .max_by_key(|s| (s.created_at, s.id))
+        else {
+            return Err(anyhow::anyhow!(
+                "Exercise {} has no set in this workout to repeat",
+                exercise_id
+            ));
+        };
+
+        let tags = get_tags_for_set(&self.db_pool, last.id).await?;
+        let aoi = get_aoi_for_set(&self.db_pool, last.id).await?;
+        let new_set = NewWorkoutSet {
+            session_id,
             exercise_id,
-            set: None,
-            sets: None,
-            exercise: None,
-        }])
+            request_string_id: last.request_string_id,
+            weight: last.weight,
+            reps: last.reps,
+            set_index: 0,
+            rpe: last.rpe,
+            notes: None,
+            rest_style: None,
+            duration_seconds: last.duration_seconds,
+            distance_meters: last.distance_meters,
+        };
+        let created = self
+            .with_transaction(move |tx| {
+                Box::pin(async move {
+                    let created = add_workout_sets_tx(tx, &new_set, 1).await?.remove(0);
+                    add_tags_to_set_tx(tx, created.id, &tags).await?;
+                    if let Some(aoi) = &aoi {
+                        set_aoi_for_set_tx(tx, created.id, aoi).await?;
+                    }
+                    Ok(created)
+                })
+            })
+            .await?;
+
+        let exercise = get_exercise(&self.db_pool, exercise_id).await?;
+        let uniffi_set = self.uniffi_set(created.clone()).await?;
+        let modifications = vec![Modification {
+            modification_type: ModificationType::SetAdded,
+            set_id: Some(created.id),
+            set_ids: vec![created.id],
+            exercise_id: Some(exercise_id),
+            set: Some(uniffi_set.clone()),
+            sets: Some(vec![uniffi_set]),
+            exercise: Some(Arc::new(UniffiExercise::from(exercise))),
+            suggested_existing: None,
+        }];
+        self.notify_modifications(&modifications).await;
+        Ok(modifications)
     }
 
     pub async fn get_all_exercises(&self) -> Result<Vec<Exercise>> {
         crate::db::operations::get_all_exercises(&self.db_pool).await
     }
+
+    pub async fn search_exercises(&self, prefix: &str, limit: i64) -> Result<Vec<Exercise>> {
+        crate::db::operations::search_exercises(&self.db_pool, prefix, limit).await
+    }
+}
+
+/// Reports a deleted set.
+pub(crate) fn removed_set_modification(set_id: i64, exercise_id: Option<i64>) -> Modification {
+    Modification {
+        modification_type: ModificationType::SetRemoved,
+        set_id: Some(set_id),
+        set_ids: vec![set_id],
+        exercise_id,
+        set: None,
+        sets: None,
+        exercise: None,
+        suggested_existing: None,
+    }
+}
+
+/// Matches parsed exercise names to exercises inside a transaction, where the session can't
+/// be borrowed. Built by [`Session::exercise_resolver`]; exercises it creates are matched by
+/// later sets of the same transaction.
+pub(crate) struct ExerciseResolver {
+    /// Sorted by id so ties go to the oldest exercise, as with a plain table scan.
+    known: Vec<(i64, String)>,
+    threshold: f64,
+}
+
+impl ExerciseResolver {
+    /// Picks the exercise a parsed set belongs to. Aliases are checked before fuzzy
+    /// matching against known names. A set with no exercise name but with a weight, rep
+    /// count, duration or distance ("another 5 reps") continues the most recently logged
+    /// exercise of the workout.
+    async fn resolve_tx(
+        &mut self,
+        tx: &mut Transaction<'_, Sqlite>,
+        session_id: i64,
+        parsed: &ParsedSet,
+    ) -> Result<Exercise> {
+        let name = parsed.exercise.trim();
+        if !name.is_empty() {
+            return self.resolve_name_tx(tx, name).await;
+        }
+        if parsed.weight.is_none()
+            && parsed.reps.is_none()
+            && parsed.duration_seconds.is_none()
+            && parsed.distance_meters.is_none()
+        {
+            return Err(anyhow::anyhow!(
+                "Could not determine an exercise, weight or reps from '{}'",
+                parsed.original_string
+            ));
+        }
+
+        let sets = get_sets_for_session_tx(tx, session_id).await?;
+        let Some(last_set) = sets.iter().max_by_key(|s| (s.created_at, s.id)) else {
+            return Err(anyhow::anyhow!(
+                "No exercise given in '{}' and no exercise logged in this workout to continue",
+                parsed.original_string
+            ));
+        };
+        get_exercise_tx(tx, last_set.exercise_id).await
+    }
+
+    /// Picks the exercise a name refers to, through its aliases first and then by fuzzy
+    /// matching against known names, creating it if nothing matches.
+    pub(crate) async fn resolve_name_tx(
+        &mut self,
+        tx: &mut Transaction<'_, Sqlite>,
+        name: &str,
+    ) -> Result<Exercise> {
+        if let Some(exercise) = resolve_alias_tx(tx, name).await? {
+            return Ok(exercise);
+        }
+        let known: Vec<String> = self.known.iter().map(|(_, name)| name.clone()).collect();
+        let name =
+            fuzzy_match_exercise(name, &known, self.threshold).unwrap_or_else(|| name.to_string());
+        let exercise = get_or_create_exercise_tx(tx, &name).await?;
+        if !self.known.iter().any(|(id, _)| *id == exercise.id) {
+            self.known.push((exercise.id, exercise.name.clone()));
+        }
+        Ok(exercise)
+    }
+}
+
+/// Sets logged for one parsed set by [`add_parsed_set_tx`].
+pub(crate) struct AddedSets {
+    pub(crate) exercise: Exercise,
+    /// The workout had no set of the exercise before.
+    pub(crate) new_to_workout: bool,
+    /// No workout had a set of the exercise before.
+    pub(crate) first_logged: bool,
+    pub(crate) sets: Vec<WorkoutSet>,
+}
+
+/// Logs a parsed set in a workout, `set_count` times, with its tags and area of interest.
+pub(crate) async fn add_parsed_set_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    resolver: &mut ExerciseResolver,
+    session_id: i64,
+    parsed: &ParsedSet,
+) -> Result<AddedSets> {
+    let exercise = resolver.resolve_tx(tx, session_id, parsed).await?;
+    let (in_workout, anywhere) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(CASE WHEN session_id = ?1 THEN 1 END), COUNT(*)
+         FROM workout_sets WHERE exercise_id = ?2",
+    )
+    .bind(session_id)
+    .bind(exercise.id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    let request_str_content = if !parsed.original_string.is_empty() {
+        parsed.original_string.clone()
+    } else {
+        format!(
+            "{} {} reps rpe:{:?}",
+            parsed.exercise,
+            parsed.reps.unwrap_or(0),
+            parsed.rpe
+        )
+    };
+    let request = create_request_string_for_username_tx(tx, "cli", request_str_content).await?;
+
+    let new_set = NewWorkoutSet {
+        session_id,
+        exercise_id: exercise.id,
+        request_string_id: request.id,
+        weight: parsed.weight_in_kg().unwrap_or(0.0) as f64,
+        reps: parsed.reps.unwrap_or(0) as i64,
+        set_index: 0,
+        rpe: parsed.rpe.map(|r| r as f64),
+        notes: None,
+        rest_style: None,
+        duration_seconds: parsed.duration_seconds,
+        distance_meters: parsed.distance_meters,
+    };
+    let set_count = parsed.set_count.unwrap_or(1).max(1) as i64;
+    let sets = add_workout_sets_tx(tx, &new_set, set_count).await?;
+    for set in &sets {
+        add_tags_to_set_tx(tx, set.id, &parsed.tags).await?;
+        if let Some(aoi) = &parsed.aoi {
+            set_aoi_for_set_tx(tx, set.id, aoi).await?;
+        }
+    }
+
+    Ok(AddedSets {
+        exercise,
+        new_to_workout: in_workout == 0,
+        first_logged: anywhere == 0,
+        sets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::operations::{add_exercise_alias, get_tags_for_set};
+    use crate::llm::{LlmInterface, PromptBuilder, PromptContext, WeightUnit, parse_set_string};
+
+    fn parsed(exercise: &str, weight: f32, reps: i32) -> ParsedSet {
+        ParsedSet {
+            exercise: exercise.to_string(),
+            weight: Some(weight),
+            unit: None,
+            reps: Some(reps),
+            rpe: None,
+            set_count: None,
+            duration_seconds: None,
+            distance_meters: None,
+            tags: vec![],
+            aoi: None,
+            original_string: format!("{} {}kg x {}", exercise, weight, reps),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_active_workout_grouped_interleaved() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Squat", 140.0, 3))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 102.5, 4))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Squat", 145.0, 2))
+            .await
+            .unwrap();
+
+        let grouped = session.get_active_workout_grouped().await.unwrap();
+        assert_eq!(grouped.len(), 2);
+
+        let (bench, bench_sets) = &grouped[0];
+        assert_eq!(bench.name, "Bench Press");
+        assert_eq!(
+            bench_sets.iter().map(|s| s.set_index).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            bench_sets.iter().map(|s| s.weight).collect::<Vec<_>>(),
+            vec![100.0, 102.5]
+        );
+
+        let (squat, squat_sets) = &grouped[1];
+        assert_eq!(squat.name, "Squat");
+        assert_eq!(
+            squat_sets.iter().map(|s| s.set_index).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert!(squat_sets.iter().all(|s| s.exercise_id == squat.id));
+    }
+
+    fn continuation_parser() -> LlmInterface {
+        LlmInterface::new_mock_fn(|_, _| {
+            r#"{"exercise":null,"weight":null,"reps":5,"rpe":null,"set_count":null,"tags":[],"aoi":null}"#
+                .to_string()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_empty_exercise_continues_last_exercise() {
+        let session = Session::new_for_tests(continuation_parser()).await.unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5))
+            .await
+            .unwrap();
+
+        let builder = PromptBuilder::new(PromptContext::default());
+        let continuation = parse_set_string(&session.llm_backend, &builder, "another 5 reps")
+            .await
+            .unwrap();
+        assert!(continuation.exercise.is_empty());
+        session.add_set_from_parsed(&continuation).await.unwrap();
+
+        let grouped = session.get_active_workout_grouped().await.unwrap();
+        assert_eq!(grouped.len(), 1);
+        let (bench, bench_sets) = &grouped[0];
+        assert_eq!(bench.name, "Bench Press");
+        assert_eq!(
+            bench_sets.iter().map(|s| s.reps).collect::<Vec<_>>(),
+            vec![5, 5]
+        );
+        assert!(
+            session
+                .get_all_exercises()
+                .await
+                .unwrap()
+                .iter()
+                .all(|e| !e.name.is_empty())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_exercise_without_active_exercise_errors() {
+        let session = Session::new_for_tests(continuation_parser()).await.unwrap();
+        session.new_workout().await.unwrap();
+
+        let builder = PromptBuilder::new(PromptContext::default());
+        let continuation = parse_set_string(&session.llm_backend, &builder, "another 5 reps")
+            .await
+            .unwrap();
+        assert!(session.add_set_from_parsed(&continuation).await.is_err());
+        assert!(session.get_all_sets().await.unwrap().is_empty());
+        assert!(session.get_all_exercises().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replace_set_without_exercise_keeps_exercise() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5))
+            .await
+            .unwrap();
+        let set_id = session.get_all_sets().await.unwrap()[0].id;
+
+        session
+            .replace_set_from_parsed(set_id, &parsed("", 102.5, 4))
+            .await
+            .unwrap();
+
+        let exercises = session.get_all_exercises().await.unwrap();
+        assert_eq!(exercises.len(), 1);
+        assert_eq!(exercises[0].name, "Bench Press");
+        let set = &session.get_all_sets().await.unwrap()[0];
+        assert_eq!((set.weight, set.reps), (102.5, 4));
+    }
+
+    #[tokio::test]
+    async fn test_add_set_from_parsed_persists_tags() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+
+        let mut tagged = parsed("Squat", 140.0, 3);
+        tagged.set_count = Some(2);
+        tagged.tags = vec!["paused".to_string()];
+        session.add_set_from_parsed(&tagged).await.unwrap();
+
+        let sets = session.get_all_sets().await.unwrap();
+        assert_eq!(sets.len(), 2);
+        for set in &sets {
+            assert_eq!(
+                get_tags_for_set(&session.db_pool, set.id).await.unwrap(),
+                vec!["paused".to_string()]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_exercise_flags_near_match() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.set_exercise_match_threshold(1.0).await;
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5))
+            .await
+            .unwrap();
+
+        let modifications = session
+            .add_set_from_parsed_with_modifications(&parsed("Bnech Press", 100.0, 5))
+            .await
+            .unwrap();
+        assert_eq!(modifications.len(), 1);
+        let created = modifications[0].exercise.as_ref().unwrap();
+        assert_eq!(created.name, "Bnech Press");
+        let suggestion = modifications[0].suggested_existing.as_ref().unwrap();
+        assert_eq!(suggestion.name, "Bench Press");
+        assert!(suggestion.similarity > 0.8 && suggestion.similarity < 1.0);
+
+        let modifications = session
+            .add_set_from_parsed_with_modifications(&parsed("Deadlift", 180.0, 3))
+            .await
+            .unwrap();
+        assert!(modifications[0].suggested_existing.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_close_name_of_different_lift_is_only_suggested() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Dumbbell Curl", 15.0, 10))
+            .await
+            .unwrap();
+
+        let modifications = session
+            .add_set_from_parsed_with_modifications(&parsed("Dumbbell Row", 30.0, 10))
+            .await
+            .unwrap();
+        let created = modifications[0].exercise.as_ref().unwrap();
+        assert_eq!(created.name, "Dumbbell Row");
+        let suggestion = modifications[0].suggested_existing.as_ref().unwrap();
+        assert_eq!(suggestion.name, "Dumbbell Curl");
+        assert_eq!(session.get_all_exercises().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_close_exercise_name_reuses_existing_exercise() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Barbell Bench Press", 100.0, 5))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Barbell Bench", 100.0, 5))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Dip", 0.0, 12))
+            .await
+            .unwrap();
+
+        let grouped = session.get_active_workout_grouped().await.unwrap();
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[0].0.name, "Barbell Bench Press");
+        assert_eq!(grouped[0].1.len(), 2);
+        assert_eq!(grouped[1].0.name, "Bench Dip");
+    }
+
+    #[tokio::test]
+    async fn test_update_workout_set_full_changes_rpe_and_exercise() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5))
+            .await
+            .unwrap();
+        let set = session.get_all_sets().await.unwrap().remove(0);
+
+        let (updated, modifications) = session
+            .update_workout_set_full(set.id, None, None, Some(8.5), Some("Incline Bench Press"))
+            .await
+            .unwrap();
+        let incline = session
+            .get_or_create_exercise("Incline Bench Press")
+            .await
+            .unwrap();
+        assert_eq!(updated.rpe, Some(8.5));
+        assert_eq!(updated.exercise_id, incline.id);
+        assert_eq!(updated.weight, 100.0);
+        assert_eq!(updated.reps, 5);
+        assert_eq!(modifications.len(), 1);
+        assert_eq!(
+            modifications[0].exercise.as_ref().unwrap().name,
+            "Incline Bench Press"
+        );
+
+        let stored = get_workout_set(&session.db_pool, set.id).await.unwrap();
+        assert_eq!(stored.rpe, Some(8.5));
+        assert_eq!(stored.exercise_id, incline.id);
+    }
+
+    #[tokio::test]
+    async fn test_restore_set_clears_added_fields_and_recreates_deleted_sets() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5))
+            .await
+            .unwrap();
+        let original = session.get_all_sets().await.unwrap().remove(0);
+        assert_eq!(original.rpe, None);
+
+        session
+            .update_workout_set_full(original.id, Some(3), None, Some(9.0), None)
+            .await
+            .unwrap();
+        let restored = session.restore_set(&original).await.unwrap();
+        assert_eq!(restored.rpe, None);
+        assert_eq!(restored.reps, 5);
+
+        session.delete_set(original.id).await.unwrap();
+        session.restore_set(&original).await.unwrap();
+        let sets = session.get_all_sets().await.unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].id, original.id);
+        assert_eq!(sets[0].created_at, original.created_at);
+    }
+
+    #[tokio::test]
+    async fn test_reorder_set_moves_last_set_first() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        let mut bench = parsed("Bench Press", 100.0, 5);
+        bench.set_count = Some(3);
+        session.add_set_from_parsed(&bench).await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Squat", 140.0, 3))
+            .await
+            .unwrap();
+
+        let grouped = session.get_active_workout_grouped().await.unwrap();
+        let ids: Vec<i64> = grouped[0].1.iter().map(|s| s.id).collect();
+        let squat_id = grouped[1].1[0].id;
+
+        let modifications = session.reorder_set(ids[2], 1).await.unwrap();
+        assert_eq!(modifications.len(), 3);
+        assert!(
+            modifications
+                .iter()
+                .all(|m| matches!(m.modification_type, ModificationType::SetModified))
+        );
+
+        let mut bench_sets: Vec<WorkoutSet> = session
+            .get_all_sets()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|s| s.exercise_id == grouped[0].0.id)
+            .collect();
+        bench_sets.sort_by_key(|s| s.set_index);
+        assert_eq!(
+            bench_sets
+                .iter()
+                .map(|s| (s.id, s.set_index))
+                .collect::<Vec<_>>(),
+            vec![(ids[2], 1), (ids[0], 2), (ids[1], 3)]
+        );
+
+        let squat = get_workout_set(&session.db_pool, squat_id).await.unwrap();
+        assert_eq!(squat.set_index, 1);
+
+        assert!(session.reorder_set(ids[2], 1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_repeat_last_set_copies_it_as_next_set() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        let squat = session.get_or_create_exercise("Squat").await.unwrap();
+        assert!(session.repeat_last_set(squat.id).await.is_err());
+
+        let mut bench = parsed("Bench Press", 100.0, 5);
+        bench.rpe = Some(8.0);
+        bench.tags = vec!["paused".to_string()];
+        session.add_set_from_parsed(&bench).await.unwrap();
+        let original = session.get_all_sets().await.unwrap().remove(0);
+
+        let modifications = session.repeat_last_set(original.exercise_id).await.unwrap();
+        assert_eq!(modifications.len(), 1);
+        assert!(matches!(
+            modifications[0].modification_type,
+            ModificationType::SetAdded
+        ));
+        let copy_id = modifications[0].set_id.unwrap();
+        assert_ne!(copy_id, original.id);
+
+        let copy = get_workout_set(&session.db_pool, copy_id).await.unwrap();
+        assert_eq!(copy.set_index, original.set_index + 1);
+        assert_eq!(
+            (copy.weight, copy.reps, copy.rpe),
+            (original.weight, original.reps, original.rpe)
+        );
+        assert_eq!(modifications[0].set.as_ref().unwrap().tags, vec!["paused"]);
+        assert!(session.repeat_last_set(squat.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pound_weights_are_stored_in_kg() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        let workout_id = session.get_workout_id().await.unwrap();
+        let mut bench = parsed("Bench Press", 225.0, 5);
+        bench.unit = Some(WeightUnit::Lb);
+        session.add_set_from_parsed(&bench).await.unwrap();
+
+        let sets = get_sets_for_session(&session.db_pool, workout_id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(sets.len(), 1);
+        assert!((sets[0].weight - 102.0582).abs() < 1e-3);
+    }
+
+    #[tokio::test]
+    async fn test_cardio_set_is_parsed_and_stored() {
+        let llm = LlmInterface::new_mock_fn(|_, _| {
+            r#"{"exercise":"Running","weight":null,"reps":null,"rpe":null,"set_count":null,"duration_seconds":1500,"distance_meters":5000.0,"tags":[],"aoi":null}"#
+                .to_string()
+        });
+        let builder = PromptBuilder::new(PromptContext::default());
+        let run = parse_set_string(&llm, &builder, "ran 5km in 25 min")
+            .await
+            .unwrap();
+        assert_eq!(run.duration_seconds, Some(1500));
+        assert_eq!(run.distance_meters, Some(5000.0));
+
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        let workout_id = session.get_workout_id().await.unwrap();
+        session.add_set_from_parsed(&run).await.unwrap();
+
+        let sets = get_sets_for_session(&session.db_pool, workout_id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].duration_seconds, Some(1500));
+        assert_eq!(sets[0].distance_meters, Some(5000.0));
+        assert_eq!(
+            sets[0].to_string(),
+            format!("Exercise {}: 5.00 km in 25:00", sets[0].id)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_group_and_ungroup_superset() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 80.0, 8))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Barbell Row", 70.0, 8))
+            .await
+            .unwrap();
+        let set_ids: Vec<i64> = session
+            .get_all_sets()
+            .await
+            .unwrap()
+            .iter()
+            .map(|s| s.id)
+            .collect();
+
+        let group_id = session
+            .group_sets_into_superset(set_ids.clone())
+            .await
+            .unwrap();
+        let sets = session.get_all_sets().await.unwrap();
+        assert!(sets.iter().all(|s| s.superset_group == Some(group_id)));
+        let context = session.build_workout_context_string().await.unwrap();
+        assert!(context.contains(&format!("Superset {}: Bench Press + Barbell Row", group_id)));
+
+        assert_eq!(session.ungroup_superset(group_id).await.unwrap(), 2);
+        let sets = session.get_all_sets().await.unwrap();
+        assert!(sets.iter().all(|s| s.superset_group.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_alias_reuses_canonical_exercise() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Incline Dumbbell Press", 30.0, 10))
+            .await
+            .unwrap();
+        let canonical = session.get_all_exercises().await.unwrap()[0].clone();
+        add_exercise_alias(&session.db_pool, canonical.id, "incline db")
+            .await
+            .unwrap();
+
+        session
+            .add_set_from_parsed(&parsed("Incline DB", 32.5, 8))
+            .await
+            .unwrap();
+        assert_eq!(session.get_all_exercises().await.unwrap().len(), 1);
+        let sets = session.get_all_sets().await.unwrap();
+        assert_eq!(sets.len(), 2);
+        assert!(sets.iter().all(|s| s.exercise_id == canonical.id));
+    }
 }
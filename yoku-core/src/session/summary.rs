@@ -1,29 +1,74 @@
+use crate::db::models::WorkoutSet;
 use crate::db::operations::{
-    get_exercise_entries, get_sets_for_session, get_workout_session, update_workout_summary,
+    get_exercise, get_exercise_entries, get_sets_for_session, get_workout_session,
+    update_workout_summary,
 };
 use crate::llm::{
-    PromptBuilder, PromptContext, WorkoutSuggestion, WorkoutSummary, generate_workout_suggestions,
-    generate_workout_summary,
+    ProgressTrend, PromptBuilder, PromptContext, WorkoutSuggestion, WorkoutSummary,
+    generate_workout_suggestions, generate_workout_summary,
 };
-use crate::session::Session;
+use crate::session::{NoActiveWorkout, Session};
 use crate::uniffi_interface::objects::{
     ActiveWorkoutState, Exercise as UniffiExercise, WorkoutSession as UniffiWorkoutSession,
-    WorkoutSet as UniffiWorkoutSet,
 };
 use anyhow::Result;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Sets per exercise fed into the suggestions' past-performance summary.
+const PAST_PERFORMANCE_SETS: usize = 10;
+/// Each older set counts this fraction of the one after it in recency-weighted averages.
+const RECENCY_DECAY: f64 = 0.8;
+
+/// Mean of `values`, ordered oldest first, where each value weighs [`RECENCY_DECAY`] times
+/// the one after it, so the latest set dominates. Returns `None` for an empty slice.
+fn recency_weighted_mean(values: &[f64]) -> Option<f64> {
+    let mut weight = 1.0;
+    let mut weighted_sum = 0.0;
+    let mut total_weight = 0.0;
+    for value in values.iter().rev() {
+        weighted_sum += value * weight;
+        total_weight += weight;
+        weight *= RECENCY_DECAY;
+    }
+    (total_weight > 0.0).then(|| weighted_sum / total_weight)
+}
+
+const NO_PAST_PERFORMANCE: &str = "No significant past performance data available.";
+
+/// Recency-weighted weight, reps and RPE over an exercise's last
+/// [`PAST_PERFORMANCE_SETS`] sets, ordered oldest first. `None` without sets.
+fn past_performance_line(name: &str, sets: &[WorkoutSet]) -> Option<String> {
+    let recent = &sets[sets.len().saturating_sub(PAST_PERFORMANCE_SETS)..];
+    let weights: Vec<f64> = recent.iter().map(|s| s.weight).collect();
+    let reps: Vec<f64> = recent.iter().map(|s| s.reps as f64).collect();
+    let rpes: Vec<f64> = recent.iter().filter_map(|s| s.rpe).collect();
+    let avg_weight = recency_weighted_mean(&weights)?;
+    let avg_reps = recency_weighted_mean(&reps)?;
+    let avg_rpe = recency_weighted_mean(&rpes)
+        .map(|rpe| format!(" @ RPE {:.1}", rpe))
+        .unwrap_or_default();
+    Some(format!(
+        "{}: avg {:.1}kg x {} reps{} (from {} recent sets)",
+        name,
+        avg_weight,
+        avg_reps.round() as i64,
+        avg_rpe,
+        recent.len()
+    ))
+}
+
 impl Session {
     pub async fn get_active_workout_state(&self) -> Result<ActiveWorkoutState> {
         let workout_id = self.get_workout_id().await;
         let Some(workout_id) = workout_id else {
-            return Err(anyhow::anyhow!("No active workout"));
+            return Err(NoActiveWorkout.into());
         };
 
         let workout = get_workout_session(&self.db_pool, workout_id).await?;
-        let sets = get_sets_for_session(&self.db_pool, workout_id).await?;
+        let sets = get_sets_for_session(&self.db_pool, workout_id, None, None).await?;
         let exercises = self.get_all_exercises().await?;
+        let uniffi_sets = self.uniffi_sets(sets).await?;
 
         Ok(ActiveWorkoutState {
             workout: Arc::new(UniffiWorkoutSession::try_from(workout)?),
@@ -31,20 +76,14 @@ impl Session {
                 .into_iter()
                 .map(|e| Arc::new(UniffiExercise::from(e)))
                 .collect(),
-            sets: sets
-                .into_iter()
-                .map(|s| Arc::new(UniffiWorkoutSet::from(s)))
-                .collect(),
+            sets: uniffi_sets,
         })
     }
 
     pub async fn get_workout_suggestions(&self) -> Result<Vec<WorkoutSuggestion>> {
-        let session_id = self
-            .get_workout_id()
-            .await
-            .ok_or_else(|| anyhow::anyhow!("No active workout in session"))?;
+        let session_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
 
-        let sets = get_sets_for_session(&self.db_pool, session_id).await?;
+        let sets = get_sets_for_session(&self.db_pool, session_id, None, None).await?;
         let _workout = get_workout_session(&self.db_pool, session_id).await?;
 
         let mut exercise_counts: HashMap<i64, i64> = HashMap::new();
@@ -52,9 +91,7 @@ impl Session {
             *exercise_counts.entry(set.exercise_id).or_insert(0) += 1;
         }
 
-        let all_exercises = self.get_all_exercises().await?;
-        let exercise_map: HashMap<i64, String> =
-            all_exercises.into_iter().map(|e| (e.id, e.name)).collect();
+        let exercise_map = self.exercise_map().await?;
 
         let current_exercises: Vec<(String, i64)> = exercise_counts
             .iter()
@@ -64,27 +101,18 @@ impl Session {
         let mut past_performance_parts = Vec::new();
         for (ex_id, _count) in &exercise_counts {
             if let Some(ex_name) = exercise_map.get(ex_id) {
-                let past_sets = get_exercise_entries(&self.db_pool, *ex_id, Some(10))
-                    .await
-                    .ok();
-                if let Some(sets) = past_sets {
-                    if !sets.is_empty() {
-                        let avg_weight =
-                            sets.iter().map(|s| s.weight).sum::<f64>() / sets.len() as f64;
-                        let avg_reps = sets.iter().map(|s| s.reps).sum::<i64>() / sets.len() as i64;
-                        past_performance_parts.push(format!(
-                            "{}: avg {:.1}kg x {} reps (from {} recent sets)",
-                            ex_name,
-                            avg_weight,
-                            avg_reps,
-                            sets.len()
-                        ));
-                    }
+                let past_sets =
+                    get_exercise_entries(&self.db_pool, *ex_id, Some(PAST_PERFORMANCE_SETS as i64))
+                        .await
+                        .ok();
+                if let Some(line) = past_sets.and_then(|sets| past_performance_line(ex_name, &sets))
+                {
+                    past_performance_parts.push(line);
                 }
             }
         }
         let past_performance = if past_performance_parts.is_empty() {
-            "No significant past performance data available.".to_string()
+            NO_PAST_PERFORMANCE.to_string()
         } else {
             past_performance_parts.join("\n")
         };
@@ -96,20 +124,63 @@ impl Session {
         };
         let builder = PromptBuilder::new(ctx);
 
+        let trends = self
+            .exercise_trends(session_id, exercise_counts.keys().copied(), &exercise_map)
+            .await?;
+
         generate_workout_suggestions(
             self.llm_backend.as_ref(),
             &builder,
             &current_exercises,
             &past_performance,
+            &trends,
+        )
+        .await
+    }
+
+    /// Suggestions for one exercise, e.g. on its detail screen, from its recent weight, reps
+    /// and RPE and its trend in the latest workout it was logged in. Needs no active workout.
+    pub async fn get_exercise_suggestions(
+        &self,
+        exercise_id: i64,
+    ) -> Result<Vec<WorkoutSuggestion>> {
+        let exercise = get_exercise(&self.db_pool, exercise_id).await?;
+        let sets = get_exercise_entries(&self.db_pool, exercise_id, None).await?;
+        let latest_session_id = sets.last().map(|s| s.session_id);
+
+        let latest_count = sets
+            .iter()
+            .filter(|s| Some(s.session_id) == latest_session_id)
+            .count() as i64;
+        let current_exercises = vec![(exercise.name.clone(), latest_count)];
+        let past_performance = past_performance_line(&exercise.name, &sets)
+            .unwrap_or_else(|| NO_PAST_PERFORMANCE.to_string());
+
+        let trends = match latest_session_id {
+            Some(session_id) => {
+                let exercise_map = HashMap::from([(exercise_id, exercise.name.clone())]);
+                self.exercise_trends(session_id, std::iter::once(exercise_id), &exercise_map)
+                    .await?
+            }
+            None => vec![],
+        };
+
+        let builder = PromptBuilder::new(PromptContext {
+            known_exercises: self.exercise_map().await?.into_values().collect(),
+            ..Default::default()
+        });
+        generate_workout_suggestions(
+            self.llm_backend.as_ref(),
+            &builder,
+            &current_exercises,
+            &past_performance,
+            &trends,
         )
         .await
     }
 
     pub async fn get_workout_summary(&self) -> Result<WorkoutSummary> {
-        let session_id = self
-            .get_workout_id()
-            .await
-            .ok_or_else(|| anyhow::anyhow!("No active workout in session"))?;
+        let session_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
 
         let workout = get_workout_session(&self.db_pool, session_id).await?;
         if let Some(cached_summary) = workout.summary {
@@ -129,16 +200,14 @@ impl Session {
             }
         }
 
-        let sets = get_sets_for_session(&self.db_pool, session_id).await?;
+        let sets = get_sets_for_session(&self.db_pool, session_id, None, None).await?;
 
         let mut exercise_counts: HashMap<i64, i64> = HashMap::new();
         for set in &sets {
             *exercise_counts.entry(set.exercise_id).or_insert(0) += 1;
         }
 
-        let all_exercises = self.get_all_exercises().await?;
-        let exercise_map: HashMap<i64, String> =
-            all_exercises.into_iter().map(|e| (e.id, e.name)).collect();
+        let exercise_map = self.exercise_map().await?;
 
         let mut exercise_details = Vec::new();
         for (ex_id, count) in &exercise_counts {
@@ -205,11 +274,16 @@ impl Session {
             })
             .collect();
 
+        let trends = self
+            .exercise_trends(session_id, exercise_counts.keys().copied(), &exercise_map)
+            .await?;
+
         let summary = generate_workout_summary(
             self.llm_backend.as_ref(),
             &builder,
             &current_exercises,
             &detailed_exercises,
+            &trends,
         )
         .await?;
 
@@ -221,4 +295,124 @@ impl Session {
 
         Ok(summary)
     }
+
+    /// Classifies each exercise's top weight in this workout against its top weight in up to
+    /// three earlier workouts. Exercises with no earlier history are left out.
+    async fn exercise_trends(
+        &self,
+        session_id: i64,
+        exercise_ids: impl Iterator<Item = i64>,
+        exercise_map: &HashMap<i64, String>,
+    ) -> Result<Vec<(String, ProgressTrend)>> {
+        let mut trends = Vec::new();
+        for exercise_id in exercise_ids {
+            let Some(name) = exercise_map.get(&exercise_id) else {
+                continue;
+            };
+            let entries = get_exercise_entries(&self.db_pool, exercise_id, None).await?;
+
+            let mut current_top: Option<f64> = None;
+            let mut session_order: Vec<i64> = Vec::new();
+            let mut top_by_session: HashMap<i64, f64> = HashMap::new();
+            for set in &entries {
+                if set.session_id == session_id {
+                    current_top = Some(current_top.map_or(set.weight, |w| w.max(set.weight)));
+                    continue;
+                }
+                let top = top_by_session.entry(set.session_id).or_insert_with(|| {
+                    session_order.push(set.session_id);
+                    set.weight
+                });
+                *top = top.max(set.weight);
+            }
+
+            let Some(current_top) = current_top else {
+                continue;
+            };
+            let previous: Vec<f64> = session_order
+                .iter()
+                .rev()
+                .take(3)
+                .filter_map(|id| top_by_session.get(id).copied())
+                .collect();
+            if let Some(trend) = ProgressTrend::from_top_weights(&previous, current_top) {
+                trends.push((name.clone(), trend));
+            }
+        }
+        trends.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(trends)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LlmInterface, ParsedSet};
+
+    fn parsed(exercise: &str, weight: f32, reps: i32, rpe: Option<f32>) -> ParsedSet {
+        ParsedSet {
+            exercise: exercise.to_string(),
+            weight: Some(weight),
+            unit: None,
+            reps: Some(reps),
+            rpe,
+            set_count: None,
+            duration_seconds: None,
+            distance_meters: None,
+            tags: vec![],
+            aoi: None,
+            original_string: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exercise_suggestions_are_scoped_to_the_exercise() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_s, u| {
+            if !u.contains("Past Performance Summary") {
+                return String::new();
+            }
+            assert!(u.contains("- Bench Press (2 sets)"));
+            assert!(u.contains("Bench Press: avg"));
+            assert!(u.contains("@ RPE"));
+            assert!(!u.contains("- Squat"));
+            assert!(!u.contains("Squat: avg"));
+            r#"{"suggestions": [{"title": "Increase Bench Press to 102.5kg", "suggestion_type": "progression", "exercise_name": "Bench Press"}]}"#
+                .to_string()
+        }))
+        .await
+        .unwrap();
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5, Some(8.0)))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Bench Press", 100.0, 5, Some(8.5)))
+            .await
+            .unwrap();
+        session
+            .add_set_from_parsed(&parsed("Squat", 140.0, 5, None))
+            .await
+            .unwrap();
+        session.complete_workout(1800).await.unwrap();
+
+        let bench = session.get_or_create_exercise("Bench Press").await.unwrap();
+        let suggestions = session.get_exercise_suggestions(bench.id).await.unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].title, "Increase Bench Press to 102.5kg");
+    }
+
+    #[test]
+    fn test_recency_weighted_mean_favours_latest() {
+        assert_eq!(recency_weighted_mean(&[]), None);
+        assert_eq!(recency_weighted_mean(&[80.0]), Some(80.0));
+
+        // 120 is three sets old: (120*0.512 + 100*0.64 + 100*0.8 + 100) / 2.952
+        let mean = recency_weighted_mean(&[120.0, 100.0, 100.0, 100.0]).unwrap();
+        assert!((mean - 103.469).abs() < 1e-3);
+        assert!(mean < 105.0, "plain mean would be 105");
+
+        let rising = recency_weighted_mean(&[60.0, 80.0, 100.0]).unwrap();
+        assert!(rising > 80.0);
+    }
 }
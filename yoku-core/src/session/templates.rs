@@ -0,0 +1,153 @@
+use crate::db::models::{NewTemplateExercise, TemplateExercise, WorkoutTemplate};
+use crate::db::operations::{
+    add_multiple_sets_to_workout, create_request_string_for_username, create_workout_template,
+    get_exercise_entries, get_template_exercises, get_workout_template, get_workout_templates,
+};
+use crate::session::{NoActiveWorkout, Session};
+use anyhow::Result;
+
+impl Session {
+    pub async fn create_workout_template(
+        &self,
+        name: &str,
+        exercises: &[NewTemplateExercise],
+    ) -> Result<WorkoutTemplate> {
+        create_workout_template(&self.db_pool, name, exercises).await
+    }
+
+    /// Every template with its exercises in order, sorted by template name.
+    pub async fn get_workout_templates(
+        &self,
+    ) -> Result<Vec<(WorkoutTemplate, Vec<TemplateExercise>)>> {
+        let mut templates = Vec::new();
+        for template in get_workout_templates(&self.db_pool).await? {
+            let exercises = get_template_exercises(&self.db_pool, template.id).await?;
+            templates.push((template, exercises));
+        }
+        Ok(templates)
+    }
+
+    /// Starts a new in-progress workout named after the template, completing any workout
+    /// still in progress, and pre-fills it with the template's sets. Each placeholder set uses
+    /// the target reps and the last weight logged for that exercise (0 if never logged), so
+    /// the user only has to edit what changed. Returns the new workout id.
+    pub async fn instantiate_template(&self, template_id: i64) -> Result<i64> {
+        let template = get_workout_template(&self.db_pool, template_id).await?;
+        let exercises = get_template_exercises(&self.db_pool, template_id).await?;
+
+        self.new_workout_with_name(&template.name).await?;
+        let workout_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
+
+        let request_string = create_request_string_for_username(
+            &self.db_pool,
+            "cli",
+            format!("template: {}", template.name),
+        )
+        .await?;
+
+        for exercise in exercises {
+            let last_weight = get_exercise_entries(&self.db_pool, exercise.exercise_id, None)
+                .await?
+                .last()
+                .map(|s| s.weight)
+                .unwrap_or(0.0);
+            add_multiple_sets_to_workout(
+                &self.db_pool,
+                &workout_id,
+                &exercise.exercise_id,
+                &request_string.id,
+                &last_weight,
+                &exercise.target_reps,
+                None,
+                exercise.target_sets,
+            )
+            .await?;
+        }
+
+        Ok(workout_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::operations::get_sets_for_session;
+    use crate::llm::{LlmInterface, ParsedSet};
+
+    #[tokio::test]
+    async fn test_instantiate_template_prefills_sets() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        let bench = session.get_or_create_exercise("Bench Press").await.unwrap();
+        let row = session.get_or_create_exercise("Barbell Row").await.unwrap();
+
+        session.new_workout().await.unwrap();
+        session
+            .add_set_from_parsed(&ParsedSet {
+                exercise: "Bench Press".to_string(),
+                weight: Some(80.0),
+                unit: None,
+                reps: Some(5),
+                rpe: None,
+                set_count: Some(1),
+                duration_seconds: None,
+                distance_meters: None,
+                tags: vec![],
+                aoi: None,
+                original_string: String::new(),
+            })
+            .await
+            .unwrap();
+        let previous_id = session.get_workout_id().await.unwrap();
+
+        let template = session
+            .create_workout_template(
+                "Push Pull",
+                &[
+                    NewTemplateExercise {
+                        exercise_id: bench.id,
+                        target_sets: 3,
+                        target_reps: 5,
+                    },
+                    NewTemplateExercise {
+                        exercise_id: row.id,
+                        target_sets: 2,
+                        target_reps: 8,
+                    },
+                ],
+            )
+            .await
+            .unwrap();
+
+        let templates = session.get_workout_templates().await.unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].0.name, "Push Pull");
+        assert_eq!(
+            templates[0]
+                .1
+                .iter()
+                .map(|e| e.exercise_id)
+                .collect::<Vec<_>>(),
+            vec![bench.id, row.id]
+        );
+
+        let workout_id = session.instantiate_template(template.id).await.unwrap();
+        assert_ne!(workout_id, previous_id);
+        assert_eq!(session.get_workout_id().await, Some(workout_id));
+        assert_eq!(
+            session.get_workout_session().await.unwrap().name.as_deref(),
+            Some("Push Pull")
+        );
+
+        let sets = get_sets_for_session(&session.db_pool, workout_id, None, None)
+            .await
+            .unwrap();
+        let bench_sets: Vec<_> = sets.iter().filter(|s| s.exercise_id == bench.id).collect();
+        let row_sets: Vec<_> = sets.iter().filter(|s| s.exercise_id == row.id).collect();
+        assert_eq!(bench_sets.len(), 3);
+        assert_eq!(row_sets.len(), 2);
+        assert!(bench_sets.iter().all(|s| s.weight == 80.0 && s.reps == 5));
+        assert!(row_sets.iter().all(|s| s.weight == 0.0 && s.reps == 8));
+    }
+}
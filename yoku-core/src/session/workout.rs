@@ -1,35 +1,61 @@
-use crate::db::models::{WorkoutSession, WorkoutStatus};
+use crate::db::models::{
+    Muscle, NewWorkoutSet, WorkoutSession, WorkoutSet, WorkoutStats, WorkoutStatus,
+};
 use crate::db::operations::{
-    check_in_progress_workout_exists, complete_workout_session, create_workout_session,
-    get_in_progress_workout, get_workout_session, update_workout_duration,
+    auto_complete_stale_workouts, check_in_progress_workout_exists, complete_workout_session,
+    complete_workout_session_tx, compute_workout_duration, create_request_string_for_username_tx,
+    create_workout_session, create_workout_session_tx, delete_workout_session,
+    get_in_progress_workout, get_in_progress_workout_tx, get_most_similar_completed_session,
+    get_muscle_set_counts, get_session_volume, get_sets_for_session, get_training_calendar,
+    get_workout_session, get_workout_stats, insert_workout_set_tx, reopen_workout_session,
+    update_workout_duration,
 };
-use crate::session::Session;
+use crate::session::{NoActiveWorkout, Session};
 use anyhow::Result;
+use sqlx::{Sqlite, Transaction};
+use std::collections::HashMap;
+
+/// Sets after which a workout is considered long enough to wrap up.
+const COMPLETION_SET_THRESHOLD: usize = 15;
+/// Summed RPE after which a workout is considered hard enough to wrap up, e.g. 14 sets at
+/// RPE 9.
+const COMPLETION_RPE_THRESHOLD: f64 = 120.0;
 
 impl Session {
+    /// Soft-deletes a workout; it can be brought back with [`Session::restore_workout`].
     pub async fn delete_workout(&self, workout_id: i64) -> Result<u64> {
         crate::db::operations::delete_workout_session(&self.db_pool, workout_id).await
     }
 
+    /// Replaces a workout's notes, or clears them with `None`.
+    pub async fn set_notes(
+        &self,
+        workout_id: i64,
+        notes: Option<String>,
+    ) -> Result<WorkoutSession> {
+        crate::db::operations::update_workout_notes(&self.db_pool, workout_id, notes).await
+    }
+
+    pub async fn restore_workout(&self, workout_id: i64) -> Result<()> {
+        crate::db::operations::restore_workout_session(&self.db_pool, workout_id).await
+    }
+
+    /// Permanently removes workouts deleted before the `older_than` unix timestamp.
+    pub async fn purge_deleted_workouts(&self, older_than: i64) -> Result<u64> {
+        crate::db::operations::purge_deleted(&self.db_pool, older_than).await
+    }
+
     pub async fn set_workout_id(&self, workout_id: i64) -> Result<()> {
         let _ = get_workout_session(&self.db_pool, workout_id).await?;
         *self.workout_id.lock().await = Some(workout_id);
         Ok(())
     }
 
-    pub async fn new_workout(&self) -> Result<bool> {
-        let had_existing = check_in_progress_workout_exists(&self.db_pool).await?;
-
-        if had_existing {
-            if let Some(existing_workout) = get_in_progress_workout(&self.db_pool).await? {
-                complete_workout_session(&self.db_pool, existing_workout.id, 0).await?;
-                let current_id = self.get_workout_id().await;
-                if current_id == Some(existing_workout.id) {
-                    *self.workout_id.lock().await = None;
-                }
-            }
-        }
-
+    /// Starts a blank in-progress workout. Any workout already in progress is completed with a
+    /// duration of 0 and its id returned, so it can be brought back with
+    /// [`Session::restore_in_progress`].
+    pub async fn new_workout(&self) -> Result<Option<i64>> {
+        let discarded = self.discard_in_progress().await?;
         let workout = create_workout_session(
             &self.db_pool,
             None,
@@ -40,22 +66,12 @@ impl Session {
         )
         .await?;
         self.set_workout_id(workout.id).await?;
-        Ok(had_existing)
+        Ok(discarded)
     }
 
-    pub async fn new_workout_with_name(&self, name: &str) -> Result<bool> {
-        let had_existing = check_in_progress_workout_exists(&self.db_pool).await?;
-
-        if had_existing {
-            if let Some(existing_workout) = get_in_progress_workout(&self.db_pool).await? {
-                complete_workout_session(&self.db_pool, existing_workout.id, 0).await?;
-                let current_id = self.get_workout_id().await;
-                if current_id == Some(existing_workout.id) {
-                    *self.workout_id.lock().await = None;
-                }
-            }
-        }
-
+    /// Like [`Session::new_workout`], naming the new workout.
+    pub async fn new_workout_with_name(&self, name: &str) -> Result<Option<i64>> {
+        let discarded = self.discard_in_progress().await?;
         let workout = create_workout_session(
             &self.db_pool,
             None,
@@ -66,7 +82,108 @@ impl Session {
         )
         .await?;
         self.set_workout_id(workout.id).await?;
-        Ok(had_existing)
+        Ok(discarded)
+    }
+
+    async fn discard_in_progress(&self) -> Result<Option<i64>> {
+        let Some(existing_workout) = get_in_progress_workout(&self.db_pool).await? else {
+            return Ok(None);
+        };
+        complete_workout_session(&self.db_pool, existing_workout.id, 0).await?;
+        let current_id = self.get_workout_id().await;
+        if current_id == Some(existing_workout.id) {
+            *self.workout_id.lock().await = None;
+        }
+        Ok(Some(existing_workout.id))
+    }
+
+    /// Reopens a completed workout, typically one discarded by [`Session::new_workout`], and
+    /// makes it the active workout. A workout in progress in its place is deleted if it has no
+    /// sets yet and completed otherwise.
+    pub async fn restore_in_progress(&self, workout_id: i64) -> Result<()> {
+        if let Some(current) = get_in_progress_workout(&self.db_pool).await? {
+            if current.id == workout_id {
+                return self.set_workout_id(workout_id).await;
+            }
+            let sets = get_sets_for_session(&self.db_pool, current.id, Some(1), None).await?;
+            if sets.is_empty() {
+                delete_workout_session(&self.db_pool, current.id).await?;
+            } else {
+                complete_workout_session(&self.db_pool, current.id, 0).await?;
+            }
+        }
+        reopen_workout_session(&self.db_pool, workout_id).await?;
+        self.set_workout_id(workout_id).await
+    }
+
+    /// Copies a workout and all of its sets into a new completed session. Either the whole copy
+    /// is written or nothing is.
+    pub async fn duplicate_workout(&self, workout_id: i64) -> Result<WorkoutSession> {
+        let source = get_workout_session(&self.db_pool, workout_id).await?;
+        let sets = get_sets_for_session(&self.db_pool, workout_id, None, None).await?;
+
+        self.with_transaction(|tx| {
+            Box::pin(async move {
+                let copy = create_workout_session_tx(
+                    tx,
+                    source.user_id,
+                    source.name,
+                    source.notes,
+                    Some(source.duration_seconds),
+                    Some(WorkoutStatus::Completed),
+                )
+                .await?;
+                copy_sets_tx(tx, copy.id, sets, None).await?;
+                Ok(copy)
+            })
+        })
+        .await
+    }
+
+    /// Starts a new in-progress workout repeating every set of `source_session_id`, completing
+    /// any workout still in progress. Set indices restart at 1 for each exercise and the copied
+    /// sets get a fresh request string. The new workout and its sets are written together.
+    /// Returns the new workout id.
+    pub async fn copy_workout(&self, source_session_id: i64) -> Result<i64> {
+        let source = get_workout_session(&self.db_pool, source_session_id).await?;
+        let mut sets = get_sets_for_session(&self.db_pool, source_session_id, None, None).await?;
+        if sets.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Workout {} has no sets to copy",
+                source_session_id
+            ));
+        }
+        sets.sort_by_key(|s| (s.created_at, s.id));
+
+        let copy = self
+            .with_transaction(|tx| {
+                Box::pin(async move {
+                    if let Some(existing) = get_in_progress_workout_tx(tx).await? {
+                        complete_workout_session_tx(tx, existing.id, 0).await?;
+                    }
+                    let copy = create_workout_session_tx(
+                        tx,
+                        None,
+                        source.name,
+                        None,
+                        None,
+                        Some(WorkoutStatus::InProgress),
+                    )
+                    .await?;
+                    let request_string = create_request_string_for_username_tx(
+                        tx,
+                        "cli",
+                        format!("copy of workout {}", source_session_id),
+                    )
+                    .await?;
+                    copy_sets_tx(tx, copy.id, sets, Some(request_string.id)).await?;
+                    Ok(copy)
+                })
+            })
+            .await?;
+        self.set_workout_id(copy.id).await?;
+
+        Ok(copy.id)
     }
 
     pub async fn get_workout_session(&self) -> Result<WorkoutSession> {
@@ -74,7 +191,7 @@ impl Session {
         if let Some(workout_id) = workout_id {
             get_workout_session(&self.db_pool, workout_id).await
         } else {
-            Err(anyhow::anyhow!("No active workout"))
+            Err(NoActiveWorkout.into())
         }
     }
 
@@ -82,40 +199,500 @@ impl Session {
         crate::db::operations::get_all_workout_sessions(
             &self.db_pool,
             Some(WorkoutStatus::Completed),
+            None,
+            None,
+            None,
+            None,
         )
         .await
     }
 
     pub async fn get_all_workouts_including_in_progress(&self) -> Result<Vec<WorkoutSession>> {
-        crate::db::operations::get_all_workout_sessions(&self.db_pool, None).await
+        crate::db::operations::get_all_workout_sessions(&self.db_pool, None, None, None, None, None)
+            .await
+    }
+
+    /// Workouts of any status created at or after `after` and before `before`, in unix
+    /// seconds. Either bound may be left open.
+    pub async fn get_workouts_in_range(
+        &self,
+        after: Option<i64>,
+        before: Option<i64>,
+    ) -> Result<Vec<WorkoutSession>> {
+        crate::db::operations::get_all_workout_sessions(
+            &self.db_pool,
+            None,
+            after,
+            before,
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// One page of workouts of any status, newest first.
+    pub async fn get_workouts_page(&self, limit: i64, offset: i64) -> Result<Vec<WorkoutSession>> {
+        crate::db::operations::get_all_workout_sessions(
+            &self.db_pool,
+            None,
+            None,
+            None,
+            Some(limit),
+            Some(offset),
+        )
+        .await
     }
 
     pub async fn get_in_progress_workout(&self) -> Result<Option<WorkoutSession>> {
         get_in_progress_workout(&self.db_pool).await
     }
 
-    pub async fn complete_workout(&self, duration_seconds: i64) -> Result<()> {
+    /// Completes the active workout and returns the duration stored. A duration of 0 is
+    /// replaced by the span between its first and last set.
+    pub async fn complete_workout(&self, duration_seconds: i64) -> Result<i64> {
         let workout_id = self.get_workout_id().await;
         if let Some(workout_id) = workout_id {
+            let duration_seconds = if duration_seconds == 0 {
+                compute_workout_duration(&self.db_pool, workout_id).await?
+            } else {
+                duration_seconds
+            };
             complete_workout_session(&self.db_pool, workout_id, duration_seconds).await?;
             *self.workout_id.lock().await = None;
-            Ok(())
+            Ok(duration_seconds)
         } else {
-            Err(anyhow::anyhow!("No active workout to complete"))
+            Err(NoActiveWorkout.into())
         }
     }
 
+    /// Span between the first and last set of the active workout, in seconds.
+    pub async fn compute_workout_duration(&self) -> Result<i64> {
+        let workout_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
+        compute_workout_duration(&self.db_pool, workout_id).await
+    }
+
     pub async fn update_workout_elapsed_time(&self, elapsed_seconds: i64) -> Result<()> {
         let workout_id = self.get_workout_id().await;
         if let Some(workout_id) = workout_id {
             update_workout_duration(&self.db_pool, workout_id, elapsed_seconds).await?;
             Ok(())
         } else {
-            Err(anyhow::anyhow!("No active workout to update"))
+            Err(NoActiveWorkout.into())
         }
     }
 
     pub async fn check_in_progress_workout_exists(&self) -> Result<bool> {
         check_in_progress_workout_exists(&self.db_pool).await
     }
+
+    /// Closes abandoned in-progress workouts, forgetting the active workout if it was one
+    /// of them.
+    pub async fn auto_complete_stale_workouts(&self, inactivity_seconds: i64) -> Result<u64> {
+        let closed = auto_complete_stale_workouts(&self.db_pool, inactivity_seconds).await?;
+        if let Some(workout_id) = self.get_workout_id().await {
+            let workout = get_workout_session(&self.db_pool, workout_id).await?;
+            if workout.status == WorkoutStatus::Completed {
+                *self.workout_id.lock().await = None;
+            }
+        }
+        Ok(closed)
+    }
+
+    pub async fn get_workout_stats(&self) -> Result<WorkoutStats> {
+        get_workout_stats(&self.db_pool).await
+    }
+
+    pub async fn get_session_volume(&self, workout_id: i64) -> Result<f64> {
+        get_session_volume(&self.db_pool, workout_id).await
+    }
+
+    pub async fn get_muscle_set_counts(&self, workout_id: i64) -> Result<Vec<(Muscle, i64)>> {
+        get_muscle_set_counts(&self.db_pool, workout_id).await
+    }
+
+    pub async fn get_training_calendar(&self, days: i64) -> Result<Vec<(String, i64, f64)>> {
+        get_training_calendar(&self.db_pool, days).await
+    }
+
+    /// Records how many sets the active workout is meant to have, e.g. from a planned workout.
+    pub async fn set_target_set_count(&self, target_sets: i64) -> Result<()> {
+        let workout_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
+        *self.target_set_count.lock().await = Some((workout_id, target_sets));
+        Ok(())
+    }
+
+    /// Estimates the seconds left in the active workout as the sets still to do times the
+    /// average time per set (including rest) so far. The target comes from
+    /// [`Session::set_target_set_count`], falling back to the set count of the most similar
+    /// completed workout. Returns `None` without a target or before the first set.
+    pub async fn estimate_remaining_time(&self) -> Result<Option<i64>> {
+        let workout_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
+        let workout = get_workout_session(&self.db_pool, workout_id).await?;
+        let sets = get_sets_for_session(&self.db_pool, workout_id, None, None).await?;
+        let Some(last_set_at) = sets.iter().map(|s| s.created_at).max() else {
+            return Ok(None);
+        };
+
+        let target_sets = match *self.target_set_count.lock().await {
+            Some((id, target)) if id == workout_id => Some(target),
+            _ => None,
+        };
+        let target_sets = match target_sets {
+            Some(target) => target,
+            None => match get_most_similar_completed_session(&self.db_pool, workout_id).await? {
+                Some(template_id) => get_sets_for_session(&self.db_pool, template_id, None, None)
+                    .await?
+                    .len() as i64,
+                None => return Ok(None),
+            },
+        };
+
+        let completed = sets.len() as i64;
+        let remaining_sets = (target_sets - completed).max(0);
+        let elapsed = (last_set_at - workout.created_at).max(workout.duration_seconds);
+        Ok(Some(remaining_sets * elapsed / completed))
+    }
+
+    /// Whether the UI should nudge the user to finish the active workout: either enough sets
+    /// have been logged or their summed RPE, a crude fatigue proxy, is high enough.
+    pub async fn should_suggest_completion(&self) -> Result<bool> {
+        let workout_id = self.get_workout_id().await.ok_or(NoActiveWorkout)?;
+        let sets = get_sets_for_session(&self.db_pool, workout_id, None, None).await?;
+        let rpe_total: f64 = sets.iter().filter_map(|s| s.rpe).sum();
+        Ok(sets.len() >= COMPLETION_SET_THRESHOLD || rpe_total >= COMPLETION_RPE_THRESHOLD)
+    }
+}
+
+/// Inserts a copy of each of `sets` into workout `session_id`. Given a request string, the
+/// copies are logged afresh under it: notes are dropped and set indices restart at 1 for each
+/// exercise in the order given. Otherwise every set is copied as it is.
+async fn copy_sets_tx(
+    tx: &mut Transaction<'_, Sqlite>,
+    session_id: i64,
+    sets: Vec<WorkoutSet>,
+    request_string_id: Option<i64>,
+) -> Result<()> {
+    let mut next_index: HashMap<i64, i64> = HashMap::new();
+    for set in sets {
+        let (request_string_id, set_index, notes) = match request_string_id {
+            Some(request_string_id) => {
+                let set_index = next_index.entry(set.exercise_id).or_insert(0);
+                *set_index += 1;
+                (request_string_id, *set_index, None)
+            }
+            None => (set.request_string_id, set.set_index, set.notes),
+        };
+        let new_set = NewWorkoutSet {
+            session_id,
+            exercise_id: set.exercise_id,
+            request_string_id,
+            weight: set.weight,
+            reps: set.reps,
+            set_index,
+            rpe: set.rpe,
+            notes,
+            rest_style: set.rest_style,
+            duration_seconds: set.duration_seconds,
+            distance_meters: set.distance_meters,
+        };
+        insert_workout_set_tx(tx, &new_set).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LlmInterface, ParsedSet};
+
+    async fn session_with_logged_workout() -> (Session, i64) {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout_with_name("Push day").await.unwrap();
+        let parsed = ParsedSet {
+            exercise: "Bench Press".to_string(),
+            weight: Some(100.0),
+            unit: None,
+            reps: Some(5),
+            rpe: None,
+            set_count: Some(3),
+            duration_seconds: None,
+            distance_meters: None,
+            tags: vec![],
+            aoi: None,
+            original_string: "3x5 bench 100kg".to_string(),
+        };
+        session.add_set_from_parsed(&parsed).await.unwrap();
+        let workout_id = session.get_workout_id().await.unwrap();
+        (session, workout_id)
+    }
+
+    fn bench_set(set_count: i32) -> ParsedSet {
+        ParsedSet {
+            exercise: "Bench Press".to_string(),
+            weight: Some(100.0),
+            unit: None,
+            reps: Some(5),
+            rpe: None,
+            set_count: Some(set_count),
+            duration_seconds: None,
+            distance_meters: None,
+            tags: vec![],
+            aoi: None,
+            original_string: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_estimate_remaining_time_shrinks_as_sets_are_logged() {
+        let (session, _) = session_with_logged_workout().await;
+        session.set_target_set_count(9).await.unwrap();
+
+        session.update_workout_elapsed_time(900).await.unwrap();
+        let first = session.estimate_remaining_time().await.unwrap().unwrap();
+        assert_eq!(first, 6 * 300);
+
+        session.add_set_from_parsed(&bench_set(2)).await.unwrap();
+        session.update_workout_elapsed_time(1500).await.unwrap();
+        let second = session.estimate_remaining_time().await.unwrap().unwrap();
+        assert!(second > 0);
+        assert!(second < first);
+        assert_eq!(second, 4 * 300);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_remaining_time_falls_back_to_similar_workout() {
+        let (session, _) = session_with_logged_workout().await;
+        session.add_set_from_parsed(&bench_set(1)).await.unwrap();
+        session.complete_workout(1200).await.unwrap();
+
+        session.new_workout().await.unwrap();
+        assert_eq!(session.estimate_remaining_time().await.unwrap(), None);
+
+        session.add_set_from_parsed(&bench_set(1)).await.unwrap();
+        session.update_workout_elapsed_time(300).await.unwrap();
+        assert_eq!(
+            session.estimate_remaining_time().await.unwrap(),
+            Some(3 * 300)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_should_suggest_completion_after_enough_sets() {
+        let (session, _) = session_with_logged_workout().await;
+        session.add_set_from_parsed(&bench_set(11)).await.unwrap();
+        assert!(!session.should_suggest_completion().await.unwrap());
+
+        session.add_set_from_parsed(&bench_set(1)).await.unwrap();
+        assert!(session.should_suggest_completion().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_should_suggest_completion_when_fatigued() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        let mut hard = bench_set(12);
+        hard.rpe = Some(9.5);
+        session.add_set_from_parsed(&hard).await.unwrap();
+        assert!(!session.should_suggest_completion().await.unwrap());
+
+        // 13 sets is under the set threshold, but 13 x 9.5 crosses the RPE one.
+        hard.set_count = Some(1);
+        session.add_set_from_parsed(&hard).await.unwrap();
+        assert!(session.should_suggest_completion().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_workout_copies_sets() {
+        let (session, workout_id) = session_with_logged_workout().await;
+
+        let copy = session.duplicate_workout(workout_id).await.unwrap();
+        assert_ne!(copy.id, workout_id);
+        assert_eq!(copy.name, Some("Push day".to_string()));
+        assert_eq!(copy.status, WorkoutStatus::Completed);
+
+        let copied_sets = get_sets_for_session(&session.db_pool, copy.id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(copied_sets.len(), 3);
+        assert_eq!(
+            copied_sets.iter().map(|s| s.set_index).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_copy_workout_repeats_sets_in_new_workout() {
+        let (session, workout_id) = session_with_logged_workout().await;
+        let mut row = bench_set(2);
+        row.exercise = "Barbell Row".to_string();
+        row.weight = Some(70.0);
+        row.reps = Some(8);
+        row.rpe = Some(8.0);
+        session.add_set_from_parsed(&row).await.unwrap();
+        session.complete_workout(1800).await.unwrap();
+
+        let copy_id = session.copy_workout(workout_id).await.unwrap();
+        assert_ne!(copy_id, workout_id);
+        assert_eq!(session.get_workout_id().await, Some(copy_id));
+        let copy = session.get_workout_session().await.unwrap();
+        assert_eq!(copy.status, WorkoutStatus::InProgress);
+        assert_eq!(copy.name, Some("Push day".to_string()));
+
+        let source_sets = get_sets_for_session(&session.db_pool, workout_id, None, None)
+            .await
+            .unwrap();
+        let copied_sets = get_sets_for_session(&session.db_pool, copy_id, None, None)
+            .await
+            .unwrap();
+        assert_eq!(copied_sets.len(), 5);
+        assert!(copied_sets.iter().all(|c| {
+            source_sets
+                .iter()
+                .all(|s| s.request_string_id != c.request_string_id)
+        }));
+
+        let summary = |sets: &[crate::db::models::WorkoutSet]| {
+            let mut summary: Vec<_> = sets
+                .iter()
+                .map(|s| (s.exercise_id, s.set_index, s.weight, s.reps, s.rpe))
+                .collect();
+            summary.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            summary
+        };
+        assert_eq!(summary(&copied_sets), summary(&source_sets));
+    }
+
+    #[tokio::test]
+    async fn test_copy_workout_rejects_empty_source() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        let empty_id = session.get_workout_id().await.unwrap();
+
+        assert!(session.copy_workout(empty_id).await.is_err());
+        assert!(session.copy_workout(empty_id + 100).await.is_err());
+        assert_eq!(session.get_workout_id().await, Some(empty_id));
+    }
+
+    #[tokio::test]
+    async fn test_copy_workout_leaves_no_workout_behind_on_failure() {
+        let (session, workout_id) = session_with_logged_workout().await;
+        session.complete_workout(1800).await.unwrap();
+        session.new_workout().await.unwrap();
+        let in_progress_id = session.get_workout_id().await.unwrap();
+
+        sqlx::query(&format!(
+            "CREATE TRIGGER fail_second_copied_set BEFORE INSERT ON workout_sets
+             WHEN NEW.session_id != {} AND NEW.set_index = 2
+             BEGIN SELECT RAISE(ABORT, 'injected failure'); END",
+            workout_id
+        ))
+        .execute(&session.db_pool)
+        .await
+        .unwrap();
+
+        assert!(session.copy_workout(workout_id).await.is_err());
+        let workouts = session
+            .get_all_workouts_including_in_progress()
+            .await
+            .unwrap();
+        assert_eq!(workouts.len(), 2);
+        assert!(workouts.iter().any(|w| w.id == workout_id));
+        // The workout that was in progress is only discarded if the copy succeeds.
+        let in_progress = workouts.iter().find(|w| w.id == in_progress_id).unwrap();
+        assert_eq!(in_progress.status, WorkoutStatus::InProgress);
+        assert_eq!(session.get_workout_id().await, Some(in_progress_id));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_workout_rolls_back_on_failure() {
+        let (session, workout_id) = session_with_logged_workout().await;
+
+        // Fail the copy part-way through: the session row and the first set are written
+        // before the second set insert aborts.
+        sqlx::query(&format!(
+            "CREATE TRIGGER fail_second_copied_set BEFORE INSERT ON workout_sets
+             WHEN NEW.session_id != {} AND NEW.set_index = 2
+             BEGIN SELECT RAISE(ABORT, 'injected failure'); END",
+            workout_id
+        ))
+        .execute(&session.db_pool)
+        .await
+        .unwrap();
+
+        let result = session.duplicate_workout(workout_id).await;
+        assert!(result.is_err());
+
+        let workouts = session
+            .get_all_workouts_including_in_progress()
+            .await
+            .unwrap();
+        assert_eq!(workouts.len(), 1);
+        assert_eq!(workouts[0].id, workout_id);
+
+        let set_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workout_sets")
+            .fetch_one(&session.db_pool)
+            .await
+            .unwrap();
+        assert_eq!(set_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_new_workout_returns_discarded_workout_for_restore() {
+        let (session, logged_id) = session_with_logged_workout().await;
+
+        let discarded = session.new_workout().await.unwrap();
+        assert_eq!(discarded, Some(logged_id));
+        let blank_id = session.get_workout_id().await.unwrap();
+        assert_ne!(blank_id, logged_id);
+        assert_eq!(
+            get_workout_session(&session.db_pool, logged_id)
+                .await
+                .unwrap()
+                .status,
+            WorkoutStatus::Completed
+        );
+
+        session.restore_in_progress(logged_id).await.unwrap();
+        assert_eq!(session.get_workout_id().await, Some(logged_id));
+        let restored = get_in_progress_workout(&session.db_pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(restored.id, logged_id);
+        assert_eq!(session.get_all_sets().await.unwrap().len(), 3);
+        assert!(
+            get_workout_session(&session.db_pool, blank_id)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restore_in_progress_keeps_logged_replacement() {
+        let (session, logged_id) = session_with_logged_workout().await;
+        assert_eq!(session.new_workout().await.unwrap(), Some(logged_id));
+        let replacement_id = session.get_workout_id().await.unwrap();
+        session.add_set_from_parsed(&bench_set(1)).await.unwrap();
+
+        session.restore_in_progress(logged_id).await.unwrap();
+        let replacement = get_workout_session(&session.db_pool, replacement_id)
+            .await
+            .unwrap();
+        assert_eq!(replacement.status, WorkoutStatus::Completed);
+        assert!(session.restore_in_progress(logged_id + 100).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_new_workout_without_existing_discards_nothing() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        assert_eq!(session.new_workout().await.unwrap(), None);
+    }
 }
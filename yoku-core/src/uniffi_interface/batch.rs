@@ -0,0 +1,181 @@
+use crate::session::Session;
+use crate::uniffi_interface::errors::YokuError;
+use crate::uniffi_interface::objects::{
+    ActiveWorkoutState, Exercise, WorkoutStats, WorkoutSuggestion,
+};
+use std::sync::Arc;
+
+#[derive(uniffi::Enum, Debug, Clone)]
+pub enum QueryRequest {
+    Stats,
+    Suggestions,
+    ActiveState,
+    RecentExercises { limit: i64 },
+}
+
+#[derive(uniffi::Enum)]
+pub enum QueryResponse {
+    Stats {
+        stats: WorkoutStats,
+    },
+    Suggestions {
+        suggestions: Vec<Arc<WorkoutSuggestion>>,
+    },
+    ActiveState {
+        state: ActiveWorkoutState,
+    },
+    RecentExercises {
+        exercises: Vec<Arc<Exercise>>,
+    },
+    NoActiveWorkout,
+    Failed {
+        message: String,
+    },
+}
+
+impl From<YokuError> for QueryResponse {
+    fn from(e: YokuError) -> Self {
+        match e {
+            YokuError::NoActiveWorkout => QueryResponse::NoActiveWorkout,
+            other => QueryResponse::Failed {
+                message: other.to_string(),
+            },
+        }
+    }
+}
+
+async fn run_query(session: &Session, request: &QueryRequest) -> Result<QueryResponse, YokuError> {
+    let response = match request {
+        QueryRequest::Stats => QueryResponse::Stats {
+            stats: session.get_workout_stats().await?.into(),
+        },
+        QueryRequest::Suggestions => QueryResponse::Suggestions {
+            suggestions: session
+                .get_workout_suggestions()
+                .await?
+                .into_iter()
+                .map(|s| Arc::new(WorkoutSuggestion::from(s)))
+                .collect(),
+        },
+        QueryRequest::ActiveState => QueryResponse::ActiveState {
+            state: session.get_active_workout_state().await?,
+        },
+        QueryRequest::RecentExercises { limit } => QueryResponse::RecentExercises {
+            exercises: session
+                .get_recent_exercises(*limit)
+                .await?
+                .into_iter()
+                .map(|e| Arc::new(Exercise::from(e)))
+                .collect(),
+        },
+    };
+    Ok(response)
+}
+
+/// Answers each request in order; a failing request yields a `Failed` or
+/// `NoActiveWorkout` response in its slot rather than failing the whole batch.
+pub async fn run_batch(session: &Session, requests: &[QueryRequest]) -> Vec<QueryResponse> {
+    let mut responses = Vec::with_capacity(requests.len());
+    for request in requests {
+        let response = run_query(session, request)
+            .await
+            .unwrap_or_else(QueryResponse::from);
+        responses.push(response);
+    }
+    responses
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn batch_query(session: &Session, requests: Vec<QueryRequest>) -> Vec<QueryResponse> {
+    run_batch(session, &requests).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{LlmInterface, ParsedSet};
+
+    const SUGGESTIONS_REPLY: &str = r#"{"suggestions":[{"title":"Overhead Press","subtitle":"3x8","suggestion_type":"exercise","exercise_name":"Overhead Press","reasoning":"Balance pushing volume"}]}"#;
+
+    async fn session_with_logged_workout() -> Session {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| {
+            SUGGESTIONS_REPLY.to_string()
+        }))
+        .await
+        .unwrap();
+        session.new_workout().await.unwrap();
+        let parsed = ParsedSet {
+            exercise: "Bench Press".to_string(),
+            weight: Some(100.0),
+            unit: None,
+            reps: Some(5),
+            rpe: None,
+            set_count: Some(2),
+            duration_seconds: None,
+            distance_meters: None,
+            tags: vec![],
+            aoi: None,
+            original_string: "2x5 bench 100kg".to_string(),
+        };
+        session.add_set_from_parsed(&parsed).await.unwrap();
+        session
+    }
+
+    #[tokio::test]
+    async fn test_batch_matches_individual_calls() {
+        let session = session_with_logged_workout().await;
+
+        let responses = run_batch(
+            &session,
+            &[
+                QueryRequest::Stats,
+                QueryRequest::RecentExercises { limit: 5 },
+                QueryRequest::Suggestions,
+            ],
+        )
+        .await;
+        assert_eq!(responses.len(), 3);
+
+        let QueryResponse::Stats { stats } = &responses[0] else {
+            panic!("expected stats response");
+        };
+        let expected_stats: WorkoutStats = session.get_workout_stats().await.unwrap().into();
+        assert_eq!(stats, &expected_stats);
+
+        let QueryResponse::RecentExercises { exercises } = &responses[1] else {
+            panic!("expected recent exercises response");
+        };
+        let expected_recent = session.get_recent_exercises(5).await.unwrap();
+        assert_eq!(
+            exercises.iter().map(|e| e.id).collect::<Vec<_>>(),
+            expected_recent.iter().map(|e| e.id).collect::<Vec<_>>()
+        );
+
+        let QueryResponse::Suggestions { suggestions } = &responses[2] else {
+            panic!("expected suggestions response");
+        };
+        let expected_suggestions = session.get_workout_suggestions().await.unwrap();
+        assert_eq!(
+            suggestions
+                .iter()
+                .map(|s| s.title.clone())
+                .collect::<Vec<_>>(),
+            expected_suggestions
+                .iter()
+                .map(|s| s.title.clone())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_reports_missing_workout_per_request() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+
+        let responses =
+            run_batch(&session, &[QueryRequest::ActiveState, QueryRequest::Stats]).await;
+        assert!(matches!(responses[0], QueryResponse::NoActiveWorkout));
+        assert!(matches!(responses[1], QueryResponse::Stats { .. }));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::llm::{LlmBadJson, LlmTimeout, LlmUnavailable};
+use crate::session::NoActiveWorkout;
 use thiserror::Error as ThisError;
 use uniffi::Error;
 
@@ -8,10 +10,30 @@ pub enum YokuError {
     Common(String),
     #[error("date conversion error: {0}")]
     DateConversionError(String),
+    #[error("no active workout")]
+    NoActiveWorkout,
+    /// The model could not be reached, rejected the request or timed out.
+    #[error("LLM unavailable: {0}")]
+    LlmUnavailable(String),
+    /// The model answered, but not with JSON in the expected shape.
+    #[error("LLM returned invalid JSON")]
+    LlmBadJson { raw: String },
 }
 
 impl From<anyhow::Error> for YokuError {
     fn from(e: anyhow::Error) -> Self {
+        if e.downcast_ref::<NoActiveWorkout>().is_some() {
+            return YokuError::NoActiveWorkout;
+        }
+        if let Some(bad_json) = e.downcast_ref::<LlmBadJson>() {
+            return YokuError::LlmBadJson {
+                raw: bad_json.raw.clone(),
+            };
+        }
+        if e.downcast_ref::<LlmUnavailable>().is_some() || e.downcast_ref::<LlmTimeout>().is_some()
+        {
+            return YokuError::LlmUnavailable(format!("{:#}", e));
+        }
         YokuError::Common(e.to_string())
     }
 }
@@ -33,3 +55,63 @@ impl YokuError {
         YokuError::Common(d.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::LlmInterface;
+    use crate::session::Session;
+
+    async fn session_without_workout() -> Session {
+        Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_all_sets_without_workout_is_typed() {
+        let session = session_without_workout().await;
+        let err = YokuError::from(session.get_all_sets().await.unwrap_err());
+        assert!(matches!(err, YokuError::NoActiveWorkout));
+    }
+
+    #[tokio::test]
+    async fn test_get_workout_summary_without_workout_is_typed() {
+        let session = session_without_workout().await;
+        let err = YokuError::from(session.get_workout_summary().await.unwrap_err());
+        assert!(matches!(err, YokuError::NoActiveWorkout));
+    }
+
+    #[tokio::test]
+    async fn test_bad_json_reply_is_typed() {
+        let llm = LlmInterface::new_mock_fn(|_, _| "```json\nnot json\n```".to_string());
+        let err = llm
+            .call_json::<serde_json::Value>("system", "user")
+            .await
+            .unwrap_err();
+        match YokuError::from(err) {
+            YokuError::LlmBadJson { raw } => assert_eq!(raw, "not json"),
+            other => panic!("expected LlmBadJson, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_llm_timeout_is_unavailable() {
+        let llm =
+            LlmInterface::new_mock_delayed_fn(std::time::Duration::from_millis(200), |_, _| {
+                "{}".to_string()
+            })
+            .with_timeout(std::time::Duration::from_millis(10));
+        let err = llm
+            .call_json::<serde_json::Value>("system", "user")
+            .await
+            .unwrap_err();
+        assert!(matches!(YokuError::from(err), YokuError::LlmUnavailable(_)));
+    }
+
+    #[test]
+    fn test_other_errors_stay_common() {
+        let err = YokuError::from(anyhow::anyhow!("database is locked"));
+        assert!(matches!(err, YokuError::Common(_)));
+    }
+}
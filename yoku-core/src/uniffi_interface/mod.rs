@@ -1,3 +1,4 @@
+pub mod batch;
 pub mod errors;
 pub mod logging;
 pub mod modifications;
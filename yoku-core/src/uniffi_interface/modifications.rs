@@ -7,6 +7,16 @@ pub struct Modification {
     pub set: Option<std::sync::Arc<crate::uniffi_interface::objects::WorkoutSet>>,
     pub sets: Option<Vec<std::sync::Arc<crate::uniffi_interface::objects::WorkoutSet>>>,
     pub exercise: Option<std::sync::Arc<crate::uniffi_interface::objects::Exercise>>,
+    pub suggested_existing: Option<SuggestedExercise>,
+}
+
+/// An existing exercise whose name is a near match for a newly logged one, so the UI can
+/// offer to merge a likely typo.
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct SuggestedExercise {
+    pub exercise_id: i64,
+    pub name: String,
+    pub similarity: f64,
 }
 
 #[derive(Debug, Clone, uniffi::Enum)]
@@ -22,3 +32,9 @@ pub struct UpdateWorkoutSetResult {
     pub set: std::sync::Arc<crate::uniffi_interface::objects::WorkoutSet>,
     pub modifications: Vec<Modification>,
 }
+
+/// Implemented by the app to be told about set changes as they happen instead of polling.
+#[uniffi::export(with_foreign)]
+pub trait ModificationObserver: Send + Sync {
+    fn on_modifications(&self, modifications: Vec<Modification>);
+}
@@ -4,8 +4,9 @@ use crate::{db, uniffi_interface::errors};
 
 #[derive(uniffi::Object, Debug, Clone)]
 pub struct Exercise {
-    id: i64,
-    name: String,
+    pub id: i64,
+    pub name: String,
+    pub is_favorite: bool,
 }
 
 #[uniffi::export]
@@ -17,6 +18,10 @@ impl Exercise {
     fn name(&self) -> String {
         self.name.clone()
     }
+
+    fn is_favorite(&self) -> bool {
+        self.is_favorite
+    }
 }
 
 impl From<db::models::Exercise> for Exercise {
@@ -24,6 +29,7 @@ impl From<db::models::Exercise> for Exercise {
         Exercise {
             id: e.id,
             name: e.name,
+            is_favorite: e.is_favorite,
         }
     }
 }
@@ -43,6 +49,59 @@ impl From<db::models::WorkoutStatus> for WorkoutStatus {
     }
 }
 
+#[derive(uniffi::Enum, Copy, Clone, Debug, PartialEq)]
+pub enum RestStyle {
+    Hypertrophy,
+    Strength,
+    Conditioning,
+    Giant,
+}
+
+impl From<db::models::RestStyle> for RestStyle {
+    fn from(s: db::models::RestStyle) -> Self {
+        match s {
+            db::models::RestStyle::Hypertrophy => RestStyle::Hypertrophy,
+            db::models::RestStyle::Strength => RestStyle::Strength,
+            db::models::RestStyle::Conditioning => RestStyle::Conditioning,
+            db::models::RestStyle::Giant => RestStyle::Giant,
+        }
+    }
+}
+
+#[derive(uniffi::Enum, Copy, Clone, Debug, PartialEq)]
+pub enum MovementPattern {
+    Unknown,
+    HorizontalPush,
+    HorizontalPull,
+    VerticalPush,
+    VerticalPull,
+    HipHinge,
+    Squat,
+    Lunge,
+    Carry,
+    Rotation,
+    Isolation,
+}
+
+impl From<db::models::ExercisePatternType> for MovementPattern {
+    fn from(p: db::models::ExercisePatternType) -> Self {
+        use db::models::ExercisePatternType as P;
+        match p {
+            P::Unknown => MovementPattern::Unknown,
+            P::HorizontalPush => MovementPattern::HorizontalPush,
+            P::HorizontalPull => MovementPattern::HorizontalPull,
+            P::VerticalPush => MovementPattern::VerticalPush,
+            P::VerticalPull => MovementPattern::VerticalPull,
+            P::HipHinge => MovementPattern::HipHinge,
+            P::Squat => MovementPattern::Squat,
+            P::Lunge => MovementPattern::Lunge,
+            P::Carry => MovementPattern::Carry,
+            P::Rotation => MovementPattern::Rotation,
+            P::Isolation => MovementPattern::Isolation,
+        }
+    }
+}
+
 #[derive(uniffi::Object)]
 pub struct WorkoutSession {
     pub id: i64,
@@ -108,6 +167,12 @@ pub struct WorkoutSet {
     pub reps: i64,
     pub rpe: Option<f64>,
     pub notes: Option<String>,
+    pub rest_style: RestStyle,
+    pub duration_seconds: Option<i64>,
+    pub distance_meters: Option<f64>,
+    pub superset_group: Option<i64>,
+    pub tags: Vec<String>,
+    pub aoi: Option<String>,
 }
 
 #[uniffi::export]
@@ -133,11 +198,41 @@ impl WorkoutSet {
         self.rpe
     }
 
+    /// Reps in reserve implied by the set's RPE, `None` when no RPE was logged.
+    fn reps_in_reserve(&self) -> Option<f64> {
+        self.rpe.map(crate::metrics::rir_from_rpe)
+    }
+
     fn notes(&self) -> Option<String> {
         self.notes.clone()
     }
+
+    fn rest_style(&self) -> RestStyle {
+        self.rest_style
+    }
+
+    fn duration_seconds(&self) -> Option<i64> {
+        self.duration_seconds
+    }
+
+    fn distance_meters(&self) -> Option<f64> {
+        self.distance_meters
+    }
+
+    fn superset_group(&self) -> Option<i64> {
+        self.superset_group
+    }
+
+    fn tags(&self) -> Vec<String> {
+        self.tags.clone()
+    }
+
+    fn aoi(&self) -> Option<String> {
+        self.aoi.clone()
+    }
 }
 
+/// Leaves tags and area of interest empty; [`crate::session::Session::uniffi_set`] loads them.
 impl From<db::models::WorkoutSet> for WorkoutSet {
     fn from(s: db::models::WorkoutSet) -> Self {
         WorkoutSet {
@@ -147,6 +242,12 @@ impl From<db::models::WorkoutSet> for WorkoutSet {
             reps: s.reps,
             rpe: s.rpe,
             notes: s.notes,
+            duration_seconds: s.duration_seconds,
+            distance_meters: s.distance_meters,
+            superset_group: s.superset_group,
+            rest_style: s.rest_style.unwrap_or_default().into(),
+            tags: Vec::new(),
+            aoi: None,
         }
     }
 }
@@ -158,6 +259,7 @@ pub struct WorkoutSuggestion {
     pub suggestion_type: String,
     pub exercise_name: Option<String>,
     pub reasoning: Option<String>,
+    pub confidence: Option<f64>,
 }
 
 #[uniffi::export]
@@ -181,6 +283,10 @@ impl WorkoutSuggestion {
     fn reasoning(&self) -> Option<String> {
         self.reasoning.clone()
     }
+
+    fn confidence(&self) -> Option<f64> {
+        self.confidence
+    }
 }
 
 impl From<crate::llm::WorkoutSuggestion> for WorkoutSuggestion {
@@ -191,6 +297,7 @@ impl From<crate::llm::WorkoutSuggestion> for WorkoutSuggestion {
             suggestion_type: s.suggestion_type,
             exercise_name: s.exercise_name,
             reasoning: s.reasoning,
+            confidence: s.confidence,
         }
     }
 }
@@ -227,3 +334,194 @@ pub struct ActiveWorkoutState {
     pub exercises: Vec<std::sync::Arc<Exercise>>,
     pub sets: Vec<std::sync::Arc<WorkoutSet>>,
 }
+
+#[derive(uniffi::Record)]
+pub struct GroupedExercise {
+    pub exercise: std::sync::Arc<Exercise>,
+    pub sets: Vec<std::sync::Arc<WorkoutSet>>,
+}
+
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct WorkoutStats {
+    pub completed_workouts: i64,
+    pub total_sets: i64,
+    pub total_volume: f64,
+}
+
+impl From<db::models::WorkoutStats> for WorkoutStats {
+    fn from(s: db::models::WorkoutStats) -> Self {
+        WorkoutStats {
+            completed_workouts: s.completed_workouts,
+            total_sets: s.total_sets,
+            total_volume: s.total_volume,
+        }
+    }
+}
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct MuscleSetCount {
+    pub muscle_id: i64,
+    pub muscle_name: String,
+    pub set_count: i64,
+}
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct MuscleContribution {
+    pub muscle_name: String,
+    pub percentage: f64,
+}
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct TrainingDay {
+    pub date: String,
+    pub set_count: i64,
+    pub tonnage: f64,
+}
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct Equipment {
+    pub id: i64,
+    pub name: String,
+}
+
+impl From<db::models::Equipment> for Equipment {
+    fn from(e: db::models::Equipment) -> Self {
+        Equipment {
+            id: e.id,
+            name: e.name,
+        }
+    }
+}
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct PersonalRecord {
+    pub exercise_id: i64,
+    pub exercise_name: String,
+    pub weight: f64,
+    pub reps: i64,
+    pub previous_best: f64,
+}
+
+#[derive(uniffi::Enum, Debug, Clone)]
+pub enum ReviewIssue {
+    Imbalance {
+        dominant: String,
+        dominant_sets: i64,
+        lagging: String,
+        lagging_sets: i64,
+    },
+    MissedMuscle {
+        muscle: String,
+    },
+}
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct WorkoutReview {
+    pub personal_records: Vec<PersonalRecord>,
+    pub tonnage: f64,
+    pub previous_session_id: Option<i64>,
+    pub previous_tonnage: Option<f64>,
+    pub issues: Vec<ReviewIssue>,
+}
+
+impl From<crate::session::WorkoutReview> for WorkoutReview {
+    fn from(r: crate::session::WorkoutReview) -> Self {
+        WorkoutReview {
+            personal_records: r
+                .personal_records
+                .into_iter()
+                .map(|pr| PersonalRecord {
+                    exercise_id: pr.exercise_id,
+                    exercise_name: pr.exercise_name,
+                    weight: pr.weight,
+                    reps: pr.reps,
+                    previous_best: pr.previous_best,
+                })
+                .collect(),
+            tonnage: r.tonnage,
+            previous_session_id: r.previous_session_id,
+            previous_tonnage: r.previous_tonnage,
+            issues: r
+                .issues
+                .into_iter()
+                .map(|issue| match issue {
+                    crate::session::ReviewIssue::Imbalance {
+                        dominant,
+                        dominant_sets,
+                        lagging,
+                        lagging_sets,
+                    } => ReviewIssue::Imbalance {
+                        dominant,
+                        dominant_sets,
+                        lagging,
+                        lagging_sets,
+                    },
+                    crate::session::ReviewIssue::MissedMuscle { muscle } => {
+                        ReviewIssue::MissedMuscle { muscle }
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct TemplateExercise {
+    pub exercise_id: i64,
+    pub target_sets: i64,
+    pub target_reps: i64,
+}
+
+impl From<db::models::TemplateExercise> for TemplateExercise {
+    fn from(e: db::models::TemplateExercise) -> Self {
+        TemplateExercise {
+            exercise_id: e.exercise_id,
+            target_sets: e.target_sets,
+            target_reps: e.target_reps,
+        }
+    }
+}
+
+impl From<TemplateExercise> for db::models::NewTemplateExercise {
+    fn from(e: TemplateExercise) -> Self {
+        db::models::NewTemplateExercise {
+            exercise_id: e.exercise_id,
+            target_sets: e.target_sets,
+            target_reps: e.target_reps,
+        }
+    }
+}
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct WorkoutTemplate {
+    pub id: i64,
+    pub name: String,
+    pub exercises: Vec<TemplateExercise>,
+}
+
+/// One past set of an exercise with the workout it was logged in.
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct ExerciseHistoryEntry {
+    pub set_id: i64,
+    pub session_id: i64,
+    pub session_name: Option<String>,
+    pub weight: f64,
+    pub reps: i64,
+    pub rpe: Option<f64>,
+    /// When the workout started, as a unix timestamp.
+    pub date: i64,
+}
+
+impl From<(db::models::WorkoutSet, db::models::WorkoutSession)> for ExerciseHistoryEntry {
+    fn from((set, session): (db::models::WorkoutSet, db::models::WorkoutSession)) -> Self {
+        ExerciseHistoryEntry {
+            set_id: set.id,
+            session_id: session.id,
+            session_name: session.name,
+            weight: set.weight,
+            reps: set.reps,
+            rpe: set.rpe,
+            date: session.created_at,
+        }
+    }
+}
@@ -1,35 +1,52 @@
-use crate::db;
 use crate::db::models::UpdateWorkoutSet;
-use crate::session::Session;
+use crate::db::operations::estimate_one_rep_max;
+use crate::session::{Session, SessionConfig};
 use crate::uniffi_interface::errors::YokuError;
-use crate::uniffi_interface::modifications::{Modification, UpdateWorkoutSetResult};
+use crate::uniffi_interface::modifications::{
+    Modification, ModificationObserver, UpdateWorkoutSetResult,
+};
 use crate::uniffi_interface::objects::{
-    ActiveWorkoutState, Exercise, WorkoutSession, WorkoutSet, WorkoutSuggestion, WorkoutSummary,
+    ActiveWorkoutState, Equipment, Exercise, ExerciseHistoryEntry, GroupedExercise,
+    MovementPattern, MuscleContribution, MuscleSetCount, TemplateExercise, TrainingDay,
+    WorkoutReview, WorkoutSession, WorkoutSet, WorkoutSuggestion, WorkoutSummary, WorkoutTemplate,
 };
 use std::sync::Arc;
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn create_session(
     db_path: &str,
     model: String,
     graph_path: &str,
 ) -> std::result::Result<Session, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let session = rt.block_on(Session::new(db_path, model, graph_path))?;
+    let session = Session::new(db_path, model, graph_path).await?;
     Ok(session)
 }
 
-#[uniffi::export]
-pub async fn reset_database(session: &Session) -> std::result::Result<(), YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    rt.block_on(async {
-        db::drop_all_tables(&session.db_pool).await?;
-        db::init_database(&session.db_pool).await?;
-        Ok::<(), crate::uniffi_interface::errors::YokuError>(())
-    })?;
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn create_session_with_config(
+    db_path: &str,
+    model: String,
+    config: SessionConfig,
+) -> std::result::Result<Session, YokuError> {
+    let session = Session::new_with_config(db_path, Some(model), config).await?;
+    Ok(session)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn reset_database_confirmed(
+    session: &Session,
+    confirmation: String,
+) -> std::result::Result<(), YokuError> {
+    session.reset_database_confirmed(&confirmation).await?;
     Ok(())
 }
 
+/// Whether the configured LLM backend answers right now; a failure is reported as `false`.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn check_llm_available(session: &Session) -> bool {
+    session.llm_backend.health_check().await.unwrap_or(false)
+}
+
 #[derive(uniffi::Object)]
 pub struct LiftDataPoint {
     pub timestamp: i64,
@@ -46,58 +63,278 @@ impl LiftDataPoint {
 }
 
 #[uniffi::export]
-pub async fn delete_workout(session: &Session, id: i64) -> std::result::Result<u64, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    rt.block_on(session.delete_workout(id))
-        .map_err(|e| e.into())
+pub fn rir_from_rpe(rpe: f64) -> f64 {
+    crate::metrics::rir_from_rpe(rpe)
 }
 
 #[uniffi::export]
+pub fn rpe_from_rir(rir: f64) -> f64 {
+    crate::metrics::rpe_from_rir(rir)
+}
+
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct BodyweightDataPoint {
+    pub id: i64,
+    pub timestamp: i64,
+    pub weight: f64,
+}
+
+impl From<crate::db::models::BodyweightEntry> for BodyweightDataPoint {
+    fn from(e: crate::db::models::BodyweightEntry) -> Self {
+        BodyweightDataPoint {
+            id: e.id,
+            timestamp: e.recorded_at,
+            weight: e.weight,
+        }
+    }
+}
+
+/// Logs a bodyweight measurement; `recorded_at` defaults to now.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn add_bodyweight(
+    session: &Session,
+    weight: f64,
+    recorded_at: Option<i64>,
+) -> std::result::Result<BodyweightDataPoint, YokuError> {
+    let entry = session.add_bodyweight(weight, recorded_at).await?;
+    Ok(BodyweightDataPoint::from(entry))
+}
+
+/// Bodyweight measurements oldest first, optionally only those since a unix timestamp.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_bodyweight_series(
+    session: &Session,
+    since: Option<i64>,
+) -> std::result::Result<Vec<BodyweightDataPoint>, YokuError> {
+    let entries = session.get_bodyweight_series(since).await?;
+    Ok(entries.into_iter().map(BodyweightDataPoint::from).collect())
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn delete_bodyweight_entry(
+    session: &Session,
+    entry_id: i64,
+) -> std::result::Result<u64, YokuError> {
+    let deleted = session.delete_bodyweight_entry(entry_id).await?;
+    Ok(deleted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn delete_workout(session: &Session, id: i64) -> std::result::Result<u64, YokuError> {
+    session.delete_workout(id).await.map_err(|e| e.into())
+}
+
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn delete_set_from_workout(
     session: &Session,
     id: i64,
 ) -> std::result::Result<u64, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    rt.block_on(session.delete_set(id)).map_err(|e| e.into())
+    session.delete_set(id).await.map_err(|e| e.into())
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn get_lifts_for_exercise(
     session: &Session,
     exercise_id: i64,
     limit: Option<i64>,
 ) -> std::result::Result<Vec<f64>, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let sets = rt.block_on(session.get_sets_for_exercise(exercise_id, limit))?;
+    let sets = session.get_sets_for_exercise(exercise_id, limit).await?;
 
     let converted: Vec<f64> = sets.into_iter().map(|lift| lift.weight).collect();
 
     Ok(converted)
 }
 
-#[uniffi::export]
+/// Weight of each logged set of an exercise with the time it was logged, oldest first.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_lift_series(
+    session: &Session,
+    exercise_id: i64,
+    limit: Option<i64>,
+) -> std::result::Result<Vec<Arc<LiftDataPoint>>, YokuError> {
+    let mut sets = session.get_sets_for_exercise(exercise_id, limit).await?;
+    sets.sort_by_key(|s| (s.created_at, s.id));
+
+    let converted: Vec<Arc<LiftDataPoint>> = sets
+        .into_iter()
+        .map(|set| {
+            Arc::new(LiftDataPoint {
+                timestamp: set.created_at,
+                lift: set.weight,
+            })
+        })
+        .collect();
+
+    Ok(converted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_muscle_set_counts(
+    session: &Session,
+    workout_id: i64,
+) -> std::result::Result<Vec<MuscleSetCount>, YokuError> {
+    let counts = session.get_muscle_set_counts(workout_id).await?;
+
+    let converted: Vec<MuscleSetCount> = counts
+        .into_iter()
+        .map(|(muscle, set_count)| MuscleSetCount {
+            muscle_id: muscle.id,
+            muscle_name: muscle.name,
+            set_count,
+        })
+        .collect();
+
+    Ok(converted)
+}
+
+/// Total weight x reps lifted in a workout.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_session_volume(
+    session: &Session,
+    workout_id: i64,
+) -> std::result::Result<f64, YokuError> {
+    let volume = session.get_session_volume(workout_id).await?;
+    Ok(volume)
+}
+
+/// Total weight x reps lifted for an exercise, optionally only since a unix timestamp.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_exercise_volume(
+    session: &Session,
+    exercise_id: i64,
+    since: Option<i64>,
+) -> std::result::Result<f64, YokuError> {
+    let volume = session.get_exercise_volume(exercise_id, since).await?;
+    Ok(volume)
+}
+
+/// Each muscle's share of the workout's volume, largest first.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn analyze_muscle_balance(
+    session: &Session,
+    workout_id: i64,
+) -> std::result::Result<Vec<MuscleContribution>, YokuError> {
+    let balance = session.analyze_muscle_balance(workout_id).await?;
+
+    let mut converted: Vec<MuscleContribution> = balance
+        .into_iter()
+        .map(|(muscle_name, percentage)| MuscleContribution {
+            muscle_name,
+            percentage,
+        })
+        .collect();
+    converted.sort_by(|a, b| {
+        b.percentage
+            .total_cmp(&a.percentage)
+            .then_with(|| a.muscle_name.cmp(&b.muscle_name))
+    });
+
+    Ok(converted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_training_calendar(
+    session: &Session,
+    days: i64,
+) -> std::result::Result<Vec<TrainingDay>, YokuError> {
+    let calendar = session.get_training_calendar(days).await?;
+
+    let converted: Vec<TrainingDay> = calendar
+        .into_iter()
+        .map(|(date, set_count, tonnage)| TrainingDay {
+            date,
+            set_count,
+            tonnage,
+        })
+        .collect();
+
+    Ok(converted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn delete_workout_session(session: &Session, id: i64) -> Result<(), YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    rt.block_on(session.delete_workout(id))?;
+    session.delete_workout(id).await?;
     Ok(())
 }
 
-#[uniffi::export]
+/// Replaces a workout's notes; pass `None` to clear them.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn set_workout_notes(
+    session: &Session,
+    workout_id: i64,
+    notes: Option<String>,
+) -> std::result::Result<Arc<WorkoutSession>, YokuError> {
+    let workout = session.set_notes(workout_id, notes).await?;
+    Ok(Arc::new(WorkoutSession::try_from(workout)?))
+}
+
+/// Undoes [`delete_workout_session`] for a workout that hasn't been purged yet.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn restore_workout_session(session: &Session, id: i64) -> Result<(), YokuError> {
+    session.restore_workout(id).await?;
+    Ok(())
+}
+
+/// Registers an observer told about every batch of set changes as it happens.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn register_modification_observer(
+    session: &Session,
+    observer: Arc<dyn ModificationObserver>,
+) {
+    session.register_modification_observer(observer).await;
+}
+
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn delete_workout_set(
     session: &Session,
     id: i64,
 ) -> std::result::Result<Vec<Modification>, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let modifications = rt.block_on(session.delete_set_with_modifications(id))?;
+    let modifications = session.delete_set_with_modifications(id).await?;
     Ok(modifications)
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn repeat_last_set(
+    session: &Session,
+    exercise_id: i64,
+) -> std::result::Result<Vec<Modification>, YokuError> {
+    let modifications = session.repeat_last_set(exercise_id).await?;
+    Ok(modifications)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn reorder_set(
+    session: &Session,
+    set_id: i64,
+    new_index: i64,
+) -> std::result::Result<Vec<Modification>, YokuError> {
+    let modifications = session.reorder_set(set_id, new_index).await?;
+    Ok(modifications)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn group_sets_into_superset(
+    session: &Session,
+    set_ids: Vec<i64>,
+) -> std::result::Result<i64, YokuError> {
+    let group_id = session.group_sets_into_superset(set_ids).await?;
+    Ok(group_id)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn ungroup_superset(
+    session: &Session,
+    group_id: i64,
+) -> std::result::Result<u64, YokuError> {
+    let ungrouped = session.ungroup_superset(group_id).await?;
+    Ok(ungrouped)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn get_all_workout_sessions(
     session: &Session,
 ) -> std::result::Result<Vec<Arc<WorkoutSession>>, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let workouts = rt.block_on(session.get_all_workouts_including_in_progress())?;
+    let workouts = session.get_all_workouts_including_in_progress().await?;
 
     let converted: Vec<Arc<WorkoutSession>> = workouts
         .into_iter()
@@ -110,36 +347,275 @@ pub async fn get_all_workout_sessions(
     Ok(converted)
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_workout_sessions_in_range(
+    session: &Session,
+    after: Option<i64>,
+    before: Option<i64>,
+) -> std::result::Result<Vec<Arc<WorkoutSession>>, YokuError> {
+    let workouts = session.get_workouts_in_range(after, before).await?;
+
+    let converted: Vec<Arc<WorkoutSession>> = workouts
+        .into_iter()
+        .map(WorkoutSession::try_from)
+        .collect::<Result<Vec<WorkoutSession>, YokuError>>()?
+        .into_iter()
+        .map(Arc::new)
+        .collect();
+
+    Ok(converted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_workout_sessions_page(
+    session: &Session,
+    limit: i64,
+    offset: i64,
+) -> std::result::Result<Vec<Arc<WorkoutSession>>, YokuError> {
+    let workouts = session.get_workouts_page(limit, offset).await?;
+
+    let converted: Vec<Arc<WorkoutSession>> = workouts
+        .into_iter()
+        .map(WorkoutSession::try_from)
+        .collect::<Result<Vec<WorkoutSession>, YokuError>>()?
+        .into_iter()
+        .map(Arc::new)
+        .collect();
+
+    Ok(converted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn get_all_sets(
     session: &Session,
 ) -> std::result::Result<Vec<Arc<WorkoutSet>>, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let sets = rt.block_on(session.get_all_sets())?;
+    let sets = session.get_all_sets().await?;
+
+    Ok(session.uniffi_sets(sets).await?)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn search_sets(
+    session: &Session,
+    query: String,
+    limit: Option<i64>,
+) -> std::result::Result<Vec<Arc<WorkoutSet>>, YokuError> {
+    let sets = session.search_sets(&query, limit).await?;
 
-    let converted: Vec<Arc<WorkoutSet>> = sets
+    Ok(session.uniffi_sets(sets).await?)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_all_exercises(
+    session: &Session,
+) -> std::result::Result<Vec<Arc<Exercise>>, YokuError> {
+    let exercises = session.get_all_exercises().await?;
+
+    let converted: Vec<Arc<Exercise>> = exercises
         .into_iter()
-        .map(|ws| {
-            Arc::new(WorkoutSet {
-                id: ws.id,
-                exercise_id: ws.exercise_id,
-                weight: ws.weight,
-                reps: ws.reps,
-                rpe: ws.rpe,
-                notes: ws.notes,
-            })
+        .map(|e| Arc::new(Exercise::from(e)))
+        .collect();
+
+    Ok(converted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn set_exercise_favorite(
+    session: &Session,
+    exercise_id: i64,
+    is_favorite: bool,
+) -> std::result::Result<Arc<Exercise>, YokuError> {
+    let exercise = session
+        .set_exercise_favorite(exercise_id, is_favorite)
+        .await?;
+    Ok(Arc::new(Exercise::from(exercise)))
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_favorite_exercises(
+    session: &Session,
+) -> std::result::Result<Vec<Arc<Exercise>>, YokuError> {
+    let exercises = session.get_favorite_exercises().await?;
+
+    let converted: Vec<Arc<Exercise>> = exercises
+        .into_iter()
+        .map(|e| Arc::new(Exercise::from(e)))
+        .collect();
+
+    Ok(converted)
+}
+
+/// Searches exercise names in SQLite, ranking names that start with `prefix` ahead of those
+/// that only contain it. Use this for a one-off search; for type-ahead, where every
+/// keystroke searches again, use [`prefix_search_exercises`]. `limit` must be positive.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn search_exercises(
+    session: &Session,
+    prefix: String,
+    limit: i64,
+) -> std::result::Result<Vec<Arc<Exercise>>, YokuError> {
+    let exercises = session.search_exercises(&prefix, limit).await?;
+
+    let converted: Vec<Arc<Exercise>> = exercises
+        .into_iter()
+        .map(|e| Arc::new(Exercise::from(e)))
+        .collect();
+
+    Ok(converted)
+}
+
+/// Type-ahead search over exercise names, answered from an in-memory index after the first
+/// call and matching names with a word that starts with `prefix`. Use [`search_exercises`]
+/// to also find names that contain it mid-word.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn prefix_search_exercises(
+    session: &Session,
+    prefix: &str,
+    limit: u32,
+) -> std::result::Result<Vec<Arc<Exercise>>, YokuError> {
+    let exercises = session.prefix_search(prefix, limit as usize).await?;
+
+    let converted: Vec<Arc<Exercise>> = exercises
+        .into_iter()
+        .map(|e| Arc::new(Exercise::from(e)))
+        .collect();
+
+    Ok(converted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn prune_orphan_exercises(
+    session: &Session,
+    dry_run: bool,
+) -> std::result::Result<Vec<Arc<Exercise>>, YokuError> {
+    let pruned = session.prune_orphan_exercises(dry_run).await?;
+
+    let converted: Vec<Arc<Exercise>> = pruned
+        .into_iter()
+        .map(|e| Arc::new(Exercise::from(e)))
+        .collect();
+
+    Ok(converted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn rename_exercise(
+    session: &Session,
+    exercise_id: i64,
+    new_name: String,
+) -> std::result::Result<Arc<Exercise>, YokuError> {
+    let renamed = session.rename_exercise(exercise_id, &new_name).await?;
+    Ok(Arc::new(Exercise::from(renamed)))
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn merge_exercises(
+    session: &Session,
+    keep_id: i64,
+    remove_id: i64,
+) -> std::result::Result<u64, YokuError> {
+    let moved = session.merge_exercises(keep_id, remove_id).await?;
+    Ok(moved)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn create_workout_template(
+    session: &Session,
+    name: String,
+    exercises: Vec<TemplateExercise>,
+) -> std::result::Result<WorkoutTemplate, YokuError> {
+    let new_exercises: Vec<_> = exercises.iter().cloned().map(Into::into).collect();
+    let template = session
+        .create_workout_template(&name, &new_exercises)
+        .await?;
+    Ok(WorkoutTemplate {
+        id: template.id,
+        name: template.name,
+        exercises,
+    })
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_workout_templates(
+    session: &Session,
+) -> std::result::Result<Vec<WorkoutTemplate>, YokuError> {
+    let templates = session.get_workout_templates().await?;
+
+    let converted: Vec<WorkoutTemplate> = templates
+        .into_iter()
+        .map(|(template, exercises)| WorkoutTemplate {
+            id: template.id,
+            name: template.name,
+            exercises: exercises.into_iter().map(TemplateExercise::from).collect(),
         })
         .collect();
 
     Ok(converted)
 }
 
-#[uniffi::export]
-pub async fn get_all_exercises(
+/// Starts a new workout pre-filled from a template and returns its id.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn instantiate_template(
+    session: &Session,
+    template_id: i64,
+) -> std::result::Result<i64, YokuError> {
+    let workout_id = session.instantiate_template(template_id).await?;
+    Ok(workout_id)
+}
+
+/// Starts a new workout repeating every set of an earlier one and returns its id.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn copy_workout(
+    session: &Session,
+    source_session_id: i64,
+) -> std::result::Result<i64, YokuError> {
+    let workout_id = session.copy_workout(source_session_id).await?;
+    Ok(workout_id)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn set_exercise_match_threshold(session: &Session, threshold: f64) {
+    session.set_exercise_match_threshold(threshold).await;
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn list_equipment(session: &Session) -> std::result::Result<Vec<Equipment>, YokuError> {
+    let equipment = session.list_equipment().await?;
+    Ok(equipment.into_iter().map(Equipment::from).collect())
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_or_create_equipment(
+    session: &Session,
+    name: &str,
+) -> std::result::Result<Equipment, YokuError> {
+    let equipment = session.get_or_create_equipment(name).await?;
+    Ok(Equipment::from(equipment))
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn set_equipment_owned(
+    session: &Session,
+    equipment_id: i64,
+    owned: bool,
+) -> std::result::Result<(), YokuError> {
+    session.set_equipment_owned(equipment_id, owned).await?;
+    Ok(())
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_owned_equipment(
+    session: &Session,
+) -> std::result::Result<Vec<Equipment>, YokuError> {
+    let equipment = session.get_owned_equipment().await?;
+    Ok(equipment.into_iter().map(Equipment::from).collect())
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_exercises_for_owned_equipment(
     session: &Session,
 ) -> std::result::Result<Vec<Arc<Exercise>>, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let exercises = rt.block_on(session.get_all_exercises())?;
+    let exercises = session.get_exercises_for_owned_equipment().await?;
 
     let converted: Vec<Arc<Exercise>> = exercises
         .into_iter()
@@ -149,41 +625,65 @@ pub async fn get_all_exercises(
     Ok(converted)
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn set_session_workout_session_id(
     session: &Session,
     id: i64,
 ) -> std::result::Result<(), YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    rt.block_on(session.set_workout_id(id))?;
+    session.set_workout_id(id).await?;
     Ok(())
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn create_blank_workout_session(
     session: &Session,
-) -> std::result::Result<bool, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let had_existing = rt.block_on(session.new_workout())?;
-    Ok(had_existing)
+) -> std::result::Result<Option<i64>, YokuError> {
+    let discarded = session.new_workout().await?;
+    Ok(discarded)
 }
 
-#[uniffi::export]
-pub async fn complete_workout_session(
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn restore_in_progress_workout(
     session: &Session,
-    duration_seconds: i64,
+    id: i64,
 ) -> std::result::Result<(), YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    rt.block_on(session.complete_workout(duration_seconds))?;
+    session.restore_in_progress(id).await?;
     Ok(())
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn complete_workout_session(
+    session: &Session,
+    duration_seconds: i64,
+) -> std::result::Result<i64, YokuError> {
+    let duration_seconds = session.complete_workout(duration_seconds).await?;
+    Ok(duration_seconds)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_computed_workout_duration(
+    session: &Session,
+) -> std::result::Result<i64, YokuError> {
+    let duration_seconds = session.compute_workout_duration().await?;
+    Ok(duration_seconds)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn auto_complete_stale_workouts(
+    session: &Session,
+    inactivity_seconds: i64,
+) -> std::result::Result<u64, YokuError> {
+    let closed = session
+        .auto_complete_stale_workouts(inactivity_seconds)
+        .await?;
+    Ok(closed)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn get_in_progress_workout_session(
     session: &Session,
 ) -> std::result::Result<Option<Arc<WorkoutSession>>, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let workout = rt.block_on(session.get_in_progress_workout())?;
+    let workout = session.get_in_progress_workout().await?;
     match workout {
         Some(w) => {
             let workout_uniffi: WorkoutSession = w.try_into()?;
@@ -193,63 +693,93 @@ pub async fn get_in_progress_workout_session(
     }
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn set_target_set_count(
+    session: &Session,
+    target_sets: i64,
+) -> std::result::Result<(), YokuError> {
+    session.set_target_set_count(target_sets).await?;
+    Ok(())
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn estimate_remaining_time(
+    session: &Session,
+) -> std::result::Result<Option<i64>, YokuError> {
+    let remaining = session.estimate_remaining_time().await?;
+    Ok(remaining)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn check_in_progress_workout_exists(
     session: &Session,
 ) -> std::result::Result<bool, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let exists = rt.block_on(session.check_in_progress_workout_exists())?;
+    let exists = session.check_in_progress_workout_exists().await?;
     Ok(exists)
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn update_workout_elapsed_time(
     session: &Session,
     elapsed_seconds: i64,
 ) -> std::result::Result<(), YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    rt.block_on(session.update_workout_elapsed_time(elapsed_seconds))?;
+    session.update_workout_elapsed_time(elapsed_seconds).await?;
     Ok(())
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn update_workout_set(
     session: &Session,
     set_id: i64,
     reps: Option<i64>,
     weight: Option<f64>,
 ) -> std::result::Result<UpdateWorkoutSetResult, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
     let update = UpdateWorkoutSet {
         reps,
         weight,
         ..Default::default()
     };
-    let (workout_db, modifications) =
-        rt.block_on(session.update_workout_set_with_modifications(set_id, &update))?;
-    let workout_uniffi: WorkoutSet = workout_db.into();
+    let (workout_db, modifications) = session
+        .update_workout_set_with_modifications(set_id, &update)
+        .await?;
     Ok(UpdateWorkoutSetResult {
-        set: Arc::new(workout_uniffi),
+        set: session.uniffi_set(workout_db).await?,
         modifications,
     })
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn update_workout_set_full(
+    session: &Session,
+    set_id: i64,
+    reps: Option<i64>,
+    weight: Option<f64>,
+    rpe: Option<f64>,
+    exercise_name: Option<String>,
+) -> std::result::Result<UpdateWorkoutSetResult, YokuError> {
+    let (workout_db, modifications) = session
+        .update_workout_set_full(set_id, reps, weight, rpe, exercise_name.as_deref())
+        .await?;
+    Ok(UpdateWorkoutSetResult {
+        set: session.uniffi_set(workout_db).await?,
+        modifications,
+    })
+}
+
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn get_session_workout_session(
     session: &Session,
 ) -> std::result::Result<WorkoutSession, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let workout_db = rt.block_on(session.get_workout_session())?;
+    let workout_db = session.get_workout_session().await?;
     let workout_uniffi: WorkoutSession = workout_db.try_into()?;
     Ok(workout_uniffi)
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn get_workout_suggestions(
     session: &Session,
 ) -> std::result::Result<Vec<Arc<WorkoutSuggestion>>, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let suggestions = rt.block_on(session.get_workout_suggestions())?;
+    let suggestions = session.get_workout_suggestions().await?;
     let converted: Vec<Arc<WorkoutSuggestion>> = suggestions
         .into_iter()
         .map(|s| Arc::new(WorkoutSuggestion::from(s)))
@@ -257,36 +787,286 @@ pub async fn get_workout_suggestions(
     Ok(converted)
 }
 
-#[uniffi::export]
+/// Suggestions for a single exercise based on its recent history, for its detail screen.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_exercise_suggestions(
+    session: &Session,
+    exercise_id: i64,
+) -> std::result::Result<Vec<Arc<WorkoutSuggestion>>, YokuError> {
+    let suggestions = session.get_exercise_suggestions(exercise_id).await?;
+    let converted: Vec<Arc<WorkoutSuggestion>> = suggestions
+        .into_iter()
+        .map(|s| Arc::new(WorkoutSuggestion::from(s)))
+        .collect();
+    Ok(converted)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn get_workout_summary(
     session: &Session,
 ) -> std::result::Result<WorkoutSummary, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let summary = rt.block_on(session.get_workout_summary())?;
+    let summary = session.get_workout_summary().await?;
     Ok(WorkoutSummary::from(summary))
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn classify_and_process_input(
     session: &Session,
     input: &str,
     selected_set_backend_id: Option<i64>,
     visible_set_backend_ids: Vec<i64>,
 ) -> std::result::Result<Vec<Modification>, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let modifications = rt.block_on(session.process_user_input(
-        input,
-        selected_set_backend_id,
-        visible_set_backend_ids,
-    ))?;
+    let modifications = session
+        .process_user_input(input, selected_set_backend_id, visible_set_backend_ids)
+        .await?;
     Ok(modifications)
 }
 
-#[uniffi::export]
+#[uniffi::export(async_runtime = "tokio")]
 pub async fn get_active_workout_state(
     session: &Session,
 ) -> std::result::Result<ActiveWorkoutState, YokuError> {
-    let rt = crate::runtime::init_global_runtime_blocking();
-    let state = rt.block_on(session.get_active_workout_state())?;
+    let state = session.get_active_workout_state().await?;
     Ok(state)
 }
+
+/// Whether the active workout is long or hard enough to suggest finishing it.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn should_suggest_completion(session: &Session) -> std::result::Result<bool, YokuError> {
+    let suggest = session.should_suggest_completion().await?;
+    Ok(suggest)
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn workout_review(session: &Session) -> std::result::Result<WorkoutReview, YokuError> {
+    let review = session.workout_review().await?;
+    Ok(WorkoutReview::from(review))
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_active_workout_grouped(
+    session: &Session,
+) -> std::result::Result<Vec<GroupedExercise>, YokuError> {
+    let grouped = session.get_active_workout_grouped().await?;
+
+    // Convert every set in one go so tags and areas of interest are loaded once.
+    let (exercises, sets): (Vec<_>, Vec<_>) = grouped.into_iter().unzip();
+    let counts: Vec<usize> = sets.iter().map(Vec::len).collect();
+    let mut converted_sets = session
+        .uniffi_sets(sets.into_iter().flatten().collect())
+        .await?
+        .into_iter();
+
+    let converted: Vec<GroupedExercise> = exercises
+        .into_iter()
+        .zip(counts)
+        .map(|(exercise, count)| GroupedExercise {
+            exercise: Arc::new(Exercise::from(exercise)),
+            sets: converted_sets.by_ref().take(count).collect(),
+        })
+        .collect();
+
+    Ok(converted)
+}
+
+/// Weight to load for the next set of an exercise: up 2.5kg after an easy set, held at
+/// RPE 8 to 9, slightly lower after a grinder. `None` if the exercise was never logged.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn suggest_next_weight(
+    session: &Session,
+    exercise_id: i64,
+) -> std::result::Result<Option<f64>, YokuError> {
+    Ok(session.suggest_next_weight(exercise_id).await?)
+}
+
+/// True when none of the last `window` workouts with an exercise beat the estimated 1RM
+/// of the first of them, a hint that a deload is due.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn detect_stall(
+    session: &Session,
+    exercise_id: i64,
+    window: u32,
+) -> std::result::Result<bool, YokuError> {
+    Ok(session.detect_stall(exercise_id, window as usize).await?)
+}
+
+/// Epley estimated one-rep max of each logged set of an exercise, oldest first. Sets
+/// without reps are skipped.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_estimated_1rm_series(
+    session: &Session,
+    exercise_id: i64,
+    limit: Option<i64>,
+) -> std::result::Result<Vec<Arc<LiftDataPoint>>, YokuError> {
+    let mut sets = session.get_sets_for_exercise(exercise_id, limit).await?;
+    sets.sort_by_key(|s| (s.created_at, s.id));
+
+    let converted: Vec<Arc<LiftDataPoint>> = sets
+        .into_iter()
+        .filter(|set| set.reps > 0)
+        .map(|set| {
+            Arc::new(LiftDataPoint {
+                timestamp: set.created_at,
+                lift: estimate_one_rep_max(set.weight, set.reps),
+            })
+        })
+        .collect();
+
+    Ok(converted)
+}
+
+/// Past sets of an exercise, newest first, with the date and name of each set's workout.
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_exercise_history(
+    session: &Session,
+    exercise_id: i64,
+    limit: Option<i64>,
+) -> std::result::Result<Vec<ExerciseHistoryEntry>, YokuError> {
+    Ok(session
+        .get_exercise_history(exercise_id, limit)
+        .await?
+        .into_iter()
+        .map(ExerciseHistoryEntry::from)
+        .collect())
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+pub async fn get_movement_pattern_for_exercise(
+    session: &Session,
+    exercise_id: i64,
+) -> std::result::Result<MovementPattern, YokuError> {
+    let pattern = session
+        .get_movement_pattern_for_exercise(exercise_id)
+        .await?;
+    Ok(pattern.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::operations::set_workout_set_created_at;
+    use crate::llm::{LlmInterface, ParsedSet};
+
+    async fn session_with_bench_history(sets: &[(f32, i32, i64)]) -> (Session, i64) {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        for (weight, reps, created_at) in sets {
+            session
+                .add_set_from_parsed(&ParsedSet {
+                    exercise: "Bench Press".to_string(),
+                    weight: Some(*weight),
+                    unit: None,
+                    reps: Some(*reps),
+                    rpe: None,
+                    set_count: None,
+                    duration_seconds: None,
+                    distance_meters: None,
+                    tags: vec![],
+                    aoi: None,
+                    original_string: String::new(),
+                })
+                .await
+                .unwrap();
+            let set = session
+                .get_all_sets()
+                .await
+                .unwrap()
+                .into_iter()
+                .max_by_key(|s| s.id)
+                .unwrap();
+            set_workout_set_created_at(&session.db_pool, set.id, *created_at)
+                .await
+                .unwrap();
+        }
+        let exercise_id = session.get_all_exercises().await.unwrap()[0].id;
+        (session, exercise_id)
+    }
+
+    #[tokio::test]
+    async fn test_lift_series_is_chronological() {
+        let (session, exercise_id) =
+            session_with_bench_history(&[(100.0, 5, 3_000), (90.0, 8, 1_000), (95.0, 6, 2_000)])
+                .await;
+
+        let series = get_lift_series(&session, exercise_id, None).await.unwrap();
+        assert_eq!(
+            series
+                .iter()
+                .map(|p| (p.timestamp(), p.lift()))
+                .collect::<Vec<_>>(),
+            vec![(1_000, 90.0), (2_000, 95.0), (3_000, 100.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_estimated_1rm_series_skips_sets_without_reps() {
+        let (session, exercise_id) =
+            session_with_bench_history(&[(100.0, 5, 2_000), (60.0, 0, 1_500), (90.0, 10, 1_000)])
+                .await;
+
+        let series = get_estimated_1rm_series(&session, exercise_id, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            series.iter().map(|p| p.timestamp()).collect::<Vec<_>>(),
+            vec![1_000, 2_000]
+        );
+        assert!((series[0].lift() - 120.0).abs() < 1e-9);
+        assert!((series[1].lift() - 100.0 * (1.0 + 5.0 / 30.0)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_set_tags_and_aoi_reach_uniffi_set() {
+        let session = Session::new_for_tests(LlmInterface::new_mock_fn(|_, _| String::new()))
+            .await
+            .unwrap();
+        session.new_workout().await.unwrap();
+        let modifications = session
+            .add_set_from_parsed_with_modifications(&ParsedSet {
+                exercise: "Squat".to_string(),
+                weight: Some(120.0),
+                unit: None,
+                reps: Some(5),
+                rpe: None,
+                set_count: None,
+                duration_seconds: None,
+                distance_meters: None,
+                tags: vec!["Paused".to_string(), "Tempo".to_string()],
+                aoi: Some("left knee".to_string()),
+                original_string: String::new(),
+            })
+            .await
+            .unwrap();
+
+        let added = modifications[0].set.as_ref().unwrap();
+        assert_eq!(added.tags, vec!["paused", "tempo"]);
+        assert_eq!(added.aoi.as_deref(), Some("left knee"));
+
+        let sets = get_all_sets(&session).await.unwrap();
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].tags, vec!["paused", "tempo"]);
+        assert_eq!(sets[0].aoi.as_deref(), Some("left knee"));
+
+        let found = search_sets(&session, "Squat".to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].tags, vec!["paused", "tempo"]);
+
+        let grouped = get_active_workout_grouped(&session).await.unwrap();
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].sets[0].aoi.as_deref(), Some("left knee"));
+
+        let updated = update_workout_set(&session, sets[0].id, Some(6), None)
+            .await
+            .unwrap();
+        assert_eq!(updated.set.reps, 6);
+        assert_eq!(updated.set.tags, vec!["paused", "tempo"]);
+        let updated = update_workout_set_full(&session, sets[0].id, None, None, Some(8.0), None)
+            .await
+            .unwrap();
+        assert_eq!(updated.set.aoi.as_deref(), Some("left knee"));
+    }
+}